@@ -69,10 +69,23 @@ export interface Resource {
 }
 
 export interface Keypair {
+  // Nullifier key: authorizes consuming resources, never leaves the client
   private_key: string
+  // Nullifier key commitment: published on-chain as a resource's nk_commitment
   public_key: string
 }
 
+// Decoded execute(Transaction) calldata
+export interface TransactionDetails {
+  actions: {
+    action_tree_root: string
+    nullifiers: string[]
+    commitments: string[]
+    unit_deltas: { x: string; y: string }[]
+  }[]
+  delta_proof: string
+}
+
 export interface ApiError {
   error: string
 }
@@ -85,8 +98,8 @@ interface JobResponse {
   proof_id?: string
   error?: string
   result?: {
-    transaction: string
-    resource_commitment: string
+    transaction: TransactionDetails | null
+    resource_commitment?: string
     calldata: string
     forwarder_call: {
       data: string
@@ -0,0 +1,5 @@
+// Builds the forwarder logic guest and embeds its ELF, so the ELF always
+// matches the witness crate the guest reads.
+fn main() {
+    risc0_build::embed_methods();
+}
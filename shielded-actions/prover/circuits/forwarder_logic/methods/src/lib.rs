@@ -0,0 +1,7 @@
+//! Forwarder logic guest ELF, built from `guest/` by this crate's build script
+//!
+//! Exposes `FORWARDER_LOGIC_GUEST_ELF`, `FORWARDER_LOGIC_GUEST_ID` and
+//! `FORWARDER_LOGIC_GUEST_PATH`. Set `RISC0_USE_DOCKER=1` for a reproducible
+//! build, whose image ID is the same on every machine.
+
+include!(concat!(env!("OUT_DIR"), "/methods.rs"));
@@ -2,36 +2,150 @@
 //!
 //! A logic circuit witness that outputs external_payload for ERC20 forwarder calls.
 //! This enables shield/unshield operations that transfer tokens via the Protocol Adapter.
+//!
+//! The same witness type is read by the guest program and built by the host,
+//! so the external_payload encoding lives in one place.
 
+#[cfg(feature = "prove")]
+use arm::logic_proof::LogicProver;
 use arm::{
     error::ArmError,
     logic_instance::{AppData, ExpirableBlob, LogicInstance},
-    logic_proof::LogicProver,
     nullifier_key::NullifierKey,
     resource::Resource,
     resource_logic::LogicCircuit,
     utils::bytes_to_words,
 };
-use once_cell::sync::Lazy;
-use risc0_zkvm::sha::Digest;
+use alloy_primitives::aliases::{U160, U24};
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::{SolCall, SolValue};
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+#[cfg(feature = "prove")]
+use std::sync::LazyLock;
 
 /// The compiled forwarder logic guest program ELF binary
-/// Generated by: cargo risczero build (in circuits/forwarder_logic/methods/guest)
-pub const FORWARDER_LOGIC_ELF: &[u8] = include_bytes!("../forwarder-logic-guest.bin");
+/// Built from circuits/forwarder_logic/methods/guest by the forwarder-logic-methods build script
+#[cfg(feature = "prove")]
+pub const FORWARDER_LOGIC_ELF: &[u8] = forwarder_logic_methods::FORWARDER_LOGIC_GUEST_ELF;
 
 /// The image ID (verifying key) of the forwarder logic guest program
-/// This was output during compilation: 09eb06dcde7c4febd4e9b26ca97ac21bdbd9e32dcd387ab232faac1924796a8f
-pub static FORWARDER_LOGIC_IMAGE_ID: Lazy<Digest> = Lazy::new(|| {
-    let bytes = hex::decode("09eb06dcde7c4febd4e9b26ca97ac21bdbd9e32dcd387ab232faac1924796a8f")
-        .expect("Invalid image ID hex");
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
-    Digest::from_bytes(arr)
+/// Computed from FORWARDER_LOGIC_ELF, so the two cannot drift apart
+#[cfg(feature = "prove")]
+pub static FORWARDER_LOGIC_IMAGE_ID: LazyLock<Digest> = LazyLock::new(|| {
+    risc0_zkvm::compute_image_id(FORWARDER_LOGIC_ELF).expect("Embedded forwarder logic ELF is not a valid guest program")
 });
 
-/// Deletion criterion: Never delete (persists after transaction)
-const DELETION_CRITERION_NEVER: u32 = 1;
+/// When the Protocol Adapter may delete an external_payload blob
+/// Mirrors `Logic.DeletionCriterion` in the contracts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionCriterion {
+    /// Delete after the transaction (one-shot forwarder calls)
+    Immediately,
+    /// Never delete (persists after transaction)
+    #[default]
+    Never,
+}
+
+impl DeletionCriterion {
+    /// Value emitted in `ExpirableBlob::deletion_criterion`
+    pub fn as_u32(self) -> u32 {
+        match self {
+            DeletionCriterion::Immediately => 0,
+            DeletionCriterion::Never => 1,
+        }
+    }
+
+    /// Name accepted by `FromStr` and used in JSON
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeletionCriterion::Immediately => "immediately",
+            DeletionCriterion::Never => "never",
+        }
+    }
+}
+
+impl FromStr for DeletionCriterion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "immediately" => Ok(DeletionCriterion::Immediately),
+            "never" => Ok(DeletionCriterion::Never),
+            _ => Err(format!("Unknown deletion criterion: {}. Supported: immediately, never", s)),
+        }
+    }
+}
+
+/// Uniswap V3 `ExactInputSingleParams` (SwapRouter02, no deadline)
+///
+/// `sqrtPriceLimitX96` is always 0, i.e. no price limit beyond `amount_out_minimum`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapParams {
+    /// ERC20 sold
+    pub token_in: [u8; 20],
+    /// ERC20 bought
+    pub token_out: [u8; 20],
+    /// Pool fee tier in hundredths of a bip (e.g. 3000 = 0.3%), below 2^24
+    pub fee: u32,
+    /// Receives the bought tokens
+    pub recipient: [u8; 20],
+    pub amount_in: u128,
+    pub amount_out_minimum: u128,
+}
+
+/// EIP-2612 permit signed by a shield's sender, approving the forwarder
+///
+/// The owner is the sender and the spender the forwarder, so only the signed
+/// fields are carried. `value` may exceed the shielded amount (e.g. a standing approval).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermitData {
+    pub value: u128,
+    /// Unix timestamp after which the permit is invalid
+    pub deadline: u64,
+    pub v: u8,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+/// Call a resource makes through its forwarder
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Call {
+    /// transferFrom(from, forwarder, amount): deposit into the forwarder (shield)
+    TransferFrom { from: [u8; 20], amount: u128 },
+    /// transfer(to, amount): withdraw from the forwarder (unshield)
+    Transfer { to: [u8; 20], amount: u128 },
+    /// exactInputSingle(params): swap through the Uniswap forwarder
+    ExactInputSingle(SwapParams),
+    /// Pre-built calldata and expected output, passed through verbatim
+    Raw { call_data: Vec<u8>, expected_output: Vec<u8> },
+}
+
+impl Call {
+    /// Calldata sent to the forwarder, including the function selector
+    pub fn call_data(&self, forwarder_address: [u8; 20]) -> Vec<u8> {
+        match self {
+            Call::TransferFrom { from, amount } => encode_transfer_from(*from, forwarder_address, *amount),
+            Call::Transfer { to, amount } => encode_transfer(*to, *amount),
+            Call::ExactInputSingle(params) => encode_exact_input_single(params),
+            Call::Raw { call_data, .. } => call_data.clone(),
+        }
+    }
+
+    /// Return data the forwarder call must produce
+    ///
+    /// The Protocol Adapter compares it byte for byte, so it cannot depend on how a
+    /// swap fills. ERC20 calls return abi.encode(true), and so does the Uniswap
+    /// forwarder once `amount_out_minimum` is met.
+    pub fn expected_output(&self) -> Vec<u8> {
+        match self {
+            Call::TransferFrom { .. } | Call::Transfer { .. } | Call::ExactInputSingle(_) => true.abi_encode(),
+            Call::Raw { expected_output, .. } => expected_output.clone(),
+        }
+    }
+}
 
 /// Forwarder Logic Witness
 ///
@@ -49,12 +163,12 @@ pub struct ForwarderLogicWitness {
     pub nf_key: NullifierKey,
     /// Forwarder contract address (20 bytes)
     pub forwarder_address: [u8; 20],
-    /// Call data to send to forwarder (includes function selector)
-    pub call_data: Vec<u8>,
-    /// Expected output from forwarder call
-    pub expected_output: Vec<u8>,
-    /// Whether to include external_payload (only for specific resource roles)
-    pub include_external_call: bool,
+    /// Forwarder call this resource triggers, if any (only for specific resource roles)
+    pub call: Option<Call>,
+    /// Deletion criterion of the emitted external_payload blob
+    pub deletion_criterion: DeletionCriterion,
+    /// Permit bundled ahead of a TransferFrom call, so no prior approve is needed
+    pub permit: Option<PermitData>,
 }
 
 impl LogicCircuit for ForwarderLogicWitness {
@@ -62,28 +176,22 @@ impl LogicCircuit for ForwarderLogicWitness {
         // Compute the resource tag (nullifier for consumed, commitment for created)
         let tag = self.resource.tag(self.is_consumed, &self.nf_key)?;
 
-        // For ephemeral resources, quantity must be 0
-        if self.resource.is_ephemeral {
-            assert_eq!(self.resource.quantity, 0, "Ephemeral resources must have quantity=0");
+        // Ephemeral resources carry no value unless their own call moves it
+        if self.resource.is_ephemeral && self.resource.quantity != 0 {
+            check_funded_ephemeral(&self.resource, self.is_consumed, self.forwarder_address, self.call.as_ref())?;
         }
 
-        // Build external_payload if this resource should trigger a forwarder call
-        let external_payload = if self.include_external_call && !self.call_data.is_empty() {
-            // Encode as: abi.encode(forwarderAddress, calldata, expectedOutput)
-            let blob_data = self.encode_forwarder_call();
-
-            vec![ExpirableBlob {
-                blob: bytes_to_words(&blob_data),
-                deletion_criterion: DELETION_CRITERION_NEVER,
-            }]
-        } else {
-            vec![]
-        };
+        // A call to or for the zero address would only revert on-chain, and
+        // one moving nothing would cost gas for a no-op
+        if let Some(call) = &self.call {
+            check_addresses(self.forwarder_address, call)?;
+            check_amount(call)?;
+        }
 
         let app_data = AppData {
             resource_payload: vec![],
             discovery_payload: vec![],
-            external_payload,
+            external_payload: self.build_external_payload(),
             application_payload: vec![],
         };
 
@@ -97,6 +205,7 @@ impl LogicCircuit for ForwarderLogicWitness {
 }
 
 /// Implement LogicProver to enable proof generation
+#[cfg(feature = "prove")]
 impl LogicProver for ForwarderLogicWitness {
     type Witness = ForwarderLogicWitness;
 
@@ -114,41 +223,45 @@ impl LogicProver for ForwarderLogicWitness {
 }
 
 impl ForwarderLogicWitness {
-    /// Create a new witness for a shield operation (deposit tokens)
-    pub fn new_shield(
+    fn new(
         resource: Resource,
         action_tree_root: Digest,
         nf_key: NullifierKey,
         is_consumed: bool,
         forwarder_address: [u8; 20],
-        sender: [u8; 20],
-        amount: u128,
+        call: Option<Call>,
     ) -> Self {
-        // For shield, the created resource triggers transferFrom
-        let include_external_call = !is_consumed; // Created resource triggers the call
-
-        let (call_data, expected_output) = if include_external_call {
-            // transferFrom(from, to, amount) - from sender to forwarder
-            let call_data = encode_transfer_from(sender, forwarder_address, amount);
-            let expected_output = encode_bool(true);
-            (call_data, expected_output)
-        } else {
-            (vec![], vec![])
-        };
-
         Self {
             resource,
             action_tree_root,
             is_consumed,
             nf_key,
             forwarder_address,
-            call_data,
-            expected_output,
-            include_external_call,
+            call,
+            deletion_criterion: DeletionCriterion::default(),
+            permit: None,
         }
     }
 
+    /// Create a new witness for a shield operation (deposit tokens)
+    ///
+    /// Only the created resource triggers transferFrom(sender, forwarder, amount).
+    pub fn new_shield(
+        resource: Resource,
+        action_tree_root: Digest,
+        nf_key: NullifierKey,
+        is_consumed: bool,
+        forwarder_address: [u8; 20],
+        sender: [u8; 20],
+        amount: u128,
+    ) -> Self {
+        let call = (!is_consumed).then_some(Call::TransferFrom { from: sender, amount });
+        Self::new(resource, action_tree_root, nf_key, is_consumed, forwarder_address, call)
+    }
+
     /// Create a new witness for an unshield operation (withdraw tokens)
+    ///
+    /// Only the consumed resource triggers transfer(recipient, amount).
     pub fn new_unshield(
         resource: Resource,
         action_tree_root: Digest,
@@ -158,28 +271,77 @@ impl ForwarderLogicWitness {
         recipient: [u8; 20],
         amount: u128,
     ) -> Self {
-        // For unshield, the consumed resource triggers transfer
-        let include_external_call = is_consumed; // Consumed resource triggers the call
-
-        let (call_data, expected_output) = if include_external_call {
-            // transfer(to, amount) - from forwarder to recipient
-            let call_data = encode_transfer(recipient, amount);
-            let expected_output = encode_bool(true);
-            (call_data, expected_output)
-        } else {
-            (vec![], vec![])
-        };
+        let call = is_consumed.then_some(Call::Transfer { to: recipient, amount });
+        Self::new(resource, action_tree_root, nf_key, is_consumed, forwarder_address, call)
+    }
 
-        Self {
-            resource,
-            action_tree_root,
-            is_consumed,
-            nf_key,
-            forwarder_address,
-            call_data,
-            expected_output,
-            include_external_call,
-        }
+    /// Create a witness for the ephemeral wrapper consumed by a persistent shield
+    ///
+    /// The wrapper holds `amount` of the forwarder's token and its consumption
+    /// triggers transferFrom(sender, forwarder, amount), which pays for the
+    /// persistent resource created in the same compliance unit.
+    pub fn new_deposit(
+        resource: Resource,
+        action_tree_root: Digest,
+        nf_key: NullifierKey,
+        forwarder_address: [u8; 20],
+        sender: [u8; 20],
+        amount: u128,
+    ) -> Self {
+        let call = Some(Call::TransferFrom { from: sender, amount });
+        Self::new(resource, action_tree_root, nf_key, true, forwarder_address, call)
+    }
+
+    /// Create a witness for the ephemeral wrapper created by a persistent unshield
+    ///
+    /// The wrapper balances the consumed persistent resource and its creation
+    /// triggers transfer(recipient, amount).
+    pub fn new_withdrawal(
+        resource: Resource,
+        action_tree_root: Digest,
+        nf_key: NullifierKey,
+        forwarder_address: [u8; 20],
+        recipient: [u8; 20],
+        amount: u128,
+    ) -> Self {
+        let call = Some(Call::Transfer { to: recipient, amount });
+        Self::new(resource, action_tree_root, nf_key, false, forwarder_address, call)
+    }
+
+    /// Create a new witness for a swap through the Uniswap forwarder
+    ///
+    /// Only the created resource triggers exactInputSingle(params).
+    pub fn new_swap(
+        resource: Resource,
+        action_tree_root: Digest,
+        nf_key: NullifierKey,
+        is_consumed: bool,
+        forwarder_address: [u8; 20],
+        params: SwapParams,
+    ) -> Self {
+        let call = (!is_consumed).then_some(Call::ExactInputSingle(params));
+        Self::new(resource, action_tree_root, nf_key, is_consumed, forwarder_address, call)
+    }
+
+    /// Create a witness whose call is pre-built calldata, sent verbatim
+    ///
+    /// Nothing checks that `raw_call_data` calls a known function or that
+    /// `expected_output` is what the forwarder returns; the typed constructors
+    /// should be preferred wherever they fit. Only emits a call when
+    /// `include_external_call` is set.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_raw(
+        resource: Resource,
+        action_tree_root: Digest,
+        nf_key: NullifierKey,
+        is_consumed: bool,
+        forwarder_address: [u8; 20],
+        raw_call_data: Vec<u8>,
+        expected_output: Vec<u8>,
+        include_external_call: bool,
+    ) -> Self {
+        let call = include_external_call.then_some(Call::Raw { call_data: raw_call_data, expected_output });
+        Self::new(resource, action_tree_root, nf_key, is_consumed, forwarder_address, call)
     }
 
     /// Create a witness with no external call (for padding/balance resources)
@@ -189,108 +351,209 @@ impl ForwarderLogicWitness {
         nf_key: NullifierKey,
         is_consumed: bool,
     ) -> Self {
-        Self {
-            resource,
-            action_tree_root,
-            is_consumed,
-            nf_key,
-            forwarder_address: [0u8; 20],
-            call_data: vec![],
-            expected_output: vec![],
-            include_external_call: false,
-        }
+        Self::new(resource, action_tree_root, nf_key, is_consumed, [0u8; 20], None)
     }
 
-    /// Encode the forwarder call as ABI-encoded tuple: (address, bytes, bytes)
-    fn encode_forwarder_call(&self) -> Vec<u8> {
-        // ABI encode: (address forwarder, bytes calldata, bytes expectedOutput)
-        // This matches the format expected by ProtocolAdapter._executeForwarderCall
+    /// Set the deletion criterion of the external_payload blob (defaults to Never)
+    pub fn with_deletion_criterion(mut self, deletion_criterion: DeletionCriterion) -> Self {
+        self.deletion_criterion = deletion_criterion;
+        self
+    }
 
-        // Simple ABI encoding for (address, bytes, bytes)
-        let mut result = Vec::new();
+    /// Bundle an EIP-2612 permit ahead of the shield's transferFrom (ignored for other calls)
+    pub fn with_permit(mut self, permit: Option<PermitData>) -> Self {
+        self.permit = permit;
+        self
+    }
 
-        // Offset for dynamic data starts after the 3 static slots (address + 2 offsets)
-        // address is static (32 bytes, padded)
-        // bytes offset 1 (32 bytes)
-        // bytes offset 2 (32 bytes)
+    /// The external_payload: one blob per forwarder call, or nothing without a call
+    ///
+    /// A shield with a permit emits the permit blob first, so the Protocol Adapter
+    /// executes the approval before transferFrom.
+    pub fn build_external_payload(&self) -> Vec<ExpirableBlob> {
+        let Some(call) = &self.call else {
+            return vec![];
+        };
 
-        // Address (padded to 32 bytes)
-        result.extend_from_slice(&[0u8; 12]);
-        result.extend_from_slice(&self.forwarder_address);
+        let permit = match (call, &self.permit) {
+            (Call::TransferFrom { from, .. }, Some(permit)) => {
+                Some(encode_permit(*from, self.forwarder_address, permit))
+            }
+            _ => None,
+        };
 
-        // Offset to call_data (96 = 0x60)
-        result.extend_from_slice(&[0u8; 31]);
-        result.push(0x60);
+        permit
+            .into_iter()
+            .chain(std::iter::once(call.call_data(self.forwarder_address)))
+            .map(|call_data| {
+                let blob_data = encode_forwarder_call(self.forwarder_address, &call_data, &call.expected_output());
+                ExpirableBlob {
+                    blob: bytes_to_words(&blob_data),
+                    deletion_criterion: self.deletion_criterion.as_u32(),
+                }
+            })
+            .collect()
+    }
+}
 
-        // Offset to expected_output (96 + 32 + call_data.len() padded to 32)
-        let call_data_slot_size = 32 + ((self.call_data.len() + 31) / 32) * 32;
-        let expected_offset = 96 + call_data_slot_size;
-        result.extend_from_slice(&u256_to_bytes(expected_offset as u128));
+/// Label of resources denominated in the token held by `forwarder_address`
+///
+/// Resources only balance against resources with the same kind, so binding the
+/// label to the forwarder keeps one token's value from paying for another's.
+pub fn token_label(forwarder_address: [u8; 20]) -> Digest {
+    *Impl::hash_bytes(&forwarder_address)
+}
 
-        // call_data (length + data)
-        result.extend_from_slice(&u256_to_bytes(self.call_data.len() as u128));
-        result.extend_from_slice(&self.call_data);
-        // Pad to 32 bytes
-        let padding = (32 - (self.call_data.len() % 32)) % 32;
-        result.extend_from_slice(&vec![0u8; padding]);
+/// Check an ephemeral resource with a non-zero quantity
+///
+/// Its value must enter or leave through its own forwarder call: a consumed
+/// wrapper deposits exactly its quantity with transferFrom, a created one
+/// withdraws it with transfer, and either is labelled with the forwarder's token.
+fn check_funded_ephemeral(
+    resource: &Resource,
+    is_consumed: bool,
+    forwarder_address: [u8; 20],
+    call: Option<&Call>,
+) -> Result<(), ArmError> {
+    let moved = match (is_consumed, call) {
+        (true, Some(Call::TransferFrom { amount, .. })) => *amount,
+        (false, Some(Call::Transfer { amount, .. })) => *amount,
+        _ => {
+            return Err(ArmError::ProveFailed(
+                "Ephemeral resource with a quantity must deposit (consumed) or withdraw (created) it".to_string(),
+            ))
+        }
+    };
+    if moved != resource.quantity {
+        return Err(ArmError::ProveFailed(format!(
+            "Ephemeral resource of quantity {} moves {} through its forwarder",
+            resource.quantity, moved
+        )));
+    }
+    if resource.label_ref != token_label(forwarder_address) {
+        return Err(ArmError::ProveFailed("Ephemeral resource label does not match its forwarder".to_string()));
+    }
+    Ok(())
+}
 
-        // expected_output (length + data)
-        result.extend_from_slice(&u256_to_bytes(self.expected_output.len() as u128));
-        result.extend_from_slice(&self.expected_output);
-        // Pad to 32 bytes
-        let padding = (32 - (self.expected_output.len() % 32)) % 32;
-        result.extend_from_slice(&vec![0u8; padding]);
+/// Reject a call whose forwarder, or whose sender or recipient, is the zero address
+fn check_addresses(forwarder_address: [u8; 20], call: &Call) -> Result<(), ArmError> {
+    if forwarder_address == [0u8; 20] {
+        return Err(ArmError::ProveFailed("Forwarder call to the zero address".to_string()));
+    }
+    let user_address = match call {
+        Call::TransferFrom { from, .. } => Some(("sender", from)),
+        Call::Transfer { to, .. } => Some(("recipient", to)),
+        Call::ExactInputSingle(params) => Some(("swap recipient", &params.recipient)),
+        Call::Raw { .. } => None,
+    };
+    match user_address {
+        Some((role, address)) if *address == [0u8; 20] => {
+            Err(ArmError::ProveFailed(format!("Forwarder call with a zero {} address", role)))
+        }
+        _ => Ok(()),
+    }
+}
 
-        result
+/// Reject a token transfer or swap of zero; raw calls carry no amount to check
+fn check_amount(call: &Call) -> Result<(), ArmError> {
+    let amount = match call {
+        Call::TransferFrom { amount, .. } | Call::Transfer { amount, .. } => *amount,
+        Call::ExactInputSingle(params) => params.amount_in,
+        Call::Raw { .. } => return Ok(()),
+    };
+    if amount == 0 {
+        return Err(ArmError::ProveFailed("Forwarder call moving a zero amount".to_string()));
     }
+    Ok(())
+}
+
+/// Encode a forwarder call as abi.encode(address forwarder, bytes calldata, bytes expectedOutput)
+///
+/// This matches the format expected by ProtocolAdapter._executeForwarderCall.
+/// The three values are encoded as top-level parameters, like Solidity's
+/// `abi.encode(a, b, c)`, so there is no leading tuple offset.
+pub fn encode_forwarder_call(forwarder_address: [u8; 20], call_data: &[u8], expected_output: &[u8]) -> Vec<u8> {
+    (
+        Address::from(forwarder_address),
+        Bytes::copy_from_slice(call_data),
+        Bytes::copy_from_slice(expected_output),
+    )
+        .abi_encode_params()
 }
 
 /// Encode transferFrom(from, to, amount) call
 fn encode_transfer_from(from: [u8; 20], to: [u8; 20], amount: u128) -> Vec<u8> {
     let mut data = vec![0x23, 0xb8, 0x72, 0xdd]; // transferFrom selector
-
-    // from address (padded)
-    data.extend_from_slice(&[0u8; 12]);
-    data.extend_from_slice(&from);
-
-    // to address (padded)
-    data.extend_from_slice(&[0u8; 12]);
-    data.extend_from_slice(&to);
-
-    // amount (uint256)
-    data.extend_from_slice(&u256_to_bytes(amount));
-
+    data.extend_from_slice(&(Address::from(from), Address::from(to), U256::from(amount)).abi_encode_params());
     data
 }
 
 /// Encode transfer(to, amount) call
 fn encode_transfer(to: [u8; 20], amount: u128) -> Vec<u8> {
     let mut data = vec![0xa9, 0x05, 0x9c, 0xbb]; // transfer selector
+    data.extend_from_slice(&(Address::from(to), U256::from(amount)).abi_encode_params());
+    data
+}
 
-    // to address (padded)
-    data.extend_from_slice(&[0u8; 12]);
-    data.extend_from_slice(&to);
-
-    // amount (uint256)
-    data.extend_from_slice(&u256_to_bytes(amount));
+/// EIP-2612 ABI
+mod erc20 {
+    alloy_sol_types::sol! {
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+    }
+}
 
-    data
+/// Encode permit(owner, spender, value, deadline, v, r, s) call
+///
+/// The forwarder answers it with abi.encode(true), like transferFrom.
+pub fn encode_permit(owner: [u8; 20], spender: [u8; 20], permit: &PermitData) -> Vec<u8> {
+    erc20::permitCall {
+        owner: Address::from(owner),
+        spender: Address::from(spender),
+        value: U256::from(permit.value),
+        deadline: U256::from(permit.deadline),
+        v: permit.v,
+        r: permit.r.into(),
+        s: permit.s.into(),
+    }
+    .abi_encode()
 }
 
-/// Encode a boolean as ABI-encoded bytes
-fn encode_bool(value: bool) -> Vec<u8> {
-    let mut data = vec![0u8; 32];
-    if value {
-        data[31] = 1;
+/// Uniswap ABI as decoded by `UniswapV3Forwarder.forwardCall`
+mod uniswap {
+    alloy_sol_types::sol! {
+        /// SwapRouter02 `ExactInputSingleParams` (no deadline field)
+        struct ExactInputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint24 fee;
+            address recipient;
+            uint256 amountIn;
+            uint256 amountOutMinimum;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        function exactInputSingle(ExactInputSingleParams params) external payable returns (uint256 amountOut);
     }
-    data
 }
 
-/// Convert u128 to 32-byte big-endian representation
-fn u256_to_bytes(value: u128) -> [u8; 32] {
-    let mut bytes = [0u8; 32];
-    bytes[16..].copy_from_slice(&value.to_be_bytes());
-    bytes
+/// Encode exactInputSingle((address,address,uint24,address,uint256,uint256,uint160)) call
+///
+/// SwapRouter02 takes no deadline in the params struct, and the forwarder rejects
+/// the older SwapRouter layout that has one.
+pub fn encode_exact_input_single(params: &SwapParams) -> Vec<u8> {
+    uniswap::exactInputSingleCall {
+        params: uniswap::ExactInputSingleParams {
+            tokenIn: Address::from(params.token_in),
+            tokenOut: Address::from(params.token_out),
+            fee: U24::from(params.fee),
+            recipient: Address::from(params.recipient),
+            amountIn: U256::from(params.amount_in),
+            amountOutMinimum: U256::from(params.amount_out_minimum),
+            sqrtPriceLimitX96: U160::ZERO,
+        },
+    }
+    .abi_encode()
 }
 
 #[cfg(test)]
@@ -323,4 +586,202 @@ mod tests {
         // Check length: 4 + 32 + 32 = 68
         assert_eq!(encoded.len(), 68);
     }
+
+    #[test]
+    fn test_encode_exact_input_single_round_trips() {
+        let params = SwapParams {
+            token_in: [0x11u8; 20],
+            token_out: [0x22u8; 20],
+            fee: 3000,
+            recipient: [0x33u8; 20],
+            amount_in: 1000000,
+            amount_out_minimum: u128::MAX,
+        };
+        let call = Call::ExactInputSingle(params);
+        let encoded = call.call_data([0x44u8; 20]);
+
+        let signature = "exactInputSingle((address,address,uint24,address,uint256,uint256,uint160))";
+        assert_eq!(&encoded[0..4], &alloy_primitives::keccak256(signature)[..4]);
+        assert_eq!(&encoded[0..4], &[0x04, 0xe4, 0x5a, 0xaf]);
+        // 4 + 7 * 32 = 228
+        assert_eq!(encoded.len(), 228);
+
+        let decoded = uniswap::exactInputSingleCall::abi_decode(&encoded).unwrap().params;
+        assert_eq!(decoded.tokenIn, Address::from(params.token_in));
+        assert_eq!(decoded.tokenOut, Address::from(params.token_out));
+        assert_eq!(decoded.fee, U24::from(3000u32));
+        assert_eq!(decoded.recipient, Address::from(params.recipient));
+        assert_eq!(decoded.amountIn, U256::from(params.amount_in));
+        assert_eq!(decoded.amountOutMinimum, U256::from(u128::MAX));
+        assert_eq!(decoded.sqrtPriceLimitX96, U160::ZERO);
+
+        assert_eq!(call.expected_output(), true.abi_encode());
+    }
+
+    #[test]
+    fn test_encode_forwarder_call_round_trips() {
+        let expected_output = true.abi_encode();
+        for len in [0usize, 4, 32, 33, 64] {
+            let call_data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let encoded = encode_forwarder_call([0x11u8; 20], &call_data, &expected_output);
+
+            // Head (address + 2 offsets) plus both padded byte strings
+            assert_eq!(encoded.len(), 96 + 32 + len.div_ceil(32) * 32 + 64, "call_data length {}", len);
+            let (forwarder, input, output) =
+                <(Address, Bytes, Bytes) as SolValue>::abi_decode_params(&encoded).unwrap();
+            assert_eq!(forwarder, Address::from([0x11u8; 20]));
+            assert_eq!(input.as_ref(), call_data.as_slice());
+            assert_eq!(output.as_ref(), expected_output.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_only_the_moving_resource_calls_the_forwarder() {
+        let shield = |is_consumed| ForwarderLogicWitness::new_shield(
+            Resource::default(), Digest::default(), NullifierKey::default(), is_consumed, [0x11u8; 20], [0x22u8; 20], 5,
+        );
+        let unshield = |is_consumed| ForwarderLogicWitness::new_unshield(
+            Resource::default(), Digest::default(), NullifierKey::default(), is_consumed, [0x11u8; 20], [0x33u8; 20], 5,
+        );
+
+        assert_eq!(shield(false).call, Some(Call::TransferFrom { from: [0x22u8; 20], amount: 5 }));
+        assert!(shield(true).build_external_payload().is_empty());
+        assert_eq!(unshield(true).call, Some(Call::Transfer { to: [0x33u8; 20], amount: 5 }));
+        assert!(unshield(false).build_external_payload().is_empty());
+
+        let params = SwapParams {
+            token_in: [0x11u8; 20], token_out: [0x22u8; 20], fee: 500, recipient: [0x33u8; 20], amount_in: 5, amount_out_minimum: 1,
+        };
+        let swap = |is_consumed| ForwarderLogicWitness::new_swap(
+            Resource::default(), Digest::default(), NullifierKey::default(), is_consumed, [0x44u8; 20], params,
+        );
+        assert_eq!(swap(false).call, Some(Call::ExactInputSingle(params)));
+        assert!(swap(true).build_external_payload().is_empty());
+    }
+
+    #[test]
+    fn test_permit_precedes_transfer_from() {
+        let permit = PermitData { value: 5, deadline: 1_700_000_000, v: 27, r: [0xaau8; 32], s: [0xbbu8; 32] };
+        let shield = ForwarderLogicWitness::new_shield(
+            Resource::default(), Digest::default(), NullifierKey::default(), false, [0x11u8; 20], [0x22u8; 20], 5,
+        );
+        assert_eq!(shield.clone().build_external_payload().len(), 1);
+
+        let payload = shield.clone().with_permit(Some(permit)).build_external_payload();
+        assert_eq!(payload.len(), 2);
+        assert_eq!(payload[1].blob, shield.build_external_payload()[0].blob);
+
+        let encoded = encode_permit([0x22u8; 20], [0x11u8; 20], &permit);
+        let signature = "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)";
+        assert_eq!(&encoded[0..4], &alloy_primitives::keccak256(signature)[..4]);
+        let decoded = erc20::permitCall::abi_decode(&encoded).unwrap();
+        assert_eq!(decoded.owner, Address::from([0x22u8; 20]));
+        assert_eq!(decoded.spender, Address::from([0x11u8; 20]));
+        assert_eq!(decoded.deadline, U256::from(1_700_000_000u64));
+        assert_eq!((decoded.v, decoded.r.0, decoded.s.0), (27, [0xaau8; 32], [0xbbu8; 32]));
+
+        // Only a shield's transferFrom takes a permit
+        let unshield = ForwarderLogicWitness::new_unshield(
+            Resource::default(), Digest::default(), NullifierKey::default(), true, [0x11u8; 20], [0x33u8; 20], 5,
+        );
+        assert_eq!(unshield.with_permit(Some(permit)).build_external_payload().len(), 1);
+    }
+
+    #[test]
+    fn test_raw_call_is_passed_through() {
+        let call_data = vec![0xde, 0xad, 0xbe, 0xef, 0x01];
+        let raw = |include| ForwarderLogicWitness::new_raw(
+            Resource::default(), Digest::default(), NullifierKey::default(), false, [0x11u8; 20], call_data.clone(), vec![0x02], include,
+        );
+        assert!(raw(false).build_external_payload().is_empty());
+
+        let payload = raw(true).build_external_payload();
+        assert_eq!(payload.len(), 1);
+        assert_eq!(payload[0].blob, bytes_to_words(&encode_forwarder_call([0x11u8; 20], &call_data, &[0x02])));
+    }
+
+    #[test]
+    fn test_constrain_rejects_zero_addresses() {
+        let shield = |forwarder, sender| ForwarderLogicWitness::new_shield(
+            Resource::default(), Digest::default(), NullifierKey::default(), false, forwarder, sender, 5,
+        );
+        assert!(shield([0x11u8; 20], [0x22u8; 20]).constrain().is_ok());
+        assert!(shield([0u8; 20], [0x22u8; 20]).constrain().is_err());
+        assert!(shield([0x11u8; 20], [0u8; 20]).constrain().is_err());
+
+        let unshield = ForwarderLogicWitness::new_unshield(
+            Resource::default(), Digest::default(), NullifierKey::default(), true, [0x11u8; 20], [0u8; 20], 5,
+        );
+        assert!(check_addresses(unshield.forwarder_address, unshield.call.as_ref().unwrap()).is_err());
+
+        // No call, so no forwarder is needed
+        let padding = ForwarderLogicWitness::new_no_call(Resource::default(), Digest::default(), NullifierKey::default(), false);
+        assert!(padding.constrain().is_ok());
+    }
+
+    #[test]
+    fn test_constrain_rejects_zero_amounts() {
+        let shield = |amount| ForwarderLogicWitness::new_shield(
+            Resource::default(), Digest::default(), NullifierKey::default(), false, [0x11u8; 20], [0x22u8; 20], amount,
+        );
+        assert!(shield(1).constrain().is_ok());
+        assert!(shield(0).constrain().is_err());
+
+        assert!(check_amount(&Call::Transfer { to: [0x33u8; 20], amount: 0 }).is_err());
+        assert!(check_amount(&Call::Raw { call_data: vec![], expected_output: vec![] }).is_ok());
+    }
+
+    #[test]
+    fn test_funded_ephemeral_must_move_its_quantity() {
+        let forwarder = [0x11u8; 20];
+        let wrapper = Resource {
+            label_ref: token_label(forwarder),
+            quantity: 5,
+            is_ephemeral: true,
+            ..Default::default()
+        };
+        let deposit = Call::TransferFrom { from: [0x22u8; 20], amount: 5 };
+        let withdrawal = Call::Transfer { to: [0x33u8; 20], amount: 5 };
+
+        assert!(check_funded_ephemeral(&wrapper, true, forwarder, Some(&deposit)).is_ok());
+        assert!(check_funded_ephemeral(&wrapper, false, forwarder, Some(&withdrawal)).is_ok());
+
+        // Wrong direction, no call, wrong amount, wrong token
+        assert!(check_funded_ephemeral(&wrapper, false, forwarder, Some(&deposit)).is_err());
+        assert!(check_funded_ephemeral(&wrapper, true, forwarder, None).is_err());
+        let short = Call::TransferFrom { from: [0x22u8; 20], amount: 4 };
+        assert!(check_funded_ephemeral(&wrapper, true, forwarder, Some(&short)).is_err());
+        assert!(check_funded_ephemeral(&wrapper, true, [0x44u8; 20], Some(&deposit)).is_err());
+
+        // Created wrapper, so the tag is a commitment and needs no key check
+        let witness = ForwarderLogicWitness::new_withdrawal(
+            wrapper.clone(), Digest::default(), NullifierKey::default(), forwarder, [0x33u8; 20], 5,
+        );
+        assert!(witness.constrain().is_ok());
+        let padding = ForwarderLogicWitness::new_no_call(wrapper, Digest::default(), NullifierKey::default(), false);
+        assert!(padding.constrain().is_err());
+    }
+
+    #[test]
+    fn test_deletion_criterion_propagates() {
+        // Created shield resource, so the tag is a commitment and needs no key check
+        let witness = ForwarderLogicWitness::new_shield(
+            Resource::default(),
+            Digest::default(),
+            NullifierKey::default(),
+            false,
+            [0x11u8; 20],
+            [0x22u8; 20],
+            1000000u128,
+        );
+
+        let instance = witness.clone().constrain().unwrap();
+        assert_eq!(instance.app_data.external_payload[0].deletion_criterion, 1);
+
+        let instance = witness
+            .with_deletion_criterion(DeletionCriterion::Immediately)
+            .constrain()
+            .unwrap();
+        assert_eq!(instance.app_data.external_payload[0].deletion_criterion, 0);
+    }
 }
@@ -0,0 +1,87 @@
+//! Unshield recipient allow-list
+//!
+//! Operators can restrict unshield withdrawals to known addresses by setting
+//! `UNSHIELD_ALLOWED_RECIPIENTS` to a comma-separated list of addresses.
+//! When unset, any recipient is accepted.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+
+/// Set of addresses allowed to receive unshielded tokens
+pub type RecipientAllowList = HashSet<[u8; 20]>;
+
+fn parse_recipient(addr: &str) -> Result<[u8; 20]> {
+    let addr = addr.trim();
+    let bytes = hex::decode(addr.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Invalid recipient address '{}': {}", addr, e))?;
+    if bytes.len() != 20 {
+        return Err(anyhow!("Recipient address '{}' must be 20 bytes, got {}", addr, bytes.len()));
+    }
+    let mut arr = [0u8; 20];
+    arr.copy_from_slice(&bytes);
+    Ok(arr)
+}
+
+/// Parse a list of addresses separated by commas or whitespace
+pub fn parse_allow_list<'a>(addresses: impl IntoIterator<Item = &'a str>) -> Result<RecipientAllowList> {
+    addresses
+        .into_iter()
+        .flat_map(|entry| entry.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|addr| !addr.is_empty())
+        .map(parse_recipient)
+        .collect()
+}
+
+/// Load the allow-list from UNSHIELD_ALLOWED_RECIPIENTS (None when unset)
+pub fn allow_list_from_env() -> Result<Option<RecipientAllowList>> {
+    match std::env::var("UNSHIELD_ALLOWED_RECIPIENTS") {
+        Ok(list) if !list.trim().is_empty() => Ok(Some(parse_allow_list([list.as_str()])?)),
+        _ => Ok(None),
+    }
+}
+
+/// Reject recipients that are not on the allow-list
+pub fn check_recipient(allowed: Option<&RecipientAllowList>, recipient: &str) -> Result<()> {
+    let Some(allowed) = allowed else {
+        return Ok(());
+    };
+    if allowed.contains(&parse_recipient(recipient)?) {
+        Ok(())
+    } else {
+        Err(anyhow!("Recipient {} is not on the unshield allow-list", recipient.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: &str = "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE";
+    const DENIED: &str = "0x1234567890123456789012345678901234567890";
+
+    #[test]
+    fn test_allowed_recipient() {
+        let list = parse_allow_list([format!("{}, 0xD5307D777dC60b763b74945BF5A42ba93ce44e4b", ALLOWED).as_str()]).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(check_recipient(Some(&list), ALLOWED).is_ok());
+        // Matching is on address bytes, not on the string's case
+        assert!(check_recipient(Some(&list), &ALLOWED.to_lowercase()).is_ok());
+    }
+
+    #[test]
+    fn test_denied_recipient() {
+        let list = parse_allow_list([ALLOWED]).unwrap();
+        let err = check_recipient(Some(&list), DENIED).unwrap_err();
+        assert!(err.to_string().contains("not on the unshield allow-list"));
+    }
+
+    #[test]
+    fn test_no_list_allows_everyone() {
+        assert!(check_recipient(None, DENIED).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_entry_rejected() {
+        assert!(parse_allow_list(["0x1234"]).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! Token amount parsing shared by the local-prove CLI and the prover service
+//!
+//! Both interfaces accept human-readable amounts like "0.1" and scale them by
+//! the token's decimals, so they must go through the same implementation.
+//!
+//! Fee-on-transfer tokens credit the forwarder less than the transferred
+//! amount, so a shielded resource for the full amount would be backed by
+//! fewer tokens than it claims. Shielding such tokens is rejected.
+
+use anyhow::{anyhow, Result};
+use tracing::info;
+
+use crate::tokens::TokenInfo;
+
+/// Largest decimals whose scale factor fits in a u128
+pub const MAX_TOKEN_DECIMALS: u32 = 38;
+
+/// Reject shielding fee-on-transfer tokens, which would over-credit the shielded resource
+pub fn ensure_shieldable(token: &TokenInfo) -> Result<()> {
+    if token.fee_on_transfer {
+        return Err(anyhow!(
+            "{} is a fee-on-transfer token: the forwarder would receive less than the shielded amount. \
+             Shielding fee-on-transfer tokens is not supported",
+            token.symbol
+        ));
+    }
+    Ok(())
+}
+
+/// Reject a permit for a token whose forwarder predates permit support,
+/// where the permit call would revert the whole transaction
+pub fn ensure_permit_supported(token: &TokenInfo) -> Result<()> {
+    if !token.forwarder_permit {
+        return Err(anyhow!(
+            "The {} forwarder does not accept permit. Approve the forwarder instead, or redeploy it \
+             and set forwarder_permit for {} in TOKEN_CONFIG",
+            token.symbol, token.symbol
+        ));
+    }
+    Ok(())
+}
+
+/// Reject zero amounts, which would only produce a no-op forwarder call
+pub fn ensure_nonzero(amount: u128) -> Result<u128> {
+    if amount == 0 {
+        return Err(anyhow!("Amount must be greater than zero"));
+    }
+    Ok(amount)
+}
+
+/// Reject zero and amounts above `token`'s `max_amount` (smallest units)
+pub fn ensure_amount_bounds(amount: u128, token: &TokenInfo) -> Result<u128> {
+    let amount = ensure_nonzero(amount)?;
+    match token.max_amount {
+        Some(max) if amount > max => Err(anyhow!(
+            "Amount {} exceeds the {} maximum of {} smallest units per request",
+            amount, token.symbol, max
+        )),
+        _ => Ok(amount),
+    }
+}
+
+/// Parse a quantity that is already in smallest units, e.g. a swap's minimum output
+pub fn parse_quantity(quantity: &str) -> Result<u128> {
+    quantity.trim().parse()
+        .map_err(|e| anyhow!("Invalid quantity '{}' (expected smallest units): {}", quantity, e))
+}
+
+/// Convert human-readable token amount to smallest units of `token`
+///
+/// Integers above 1e9 are taken to be smallest units already. Anything else is
+/// a token amount, scaled with integer arithmetic so no precision is lost.
+///
+/// Zero, negative and empty amounts are rejected, as are amounts with more
+/// fractional digits than the token has (e.g. "0.0000001" USDC), ones that
+/// overflow a u128 and ones above the token's `max_amount`.
+pub fn parse_token_amount(amount: &str, token: &TokenInfo) -> Result<u128> {
+    let token_info = token;
+    let decimals = token.decimals;
+    let token = token.symbol.as_str();
+    if decimals > MAX_TOKEN_DECIMALS {
+        return Err(anyhow!("{} has {} decimals; at most {} are supported", token, decimals, MAX_TOKEN_DECIMALS));
+    }
+
+    let amount_str = amount.trim();
+    if amount_str.is_empty() {
+        return Err(anyhow!("Amount must not be empty"));
+    }
+    if amount_str.starts_with('-') {
+        return Err(anyhow!("Amount must not be negative: '{}'", amount));
+    }
+
+    // If it's a large number, assume it's already in smallest units
+    if let Ok(val) = amount_str.parse::<u128>() {
+        if val > 1_000_000_000 {
+            return ensure_amount_bounds(val, token_info);
+        }
+    }
+
+    // Handle amounts like ".1" and "1." as well as "1.1"
+    let (whole, fraction) = amount_str.split_once('.').unwrap_or((amount_str, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(anyhow!("Invalid amount '{}': expected a decimal number", amount));
+    }
+
+    // Trailing zeros carry no value, so "1.500000000" is fine for USDC
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(anyhow!(
+            "Amount '{}' has more fractional digits than {} supports ({} decimals)",
+            amount, token, decimals
+        ));
+    }
+
+    let overflow = || anyhow!("Amount '{}' overflows for {} ({} decimals)", amount, token, decimals);
+    let whole_units: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| overflow())? };
+    // Right-pad the fraction to exactly `decimals` digits
+    let fraction_units: u128 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{:0<width$}", fraction, width = decimals as usize).parse().map_err(|_| overflow())?
+    };
+
+    let smallest_units = whole_units
+        .checked_mul(10u128.pow(decimals))
+        .and_then(|scaled| scaled.checked_add(fraction_units))
+        .ok_or_else(overflow)?;
+
+    let smallest_units = ensure_amount_bounds(smallest_units, token_info)?;
+
+    info!("Parsed amount '{}' for {} -> {} smallest units ({} decimals)",
+          amount, token, smallest_units, decimals);
+
+    Ok(smallest_units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn token(symbol: &str) -> TokenInfo {
+        Config::default().tokens.get(symbol).unwrap().clone()
+    }
+
+    fn token_with_decimals(decimals: u32) -> TokenInfo {
+        TokenInfo::new("BIG", "0x1111111111111111111111111111111111111111", "0x2222222222222222222222222222222222222222", decimals)
+    }
+
+    #[test]
+    fn test_parse_human_usdc() {
+        assert_eq!(parse_token_amount("1.5", &token("USDC")).unwrap(), 1_500_000);
+        assert_eq!(parse_token_amount(".25", &token("USDC")).unwrap(), 250_000);
+    }
+
+    #[test]
+    fn test_parse_large_value_is_smallest_units() {
+        assert_eq!(parse_token_amount("2000000000", &token("USDC")).unwrap(), 2_000_000_000);
+    }
+
+    #[test]
+    fn test_parse_invalid_amount() {
+        for amount in ["abc", ".", "1.2.3", "1e6", "+1.5", "1,5"] {
+            assert!(parse_token_amount(amount, &token("USDC")).is_err(), "{}", amount);
+        }
+    }
+
+    #[test]
+    fn test_parse_exact_weth() {
+        // 0.1 has no exact f64 representation
+        assert_eq!(parse_token_amount("0.1", &token("WETH")).unwrap(), 100_000_000_000_000_000);
+        assert_eq!(parse_token_amount("123456789.123456789123456789", &token("WETH")).unwrap(),
+                   123_456_789_123_456_789_123_456_789);
+    }
+
+    #[test]
+    fn test_parse_whole_amount() {
+        // Integers up to 1e9 are whole tokens; only larger ones are smallest units
+        assert_eq!(parse_token_amount("1000000", &token("USDC")).unwrap(), 1_000_000_000_000);
+        assert_eq!(parse_token_amount("1000000", &token("WETH")).unwrap(), 10u128.pow(24));
+    }
+
+    #[test]
+    fn test_wei_amounts_are_not_truncated() {
+        // Above u64::MAX once scaled or as raw wei; these used to become 0 in the sync endpoints
+        assert_eq!(parse_token_amount("2000000000000000000", &token("WETH")).unwrap(), 2_000_000_000_000_000_000);
+        assert_eq!(parse_quantity("2000000000000000000").unwrap(), 2_000_000_000_000_000_000);
+        assert_eq!(parse_quantity("20000000000000000000").unwrap(), 20_000_000_000_000_000_000);
+        assert!(parse_quantity("1.5").is_err());
+        assert!(parse_quantity("").is_err());
+    }
+
+    #[test]
+    fn test_parse_usdc_fraction() {
+        assert_eq!(parse_token_amount("123456.789012", &token("USDC")).unwrap(), 123_456_789_012);
+        assert!(parse_token_amount("123456.7890123", &token("USDC")).is_err());
+    }
+
+    #[test]
+    fn test_reject_zero_amounts() {
+        for amount in ["0", "0.0", ".0", "-0"] {
+            let err = parse_token_amount(amount, &token("USDC")).unwrap_err();
+            assert!(err.to_string().contains("greater than zero") || err.to_string().contains("negative"));
+        }
+        assert!(ensure_nonzero(0).is_err());
+        assert_eq!(ensure_nonzero(1).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_reject_negative_and_empty_amounts() {
+        assert!(parse_token_amount("-1", &token("USDC")).unwrap_err().to_string().contains("negative"));
+        assert!(parse_token_amount("", &token("USDC")).unwrap_err().to_string().contains("empty"));
+        assert!(parse_token_amount("   ", &token("WETH")).unwrap_err().to_string().contains("empty"));
+    }
+
+    #[test]
+    fn test_reject_amount_lost_to_precision() {
+        let err = parse_token_amount("0.0000001", &token("USDC")).unwrap_err();
+        assert!(err.to_string().contains("more fractional digits"));
+        assert_eq!(parse_token_amount("1.500000000", &token("USDC")).unwrap(), 1_500_000);
+        // The same value is representable with 18 decimals
+        assert_eq!(parse_token_amount("0.0000001", &token("WETH")).unwrap(), 100_000_000_000);
+    }
+
+    #[test]
+    fn test_parse_24_decimal_token() {
+        let big = token_with_decimals(24);
+        assert_eq!(parse_token_amount("2", &big).unwrap(), 2 * 10u128.pow(24));
+        assert_eq!(parse_token_amount("0.5", &big).unwrap(), 5 * 10u128.pow(23));
+        // 10^39 smallest units do not fit in a u128
+        assert!(parse_token_amount("1000000", &token_with_decimals(33)).unwrap_err()
+            .to_string().contains("overflows"));
+        assert!(parse_token_amount("1", &token_with_decimals(39)).is_err());
+    }
+
+    #[test]
+    fn test_amount_bounds() {
+        let max = u128::MAX.to_string();
+        // Fits in a u128, so it is taken as smallest units; one more overflows
+        assert_eq!(parse_token_amount(&max, &token("WETH")).unwrap(), u128::MAX);
+        assert!(parse_token_amount("340282366920938463463374607431768211456", &token("WETH")).unwrap_err()
+            .to_string().contains("overflows"));
+
+        let capped = TokenInfo { max_amount: Some(1_000_000_000_000), ..token("USDC") };
+        assert_eq!(parse_token_amount("1000000", &capped).unwrap(), 1_000_000_000_000);
+        for amount in ["1000000.000001", "1000000000001", max.as_str()] {
+            let err = parse_token_amount(amount, &capped).unwrap_err();
+            assert!(err.to_string().contains("exceeds the USDC maximum"), "{}: {}", amount, err);
+        }
+        for amount in ["0", "-0", "-5", " -1.5 ", "--1"] {
+            assert!(parse_token_amount(amount, &capped).is_err(), "{}", amount);
+        }
+        assert!(ensure_amount_bounds(0, &capped).is_err());
+        assert!(ensure_amount_bounds(1_000_000_000_000, &capped).is_ok());
+    }
+
+    #[test]
+    fn test_reject_fee_on_transfer_shield() {
+        let fot = TokenInfo { fee_on_transfer: true, ..token_with_decimals(18) };
+        let err = ensure_shieldable(&fot).unwrap_err();
+        assert!(err.to_string().contains("fee-on-transfer"));
+        assert!(ensure_shieldable(&token("USDC")).is_ok());
+    }
+
+    #[test]
+    fn test_permit_needs_forwarder_support() {
+        let err = ensure_permit_supported(&token("USDC")).unwrap_err();
+        assert!(err.to_string().contains("does not accept permit"));
+        assert!(ensure_permit_supported(&TokenInfo { forwarder_permit: true, ..token("USDC") }).is_ok());
+    }
+}
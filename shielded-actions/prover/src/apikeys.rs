@@ -0,0 +1,213 @@
+//! Runtime-managed API keys
+//!
+//! Keys come from `API_KEYS` (comma-separated) at startup and can be added or
+//! revoked through the admin endpoints without a restart. Only SHA-256 hashes
+//! are kept; the raw key is returned once, when it is created.
+//!
+//! Set `API_KEYS_FILE` to persist keys created at runtime across restarts.
+//! Keys from `API_KEYS` are never written to the file, so revoking one only
+//! lasts until the next restart.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Compare two byte strings without short-circuiting on the first mismatch
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Key metadata, safe to return from the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub label: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiKeyRecord {
+    #[serde(flatten)]
+    info: ApiKeyInfo,
+    /// Hex SHA-256 of the raw key
+    hash: String,
+    /// Loaded from API_KEYS rather than created at runtime
+    #[serde(skip)]
+    from_env: bool,
+}
+
+/// Set of valid API keys
+#[derive(Debug, Default)]
+pub struct ApiKeyStore {
+    keys: RwLock<Vec<ApiKeyRecord>>,
+    path: Option<PathBuf>,
+}
+
+impl ApiKeyStore {
+    /// Load keys from API_KEYS and, when set, API_KEYS_FILE
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("API_KEYS_FILE").ok().filter(|v| !v.is_empty()).map(PathBuf::from);
+        let env_keys = std::env::var("API_KEYS").unwrap_or_default();
+        Self::load(&env_keys, path)
+    }
+
+    fn load(env_keys: &str, path: Option<PathBuf>) -> Result<Self> {
+        let created_at = now_secs();
+        let mut keys: Vec<ApiKeyRecord> = env_keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .enumerate()
+            .map(|(i, key)| ApiKeyRecord {
+                info: ApiKeyInfo {
+                    id: format!("env-{}", i),
+                    label: "API_KEYS".to_string(),
+                    created_at,
+                },
+                hash: hash_key(key),
+                from_env: true,
+            })
+            .collect();
+
+        if let Some(path) = &path {
+            if path.exists() {
+                let persisted: Vec<ApiKeyRecord> = serde_json::from_slice(&std::fs::read(path)?)
+                    .map_err(|e| anyhow!("Invalid API key file {}: {}", path.display(), e))?;
+                keys.extend(persisted);
+            }
+        }
+
+        Ok(Self { keys: RwLock::new(keys), path })
+    }
+
+    /// Whether any key is configured
+    pub fn is_empty(&self) -> bool {
+        self.keys.read().map(|keys| keys.is_empty()).unwrap_or(true)
+    }
+
+    /// Whether `key` matches one of the stored hashes
+    pub fn verify(&self, key: &str) -> bool {
+        self.identify(key).is_some()
+    }
+
+    /// Id of the stored key `key` matches, if any
+    pub fn identify(&self, key: &str) -> Option<String> {
+        let hash = hash_key(key);
+        let keys = self.keys.read().ok()?;
+        // Check every record so timing does not reveal which one matched
+        keys.iter().fold(None, |found, record| {
+            if constant_time_eq(record.hash.as_bytes(), hash.as_bytes()) {
+                Some(record.info.id.clone())
+            } else {
+                found
+            }
+        })
+    }
+
+    /// Metadata of every key
+    pub fn list(&self) -> Vec<ApiKeyInfo> {
+        self.keys
+            .read()
+            .map(|keys| keys.iter().map(|record| record.info.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Create a key, returning its metadata and the raw key (only available now)
+    pub fn create(&self, label: &str) -> Result<(ApiKeyInfo, String)> {
+        let key = format!("sk_{}", hex::encode(rand::random::<[u8; 32]>()));
+        let info = ApiKeyInfo {
+            id: hex::encode(rand::random::<[u8; 8]>()),
+            label: label.to_string(),
+            created_at: now_secs(),
+        };
+
+        let mut keys = self.keys.write().map_err(|_| anyhow!("API key store lock poisoned"))?;
+        keys.push(ApiKeyRecord {
+            info: info.clone(),
+            hash: hash_key(&key),
+            from_env: false,
+        });
+        self.persist(&keys)?;
+
+        Ok((info, key))
+    }
+
+    /// Revoke a key by id, returning whether it existed
+    pub fn revoke(&self, id: &str) -> Result<bool> {
+        let mut keys = self.keys.write().map_err(|_| anyhow!("API key store lock poisoned"))?;
+        let before = keys.len();
+        keys.retain(|record| record.info.id != id);
+        if keys.len() == before {
+            return Ok(false);
+        }
+        self.persist(&keys)?;
+        Ok(true)
+    }
+
+    fn persist(&self, keys: &[ApiKeyRecord]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let runtime_keys: Vec<&ApiKeyRecord> = keys.iter().filter(|record| !record.from_env).collect();
+        std::fs::write(path, serde_json::to_vec_pretty(&runtime_keys)?)
+            .map_err(|e| anyhow!("Failed to write API key file {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_verify_revoke() {
+        let store = ApiKeyStore::load("", None).unwrap();
+        assert!(store.is_empty());
+
+        let (info, key) = store.create("backend").unwrap();
+        assert!(store.verify(&key));
+        assert!(!store.verify("sk_wrong"));
+        assert_eq!(store.identify(&key), Some(info.id.clone()));
+        assert_eq!(store.identify("sk_wrong"), None);
+        assert_eq!(store.list().len(), 1);
+        assert_eq!(store.list()[0].label, "backend");
+
+        assert!(store.revoke(&info.id).unwrap());
+        assert!(!store.revoke(&info.id).unwrap());
+        assert!(!store.verify(&key));
+    }
+
+    #[test]
+    fn test_env_keys_are_hashed() {
+        let store = ApiKeyStore::load("alpha, beta", None).unwrap();
+        assert!(store.verify("alpha"));
+        assert!(store.verify("beta"));
+        let records = store.keys.read().unwrap();
+        assert!(records.iter().all(|record| record.hash != "alpha" && record.hash.len() == 64));
+    }
+
+    #[test]
+    fn test_persists_runtime_keys_only() {
+        let path = std::env::temp_dir().join(format!("api-keys-{}.json", hex::encode(rand::random::<[u8; 4]>())));
+        let store = ApiKeyStore::load("from-env", Some(path.clone())).unwrap();
+        let (_, key) = store.create("runtime").unwrap();
+
+        let reloaded = ApiKeyStore::load("", Some(path.clone())).unwrap();
+        assert!(reloaded.verify(&key));
+        assert!(!reloaded.verify("from-env"));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&key));
+        std::fs::remove_file(path).ok();
+    }
+}
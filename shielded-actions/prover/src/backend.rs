@@ -0,0 +1,368 @@
+//! Proving backends
+//!
+//! [`ProverService`] picks one backend when it is created and hands every
+//! proof to it:
+//!
+//! - [`LocalArmBackend`] (`USE_REAL_ARM=1`): real ARM transactions with forwarder
+//!   calls, proved locally (Groth16 needs Docker)
+//! - [`BonsaiBackend`] (`BONSAI_API_KEY`): the request journal, proved on Bonsai
+//! - [`MockBackend`] (otherwise): fake receipts over the request journal, for
+//!   development and tests
+//!
+//! Caching, proof ids and request checks common to all backends stay in
+//! [`ProverService`].
+
+use async_trait::async_trait;
+
+use crate::amount::{ensure_nonzero, parse_quantity, parse_token_amount};
+use crate::error::ProverError;
+use crate::job::JobContext;
+use crate::proofs::{BatchEntry, RawCall, SwapOrder, DEFAULT_SWAP_FEE};
+use crate::prover::{resource_token, ProofResponse, ProverService};
+use forwarder_logic_witness::{DeletionCriterion, PermitData};
+
+/// Parameters of a shield proof
+pub struct ShieldRequest<'a> {
+    pub proof_id: String,
+    pub token: &'a str,
+    pub amount: &'a str,
+    pub sender: &'a str,
+    pub nullifier_key: &'a str,
+    pub deletion_criterion: DeletionCriterion,
+    pub permit: Option<PermitData>,
+}
+
+/// Parameters of a swap proof
+pub struct SwapRequest<'a> {
+    pub proof_id: String,
+    pub input_resource: &'a serde_json::Value,
+    pub output_token: &'a str,
+    pub nullifier_key: &'a str,
+    pub min_amount_out: &'a str,
+}
+
+/// Parameters of an unshield proof
+pub struct UnshieldRequest<'a> {
+    pub proof_id: String,
+    pub resource: &'a serde_json::Value,
+    pub recipient: &'a str,
+    pub nullifier_key: &'a str,
+    pub deletion_criterion: DeletionCriterion,
+}
+
+/// Parameters of a raw forwarder call proof
+pub struct RawRequest<'a> {
+    pub proof_id: String,
+    pub raw: &'a RawCall,
+    pub deletion_criterion: DeletionCriterion,
+}
+
+/// Parameters of a batch proof: one action per entry
+pub struct BatchRequest<'a> {
+    pub proof_id: String,
+    pub entries: &'a [BatchEntry],
+}
+
+/// Generates the proof for each kind of request
+///
+/// `prover` gives access to the service's configuration and proof sessions.
+#[async_trait]
+pub trait ProofBackend: Send + Sync {
+    /// Short name for logs: "mock", "bonsai" or "local-arm"
+    fn name(&self) -> &'static str;
+
+    /// Whether proofs are generated locally with ARM (and so need Docker)
+    fn uses_real_arm(&self) -> bool {
+        false
+    }
+
+    async fn prove_shield(
+        &self,
+        prover: &ProverService,
+        req: ShieldRequest<'_>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError>;
+
+    async fn prove_swap(
+        &self,
+        prover: &ProverService,
+        req: SwapRequest<'_>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError>;
+
+    async fn prove_unshield(
+        &self,
+        prover: &ProverService,
+        req: UnshieldRequest<'_>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError>;
+
+    async fn prove_raw(
+        &self,
+        prover: &ProverService,
+        req: RawRequest<'_>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError>;
+
+    async fn prove_batch(
+        &self,
+        prover: &ProverService,
+        req: BatchRequest<'_>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError>;
+}
+
+// Journals proved by the mock and Bonsai backends
+
+fn shield_journal(prover: &ProverService, req: &ShieldRequest<'_>) -> Result<serde_json::Value, ProverError> {
+    Ok(serde_json::json!({
+        "action": "shield",
+        "token": req.token,
+        "amount": req.amount,
+        "sender": req.sender,
+        "deletion_criterion": req.deletion_criterion,
+        "permit": req.permit.is_some(),
+        "nullifier_key_commitment": prover.hash_nullifier_key(req.nullifier_key)?,
+    }))
+}
+
+fn swap_journal(prover: &ProverService, req: &SwapRequest<'_>) -> Result<serde_json::Value, ProverError> {
+    Ok(serde_json::json!({
+        "action": "swap",
+        "input_resource": req.input_resource,
+        "output_token": req.output_token,
+        "min_amount_out": req.min_amount_out,
+        "nullifier_key_commitment": prover.hash_nullifier_key(req.nullifier_key)?,
+    }))
+}
+
+fn unshield_journal(prover: &ProverService, req: &UnshieldRequest<'_>) -> Result<serde_json::Value, ProverError> {
+    Ok(serde_json::json!({
+        "action": "unshield",
+        "resource": req.resource,
+        "recipient": req.recipient,
+        "deletion_criterion": req.deletion_criterion,
+        "nullifier_key_commitment": prover.hash_nullifier_key(req.nullifier_key)?,
+    }))
+}
+
+fn raw_journal(req: &RawRequest<'_>) -> serde_json::Value {
+    serde_json::json!({
+        "action": "raw",
+        "forwarder": format!("0x{}", hex::encode(req.raw.forwarder)),
+        "call_data": format!("0x{}", hex::encode(&req.raw.call_data)),
+        "expected_output": format!("0x{}", hex::encode(&req.raw.expected_output)),
+    })
+}
+
+fn batch_journal(req: &BatchRequest<'_>) -> serde_json::Value {
+    serde_json::json!({
+        "action": "batch",
+        "entries": req.entries.iter().map(BatchEntry::describe).collect::<Vec<_>>(),
+    })
+}
+
+/// Fake receipts over the request journal; needs neither Docker nor Bonsai
+pub struct MockBackend;
+
+#[async_trait]
+impl ProofBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn prove_shield(&self, prover: &ProverService, req: ShieldRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = shield_journal(prover, &req)?;
+        prover.create_mock_proof(req.proof_id, "shield", journal)
+    }
+
+    async fn prove_swap(&self, prover: &ProverService, req: SwapRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = swap_journal(prover, &req)?;
+        prover.create_mock_proof(req.proof_id, "swap", journal)
+    }
+
+    async fn prove_unshield(&self, prover: &ProverService, req: UnshieldRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = unshield_journal(prover, &req)?;
+        prover.create_mock_proof(req.proof_id, "unshield", journal)
+    }
+
+    async fn prove_raw(&self, prover: &ProverService, req: RawRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = raw_journal(&req);
+        prover.create_mock_proof(req.proof_id, "raw", journal)
+    }
+
+    async fn prove_batch(&self, prover: &ProverService, req: BatchRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = batch_journal(&req);
+        prover.create_mock_proof(req.proof_id, "batch", journal)
+    }
+}
+
+/// The request journal proved on Bonsai and wrapped in a Groth16 SNARK
+pub struct BonsaiBackend;
+
+#[async_trait]
+impl ProofBackend for BonsaiBackend {
+    fn name(&self) -> &'static str {
+        "bonsai"
+    }
+
+    async fn prove_shield(&self, prover: &ProverService, req: ShieldRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = shield_journal(prover, &req)?;
+        prover.submit_bonsai_proof(req.proof_id, journal).await
+    }
+
+    async fn prove_swap(&self, prover: &ProverService, req: SwapRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = swap_journal(prover, &req)?;
+        prover.submit_bonsai_proof(req.proof_id, journal).await
+    }
+
+    async fn prove_unshield(&self, prover: &ProverService, req: UnshieldRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = unshield_journal(prover, &req)?;
+        prover.submit_bonsai_proof(req.proof_id, journal).await
+    }
+
+    async fn prove_raw(&self, prover: &ProverService, req: RawRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = raw_journal(&req);
+        prover.submit_bonsai_proof(req.proof_id, journal).await
+    }
+
+    async fn prove_batch(&self, prover: &ProverService, req: BatchRequest<'_>, _job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let journal = batch_journal(&req);
+        prover.submit_bonsai_proof(req.proof_id, journal).await
+    }
+}
+
+/// Real ARM transactions with forwarder calls, proved locally
+pub struct LocalArmBackend;
+
+#[async_trait]
+impl ProofBackend for LocalArmBackend {
+    fn name(&self) -> &'static str {
+        "local-arm"
+    }
+
+    fn uses_real_arm(&self) -> bool {
+        true
+    }
+
+    async fn prove_shield(&self, prover: &ProverService, req: ShieldRequest<'_>, job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        let token_info = prover.config().tokens.get(req.token).map_err(ProverError::unknown_token)?;
+        // Parse amount, handling both decimal strings like "0.1" and raw u128 values
+        let amount = parse_token_amount(req.amount, token_info).map_err(ProverError::invalid_amount)?;
+        prover
+            .create_shield_proof_with_forwarder(req.proof_id, req.token, amount, req.sender, req.deletion_criterion, req.permit, job)
+            .await
+    }
+
+    async fn prove_swap(&self, prover: &ProverService, req: SwapRequest<'_>, job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        // Extract the sold token and amount from the input resource
+        let token_in = req.input_resource.get("token")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ProverError::invalid_request("Swap input resource is missing 'token'"))?;
+        let amount_in: u128 = req.input_resource.get("amount")
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| ProverError::invalid_amount("Swap input resource is missing a valid 'amount'"))?;
+        let order = SwapOrder {
+            token_in: token_in.to_string(),
+            amount_in: ensure_nonzero(amount_in).map_err(ProverError::invalid_amount)?,
+            token_out: req.output_token.to_string(),
+            min_amount_out: parse_quantity(req.min_amount_out)
+                .and_then(ensure_nonzero)
+                .map_err(ProverError::invalid_amount)?,
+            fee: DEFAULT_SWAP_FEE,
+        };
+        prover.create_swap_proof_with_forwarder(req.proof_id, &order, DeletionCriterion::default(), job).await
+    }
+
+    async fn prove_unshield(&self, prover: &ProverService, req: UnshieldRequest<'_>, job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        // Extract token and amount from resource
+        let token = resource_token(req.resource).map_err(ProverError::invalid_request)?;
+        prover.config().tokens.get(&token).map_err(ProverError::unknown_token)?;
+        let amount: u128 = req.resource.get("amount")
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| ProverError::invalid_amount("Unshield resource is missing a valid 'amount'"))?;
+        let amount = ensure_nonzero(amount).map_err(ProverError::invalid_amount)?;
+        prover
+            .create_unshield_proof_with_forwarder(req.proof_id, &token, amount, req.recipient, req.deletion_criterion, job)
+            .await
+    }
+
+    async fn prove_raw(&self, prover: &ProverService, req: RawRequest<'_>, job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        prover.create_raw_proof_with_forwarder(req.proof_id, req.raw, req.deletion_criterion, job).await
+    }
+
+    async fn prove_batch(&self, prover: &ProverService, req: BatchRequest<'_>, job: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+        prover.create_batch_proof_with_forwarder(req.proof_id, req.entries, job).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allowlist::parse_allow_list;
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_service_dispatches_to_mock_backend() {
+        let prover = ProverService::new(Config::default()).unwrap().with_backend(MockBackend);
+        assert!(!prover.uses_real_arm());
+
+        let nullifier_key = hex::encode([7u8; 32]);
+        let sender = "0x000000000000000000000000000000000000dEaD";
+        let response = prover
+            .create_shield_proof("USDC", "1.5", sender, &nullifier_key, DeletionCriterion::default(), None, true, None)
+            .await
+            .unwrap();
+        assert_eq!(response.status, "completed");
+        let proof = response.proof.unwrap();
+        assert!(proof.fake);
+
+        let journal: serde_json::Value = serde_json::from_slice(&hex::decode(proof.journal).unwrap()).unwrap();
+        assert_eq!(journal["action"], "shield");
+        assert_eq!(journal["amount"], "1.5");
+        assert!(!journal.to_string().contains(&nullifier_key));
+
+        // The fresh proof feeds the estimate; a cache hit does not
+        let cached = prover
+            .create_shield_proof("USDC", "1.5", sender, &nullifier_key, DeletionCriterion::default(), None, false, None)
+            .await
+            .unwrap();
+        let estimate = prover.estimate("shield", None).unwrap();
+        assert_eq!((estimate.backend.as_str(), estimate.samples, estimate.source), ("mock", 1, "history"));
+        assert!(prover.estimate("shield", Some("gpu")).is_err());
+
+        // Identical requests share a proof id, which /api/proof/{id} serves; other inputs get another
+        assert_eq!(cached.proof_id, response.proof_id);
+        assert_eq!(prover.get_proof_status(&response.proof_id).await.unwrap().status, "completed");
+        let other = prover
+            .create_shield_proof("USDC", "2", sender, &nullifier_key, DeletionCriterion::default(), None, true, None)
+            .await
+            .unwrap();
+        assert_ne!(other.proof_id, response.proof_id);
+        assert!(prover.get_proof_status("0123456789abcdef").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_allow_list_applies_to_every_backend() {
+        let prover = ProverService::new(Config::default()).unwrap().with_backend(MockBackend);
+        let allowed = "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE";
+        let denied = "0x1234567890123456789012345678901234567890";
+        prover.set_unshield_allow_list(Some(parse_allow_list([allowed]).unwrap())).unwrap();
+
+        let resource = serde_json::json!({"token": "USDC", "amount": "1000000"});
+        let nullifier_key = hex::encode([7u8; 32]);
+        let err = prover
+            .create_unshield_proof(&resource, denied, &nullifier_key, DeletionCriterion::default(), true, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProverError::InvalidRequest(_)), "{:?}", err);
+        assert!(err.to_string().contains("allow-list"));
+        let response = prover
+            .create_unshield_proof(&resource, allowed, &nullifier_key, DeletionCriterion::default(), true, None)
+            .await
+            .unwrap();
+        assert_eq!(response.status, "completed");
+    }
+}
@@ -0,0 +1,127 @@
+//! Dry delta-balance check for a batch of resource operations
+//!
+//! A transaction only balances when, for every token label, the consumed and
+//! created quantities match. This reports the per-label net quantity without
+//! proving anything, so a batch can be fixed (e.g. with a balancing ephemeral
+//! resource) before spending minutes on proofs.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Whether an operation consumes or creates a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Consumed,
+    Created,
+}
+
+/// One resource in the batch
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BalanceOperation {
+    /// Token label, e.g. "USDC"
+    pub label: String,
+    /// Quantity in smallest units (decimal string)
+    pub quantity: String,
+    pub side: Side,
+}
+
+/// Net quantity of one label
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LabelDelta {
+    pub label: String,
+    pub consumed: String,
+    pub created: String,
+    /// consumed minus created
+    pub net: String,
+    pub balanced: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BalanceReport {
+    pub labels: Vec<LabelDelta>,
+    /// True when every label nets to zero
+    pub balanced: bool,
+}
+
+/// Sum consumed and created quantities per label
+pub fn balance_report(operations: &[BalanceOperation]) -> Result<BalanceReport> {
+    let mut totals: BTreeMap<String, (u128, u128)> = BTreeMap::new();
+
+    for op in operations {
+        let quantity: u128 = op.quantity.trim().parse()
+            .map_err(|e| anyhow!("Invalid quantity '{}' for {}: {}", op.quantity, op.label, e))?;
+        let (consumed, created) = totals.entry(op.label.to_uppercase()).or_default();
+        let total = match op.side {
+            Side::Consumed => consumed,
+            Side::Created => created,
+        };
+        *total = total.checked_add(quantity)
+            .ok_or_else(|| anyhow!("Total quantity for {} overflows", op.label))?;
+    }
+
+    let labels: Vec<LabelDelta> = totals
+        .into_iter()
+        .map(|(label, (consumed, created))| {
+            let net = if consumed >= created {
+                (consumed - created).to_string()
+            } else {
+                format!("-{}", created - consumed)
+            };
+            LabelDelta {
+                label,
+                consumed: consumed.to_string(),
+                created: created.to_string(),
+                net,
+                balanced: consumed == created,
+            }
+        })
+        .collect();
+
+    let balanced = labels.iter().all(|delta| delta.balanced);
+    Ok(BalanceReport { labels, balanced })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(label: &str, quantity: &str, side: Side) -> BalanceOperation {
+        BalanceOperation {
+            label: label.to_string(),
+            quantity: quantity.to_string(),
+            side,
+        }
+    }
+
+    #[test]
+    fn test_balanced_batch() {
+        let report = balance_report(&[
+            op("USDC", "1000000", Side::Consumed),
+            op("usdc", "400000", Side::Created),
+            op("USDC", "600000", Side::Created),
+        ]).unwrap();
+        assert!(report.balanced);
+        assert_eq!(report.labels.len(), 1);
+        assert_eq!(report.labels[0].net, "0");
+    }
+
+    #[test]
+    fn test_unbalanced_batch() {
+        let report = balance_report(&[
+            op("USDC", "5", Side::Consumed),
+            op("WETH", "7", Side::Created),
+        ]).unwrap();
+        assert!(!report.balanced);
+        assert_eq!(report.labels[0].label, "USDC");
+        assert_eq!(report.labels[0].net, "5");
+        assert_eq!(report.labels[1].net, "-7");
+    }
+
+    #[test]
+    fn test_invalid_quantity() {
+        assert!(balance_report(&[op("USDC", "1.5", Side::Consumed)]).is_err());
+    }
+}
@@ -0,0 +1,673 @@
+//! Helpers for inspecting generated calldata and querying the Protocol Adapter on-chain
+
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::{Filter, TransactionRequest};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::{decode_revert_reason, SolEvent, SolValue};
+use anyhow::{anyhow, Result};
+use arm::action_tree::MerkleTree;
+use crate::config::Config;
+use evm_protocol_adapter_bindings::contract::ProtocolAdapter;
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+/// Function selector for execute(Transaction) - ed3cf91f
+pub const EXECUTE_SELECTOR: [u8; 4] = [0xed, 0x3c, 0xf9, 0x1f];
+
+alloy::sol! {
+    #[sol(rpc)]
+    interface INullifierSet {
+        function isNullifierContained(bytes32 nullifier) external view returns (bool isContained);
+    }
+
+    #[sol(rpc)]
+    interface ICommitmentTree {
+        function commitmentCount() external view returns (uint256 count);
+        function latestCommitmentTreeRoot() external view returns (bytes32 root);
+        function isCommitmentTreeRootContained(bytes32 root) external view returns (bool isContained);
+    }
+}
+
+/// Decode hex `execute(Transaction)` calldata back into the EVM transaction
+pub fn decode_execute_calldata(calldata: &str) -> Result<ProtocolAdapter::Transaction> {
+    let bytes = hex::decode(calldata.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Invalid calldata hex: {}", e))?;
+    decode_execute_bytes(&bytes)
+}
+
+/// Decode raw `execute(Transaction)` calldata, e.g. a `.bin` file written by local-prove
+pub fn decode_execute_bytes(bytes: &[u8]) -> Result<ProtocolAdapter::Transaction> {
+    if bytes.len() < 4 || bytes[..4] != EXECUTE_SELECTOR {
+        return Err(anyhow!("Calldata is not an execute(Transaction) call"));
+    }
+
+    <ProtocolAdapter::Transaction as SolValue>::abi_decode(&bytes[4..])
+        .map_err(|e| anyhow!("Failed to decode transaction: {}", e))
+}
+
+/// Nullifiers of every consumed resource in the transaction
+pub fn extract_nullifiers(tx: &ProtocolAdapter::Transaction) -> Vec<B256> {
+    tx.actions
+        .iter()
+        .flat_map(|action| action.complianceVerifierInputs.iter())
+        .map(|unit| unit.instance.consumed.nullifier)
+        .collect()
+}
+
+/// Commitments of every created resource, in the order `execute` appends them to the commitment tree
+pub fn created_commitments(tx: &ProtocolAdapter::Transaction) -> Vec<B256> {
+    tx.actions
+        .iter()
+        .flat_map(|action| action.complianceVerifierInputs.iter())
+        .map(|unit| unit.instance.created.commitment)
+        .collect()
+}
+
+/// Check that every compliance unit's nullifier and commitment has a logic proof in its action
+///
+/// This is what can be checked without the verifiers; the proofs themselves are
+/// verified by [`simulate_execute`].
+pub fn check_resource_tags(tx: &ProtocolAdapter::Transaction) -> Result<()> {
+    for (i, action) in tx.actions.iter().enumerate() {
+        let tags: HashSet<B256> = action.logicVerifierInputs.iter().map(|input| input.tag).collect();
+        let units = &action.complianceVerifierInputs;
+        if action.logicVerifierInputs.len() != 2 * units.len() {
+            return Err(anyhow!(
+                "Action {}: {} logic proofs for {} compliance units (expected two per unit)",
+                i, action.logicVerifierInputs.len(), units.len()
+            ));
+        }
+        for unit in units {
+            for (kind, tag) in [("nullifier", unit.instance.consumed.nullifier), ("commitment", unit.instance.created.commitment)] {
+                if !tags.contains(&tag) {
+                    return Err(anyhow!("Action {}: {} {} has no logic proof", i, kind, tag));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Human-readable view of an execute(Transaction) call
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TransactionSummary {
+    pub num_actions: usize,
+    pub actions: Vec<ActionSummary>,
+    /// Proof that the unit deltas of all actions sum to zero
+    pub delta_proof: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ActionSummary {
+    pub compliance_units: Vec<ComplianceUnitSummary>,
+    pub resources: Vec<ResourceSummary>,
+}
+
+/// The consumed/created resource pair checked by one compliance unit
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComplianceUnitSummary {
+    pub nullifier: String,
+    pub consumed_logic_ref: String,
+    pub commitment_tree_root: String,
+    pub commitment: String,
+    pub created_logic_ref: String,
+    pub unit_delta: DeltaPoint,
+}
+
+/// A resource's logic verifier input
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResourceSummary {
+    /// Nullifier for consumed resources, commitment for created ones
+    pub tag: String,
+    pub is_consumed: bool,
+    pub logic_ref: String,
+    pub external_payloads: Vec<ExternalPayloadSummary>,
+}
+
+/// An external_payload blob, decoded as a forwarder call where possible
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExternalPayloadSummary {
+    pub blob: String,
+    pub call: Option<ForwarderCall>,
+}
+
+/// Call the Protocol Adapter makes through a forwarder: abi.encode(forwarder, input, expectedOutput)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ForwarderCall {
+    pub forwarder: String,
+    /// Known forwarder name (e.g. "USDC"), if any
+    pub forwarder_name: Option<String>,
+    pub selector: String,
+    /// Function signature for known selectors
+    pub function: Option<&'static str>,
+    /// Named arguments for known selectors (amounts as decimal strings)
+    pub decoded_args: Option<serde_json::Value>,
+    pub input: String,
+    pub expected_output: String,
+}
+
+/// Function signature for a known ERC20 selector
+pub fn selector_name(selector: &[u8]) -> Option<&'static str> {
+    match selector {
+        [0x23, 0xb8, 0x72, 0xdd] => Some("transferFrom(address,address,uint256)"),
+        [0xa9, 0x05, 0x9c, 0xbb] => Some("transfer(address,uint256)"),
+        [0x09, 0x5e, 0xa7, 0xb3] => Some("approve(address,uint256)"),
+        [0x04, 0xe4, 0x5a, 0xaf] => Some("exactInputSingle((address,address,uint24,address,uint256,uint256,uint160))"),
+        _ => None,
+    }
+}
+
+/// Named arguments of a call with a known selector, None for others or malformed arguments
+pub fn decode_call_args(selector: &[u8], args: &[u8]) -> Option<serde_json::Value> {
+    match selector_name(selector)? {
+        "transferFrom(address,address,uint256)" => {
+            let (from, to, amount) = <(Address, Address, U256)>::abi_decode_params(args).ok()?;
+            Some(serde_json::json!({ "from": from.to_string(), "to": to.to_string(), "amount": amount.to_string() }))
+        }
+        "transfer(address,uint256)" => {
+            let (to, amount) = <(Address, U256)>::abi_decode_params(args).ok()?;
+            Some(serde_json::json!({ "to": to.to_string(), "amount": amount.to_string() }))
+        }
+        "approve(address,uint256)" => {
+            let (spender, amount) = <(Address, U256)>::abi_decode_params(args).ok()?;
+            Some(serde_json::json!({ "spender": spender.to_string(), "amount": amount.to_string() }))
+        }
+        _ => {
+            // exactInputSingle: the params struct is static, so it is encoded
+            // inline; the narrower uints decode as uint256
+            let (token_in, token_out, fee, recipient, amount_in, amount_out_minimum, sqrt_price_limit) =
+                <(Address, Address, U256, Address, U256, U256, U256)>::abi_decode_params(args).ok()?;
+            Some(serde_json::json!({
+                "tokenIn": token_in.to_string(),
+                "tokenOut": token_out.to_string(),
+                "fee": fee.to_string(),
+                "recipient": recipient.to_string(),
+                "amountIn": amount_in.to_string(),
+                "amountOutMinimum": amount_out_minimum.to_string(),
+                "sqrtPriceLimitX96": sqrt_price_limit.to_string(),
+            }))
+        }
+    }
+}
+
+/// Decode an external_payload blob as a forwarder call
+pub fn decode_forwarder_call(config: &Config, blob: &[u8]) -> Result<ForwarderCall> {
+    let (forwarder, input, expected_output) =
+        <(Address, Bytes, Bytes) as SolValue>::abi_decode_params(blob)
+            .map_err(|e| anyhow!("Blob is not a forwarder call: {}", e))?;
+    let selector = input.get(..4).unwrap_or(&input[..]);
+
+    Ok(ForwarderCall {
+        forwarder: forwarder.to_string(),
+        forwarder_name: config.forwarder_name(&forwarder),
+        selector: format!("0x{}", hex::encode(selector)),
+        function: selector_name(selector),
+        decoded_args: decode_call_args(selector, input.get(4..).unwrap_or_default()),
+        input: input.to_string(),
+        expected_output: expected_output.to_string(),
+    })
+}
+
+/// Forwarders called by a transaction that are not part of `config`'s deployment
+///
+/// Non-empty for a proof generated against another network's (or an older
+/// deployment's) addresses, which cannot execute here.
+pub fn foreign_forwarders(config: &Config, tx: &ProtocolAdapter::Transaction) -> Vec<String> {
+    tx.actions
+        .iter()
+        .flat_map(|action| &action.logicVerifierInputs)
+        .flat_map(|input| &input.appData.externalPayload)
+        .filter_map(|payload| decode_forwarder_call(config, &payload.blob).ok())
+        .filter(|call| call.forwarder_name.is_none())
+        .map(|call| call.forwarder)
+        .collect()
+}
+
+/// Summarize the actions, resources and forwarder calls in a transaction
+pub fn summarize_transaction(config: &Config, tx: &ProtocolAdapter::Transaction) -> TransactionSummary {
+    let nullifiers: HashSet<B256> = extract_nullifiers(tx).into_iter().collect();
+
+    let actions = tx
+        .actions
+        .iter()
+        .map(|action| ActionSummary {
+            compliance_units: action
+                .complianceVerifierInputs
+                .iter()
+                .map(|unit| ComplianceUnitSummary {
+                    nullifier: unit.instance.consumed.nullifier.to_string(),
+                    consumed_logic_ref: unit.instance.consumed.logicRef.to_string(),
+                    commitment_tree_root: unit.instance.consumed.commitmentTreeRoot.to_string(),
+                    commitment: unit.instance.created.commitment.to_string(),
+                    created_logic_ref: unit.instance.created.logicRef.to_string(),
+                    unit_delta: DeltaPoint {
+                        x: unit.instance.unitDeltaX.to_string(),
+                        y: unit.instance.unitDeltaY.to_string(),
+                    },
+                })
+                .collect(),
+            resources: action
+                .logicVerifierInputs
+                .iter()
+                .map(|input| ResourceSummary {
+                    tag: input.tag.to_string(),
+                    is_consumed: nullifiers.contains(&input.tag),
+                    logic_ref: input.verifyingKey.to_string(),
+                    external_payloads: input
+                        .appData
+                        .externalPayload
+                        .iter()
+                        .map(|payload| ExternalPayloadSummary {
+                            blob: payload.blob.to_string(),
+                            call: decode_forwarder_call(config, &payload.blob).ok(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    TransactionSummary { num_actions: tx.actions.len(), actions, delta_proof: tx.deltaProof.to_string() }
+}
+
+/// Structured view of an execute(Transaction) call, for clients relaying it themselves
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionDetails {
+    pub actions: Vec<ActionDetails>,
+    pub delta_proof: String,
+}
+
+/// Tags and delta of one action
+///
+/// Compliance unit `i` consumes `nullifiers[i]` and creates `commitments[i]`;
+/// they are the resources' `nullifier()` and `commitment()`, which the
+/// compliance proof binds to the instance.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionDetails {
+    /// Root of the tree over each unit's (nullifier, commitment), in order
+    pub action_tree_root: String,
+    pub nullifiers: Vec<String>,
+    pub commitments: Vec<String>,
+    /// Each unit's delta, a secp256k1 point; the delta proof covers their sum
+    pub unit_deltas: Vec<DeltaPoint>,
+}
+
+/// Affine coordinates of a secp256k1 point
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeltaPoint {
+    pub x: String,
+    pub y: String,
+}
+
+/// Commitments, nullifiers, action tree roots and deltas of a transaction
+pub fn transaction_details(tx: &ProtocolAdapter::Transaction) -> Result<TransactionDetails> {
+    let actions = tx
+        .actions
+        .iter()
+        .enumerate()
+        .map(|(i, action)| {
+            let units = &action.complianceVerifierInputs;
+            let mut tree = MerkleTree::new(vec![]);
+            for unit in units {
+                tree.insert(Digest::from_bytes(unit.instance.consumed.nullifier.0));
+                tree.insert(Digest::from_bytes(unit.instance.created.commitment.0));
+            }
+            let root = tree
+                .root()
+                .map_err(|e| anyhow!("Action {}: failed to compute action tree root: {:?}", i, e))?;
+
+            Ok(ActionDetails {
+                action_tree_root: format!("0x{}", hex::encode(root.as_bytes())),
+                nullifiers: units.iter().map(|unit| unit.instance.consumed.nullifier.to_string()).collect(),
+                commitments: units.iter().map(|unit| unit.instance.created.commitment.to_string()).collect(),
+                unit_deltas: units
+                    .iter()
+                    .map(|unit| DeltaPoint {
+                        x: unit.instance.unitDeltaX.to_string(),
+                        y: unit.instance.unitDeltaY.to_string(),
+                    })
+                    .collect(),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(TransactionDetails { actions, delta_proof: tx.deltaProof.to_string() })
+}
+
+/// Root of an action tree and the inclusion path of each of its leaves
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ActionTreeReport {
+    pub root: String,
+    /// Path of leaf `i`, from the leaf up
+    pub paths: Vec<Vec<PathStep>>,
+}
+
+/// One level of a Merkle path
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PathStep {
+    pub sibling: String,
+    /// Whether the sibling is hashed on the left, i.e. the node is a right child
+    pub sibling_is_left: bool,
+}
+
+/// Build the action tree over `leaves` (nullifiers and commitments, in insertion order)
+///
+/// This is the tree whose root every logic proof of an action commits to; a
+/// leaf listed twice gets the path of its first occurrence.
+pub fn action_tree(leaves: &[B256]) -> Result<ActionTreeReport> {
+    if leaves.is_empty() {
+        return Err(anyhow!("An action tree needs at least one leaf"));
+    }
+    let mut tree = MerkleTree::new(vec![]);
+    for leaf in leaves {
+        tree.insert(Digest::from_bytes(leaf.0));
+    }
+    let root = tree.root().map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    let paths = leaves
+        .iter()
+        .enumerate()
+        .map(|(i, leaf)| {
+            let path = tree
+                .generate_path(&Digest::from_bytes(leaf.0))
+                .map_err(|e| anyhow!("Failed to compute the path of leaf {}: {:?}", i, e))?;
+            Ok(path
+                .0
+                .iter()
+                .map(|(sibling, sibling_is_left)| PathStep {
+                    sibling: format!("0x{}", hex::encode(sibling.as_bytes())),
+                    sibling_is_left: *sibling_is_left,
+                })
+                .collect())
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(ActionTreeReport { root: format!("0x{}", hex::encode(root.as_bytes())), paths })
+}
+
+/// Whether any of the nullifiers is already recorded by the Protocol Adapter
+pub async fn any_nullifier_spent(rpc_url: &str, protocol_adapter: &str, nullifiers: &[B256]) -> Result<bool> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let adapter: Address = protocol_adapter.parse()?;
+    let nullifier_set = INullifierSet::new(adapter, &provider);
+
+    for nullifier in nullifiers {
+        if nullifier_set.isNullifierContained(*nullifier).call().await? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Whether the Protocol Adapter has ever had `root` as its commitment tree root
+pub async fn commitment_root_contained(rpc_url: &str, protocol_adapter: &str, root: B256) -> Result<bool> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let adapter: Address = protocol_adapter.parse()?;
+    Ok(ICommitmentTree::new(adapter, &provider).isCommitmentTreeRootContained(root).call().await?)
+}
+
+/// Number of commitments in the Protocol Adapter's tree and its latest root
+pub async fn commitment_tree_head(rpc_url: &str, protocol_adapter: &str) -> Result<(u64, B256)> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let adapter: Address = protocol_adapter.parse()?;
+    let tree = ICommitmentTree::new(adapter, &provider);
+    let count = tree.commitmentCount().call().await?;
+    let root = tree.latestCommitmentTreeRoot().call().await?;
+    Ok((u64::try_from(count).map_err(|_| anyhow!("Commitment count {} out of range", count))?, root))
+}
+
+/// Commitments appended by every `execute` from `from_block` on, read from TransactionExecuted events
+///
+/// Each event lists a consumed nullifier then a created commitment per compliance
+/// unit, in the order the adapter appended the commitments. Public RPCs may cap
+/// the block range of a log query, so `from_block` should be close to the
+/// adapter's deployment.
+pub async fn executed_commitments(rpc_url: &str, protocol_adapter: &str, from_block: u64) -> Result<Vec<B256>> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let filter = Filter::new()
+        .address(protocol_adapter.parse::<Address>()?)
+        .event_signature(ProtocolAdapter::TransactionExecuted::SIGNATURE_HASH)
+        .from_block(from_block);
+
+    let mut commitments = Vec::new();
+    for log in provider.get_logs(&filter).await? {
+        let event = log.log_decode::<ProtocolAdapter::TransactionExecuted>()
+            .map_err(|e| anyhow!("Failed to decode TransactionExecuted: {}", e))?;
+        commitments.extend(event.inner.data.tags.iter().skip(1).step_by(2).copied());
+    }
+    Ok(commitments)
+}
+
+/// Simulate `execute` calldata against the Protocol Adapter with eth_call
+///
+/// The adapter verifies every proof and runs the forwarder calls, so this fails
+/// with the revert reason exactly when broadcasting the calldata would.
+pub async fn simulate_execute(rpc_url: &str, protocol_adapter: &str, calldata: &[u8]) -> Result<()> {
+    let simulation = simulate(rpc_url, protocol_adapter, calldata).await?;
+    match simulation.revert_reason {
+        Some(reason) if !simulation.success => Err(anyhow!("execute reverted: {}", reason)),
+        _ => Ok(()),
+    }
+}
+
+/// Outcome of an `eth_call` of `execute`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Simulation {
+    pub success: bool,
+    /// Decoded `Error(string)` reason, or the node's message with the raw revert data
+    pub revert_reason: Option<String>,
+}
+
+/// `eth_call` execute on the Protocol Adapter and report whether it would succeed
+///
+/// A revert is an `Ok` simulation with its reason; only a failure to reach the
+/// node (or a malformed address) is an error.
+pub async fn simulate(rpc_url: &str, protocol_adapter: &str, calldata: &[u8]) -> Result<Simulation> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let adapter: Address = protocol_adapter.parse()?;
+    let request = TransactionRequest::default()
+        .to(adapter)
+        .input(Bytes::copy_from_slice(calldata).into());
+
+    let error = match provider.call(request).await {
+        Ok(_) => return Ok(Simulation { success: true, revert_reason: None }),
+        Err(e) => e,
+    };
+    let Some(payload) = error.as_error_resp() else {
+        return Err(anyhow!("eth_call failed: {}", error));
+    };
+    let revert_data = payload.as_revert_data();
+    let reason = match revert_data.as_deref().and_then(decode_revert_reason) {
+        Some(reason) => reason,
+        None => match revert_data {
+            Some(data) if !data.is_empty() => format!("{} (data {})", payload.message, data),
+            _ => payload.message.to_string(),
+        },
+    };
+    Ok(Simulation { success: false, revert_reason: Some(reason) })
+}
+
+/// Fixed gas of an `execute` call: calldata, the delta proof and bookkeeping
+const BASE_EXECUTE_GAS: u64 = 200_000;
+/// Verifying one compliance proof and the logic proofs of its two resources
+const GAS_PER_COMPLIANCE_UNIT: u64 = 900_000;
+/// One forwarder call, e.g. an ERC20 transfer
+const GAS_PER_EXTERNAL_PAYLOAD: u64 = 100_000;
+
+/// Gas for `execute` estimated from the transaction's shape, for when no RPC is configured
+///
+/// Deliberately generous: 1.2M for a single-unit shield or unshield.
+pub fn heuristic_execute_gas(tx: &ProtocolAdapter::Transaction) -> u64 {
+    let units: u64 = tx.actions.iter().map(|action| action.complianceVerifierInputs.len() as u64).sum();
+    let payloads: u64 = tx
+        .actions
+        .iter()
+        .flat_map(|action| &action.logicVerifierInputs)
+        .map(|input| input.appData.externalPayload.len() as u64)
+        .sum();
+    BASE_EXECUTE_GAS + units * GAS_PER_COMPLIANCE_UNIT + payloads * GAS_PER_EXTERNAL_PAYLOAD
+}
+
+/// `eth_estimateGas` of execute on the Protocol Adapter; fails if the call would revert
+pub async fn estimate_execute_gas(rpc_url: &str, protocol_adapter: &str, calldata: &[u8]) -> Result<u64> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let adapter: Address = protocol_adapter.parse()?;
+    let request = TransactionRequest::default()
+        .to(adapter)
+        .input(Bytes::copy_from_slice(calldata).into());
+
+    provider.estimate_gas(request).await.map_err(|e| anyhow!("Gas estimation failed: {}", e))
+}
+
+/// Mined `execute` transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmittedTransaction {
+    pub tx_hash: B256,
+    pub success: bool,
+    pub block_number: Option<u64>,
+    pub gas_used: u64,
+}
+
+/// Sign and send `execute` calldata to the Protocol Adapter, waiting for the receipt
+///
+/// Without a gas limit the provider estimates one, which fails if the call would revert.
+pub async fn submit_execute(
+    rpc_url: &str,
+    protocol_adapter: &str,
+    private_key: &str,
+    calldata: &[u8],
+    gas_limit: Option<u64>,
+) -> Result<SubmittedTransaction> {
+    let signer: PrivateKeySigner = private_key.trim().parse()
+        .map_err(|e| anyhow!("Invalid private key: {}", e))?;
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .connect_http(rpc_url.parse()?);
+    let adapter: Address = protocol_adapter.parse()?;
+
+    let mut request = TransactionRequest::default()
+        .with_to(adapter)
+        .with_input(Bytes::copy_from_slice(calldata));
+    if let Some(gas_limit) = gas_limit {
+        request = request.with_gas_limit(gas_limit);
+    }
+
+    let pending = provider.send_transaction(request).await
+        .map_err(|e| anyhow!("Failed to send transaction: {}", e))?;
+    let receipt = pending.get_receipt().await
+        .map_err(|e| anyhow!("Failed to get receipt: {}", e))?;
+
+    Ok(SubmittedTransaction {
+        tx_hash: receipt.transaction_hash,
+        success: receipt.status(),
+        block_number: receipt.block_number,
+        gas_used: receipt.gas_used,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+
+    #[test]
+    fn test_decode_forwarder_call() {
+        let config = Config::default();
+        let forwarder: Address = config.tokens.get("USDC").unwrap().forwarder_address.parse().unwrap();
+        let recipient = Address::repeat_byte(0x11);
+
+        let mut input = vec![0xa9, 0x05, 0x9c, 0xbb];
+        input.extend_from_slice(&(recipient, U256::from(1_000_000u64)).abi_encode_params());
+        let blob = (forwarder, Bytes::from(input), Bytes::from(true.abi_encode())).abi_encode_params();
+
+        let call = decode_forwarder_call(&config, &blob).unwrap();
+        assert_eq!(call.forwarder_name.as_deref(), Some("USDC"));
+        assert_eq!(call.selector, "0xa9059cbb");
+        assert_eq!(call.function, Some("transfer(address,uint256)"));
+        let args = call.decoded_args.unwrap();
+        assert_eq!(args["to"], recipient.to_string());
+        assert_eq!(args["amount"], "1000000");
+
+        // exactInputSingle, as encoded for the Uniswap forwarder
+        let params = forwarder_logic_witness::SwapParams {
+            token_in: [0x01; 20],
+            token_out: [0x02; 20],
+            fee: 3000,
+            recipient: recipient.into(),
+            amount_in: 1_000_000,
+            amount_out_minimum: 400_000_000_000_000,
+        };
+        let swap = forwarder_logic_witness::encode_exact_input_single(&params);
+        let args = decode_call_args(&swap[..4], &swap[4..]).unwrap();
+        assert_eq!(args["tokenIn"], Address::repeat_byte(0x01).to_string());
+        assert_eq!(args["recipient"], recipient.to_string());
+        assert_eq!((args["fee"].as_str(), args["amountIn"].as_str()), (Some("3000"), Some("1000000")));
+        assert_eq!(args["amountOutMinimum"], "400000000000000");
+        assert_eq!(args["sqrtPriceLimitX96"], "0");
+
+        // Unknown selectors and truncated arguments are left undecoded
+        assert!(decode_call_args(&[0xde, 0xad, 0xbe, 0xef], &[]).is_none());
+        assert!(decode_call_args(&[0xa9, 0x05, 0x9c, 0xbb], &[0; 32]).is_none());
+    }
+
+    #[test]
+    fn test_action_tree_paths_cover_every_leaf() {
+        assert!(action_tree(&[]).is_err());
+
+        let leaves = [B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3)];
+        let report = action_tree(&leaves).unwrap();
+        assert_eq!(report.paths.len(), 3);
+        // Three leaves pad to four, two levels below the root
+        assert!(report.paths.iter().all(|path| path.len() == 2));
+        assert_eq!(report.paths[0][0].sibling, leaves[1].to_string());
+        assert_eq!(report.paths[1][0].sibling, leaves[0].to_string());
+        assert_eq!(action_tree(&leaves).unwrap().root, report.root);
+    }
+
+    #[test]
+    fn test_transaction_details_lists_unit_tags() {
+        let mut tx = ProtocolAdapter::Transaction::default();
+        tx.actions.push(Default::default());
+        tx.actions[0].complianceVerifierInputs.push(Default::default());
+        let instance = &mut tx.actions[0].complianceVerifierInputs[0].instance;
+        instance.consumed.nullifier = B256::repeat_byte(0x01);
+        instance.created.commitment = B256::repeat_byte(0x02);
+
+        let details = transaction_details(&tx).unwrap();
+        let action = &details.actions[0];
+        assert_eq!(action.nullifiers, vec![B256::repeat_byte(0x01).to_string()]);
+        assert_eq!(action.commitments, vec![B256::repeat_byte(0x02).to_string()]);
+        assert_eq!(action.unit_deltas.len(), 1);
+        assert_eq!(created_commitments(&tx), vec![B256::repeat_byte(0x02)]);
+
+        let mut tree = MerkleTree::new(vec![]);
+        tree.insert(Digest::from_bytes([0x01; 32]));
+        tree.insert(Digest::from_bytes([0x02; 32]));
+        assert_eq!(action.action_tree_root, format!("0x{}", hex::encode(tree.root().unwrap().as_bytes())));
+    }
+
+    #[test]
+    fn test_heuristic_gas_scales_with_units_and_payloads() {
+        let mut tx = ProtocolAdapter::Transaction::default();
+        assert_eq!(heuristic_execute_gas(&tx), BASE_EXECUTE_GAS);
+
+        tx.actions.push(Default::default());
+        tx.actions[0].complianceVerifierInputs.push(Default::default());
+        tx.actions[0].logicVerifierInputs.push(Default::default());
+        tx.actions[0].logicVerifierInputs.push(Default::default());
+        tx.actions[0].logicVerifierInputs[1].appData.externalPayload.push(Default::default());
+        assert_eq!(heuristic_execute_gas(&tx), 1_200_000);
+    }
+
+    #[test]
+    fn test_rejects_non_execute_calldata() {
+        assert!(decode_execute_calldata("0x").is_err());
+        assert!(decode_execute_calldata("0xdeadbeef").is_err());
+        assert!(decode_execute_calldata("not hex").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Local copy of the Protocol Adapter's commitment tree
+//!
+//! Consuming a persistent resource needs a Merkle path from its commitment to a
+//! root the adapter has recorded, but the adapter only stores the tree's
+//! frontier. This module keeps every leaf, in the order `execute` appended
+//! them, and rebuilds the tree the way `MerkleTree.sol` does: the smallest
+//! depth that still leaves an empty leaf, padded with `EMPTY_HASH`.
+
+use alloy::primitives::{b256, B256};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::path::Path;
+
+/// `SHA256.EMPTY_HASH` (sha256("EMPTY")): the padding leaf and the empty tree's root
+pub const EMPTY_HASH: B256 = b256!("cc1d2f838445db7aec431df9ee8a871f40e7aa5e064fc056633ef8c60fab7b06");
+
+/// File the local tree is kept in unless COMMITMENT_TREE_FILE is set
+pub const DEFAULT_TREE_FILE: &str = "commitment_tree.json";
+
+/// Path of the local tree file (COMMITMENT_TREE_FILE, or [`DEFAULT_TREE_FILE`])
+pub fn tree_file() -> String {
+    std::env::var("COMMITMENT_TREE_FILE").unwrap_or_else(|_| DEFAULT_TREE_FILE.to_string())
+}
+
+/// `SHA256.hash(a, b)`: sha256 of the two nodes concatenated
+fn hash_pair(left: &B256, right: &B256) -> B256 {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    B256::from_slice(&hasher.finalize())
+}
+
+/// Commitments in the order the Protocol Adapter appended them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentTree {
+    leaves: Vec<B256>,
+}
+
+impl CommitmentTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_leaves(leaves: Vec<B256>) -> Self {
+        Self { leaves }
+    }
+
+    /// Read the tree from `path`; a missing file is an empty tree
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid commitment tree file {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn leaves(&self) -> &[B256] {
+        &self.leaves
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Append a commitment, returning its leaf index
+    pub fn push(&mut self, commitment: B256) -> usize {
+        self.leaves.push(commitment);
+        self.leaves.len() - 1
+    }
+
+    /// Leaf index of `commitment`, if it is in the tree
+    pub fn position(&self, commitment: &B256) -> Option<usize> {
+        self.leaves.iter().position(|leaf| leaf == commitment)
+    }
+
+    /// Depth the adapter's tree has after this many leaves: the smallest d with 2^d > len
+    pub fn depth(&self) -> usize {
+        (usize::BITS - self.leaves.len().leading_zeros()) as usize
+    }
+
+    /// Every level of the padded tree, leaves first and the root last
+    fn levels(&self) -> Vec<Vec<B256>> {
+        let mut level = self.leaves.clone();
+        level.resize(1 << self.depth(), EMPTY_HASH);
+
+        let mut levels = vec![level];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels.last().unwrap().chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])).collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    /// Root the adapter recorded after appending the last leaf
+    pub fn root(&self) -> B256 {
+        self.levels().pop().unwrap()[0]
+    }
+
+    /// Merkle path of leaf `index` under the current root
+    ///
+    /// Each step is the sibling node and whether it sits on the left, i.e.
+    /// whether the node being hashed up is a right child.
+    pub fn path(&self, index: usize) -> Result<Vec<(B256, bool)>> {
+        if index >= self.leaves.len() {
+            return Err(anyhow!("Leaf {} is not in a tree of {} commitments", index, self.leaves.len()));
+        }
+        let levels = self.levels();
+        let mut node = index;
+        let mut path = Vec::with_capacity(levels.len() - 1);
+        for level in &levels[..levels.len() - 1] {
+            path.push((level[node ^ 1], node & 1 == 1));
+            node >>= 1;
+        }
+        Ok(path)
+    }
+}
+
+/// Root reached by hashing `leaf` up along `path`
+pub fn root_from_path(leaf: B256, path: &[(B256, bool)]) -> B256 {
+    path.iter().fold(leaf, |node, (sibling, sibling_is_left)| {
+        if *sibling_is_left {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(i: u8) -> B256 {
+        B256::repeat_byte(i + 1)
+    }
+
+    #[test]
+    fn test_root_matches_the_adapter_tree() {
+        let mut tree = CommitmentTree::new();
+        assert_eq!((tree.depth(), tree.root()), (0, EMPTY_HASH));
+
+        // One leaf already grows the tree, so there is always an empty slot
+        tree.push(leaf(0));
+        assert_eq!(tree.depth(), 1);
+        assert_eq!(tree.root(), hash_pair(&leaf(0), &EMPTY_HASH));
+
+        tree.push(leaf(1));
+        tree.push(leaf(2));
+        assert_eq!(tree.depth(), 2);
+        let expected = hash_pair(&hash_pair(&leaf(0), &leaf(1)), &hash_pair(&leaf(2), &EMPTY_HASH));
+        assert_eq!(tree.root(), expected);
+
+        tree.push(leaf(3));
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.position(&leaf(3)), Some(3));
+        assert_eq!(tree.position(&EMPTY_HASH), None);
+    }
+
+    #[test]
+    fn test_every_path_leads_to_the_root() {
+        let mut tree = CommitmentTree::new();
+        for n in 0..9u8 {
+            tree.push(leaf(n));
+            for (i, commitment) in tree.leaves().iter().enumerate() {
+                let path = tree.path(i).unwrap();
+                assert_eq!(path.len(), tree.depth());
+                assert_eq!(root_from_path(*commitment, &path), tree.root(), "leaf {} of {}", i, tree.len());
+            }
+        }
+        assert!(tree.path(tree.len()).is_err());
+    }
+
+    #[test]
+    fn test_tree_file_round_trips() {
+        let path = std::env::temp_dir().join(format!("commitment_tree_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(CommitmentTree::load(&path).unwrap().is_empty());
+
+        let tree = CommitmentTree::from_leaves(vec![leaf(0), leaf(1)]);
+        tree.save(&path).unwrap();
+        assert_eq!(CommitmentTree::load(&path).unwrap(), tree);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
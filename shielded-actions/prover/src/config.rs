@@ -0,0 +1,319 @@
+//! Deployed contract addresses
+//!
+//! `NETWORK` (default: sepolia) selects a built-in deployment (`sepolia`, or
+//! `anvil` for a local Anvil fork of Sepolia) or names a JSON file describing a
+//! custom one (see [`Config::from_file`]). Each address can be overridden from
+//! the environment, so a redeploy only needs new env vars rather than a rebuild:
+//!
+//! - `PROTOCOL_ADAPTER_ADDRESS`
+//! - `UNISWAP_FORWARDER_ADDRESS`
+//! - `<SYMBOL>_FORWARDER_ADDRESS` for each token, e.g. `USDC_FORWARDER_ADDRESS`
+//! - `TOKEN_CONFIG` to add tokens or change their decimals (see [`crate::tokens`])
+
+use alloy::primitives::Address;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::tokens::{TokenInfo, TokenRegistry};
+
+/// Contract addresses of one network
+struct Deployment {
+    chain_id: u64,
+    /// Public RPC endpoint suggested for submitting transactions
+    rpc_url: &'static str,
+    protocol_adapter: &'static str,
+    uniswap_forwarder: &'static str,
+    /// (symbol, forwarder, ERC20, decimals)
+    tokens: &'static [(&'static str, &'static str, &'static str, u32)],
+}
+
+const SEPOLIA: Deployment = Deployment {
+    chain_id: 11_155_111,
+    rpc_url: "https://ethereum-sepolia-rpc.publicnode.com",
+    protocol_adapter: "0x08c3bdc46B115cDc71Df076d9De96EeEBaa98525",
+    uniswap_forwarder: "0x9335Fa4A31E552378Ed29b94704c52b5635cd1AA",
+    tokens: &[
+        ("USDC", "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE", "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238", 6),
+        ("WETH", "0xD5307D777dC60b763b74945BF5A42ba93ce44e4b", "0x7b79995e5f793A07Bc00c21412e50Ecae098E7f9", 18),
+    ],
+};
+
+/// A local `anvil --fork-url <sepolia rpc>`: Sepolia's contracts on Anvil's chain id
+const ANVIL: Deployment = Deployment {
+    chain_id: 31_337,
+    rpc_url: "http://127.0.0.1:8545",
+    ..SEPOLIA
+};
+
+/// Built-in deployments by network name
+const DEPLOYMENTS: &[(&str, Deployment)] = &[("sepolia", SEPOLIA), ("anvil", ANVIL)];
+
+/// Network used when NETWORK is unset
+pub const DEFAULT_NETWORK: &str = "sepolia";
+
+/// Contract addresses the prover targets
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Config {
+    pub network: String,
+    pub chain_id: u64,
+    /// RPC endpoint suggested for submitting transactions
+    pub rpc_url: String,
+    pub protocol_adapter: String,
+    pub uniswap_forwarder: String,
+    pub tokens: TokenRegistry,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::for_network(DEFAULT_NETWORK).expect("default network has a deployment")
+    }
+}
+
+impl Config {
+    /// Built-in addresses of a known network
+    pub fn for_network(network: &str) -> Result<Self> {
+        let network = network.trim().to_lowercase();
+        let (_, deployment) = DEPLOYMENTS
+            .iter()
+            .find(|(name, _)| *name == network)
+            .ok_or_else(|| {
+                let known: Vec<&str> = DEPLOYMENTS.iter().map(|(name, _)| *name).collect();
+                anyhow!("Unknown network '{}'. Known: {}", network, known.join(", "))
+            })?;
+
+        Ok(Self {
+            network,
+            chain_id: deployment.chain_id,
+            rpc_url: deployment.rpc_url.to_string(),
+            protocol_adapter: deployment.protocol_adapter.to_string(),
+            uniswap_forwarder: deployment.uniswap_forwarder.to_string(),
+            tokens: TokenRegistry::new(
+                deployment
+                    .tokens
+                    .iter()
+                    .map(|(symbol, forwarder, erc20, decimals)| TokenInfo::new(symbol, forwarder, erc20, *decimals)),
+            ),
+        })
+    }
+
+    /// Custom deployment from a JSON file:
+    ///
+    /// ```json
+    /// {
+    ///   "network": "mainnet",
+    ///   "chain_id": 1,
+    ///   "rpc_url": "https://ethereum-rpc.publicnode.com",
+    ///   "protocol_adapter": "0x...",
+    ///   "uniswap_forwarder": "0x...",
+    ///   "tokens": [{"symbol": "USDC", "forwarder": "0x...", "erc20": "0x...", "decimals": 6, "forwarder_permit": true}]
+    /// }
+    /// ```
+    pub fn from_file(path: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|e| anyhow!("Failed to read deployment {}: {}", path, e))?;
+        Self::from_json(&raw).map_err(|e| anyhow!("Invalid deployment {}: {}", path, e))
+    }
+
+    fn from_json(raw: &str) -> Result<Self> {
+        let file: DeploymentFile = serde_json::from_str(raw)?;
+        let network = file.network.trim().to_lowercase();
+        if network.is_empty() {
+            return Err(anyhow!("network must not be empty"));
+        }
+        if file.tokens.is_empty() {
+            return Err(anyhow!("a deployment needs at least one token"));
+        }
+        Ok(Self {
+            network,
+            chain_id: file.chain_id,
+            rpc_url: file.rpc_url.unwrap_or_default(),
+            protocol_adapter: file.protocol_adapter,
+            uniswap_forwarder: file.uniswap_forwarder,
+            tokens: TokenRegistry::new(
+                file.tokens
+                    .iter()
+                    .map(|token| TokenInfo {
+                        forwarder_permit: token.forwarder_permit,
+                        ..TokenInfo::new(&token.symbol, &token.forwarder, &token.erc20, token.decimals)
+                    }),
+            ),
+        })
+    }
+
+    /// A built-in network by name, or a deployment file when `network` ends in `.json`
+    pub fn load(network: &str) -> Result<Self> {
+        if network.trim().ends_with(".json") {
+            Self::from_file(network.trim())
+        } else {
+            Self::for_network(network)
+        }
+    }
+
+    /// Load the NETWORK deployment with any per-address env overrides
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_for(None)
+    }
+
+    /// Like [`from_env`](Self::from_env), with `network` (e.g. a CLI flag) taking precedence over NETWORK
+    pub fn from_env_for(network: Option<&str>) -> Result<Self> {
+        let network = network
+            .map(str::to_string)
+            .or_else(|| env_var("NETWORK"))
+            .unwrap_or_else(|| DEFAULT_NETWORK.to_string());
+        Self::load(&network)?.with_overrides(env_var)
+    }
+
+    /// Short hash of the network and every address, which changes whenever any of them does
+    ///
+    /// Proofs are only valid against the contracts they were generated for,
+    /// so cached proofs are keyed on it.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(self).expect("config serializes"));
+        hex::encode(&hasher.finalize()[..8])
+    }
+
+    fn with_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        if let Some(raw) = lookup("TOKEN_CONFIG") {
+            self.tokens.apply_overrides(&raw)?;
+        }
+
+        for (name, address) in [
+            ("PROTOCOL_ADAPTER_ADDRESS", &mut self.protocol_adapter),
+            ("UNISWAP_FORWARDER_ADDRESS", &mut self.uniswap_forwarder),
+        ] {
+            if let Some(value) = lookup(name) {
+                *address = value;
+            }
+            *address = normalize_address(name, address)?;
+        }
+
+        for token in self.tokens.iter_mut() {
+            let name = format!("{}_FORWARDER_ADDRESS", token.symbol);
+            if let Some(value) = lookup(&name) {
+                token.forwarder_address = value;
+            }
+            token.forwarder_address = normalize_address(&name, &token.forwarder_address)?;
+            token.erc20_address = normalize_address(&format!("{} ERC20", token.symbol), &token.erc20_address)?;
+        }
+        Ok(self)
+    }
+
+    /// Target a different Protocol Adapter (e.g. from a CLI flag)
+    pub fn with_protocol_adapter(mut self, address: Option<&str>) -> Result<Self> {
+        if let Some(address) = address {
+            self.protocol_adapter = normalize_address("Protocol Adapter", address)?;
+        }
+        Ok(self)
+    }
+
+    /// Name of a known forwarder contract
+    pub fn forwarder_name(&self, address: &Address) -> Option<String> {
+        if let Some(token) = self.tokens.by_forwarder(address) {
+            Some(token.symbol.clone())
+        } else if address.to_string().eq_ignore_ascii_case(&self.uniswap_forwarder) {
+            Some("Uniswap".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// JSON form of a custom deployment (see [`Config::from_file`])
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeploymentFile {
+    network: String,
+    chain_id: u64,
+    #[serde(default)]
+    rpc_url: Option<String>,
+    protocol_adapter: String,
+    uniswap_forwarder: String,
+    tokens: Vec<DeploymentToken>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeploymentToken {
+    symbol: String,
+    forwarder: String,
+    erc20: String,
+    decimals: u32,
+    /// The forwarder was deployed with permit support
+    #[serde(default)]
+    forwarder_permit: bool,
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Checksummed form of a 20-byte hex address
+fn normalize_address(name: &str, address: &str) -> Result<String> {
+    let parsed: Address = address.trim().parse()
+        .map_err(|e| anyhow!("Invalid {} address '{}' (expected 20-byte hex): {}", name, address, e))?;
+    Ok(parsed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides() {
+        let custom = "0x1234567890123456789012345678901234567890";
+        let config = Config::default()
+            .with_overrides(|name| (name == "WETH_FORWARDER_ADDRESS").then(|| custom.to_string()))
+            .unwrap();
+        assert_eq!(config.tokens.get("weth").unwrap().forwarder_address.to_lowercase(), custom);
+        assert!(config.tokens.get("USDC").unwrap().forwarder_address.eq_ignore_ascii_case(SEPOLIA.tokens[0].1));
+        assert_eq!(config.forwarder_name(&custom.parse().unwrap()).as_deref(), Some("WETH"));
+
+        let invalid = Config::default().with_overrides(|name| (name == "USDC_FORWARDER_ADDRESS").then(|| "0x1234".to_string()));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_protocol_adapter_override() {
+        let custom = "0x1234567890123456789012345678901234567890";
+        let config = Config::default().with_protocol_adapter(Some(custom)).unwrap();
+        assert_eq!(config.protocol_adapter.to_lowercase(), custom);
+        assert!(Config::default().with_protocol_adapter(Some("0x1234")).is_err());
+        assert!(Config::default().with_protocol_adapter(Some("not an address")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_network() {
+        assert_eq!(Config::for_network("Sepolia").unwrap().network, "sepolia");
+        assert!(Config::for_network("mainnet").is_err());
+    }
+
+    #[test]
+    fn test_anvil_forks_sepolia() {
+        let anvil = Config::for_network("anvil").unwrap();
+        let sepolia = Config::default();
+        assert_eq!(anvil.chain_id, 31_337);
+        assert_eq!(anvil.protocol_adapter, sepolia.protocol_adapter);
+        assert_ne!(anvil.fingerprint(), sepolia.fingerprint());
+    }
+
+    #[test]
+    fn test_deployment_from_json() {
+        let raw = r#"{
+            "network": "Mainnet",
+            "chain_id": 1,
+            "protocol_adapter": "0x1234567890123456789012345678901234567890",
+            "uniswap_forwarder": "0x2234567890123456789012345678901234567890",
+            "tokens": [{"symbol": "usdc", "forwarder": "0x3234567890123456789012345678901234567890",
+                        "erc20": "0x4234567890123456789012345678901234567890", "decimals": 6}]
+        }"#;
+        let config = Config::from_json(raw).unwrap().with_overrides(|_| None).unwrap();
+        assert_eq!(config.network, "mainnet");
+        assert_eq!(config.chain_id, 1);
+        assert_eq!(config.tokens.get("USDC").unwrap().decimals, 6);
+        assert!(config.tokens.get("WETH").is_err());
+
+        assert!(Config::from_json(&raw.replace(r#""chain_id": 1,"#, "")).is_err());
+        assert!(Config::load("missing-deployment.json").unwrap_err().to_string().contains("missing-deployment.json"));
+    }
+}
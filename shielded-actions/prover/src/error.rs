@@ -0,0 +1,126 @@
+//! Errors returned by the prover service
+//!
+//! Each variant maps to an HTTP status and a stable `code`, so clients can
+//! tell a request to fix from one worth retrying.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ProverError {
+    /// Malformed, zero or out-of-range amount (400)
+    InvalidAmount(String),
+    /// Token not in the registry (400)
+    UnknownToken(String),
+    /// Any other request the service will never prove as given (400)
+    InvalidRequest(String),
+    /// Docker is needed for Groth16 proving and is not running (503)
+    DockerUnavailable,
+    /// Proof generation or submission failed (500)
+    ProvingFailed(anyhow::Error),
+    /// Proof generation took longer than allowed (504)
+    Timeout(String),
+    /// Unknown proof id (404)
+    NotFound(String),
+}
+
+impl ProverError {
+    pub fn invalid_amount(e: impl fmt::Display) -> Self {
+        Self::InvalidAmount(e.to_string())
+    }
+
+    pub fn unknown_token(e: impl fmt::Display) -> Self {
+        Self::UnknownToken(e.to_string())
+    }
+
+    pub fn invalid_request(e: impl fmt::Display) -> Self {
+        Self::InvalidRequest(e.to_string())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidAmount(_) | Self::UnknownToken(_) | Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Self::DockerUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::ProvingFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+        }
+    }
+
+    /// Stable identifier of the variant, reported as `code` in error responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidAmount(_) => "invalid_amount",
+            Self::UnknownToken(_) => "unknown_token",
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::DockerUnavailable => "docker_unavailable",
+            Self::ProvingFailed(_) => "proving_failed",
+            Self::Timeout(_) => "timeout",
+            Self::NotFound(_) => "not_found",
+        }
+    }
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAmount(msg)
+            | Self::UnknownToken(msg)
+            | Self::InvalidRequest(msg)
+            | Self::Timeout(msg)
+            | Self::NotFound(msg) => f.write_str(msg),
+            Self::DockerUnavailable => f.write_str(
+                "Docker not available. Please ensure Docker Desktop is running. \
+                 Proof generation requires Docker for Groth16 proving.",
+            ),
+            Self::ProvingFailed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ProvingFailed(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Unclassified failures are proving failures; a ProverError passed through
+/// anyhow keeps its variant
+impl From<anyhow::Error> for ProverError {
+    fn from(e: anyhow::Error) -> Self {
+        e.downcast().unwrap_or_else(Self::ProvingFailed)
+    }
+}
+
+impl IntoResponse for ProverError {
+    fn into_response(self) -> Response {
+        (
+            self.status(),
+            Json(serde_json::json!({"error": self.to_string(), "code": self.code()})),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variant_survives_anyhow() {
+        let e: anyhow::Error = ProverError::unknown_token("Unknown token: DOGE").into();
+        let e = ProverError::from(e);
+        assert_eq!(e.code(), "unknown_token");
+        assert_eq!(e.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(e.to_string(), "Unknown token: DOGE");
+
+        let e = ProverError::from(anyhow::anyhow!("Verification failed"));
+        assert_eq!(e.code(), "proving_failed");
+        assert_eq!(e.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
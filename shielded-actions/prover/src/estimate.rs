@@ -0,0 +1,141 @@
+//! Proving time estimates for clients showing an ETA
+//!
+//! Every freshly generated proof records how long it took, per action and
+//! backend. An estimate is the average of the last [`ESTIMATE_WINDOW`] of them,
+//! or a typical time for that backend while there is no history. Cache hits are
+//! not recorded, since they say nothing about proving.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Recent proofs averaged into an estimate
+pub const ESTIMATE_WINDOW: usize = 20;
+
+/// Actions the service proves
+pub const ACTIONS: [&str; 7] = ["shield", "swap", "unshield", "raw", "batch", "delta", "ephemeral"];
+
+/// Backend names as reported by `ProofBackend::name`
+pub const BACKENDS: [&str; 3] = ["local-arm", "bonsai", "mock"];
+
+/// Canonical backend name; "local" is short for "local-arm"
+pub fn backend_name(backend: &str) -> Result<&'static str> {
+    match backend.trim().to_lowercase().as_str() {
+        "local" | "local-arm" => Ok("local-arm"),
+        "bonsai" => Ok("bonsai"),
+        "mock" => Ok("mock"),
+        other => Err(anyhow!("Unknown backend '{}'. Expected one of: local, {}", other, BACKENDS.join(", "))),
+    }
+}
+
+/// Canonical action name
+pub fn action_name(action: &str) -> Result<&'static str> {
+    let action = action.trim().to_lowercase();
+    ACTIONS
+        .iter()
+        .find(|known| **known == action)
+        .copied()
+        .ok_or_else(|| anyhow!("Unknown action '{}'. Expected one of: {}", action, ACTIONS.join(", ")))
+}
+
+/// Typical proving time without history: ~7 minutes for local Groth16, a few
+/// minutes on Bonsai, and seconds for a delta proof alone
+pub fn default_secs(action: &str, backend: &str) -> f64 {
+    match (action, backend) {
+        (_, "mock") => 1.0,
+        ("delta", _) => 10.0,
+        (_, "bonsai") => 180.0,
+        _ => 420.0,
+    }
+}
+
+/// Estimated proving time of one action on one backend
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Estimate {
+    pub action: String,
+    pub backend: String,
+    /// Expected seconds from the start of proving to the result (queue wait not included)
+    pub estimated_secs: f64,
+    /// Proofs averaged; 0 when the estimate is the default
+    pub samples: usize,
+    /// "history" or "default"
+    pub source: &'static str,
+}
+
+/// Durations of recent proofs per (action, backend)
+#[derive(Default)]
+pub struct ProvingTimes {
+    inner: Mutex<HashMap<(String, String), VecDeque<f64>>>,
+}
+
+impl ProvingTimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a proof, dropping the oldest beyond [`ESTIMATE_WINDOW`]
+    pub fn record(&self, action: &str, backend: &str, elapsed: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        let times = inner.entry((action.to_string(), backend.to_string())).or_default();
+        if times.len() == ESTIMATE_WINDOW {
+            times.pop_front();
+        }
+        times.push_back(elapsed.as_secs_f64());
+    }
+
+    pub fn estimate(&self, action: &str, backend: &str) -> Estimate {
+        let inner = self.inner.lock().unwrap();
+        let times = inner.get(&(action.to_string(), backend.to_string())).filter(|times| !times.is_empty());
+        let (estimated_secs, samples, source) = match times {
+            Some(times) => (times.iter().sum::<f64>() / times.len() as f64, times.len(), "history"),
+            None => (default_secs(action, backend), 0, "default"),
+        };
+        Estimate {
+            action: action.to_string(),
+            backend: backend.to_string(),
+            estimated_secs: (estimated_secs * 10.0).round() / 10.0,
+            samples,
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_averages_recent_proofs() {
+        let times = ProvingTimes::new();
+        let estimate = times.estimate("shield", "local-arm");
+        assert_eq!((estimate.estimated_secs, estimate.samples, estimate.source), (420.0, 0, "default"));
+        assert!(times.estimate("shield", "bonsai").estimated_secs < 420.0);
+
+        times.record("shield", "local-arm", Duration::from_secs(300));
+        times.record("shield", "local-arm", Duration::from_secs(400));
+        let estimate = times.estimate("shield", "local-arm");
+        assert_eq!((estimate.estimated_secs, estimate.samples, estimate.source), (350.0, 2, "history"));
+        // Other actions and backends keep their own history
+        assert_eq!(times.estimate("swap", "local-arm").source, "default");
+        assert_eq!(times.estimate("shield", "mock").source, "default");
+
+        // Only the last ESTIMATE_WINDOW proofs count
+        for _ in 0..ESTIMATE_WINDOW {
+            times.record("shield", "local-arm", Duration::from_secs(100));
+        }
+        let estimate = times.estimate("shield", "local-arm");
+        assert_eq!((estimate.estimated_secs, estimate.samples), (100.0, ESTIMATE_WINDOW));
+    }
+
+    #[test]
+    fn test_names_are_canonical() {
+        assert_eq!(backend_name("Local").unwrap(), "local-arm");
+        assert_eq!(backend_name("bonsai").unwrap(), "bonsai");
+        assert!(backend_name("gpu").is_err());
+        assert_eq!(action_name("SHIELD").unwrap(), "shield");
+        assert!(action_name("mint").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Idempotency keys for the job endpoints
+//!
+//! A client retrying `POST /api/shield` (or swap, unshield) with the same
+//! `Idempotency-Key` header gets the job its first request started rather than
+//! a second job. Keys are scoped per endpoint and remembered for as long as
+//! finished jobs are kept.
+
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest accepted key; a UUID is 36 characters
+pub const MAX_KEY_LEN: usize = 255;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    job_id: String,
+    created: Instant,
+}
+
+/// Job ids keyed on (endpoint, idempotency key)
+#[derive(Debug)]
+pub struct IdempotencyStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Job started under `key` on `scope` within the TTL
+    pub fn get(&self, scope: &str, key: &str, now: Instant) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&(scope.to_string(), key.to_string()))
+            .filter(|entry| now.saturating_duration_since(entry.created) < self.ttl)
+            .map(|entry| entry.job_id.clone())
+    }
+
+    /// Record `job_id` under `key`, unless another request claimed it first
+    ///
+    /// Returns the job id that won: `job_id` itself, or the one already stored.
+    pub fn claim(&self, scope: &str, key: &str, job_id: &str, now: Instant) -> String {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| now.saturating_duration_since(entry.created) < self.ttl);
+        entries
+            .entry((scope.to_string(), key.to_string()))
+            .or_insert_with(|| Entry { job_id: job_id.to_string(), created: now })
+            .job_id
+            .clone()
+    }
+
+    /// Drop `key`, e.g. once its job has been deleted
+    pub fn forget(&self, scope: &str, key: &str) {
+        self.entries.lock().unwrap().remove(&(scope.to_string(), key.to_string()));
+    }
+}
+
+/// Check an Idempotency-Key header value: 1 to MAX_KEY_LEN visible ASCII characters
+pub fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        bail!("Idempotency-Key must be 1 to {} characters", MAX_KEY_LEN);
+    }
+    if !key.bytes().all(|b| b.is_ascii_graphic()) {
+        bail!("Idempotency-Key must be visible ASCII");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_claim_wins_per_scope_until_expiry() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(store.get("shield", "retry-1", start), None);
+        assert_eq!(store.claim("shield", "retry-1", "job_a", start), "job_a");
+        assert_eq!(store.claim("shield", "retry-1", "job_b", start), "job_a");
+        assert_eq!(store.get("shield", "retry-1", start).as_deref(), Some("job_a"));
+
+        // The same key on another endpoint is a different request
+        assert_eq!(store.claim("swap", "retry-1", "job_c", start), "job_c");
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(store.get("shield", "retry-1", later), None);
+        assert_eq!(store.claim("shield", "retry-1", "job_d", later), "job_d");
+
+        store.forget("shield", "retry-1");
+        assert_eq!(store.get("shield", "retry-1", later), None);
+
+        assert!(validate_key("3f2c9a1e-7b4d-4e8a-9c21-5d6f0a1b2c3d").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("has space").is_err());
+        assert!(validate_key(&"k".repeat(MAX_KEY_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+//! Per-job handles shared between the server and the prover
+
+use crate::joblog::JobLog;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// What the prover needs from the job it is working on: where to write
+/// its log and progress, and whether the client has cancelled the job
+#[derive(Clone, Default)]
+pub struct JobContext {
+    pub log: JobLog,
+    pub progress: JobProgress,
+    pub cancel: CancellationToken,
+}
+
+impl JobContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+/// Current proving stage of a job, e.g. "Generating ZK proofs..."
+#[derive(Clone, Default)]
+pub struct JobProgress(Arc<Mutex<Option<String>>>);
+
+impl JobProgress {
+    pub fn set(&self, line: impl Into<String>) {
+        *self.0.lock().unwrap() = Some(line.into());
+    }
+
+    pub fn latest(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,119 @@
+//! Bounded per-job log of proving output
+//!
+//! The prover records why a real proof failed here, and subprocess output can
+//! be added line by line. Only the most recent output is kept so a noisy job
+//! cannot grow without bound.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Maximum bytes of output kept per job
+pub const MAX_JOB_LOG_BYTES: usize = 64 * 1024;
+
+#[derive(Default)]
+struct LogInner {
+    lines: VecDeque<String>,
+    bytes: usize,
+    truncated: bool,
+}
+
+/// Shared ring buffer of output lines, cheap to clone
+#[derive(Clone)]
+pub struct JobLog {
+    inner: Arc<Mutex<LogInner>>,
+    max_bytes: usize,
+}
+
+impl Default for JobLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Snapshot of a job log
+#[derive(Debug, Clone, Serialize)]
+pub struct JobLogSnapshot {
+    pub lines: Vec<String>,
+    /// Whether older lines were dropped to stay within the size cap
+    pub truncated: bool,
+}
+
+impl JobLog {
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_JOB_LOG_BYTES)
+    }
+
+    pub fn with_capacity(max_bytes: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            max_bytes,
+        }
+    }
+
+    /// Append a line, dropping the oldest lines once over the size cap
+    pub fn push(&self, line: impl Into<String>) {
+        let mut line = line.into();
+        if line.len() > self.max_bytes {
+            let mut cut = line.len() - self.max_bytes;
+            while !line.is_char_boundary(cut) {
+                cut += 1;
+            }
+            line.drain(..cut);
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.bytes += line.len();
+        inner.lines.push_back(line);
+        while inner.bytes > self.max_bytes {
+            let Some(dropped) = inner.lines.pop_front() else {
+                break;
+            };
+            inner.bytes -= dropped.len();
+            inner.truncated = true;
+        }
+    }
+
+    /// Append captured subprocess output, one entry per line
+    pub fn push_output(&self, stream: &str, output: &[u8]) {
+        for line in String::from_utf8_lossy(output).lines() {
+            self.push(format!("[{}] {}", stream, line));
+        }
+    }
+
+    pub fn snapshot(&self) -> JobLogSnapshot {
+        let inner = self.inner.lock().unwrap();
+        JobLogSnapshot {
+            lines: inner.lines.iter().cloned().collect(),
+            truncated: inner.truncated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_is_bounded() {
+        let log = JobLog::with_capacity(32);
+        for i in 0..100 {
+            log.push(format!("line {:03}", i));
+        }
+
+        let snapshot = log.snapshot();
+        assert!(snapshot.truncated);
+        assert!(snapshot.lines.iter().map(String::len).sum::<usize>() <= 32);
+        assert_eq!(snapshot.lines.last().unwrap(), "line 099");
+    }
+
+    #[test]
+    fn test_push_output_splits_lines() {
+        let log = JobLog::new();
+        log.push_output("stderr", b"error: proving failed\ncaused by: docker not running\n");
+
+        let snapshot = log.snapshot();
+        assert!(!snapshot.truncated);
+        assert_eq!(snapshot.lines, ["[stderr] error: proving failed", "[stderr] caused by: docker not running"]);
+    }
+}
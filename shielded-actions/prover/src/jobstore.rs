@@ -0,0 +1,500 @@
+//! Job storage
+//!
+//! Handlers reach jobs only through the [`JobStore`] trait, so the backend can
+//! change without touching them. By default jobs live in memory for the life of
+//! the process ([`MemoryJobStore`]).
+//!
+//! With `JOB_STORE=sqlite:<path>`, every job status transition is written
+//! through to SQLite so jobs survive a restart ([`SqliteJobStore`]). With
+//! `JOB_STORE=redis://...` (built with the `redis` feature), jobs live in Redis
+//! so any of several replicas can answer a poll (`RedisJobStore`). Jobs are
+//! stored as their JSON serialization keyed on `job_id`, which keeps the schema
+//! independent of the fields `JobStatus` grows over time.
+
+use crate::JobStatus;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+/// Change applied to a stored job; returning false leaves the job as it was
+pub type JobUpdate = Box<dyn FnOnce(&mut JobStatus) -> bool + Send>;
+
+#[async_trait]
+pub trait JobStore: Send + Sync {
+    /// Insert or replace a job
+    async fn insert(&self, job: JobStatus) -> Result<()>;
+
+    async fn get(&self, job_id: &str) -> Result<Option<JobStatus>>;
+
+    /// Apply `update` to a job atomically
+    ///
+    /// Returns the updated job, or None when the job is unknown or `update`
+    /// declined the change.
+    async fn update_status(&self, job_id: &str, update: JobUpdate) -> Result<Option<JobStatus>>;
+
+    /// Every stored job, in no particular order
+    async fn list(&self) -> Result<Vec<JobStatus>>;
+
+    /// Jobs started by this process, whose tasks run here
+    ///
+    /// The same as [`list`](Self::list) unless the store is shared between replicas.
+    async fn list_local(&self) -> Result<Vec<JobStatus>> {
+        self.list().await
+    }
+
+    /// Remove a job, returning it if it existed
+    async fn remove(&self, job_id: &str) -> Result<Option<JobStatus>>;
+}
+
+/// Open the store configured by JOB_STORE (in memory when unset)
+///
+/// `ttl` is how long finished jobs are kept (JOB_TTL_SECS).
+pub fn from_env(ttl: Duration) -> Result<Arc<dyn JobStore>> {
+    match std::env::var("JOB_STORE") {
+        Ok(spec) if !spec.trim().is_empty() => open(spec.trim(), ttl),
+        _ => Ok(Arc::new(MemoryJobStore::default())),
+    }
+}
+
+/// Open a store from a `sqlite:<path>` or `redis://...` spec
+pub fn open(spec: &str, ttl: Duration) -> Result<Arc<dyn JobStore>> {
+    if spec.starts_with("redis://") || spec.starts_with("rediss://") {
+        #[cfg(feature = "redis")]
+        return Ok(Arc::new(RedisJobStore::open(spec, ttl)?));
+        #[cfg(not(feature = "redis"))]
+        {
+            let _ = ttl;
+            anyhow::bail!("JOB_STORE=redis://... needs the prover built with --features redis");
+        }
+    }
+    Ok(Arc::new(SqliteJobStore::open(spec)?))
+}
+
+/// Jobs kept in a map, lost on restart
+#[derive(Default)]
+pub struct MemoryJobStore {
+    jobs: RwLock<HashMap<String, JobStatus>>,
+}
+
+impl MemoryJobStore {
+    fn with_jobs(jobs: impl IntoIterator<Item = JobStatus>) -> Self {
+        Self {
+            jobs: RwLock::new(jobs.into_iter().map(|job| (job.job_id.clone(), job)).collect()),
+        }
+    }
+}
+
+#[async_trait]
+impl JobStore for MemoryJobStore {
+    async fn insert(&self, job: JobStatus) -> Result<()> {
+        self.jobs.write().await.insert(job.job_id.clone(), job);
+        Ok(())
+    }
+
+    async fn get(&self, job_id: &str) -> Result<Option<JobStatus>> {
+        Ok(self.jobs.read().await.get(job_id).cloned())
+    }
+
+    async fn update_status(&self, job_id: &str, update: JobUpdate) -> Result<Option<JobStatus>> {
+        let mut jobs = self.jobs.write().await;
+        let Some(job) = jobs.get_mut(job_id) else {
+            return Ok(None);
+        };
+        Ok(update(job).then(|| job.clone()))
+    }
+
+    async fn list(&self) -> Result<Vec<JobStatus>> {
+        Ok(self.jobs.read().await.values().cloned().collect())
+    }
+
+    async fn remove(&self, job_id: &str) -> Result<Option<JobStatus>> {
+        Ok(self.jobs.write().await.remove(job_id))
+    }
+}
+
+/// Jobs written through to SQLite, the source of truth, with an in-memory copy
+///
+/// Logs, the cancellation token and status subscribers are not persisted, so
+/// they are only kept in the in-memory copy.
+pub struct SqliteJobStore {
+    conn: Mutex<Connection>,
+    memory: MemoryJobStore,
+}
+
+impl SqliteJobStore {
+    /// Open a store from a `sqlite:<path>` spec, loading the jobs it holds
+    pub fn open(spec: &str) -> Result<Self> {
+        let path = spec
+            .strip_prefix("sqlite:")
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| anyhow!("Unsupported JOB_STORE '{}' (expected sqlite:<path>)", spec))?;
+
+        let conn = Connection::open(path)
+            .map_err(|e| anyhow!("Failed to open job store {}: {}", path, e))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                job_id TEXT PRIMARY KEY,
+                job TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        let mut store = Self { conn: Mutex::new(conn), memory: MemoryJobStore::default() };
+        let jobs = store.all_rows()?.into_iter().filter_map(|json| match serde_json::from_str::<JobStatus>(&json) {
+            Ok(job) => Some(job),
+            Err(e) => {
+                warn!("Skipping corrupt stored job: {}", e);
+                None
+            }
+        });
+        store.memory = MemoryJobStore::with_jobs(jobs);
+        Ok(store)
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| anyhow!("Job store lock poisoned"))
+    }
+
+    fn persist(&self, job: &JobStatus) -> Result<()> {
+        self.put_row(&job.job_id, &serde_json::to_string(job)?)
+    }
+
+    /// Insert or replace a job's serialized state
+    fn put_row(&self, job_id: &str, job: &str) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO jobs (job_id, job) VALUES (?1, ?2)
+             ON CONFLICT(job_id) DO UPDATE SET job = excluded.job",
+            params![job_id, job],
+        )?;
+        Ok(())
+    }
+
+    fn get_row(&self, job_id: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn()?
+            .query_row("SELECT job FROM jobs WHERE job_id = ?1", params![job_id], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn all_rows(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT job FROM jobs")?;
+        let jobs = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(jobs)
+    }
+
+    fn delete_row(&self, job_id: &str) -> Result<()> {
+        self.conn()?.execute("DELETE FROM jobs WHERE job_id = ?1", params![job_id])?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JobStore for SqliteJobStore {
+    async fn insert(&self, job: JobStatus) -> Result<()> {
+        self.persist(&job)?;
+        self.memory.insert(job).await
+    }
+
+    async fn get(&self, job_id: &str) -> Result<Option<JobStatus>> {
+        let cached = self.memory.get(job_id).await?;
+        let stored = match self.get_row(job_id) {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Failed to read job {} from the job store: {}", job_id, e);
+                return Ok(cached);
+            }
+        };
+        match stored.map(|json| serde_json::from_str::<JobStatus>(&json)) {
+            Some(Ok(mut job)) => {
+                if let Some(cached) = cached {
+                    job.context = cached.context;
+                    job.events = cached.events;
+                }
+                Ok(Some(job))
+            }
+            Some(Err(e)) => {
+                warn!("Stored job {} is corrupt: {}", job_id, e);
+                Ok(cached)
+            }
+            None => Ok(cached),
+        }
+    }
+
+    async fn update_status(&self, job_id: &str, update: JobUpdate) -> Result<Option<JobStatus>> {
+        let updated = self.memory.update_status(job_id, update).await?;
+        if let Some(job) = &updated {
+            self.persist(job)?;
+        }
+        Ok(updated)
+    }
+
+    async fn list(&self) -> Result<Vec<JobStatus>> {
+        self.memory.list().await
+    }
+
+    async fn remove(&self, job_id: &str) -> Result<Option<JobStatus>> {
+        self.delete_row(job_id)?;
+        self.memory.remove(job_id).await
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use self::redis_store::RedisJobStore;
+
+#[cfg(feature = "redis")]
+mod redis_store {
+    use super::*;
+    use deadpool_redis::redis::{self, AsyncCommands};
+    use deadpool_redis::{Pool, Runtime};
+
+    fn job_key(job_id: &str) -> String {
+        format!("job:{}", job_id)
+    }
+
+    /// Jobs kept in Redis under `job:{id}`, shared by every replica
+    ///
+    /// Each write resets the key's expiry to the job TTL. Logs, the
+    /// cancellation token and status subscribers only exist on the replica
+    /// running the job, so cancelling through another replica marks the job
+    /// cancelled without stopping its proof. Updates are atomic within a
+    /// replica; concurrent updates from two replicas are last-writer-wins.
+    pub struct RedisJobStore {
+        pool: Pool,
+        ttl: Duration,
+        /// Jobs started here, with their in-process handles
+        local: MemoryJobStore,
+        /// Serializes this replica's read-modify-write updates
+        updating: tokio::sync::Mutex<()>,
+    }
+
+    impl RedisJobStore {
+        /// Create a connection pool for a `redis://` URL; connections are opened on first use
+        pub fn open(url: &str, ttl: Duration) -> Result<Self> {
+            let pool = deadpool_redis::Config::from_url(url)
+                .create_pool(Some(Runtime::Tokio1))
+                .map_err(|e| anyhow!("Failed to create Redis pool for JOB_STORE: {}", e))?;
+            Ok(Self {
+                pool,
+                ttl: ttl.max(Duration::from_secs(1)),
+                local: MemoryJobStore::default(),
+                updating: tokio::sync::Mutex::new(()),
+            })
+        }
+
+        async fn conn(&self) -> Result<deadpool_redis::Connection> {
+            self.pool.get().await.map_err(|e| anyhow!("Failed to connect to Redis: {}", e))
+        }
+
+        async fn put(&self, job: &JobStatus) -> Result<()> {
+            let json = serde_json::to_string(job)?;
+            let _: () = self.conn().await?.set_ex(job_key(&job.job_id), json, self.ttl.as_secs()).await?;
+            Ok(())
+        }
+
+        async fn fetch(&self, job_id: &str) -> Result<Option<JobStatus>> {
+            let json: Option<String> = self.conn().await?.get(job_key(job_id)).await?;
+            let Some(json) = json else {
+                return Ok(None);
+            };
+            let mut job: JobStatus = serde_json::from_str(&json).map_err(|e| anyhow!("Stored job {} is corrupt: {}", job_id, e))?;
+            self.attach_local(&mut job).await;
+            Ok(Some(job))
+        }
+
+        /// Give a job read from Redis the handles of its local copy, if it runs here
+        async fn attach_local(&self, job: &mut JobStatus) {
+            if let Ok(Some(local)) = self.local.get(&job.job_id).await {
+                job.context = local.context;
+                job.events = local.events;
+            }
+        }
+    }
+
+    #[async_trait]
+    impl JobStore for RedisJobStore {
+        async fn insert(&self, job: JobStatus) -> Result<()> {
+            self.put(&job).await?;
+            self.local.insert(job).await
+        }
+
+        async fn get(&self, job_id: &str) -> Result<Option<JobStatus>> {
+            match self.fetch(job_id).await {
+                Ok(job) => Ok(job),
+                Err(e) => {
+                    warn!("Failed to read job {} from Redis: {}", job_id, e);
+                    self.local.get(job_id).await
+                }
+            }
+        }
+
+        async fn update_status(&self, job_id: &str, update: JobUpdate) -> Result<Option<JobStatus>> {
+            let _updating = self.updating.lock().await;
+            let Some(mut job) = self.fetch(job_id).await? else {
+                return Ok(None);
+            };
+            if !update(&mut job) {
+                return Ok(None);
+            }
+            self.put(&job).await?;
+            if self.local.get(job_id).await?.is_some() {
+                self.local.insert(job.clone()).await?;
+            }
+            Ok(Some(job))
+        }
+
+        async fn list(&self) -> Result<Vec<JobStatus>> {
+            let mut conn = self.conn().await?;
+            let keys: Vec<String> = {
+                let mut keys = Vec::new();
+                let mut iter = conn.scan_match::<_, String>("job:*").await?;
+                while let Some(key) = iter.next_item().await {
+                    keys.push(key);
+                }
+                keys
+            };
+            if keys.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+            let mut jobs = Vec::with_capacity(values.len());
+            // Keys that expired since the scan come back as nil
+            for json in values.into_iter().flatten() {
+                match serde_json::from_str::<JobStatus>(&json) {
+                    Ok(mut job) => {
+                        self.attach_local(&mut job).await;
+                        jobs.push(job);
+                    }
+                    Err(e) => warn!("Skipping corrupt stored job: {}", e),
+                }
+            }
+            Ok(jobs)
+        }
+
+        async fn list_local(&self) -> Result<Vec<JobStatus>> {
+            self.local.list().await
+        }
+
+        async fn remove(&self, job_id: &str) -> Result<Option<JobStatus>> {
+            let removed = self.get(job_id).await?;
+            let _: () = self.conn().await?.del(job_key(job_id)).await?;
+            self.local.remove(job_id).await?;
+            Ok(removed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(job_id: &str, status: &str) -> JobStatus {
+        JobStatus {
+            job_id: job_id.to_string(),
+            status: status.to_string(),
+            priority: Default::default(),
+            proof: None,
+            error: None,
+            error_code: None,
+            created_at: 0,
+            started_at: None,
+            completed_at: None,
+            failed_at: None,
+            nullifier_spent: None,
+            simulation: None,
+            estimated_gas: None,
+            callback_url: None,
+            callback_delivered: false,
+            api_key_id: None,
+            context: Default::default(),
+            events: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_final_status_is_timestamped_once() {
+        let mut completed = job("a", "generating");
+        completed.created_at = 100;
+        completed.stamp_finished(150);
+        assert_eq!((completed.completed_at, completed.failed_at, completed.duration_secs()), (None, None, None));
+
+        completed.status = "completed".to_string();
+        completed.stamp_finished(160);
+        completed.stamp_finished(170);
+        assert_eq!((completed.completed_at, completed.duration_secs()), (Some(160), Some(60)));
+
+        let mut cancelled = job("b", "cancelled");
+        cancelled.stamp_finished(5);
+        assert_eq!((cancelled.failed_at, cancelled.completed_at), (Some(5), None));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_updates_and_removes() {
+        let store = MemoryJobStore::default();
+        store.insert(job("a", "pending")).await.unwrap();
+
+        let updated = store.update_status("a", Box::new(|job| {
+            job.status = "generating".to_string();
+            true
+        })).await.unwrap();
+        assert_eq!(updated.unwrap().status, "generating");
+
+        // A declined update changes nothing
+        assert!(store.update_status("a", Box::new(|job| {
+            job.status = "failed".to_string();
+            false
+        })).await.unwrap().is_none());
+        assert_eq!(store.get("a").await.unwrap().unwrap().status, "generating");
+        assert!(store.update_status("missing", Box::new(|_| true)).await.unwrap().is_none());
+
+        assert_eq!(store.list().await.unwrap().len(), 1);
+        assert!(store.remove("a").await.unwrap().is_some());
+        assert!(store.get("a").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("jobs-{}.db", hex::encode(rand::random::<[u8; 4]>())));
+        let spec = format!("sqlite:{}", path.display());
+        let store = SqliteJobStore::open(&spec).unwrap();
+
+        store.insert(job("a", "pending")).await.unwrap();
+        store.insert(job("b", "failed")).await.unwrap();
+        store.update_status("a", Box::new(|job| {
+            job.status = "completed".to_string();
+            true
+        })).await.unwrap();
+        assert_eq!(store.get_row("a").unwrap().map(|json| json.contains(r#""status":"completed""#)), Some(true));
+        store.remove("b").await.unwrap();
+        assert!(store.get_row("b").unwrap().is_none());
+
+        // Reopening loads the same jobs
+        drop(store);
+        let store = SqliteJobStore::open(&spec).unwrap();
+        let jobs = store.list().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, "completed");
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_rejects_unknown_backend() {
+        assert!(SqliteJobStore::open("postgres://localhost/jobs").is_err());
+        assert!(SqliteJobStore::open("sqlite:").is_err());
+
+        let redis = open("redis://127.0.0.1:6379", Duration::from_secs(60));
+        if cfg!(feature = "redis") {
+            assert!(redis.is_ok());
+        } else {
+            assert!(redis.err().unwrap().to_string().contains("--features redis"));
+        }
+    }
+}
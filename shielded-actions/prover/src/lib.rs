@@ -0,0 +1,41 @@
+//! Shielded Actions prover library
+//!
+//! Exposes the proof builders and the prover service so other crates can embed
+//! proving directly. The `shielded-prover` server and the `local-prove` CLI are
+//! thin wrappers around this crate.
+
+pub mod allowlist;
+pub mod amount;
+pub mod apikeys;
+pub mod backend;
+pub mod balance;
+pub mod chain;
+pub mod commitment_tree;
+pub mod config;
+pub mod error;
+pub mod estimate;
+pub mod idempotency;
+pub mod job;
+pub mod joblog;
+pub mod nullifiers;
+pub mod proofcache;
+pub mod proofs;
+pub mod prover;
+pub mod ratelimit;
+pub mod secret;
+pub mod shield_logic;
+pub mod signature;
+pub mod telemetry;
+pub mod tokens;
+pub mod webhook;
+
+pub use config::Config;
+pub use error::ProverError;
+pub use forwarder_logic_witness::{DeletionCriterion, PermitData};
+pub use proofs::{
+    build_batch_transaction, build_ephemeral_transaction, build_raw_transaction, build_shield_transaction, build_swap_transaction, build_test_transaction,
+    build_unshield_transaction, encode_execute_calldata, fresh_nonce, BatchEntry, BatchOp, Nonce, ProofMode, ProvenActions, RawCall, SwapOrder,
+};
+pub use prover::{OperationSummary, ProofData, ProofResponse, ProverService};
+pub use shield_logic::ShieldLogicWitness;
+pub use tokens::{TokenInfo, TokenRegistry};
@@ -6,38 +6,105 @@
 //!
 //! Usage:
 //!   cargo run --release --bin local-prove -- test
-//!   cargo run --release --bin local-prove -- shield --token WETH --amount 0.01 --sender 0x...
+//!   cargo run --release --bin local-prove -- shield --token WETH --amount 0.01 --human --sender 0x...
+//!   cargo run --release --bin local-prove -- swap --token-in USDC --amount-in 1000000 --token-out WETH --min-amount-out 250000000000000
+//!   cargo run --release --bin local-prove -- raw --forwarder 0x... --call-data 0x... --expected-output 0x...
+//!   cargo run --release --bin local-prove -- verify --file shield_usdc_1000000.bin
+//!   cargo run --release --bin local-prove -- submit --file shield_usdc_1000000.bin --rpc-url <url> --private-key <key>
+//!   cargo run --release --bin local-prove -- shield --persistent --nullifier-key <key> --sender 0x...
+//!   cargo run --release --bin local-prove -- unshield --note <file>.note.json --nullifier-key <key> --recipient 0x...
+//!   cargo run --release --bin local-prove -- tree sync --rpc-url <url>
 
-use alloy::sol_types::SolValue;
 use anyhow::{anyhow, Result};
 use clap::{Parser, Subcommand};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Instant;
 
-// ARM-RISC0 imports for real proving
-use arm::action_tree::MerkleTree;
-use arm::compliance::{ComplianceWitness, INITIAL_ROOT};
-use arm::compliance_unit::ComplianceUnit;
-use arm::action::Action;
-use arm::delta_proof::DeltaWitness;
-use arm::logic_proof::LogicProver;  // Trait needed for .prove() and .verifying_key()
+use alloy::primitives::B256;
+use arm::compliance::INITIAL_ROOT;
+use arm::logic_proof::LogicProver;  // Trait needed for .proving_key() and .verifying_key()
 use arm::nullifier_key::NullifierKey;
-use arm::proving_system::ProofType;
-use arm::resource::Resource;
-use arm::resource_logic::TrivialLogicWitness;  // For ephemeral resources
-use arm::transaction::{Delta, Transaction};
-
-// Forwarder logic witness for shield/unshield with external_payload
-use forwarder_logic_witness::ForwarderLogicWitness;
+use arm::resource_logic::TrivialLogicWitness;
+use arm::transaction::Transaction;
+
+use forwarder_logic_witness::{ForwarderLogicWitness, PermitData};
+
+use shielded_prover::allowlist::{allow_list_from_env, check_recipient};
+use shielded_prover::amount::{ensure_nonzero, parse_token_amount};
+use shielded_prover::balance::{balance_report, BalanceOperation};
+use shielded_prover::chain;
+use shielded_prover::commitment_tree::{tree_file, CommitmentTree};
+use shielded_prover::proofs::{
+    build_test_transaction, fresh_nonce, get_forwarder_address, parse_checksummed_address, parse_nonce,
+    parse_nullifier_key, proof_file_name, prove_ephemeral_actions, prove_persistent_shield_actions,
+    prove_persistent_unshield_actions, prove_raw_actions, prove_shield_actions, prove_swap_actions,
+    prove_unshield_actions, shield_proof_file_name, swap_balance, swap_proof_file_name, verified_calldata, verify_execute_calldata, Nonce,
+    ProofMetadata, ProofMode, ProofOutput, ProvenActions, RawCall, ShieldedNote, SwapOrder, DEFAULT_SWAP_FEE,
+};
+use shielded_prover::signature::PermitRequest;
+use shielded_prover::{Config, DeletionCriterion};
+
+/// Set by `--json`: stdout carries only the result JSON
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--gas-limit`: overrides the suggested gas limit (0 when unset)
+static GAS_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Human-readable output: stdout normally, stderr with `--json`
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if JSON_OUTPUT.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
-// EVM Protocol Adapter bindings
-use evm_protocol_adapter_bindings::contract::ProtocolAdapter;
+/// Print a command's result: pretty JSON after the human output, or the one stdout line with `--json`
+fn emit_json<T: Serialize>(value: &T) -> Result<()> {
+    if JSON_OUTPUT.load(Ordering::Relaxed) {
+        println!("{}", serde_json::to_string(value)?);
+    } else {
+        println!("JSON output:");
+        println!("{}", serde_json::to_string_pretty(value)?);
+    }
+    Ok(())
+}
 
 /// Shielded Actions Local Prover
 #[derive(Parser)]
 #[command(name = "local-prove")]
-#[command(about = "Generate ZK proofs locally for shielded transactions on Sepolia")]
+#[command(about = "Generate ZK proofs locally for shielded transactions")]
 struct Cli {
+    /// Deployment to target: sepolia, anvil or a deployment .json file (default: NETWORK or sepolia)
+    #[arg(long, global = true)]
+    network: Option<String>,
+
+    /// Protocol Adapter the calldata targets (default: PROTOCOL_ADAPTER_ADDRESS or the NETWORK deployment)
+    #[arg(long, global = true)]
+    protocol_adapter: Option<String>,
+
+    /// 32-byte hex nonce of the consumed resource, for reproducible proofs (default: random)
+    ///
+    /// Reusing a nonce reuses the nullifier, so only one transaction per nonce can execute on-chain.
+    #[arg(long, global = true, value_parser = parse_nonce)]
+    nonce: Option<Nonce>,
+
+    /// Print only the result JSON to stdout (human-readable output goes to stderr)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Gas limit for the transaction: used by submit and put in the JSON output and `cast send` hint
+    /// (default: eth_estimateGas through RPC_URL, else a heuristic from the number of units and calls)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u64).range(21_000..))]
+    gas_limit: Option<u64>,
+
+    /// Proofs to generate: groth16 (verifies on-chain, needs Docker), succinct or fake (local iteration only)
+    #[arg(long, global = true, default_value = "groth16")]
+    proof_type: ProofMode,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -53,10 +120,18 @@ enum Commands {
         /// Number of compliance units per action
         #[arg(long, default_value = "1")]
         compliance_units: usize,
+
+        /// Output path for the calldata (defaults to a name derived from the parameters)
+        #[arg(long)]
+        out: Option<String>,
     },
 
     /// Generate a test proof using ephemeral resources (uses INITIAL_ROOT)
-    TestEphemeral,
+    TestEphemeral {
+        /// Output path for the calldata (defaults to ephemeral_test_tx.bin)
+        #[arg(long)]
+        out: Option<String>,
+    },
 
     /// Generate a shield proof that triggers a forwarder call (transferFrom)
     Shield {
@@ -66,11 +141,36 @@ enum Commands {
 
         /// Amount to shield (in smallest units, e.g. 1000000 for 1 USDC)
         #[arg(long, default_value = "1000000")]
-        amount: u128,
+        amount: String,
+
+        /// Interpret --amount as a human-readable value (e.g. 0.1) scaled by token decimals
+        #[arg(long)]
+        human: bool,
 
         /// Sender address (20 bytes hex, will call transferFrom from this address)
-        #[arg(long, default_value = "0x0000000000000000000000000000000000000001")]
+        #[arg(long, default_value = "0x0000000000000000000000000000000000000001", value_parser = checksummed_address)]
         sender: String,
+
+        /// When the forwarder call blob may be deleted on-chain (never, immediately)
+        #[arg(long, default_value = "never")]
+        deletion_criterion: DeletionCriterion,
+
+        /// EIP-2612 permit by the sender for the forwarder, bundled so no prior approve is needed
+        /// (JSON: {"value": "...", "deadline": 1700000000, "v": 27, "r": "0x...", "s": "0x..."})
+        #[arg(long, value_parser = parse_permit)]
+        permit: Option<PermitData>,
+
+        /// Create a persistent resource holding the amount, saved as a note for a later unshield
+        #[arg(long, requires = "nullifier_key")]
+        persistent: bool,
+
+        /// Hex nullifier key owning the persistent resource (e.g. the private key from /api/generate-keypair)
+        #[arg(long)]
+        nullifier_key: Option<String>,
+
+        /// Output path for the calldata (defaults to a name derived from the parameters)
+        #[arg(long)]
+        out: Option<String>,
     },
 
     /// Generate an unshield proof that triggers a forwarder call (transfer)
@@ -81,11 +181,148 @@ enum Commands {
 
         /// Amount to unshield (in smallest units)
         #[arg(long, default_value = "1000000")]
-        amount: u128,
+        amount: String,
+
+        /// Interpret --amount as a human-readable value (e.g. 0.1) scaled by token decimals
+        #[arg(long)]
+        human: bool,
 
         /// Recipient address (20 bytes hex, will receive tokens)
-        #[arg(long, default_value = "0x0000000000000000000000000000000000000001")]
+        #[arg(long, default_value = "0x0000000000000000000000000000000000000001", value_parser = checksummed_address)]
         recipient: String,
+
+        /// When the forwarder call blob may be deleted on-chain (never, immediately)
+        #[arg(long, default_value = "never")]
+        deletion_criterion: DeletionCriterion,
+
+        /// Note written by `shield --persistent`: withdraw its whole resource (ignores --token and --amount)
+        #[arg(long, requires = "nullifier_key")]
+        note: Option<String>,
+
+        /// Hex nullifier key owning the note's resource
+        #[arg(long)]
+        nullifier_key: Option<String>,
+
+        /// Output path for the calldata (defaults to a name derived from the parameters)
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Generate a swap proof that triggers a Uniswap forwarder call (exactInputSingle)
+    Swap {
+        /// Token to sell (USDC or WETH)
+        #[arg(long, default_value = "USDC")]
+        token_in: String,
+
+        /// Amount to sell (in token_in's smallest units)
+        #[arg(long, default_value = "1000000")]
+        amount_in: String,
+
+        /// Token to buy (USDC or WETH)
+        #[arg(long, default_value = "WETH")]
+        token_out: String,
+
+        /// Minimum amount bought (in token_out's smallest units); the swap must settle at exactly this amount
+        #[arg(long)]
+        min_amount_out: String,
+
+        /// Interpret --amount-in and --min-amount-out as human-readable values scaled by token decimals
+        #[arg(long)]
+        human: bool,
+
+        /// Uniswap pool fee tier in hundredths of a bip (500, 3000, 10000)
+        #[arg(long, default_value_t = DEFAULT_SWAP_FEE)]
+        fee: u32,
+
+        /// When the forwarder call blob may be deleted on-chain (never, immediately)
+        #[arg(long, default_value = "never")]
+        deletion_criterion: DeletionCriterion,
+
+        /// Output path for the calldata (defaults to a name derived from the parameters)
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Generate a proof whose created resource sends pre-built calldata to a forwarder
+    ///
+    /// Nothing checks the call; prefer shield, unshield or swap when one fits.
+    Raw {
+        /// Forwarder contract address
+        #[arg(long)]
+        forwarder: String,
+
+        /// Hex calldata, including the function selector
+        #[arg(long)]
+        call_data: String,
+
+        /// Hex return data the forwarder call must produce (e.g. abi.encode(true))
+        #[arg(long, default_value = "")]
+        expected_output: String,
+
+        /// When the forwarder call blob may be deleted on-chain (never, immediately)
+        #[arg(long, default_value = "never")]
+        deletion_criterion: DeletionCriterion,
+
+        /// Output path for the calldata (defaults to a name derived from the parameters)
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Write the embedded forwarder logic guest ELF to a file and print the logic image IDs
+    ExportGuest {
+        /// Output path for the guest ELF
+        #[arg(long, default_value = "forwarder_logic_guest.elf")]
+        out: String,
+    },
+
+    /// Retry the delta proof for actions saved after a failed delta proof
+    Rebalance {
+        /// Pending delta file written by a failed run (e.g. shield_usdc_1000000.bin.pending.json)
+        #[arg(long)]
+        file: String,
+    },
+
+    /// Report the per-label net quantity of a batch without proving
+    Balance {
+        /// JSON file with a list of {"label", "quantity", "side": "consumed" | "created"} operations
+        #[arg(long)]
+        spec: String,
+    },
+
+    /// Re-check a calldata file written by shield/unshield/swap/test
+    ///
+    /// Decodes the calldata back into a transaction and verifies its proofs and
+    /// balance locally, then, with an RPC, simulates execute on the Protocol
+    /// Adapter. Exits non-zero on failure.
+    Verify {
+        /// Calldata file (e.g. shield_usdc_1000000.bin)
+        #[arg(long)]
+        file: String,
+
+        /// Ethereum RPC to simulate execute with (defaults to RPC_URL)
+        #[arg(long)]
+        rpc_url: Option<String>,
+    },
+
+    /// Sign and send a calldata file to the Protocol Adapter, then wait for the receipt
+    Submit {
+        /// Calldata file (e.g. shield_usdc_1000000.bin)
+        #[arg(long)]
+        file: String,
+
+        /// Ethereum RPC to send the transaction through (defaults to RPC_URL)
+        #[arg(long)]
+        rpc_url: Option<String>,
+
+        /// Hex private key of the sending account (defaults to PRIVATE_KEY)
+        #[arg(long)]
+        private_key: Option<String>,
+    },
+
+    /// Inspect or update the local commitment tree (COMMITMENT_TREE_FILE) used by persistent unshields
+    Tree {
+        #[command(subcommand)]
+        action: TreeAction,
     },
 
     /// Show info about prerequisites
@@ -95,54 +332,158 @@ enum Commands {
     CheckRoot,
 }
 
-/// Contract addresses on Sepolia
-const PROTOCOL_ADAPTER: &str = "0x08c3bdc46B115cDc71Df076d9De96EeEBaa98525";
-const USDC_FORWARDER: &str = "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE";
-const WETH_FORWARDER: &str = "0xD5307D777dC60b763b74945BF5A42ba93ce44e4b";
+#[derive(Subcommand)]
+enum TreeAction {
+    /// Print the local tree and, with an RPC, compare it with the Protocol Adapter's
+    Show {
+        /// Ethereum RPC to read the adapter's tree from (defaults to RPC_URL)
+        #[arg(long)]
+        rpc_url: Option<String>,
+    },
+
+    /// Append commitments executed without `local-prove submit`
+    Add {
+        /// Hex commitments, in execution order
+        #[arg(required = true)]
+        commitments: Vec<String>,
+    },
 
-/// Function selector for execute(Transaction) - ed3cf91f
-const EXECUTE_SELECTOR: [u8; 4] = [0xed, 0x3c, 0xf9, 0x1f];
+    /// Rebuild the local tree from the Protocol Adapter's TransactionExecuted events
+    Sync {
+        /// Ethereum RPC to read the events from (defaults to RPC_URL)
+        #[arg(long)]
+        rpc_url: Option<String>,
 
-/// Output format for successful proofs
-#[derive(Debug, Serialize)]
-struct ProofOutput {
-    calldata: String,
-    to: String,
-    calldata_length: usize,
-    metadata: ProofMetadata,
+        /// Block to start from, at or before the adapter's deployment
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+    },
+}
+
+/// What `tree show` reports
+#[derive(Serialize)]
+struct TreeReport {
+    file: String,
+    commitments: usize,
+    depth: usize,
+    root: B256,
+    /// Whether the Protocol Adapter has recorded `root`, when an RPC was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_known_on_chain: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
-struct ProofMetadata {
-    proof_type: String,
-    num_actions: usize,
-    num_compliance_units: usize,
-    generation_time_secs: f64,
+/// Proven actions whose delta proof failed, saved so the expensive logic and
+/// compliance proofs are not discarded
+#[derive(Serialize, Deserialize)]
+struct PendingDelta {
+    #[serde(flatten)]
+    proven: ProvenActions,
+    /// Proof type the actions were proven with (files from before it was recorded are Groth16)
+    #[serde(default)]
+    mode: ProofMode,
+    /// Where the final calldata should be written
+    output_path: String,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-
-    println!("╔════════════════════════════════════════════╗");
-    println!("║   Shielded Actions Local Prover            ║");
-    println!("║   RISC Zero zkVM • Sepolia Testnet         ║");
-    println!("╚════════════════════════════════════════════╝\n");
+    JSON_OUTPUT.store(cli.json, Ordering::Relaxed);
+    GAS_LIMIT.store(cli.gas_limit.unwrap_or(0), Ordering::Relaxed);
+
+    // Library progress logs go to stderr so stdout stays readable
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    say!("╔════════════════════════════════════════════╗");
+    say!("║   Shielded Actions Local Prover            ║");
+    say!("║   RISC Zero zkVM                           ║");
+    say!("╚════════════════════════════════════════════╝\n");
+
+    let config = Config::from_env_for(cli.network.as_deref())?.with_protocol_adapter(cli.protocol_adapter.as_deref())?;
+    say!("Network: {} (chain {})\n", config.network, config.chain_id);
+    let adapter = config.protocol_adapter.clone();
+    let nonce = cli.nonce.unwrap_or_else(fresh_nonce);
+    let mode = cli.proof_type;
+    if !mode.verifies_on_chain() {
+        say!("⚠ --proof-type {}: proofs skip Docker but will NOT verify on-chain\n", mode.as_str());
+    }
+    if mode == ProofMode::Fake {
+        // RISC Zero produces fake receipts in dev mode
+        std::env::set_var("RISC0_DEV_MODE", "1");
+    }
 
     match cli.command {
-        Commands::Test { actions, compliance_units } => {
-            generate_test_proof(actions, compliance_units)?;
+        Commands::Test { actions, compliance_units, out } => {
+            generate_test_proof(&config, actions, compliance_units, out, mode)?;
+        }
+        Commands::TestEphemeral { out } => {
+            generate_ephemeral_test_proof(&config, out, nonce, mode)?;
+        }
+        Commands::Shield { token, amount, human, sender, deletion_criterion, permit, persistent, nullifier_key, out } => {
+            let amount = resolve_amount(&config, &amount, &token, human)?;
+            let owner = match nullifier_key {
+                Some(key) if persistent => Some(NullifierKey::from_bytes(&parse_nullifier_key(&key)?)),
+                _ => None,
+            };
+            generate_shield_proof(&config, &token, amount, &sender, deletion_criterion, permit, owner.as_ref(), out, nonce, mode)?;
+        }
+        Commands::Unshield { token, amount, human, recipient, deletion_criterion, note, nullifier_key, out } => {
+            if let (Some(note), Some(key)) = (note, nullifier_key) {
+                let owner = NullifierKey::from_bytes(&parse_nullifier_key(&key)?);
+                generate_note_unshield_proof(&config, &note, &owner, &recipient, deletion_criterion, out, mode)?;
+            } else {
+                let amount = resolve_amount(&config, &amount, &token, human)?;
+                generate_unshield_proof(&config, &token, amount, &recipient, deletion_criterion, out, nonce, mode)?;
+            }
+        }
+        Commands::Swap { token_in, amount_in, token_out, min_amount_out, human, fee, deletion_criterion, out } => {
+            let order = SwapOrder {
+                amount_in: resolve_amount(&config, &amount_in, &token_in, human)?,
+                min_amount_out: resolve_amount(&config, &min_amount_out, &token_out, human)?,
+                token_in,
+                token_out,
+                fee,
+            };
+            generate_swap_proof(&config, &order, deletion_criterion, out, nonce, mode)?;
+        }
+        Commands::Raw { forwarder, call_data, expected_output, deletion_criterion, out } => {
+            let raw = RawCall::parse(&forwarder, &call_data, &expected_output)?;
+            generate_raw_proof(&config, &raw, deletion_criterion, out, nonce, mode)?;
+        }
+        Commands::ExportGuest { out } => {
+            export_guest(&out)?;
         }
-        Commands::TestEphemeral => {
-            generate_ephemeral_test_proof()?;
+        Commands::Rebalance { file } => {
+            rebalance(&adapter, &file)?;
         }
-        Commands::Shield { token, amount, sender } => {
-            generate_shield_proof(&token, amount, &sender)?;
+        Commands::Balance { spec } => {
+            print_balance(&spec)?;
         }
-        Commands::Unshield { token, amount, recipient } => {
-            generate_unshield_proof(&token, amount, &recipient)?;
+        Commands::Verify { file, rpc_url } => {
+            let rpc_url = rpc_url.or_else(|| std::env::var("RPC_URL").ok().filter(|url| !url.is_empty()));
+            verify_file(&adapter, &file, rpc_url.as_deref())?;
+        }
+        Commands::Submit { file, rpc_url, private_key } => {
+            let rpc_url = rpc_url.or_else(|| std::env::var("RPC_URL").ok().filter(|url| !url.is_empty()))
+                .ok_or_else(|| anyhow!("Pass --rpc-url or set RPC_URL"))?;
+            let private_key = private_key.or_else(|| std::env::var("PRIVATE_KEY").ok().filter(|key| !key.is_empty()))
+                .ok_or_else(|| anyhow!("Pass --private-key or set PRIVATE_KEY"))?;
+            submit_file(&adapter, &file, &rpc_url, &private_key, cli.gas_limit)?;
+        }
+        Commands::Tree { action } => {
+            let env_rpc = || std::env::var("RPC_URL").ok().filter(|url| !url.is_empty());
+            match action {
+                TreeAction::Show { rpc_url } => show_tree(&adapter, rpc_url.or_else(env_rpc).as_deref())?,
+                TreeAction::Add { commitments } => add_to_tree(&commitments)?,
+                TreeAction::Sync { rpc_url, from_block } => {
+                    let rpc_url = rpc_url.or_else(env_rpc).ok_or_else(|| anyhow!("Pass --rpc-url or set RPC_URL"))?;
+                    sync_tree(&adapter, &rpc_url, from_block)?;
+                }
+            }
         }
         Commands::Info => {
-            print_info();
+            print_info(&config);
         }
         Commands::CheckRoot => {
             check_initial_root();
@@ -152,99 +493,101 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn print_info() {
-    println!("PREREQUISITES:");
-    println!("  1. Install RISC Zero:");
-    println!("     curl -L https://risczero.com/install | sh");
-    println!();
-    println!("  2. Install toolchain:");
-    println!("     rzup install");
-    println!();
-    println!("USAGE:");
-    println!("  # Test local proving works:");
-    println!("  cargo run --release --bin local-prove -- test");
-    println!();
-    println!("  # Generate with specific action/CU count:");
-    println!("  cargo run --release --bin local-prove -- test --actions 1 --compliance-units 1");
-    println!();
-    println!("CONTRACTS (Sepolia):");
-    println!("  ProtocolAdapter: {}", PROTOCOL_ADAPTER);
-    println!("  WETH Forwarder:  0xD5307D777dC60b763b74945BF5A42ba93ce44e4b");
-    println!("  USDC Forwarder:  0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE");
+fn print_info(config: &Config) {
+    say!("PREREQUISITES:");
+    say!("  1. Install RISC Zero:");
+    say!("     curl -L https://risczero.com/install | sh");
+    say!();
+    say!("  2. Install toolchain:");
+    say!("     rzup install");
+    say!();
+    say!("USAGE:");
+    say!("  # Test local proving works:");
+    say!("  cargo run --release --bin local-prove -- test");
+    say!();
+    say!("  # Generate with specific action/CU count:");
+    say!("  cargo run --release --bin local-prove -- test --actions 1 --compliance-units 1");
+    say!();
+    say!("CONTRACTS ({}):", config.network);
+    say!("  ProtocolAdapter: {}", config.protocol_adapter);
+    for token in config.tokens.iter() {
+        say!("  {} Forwarder:  {}", token.symbol, token.forwarder_address);
+    }
+    say!("  Uniswap Forwarder: {}", config.uniswap_forwarder);
+}
+
+/// Export the forwarder logic guest program for verifier registration and audits
+fn export_guest(out: &str) -> Result<()> {
+    let elf = ForwarderLogicWitness::proving_key();
+    std::fs::write(out, elf)?;
+
+    // Computed from the embedded ELF, so it is the image ID of the exported file
+    let forwarder_id = ForwarderLogicWitness::verifying_key();
+
+    say!("Exported forwarder logic guest ELF");
+    say!("  Saved to: {}", out);
+    say!("  Size:     {} bytes", elf.len());
+    say!();
+    say!("IMAGE IDS:");
+    say!("  Forwarder logic: 0x{}", hex::encode(forwarder_id.as_bytes()));
+    say!("  Trivial logic:   0x{}", hex::encode(TrivialLogicWitness::verifying_key().as_bytes()));
+
+    Ok(())
 }
 
 fn check_initial_root() {
-    println!("Checking ARM INITIAL_ROOT value...\n");
+    say!("Checking ARM INITIAL_ROOT value...\n");
 
     // The on-chain EMPTY_HASH is sha256("EMPTY") = 0xcc1d2f838445db7aec431df9ee8a871f40e7aa5e064fc056633ef8c60fab7b06
     let expected_on_chain = "cc1d2f838445db7aec431df9ee8a871f40e7aa5e064fc056633ef8c60fab7b06";
 
     let initial_root_hex = hex::encode(INITIAL_ROOT.as_bytes());
 
-    println!("ARM INITIAL_ROOT:     0x{}", initial_root_hex);
-    println!("On-chain EMPTY_HASH:  0x{}", expected_on_chain);
-    println!();
+    say!("ARM INITIAL_ROOT:     0x{}", initial_root_hex);
+    say!("On-chain EMPTY_HASH:  0x{}", expected_on_chain);
+    say!();
 
     if initial_root_hex == expected_on_chain {
-        println!("✓ MATCH! The ARM INITIAL_ROOT matches the on-chain EMPTY_HASH.");
-        println!("  Ephemeral resources using INITIAL_ROOT will verify on-chain.");
+        say!("✓ MATCH! The ARM INITIAL_ROOT matches the on-chain EMPTY_HASH.");
+        say!("  Ephemeral resources using INITIAL_ROOT will verify on-chain.");
     } else {
-        println!("✗ MISMATCH! The roots don't match.");
-        println!("  This means the ARM version may not be compatible with the deployed contracts.");
+        say!("✗ MISMATCH! The roots don't match.");
+        say!("  This means the ARM version may not be compatible with the deployed contracts.");
     }
 }
 
 /// Generate a test proof using ARM's test transaction generator
-fn generate_test_proof(n_actions: usize, n_cus: usize) -> Result<()> {
-    println!("Generating TEST proof...");
-    println!("  Actions: {}", n_actions);
-    println!("  Compliance Units per Action: {}", n_cus);
-    println!();
+fn generate_test_proof(config: &Config, n_actions: usize, n_cus: usize, out: Option<String>, mode: ProofMode) -> Result<()> {
+    say!("Generating TEST proof...");
+    say!("  Actions: {}", n_actions);
+    say!("  Compliance Units per Action: {}", n_cus);
+    say!();
 
     let start = Instant::now();
 
-    // Use ARM's built-in test transaction generator
-    // This creates a valid transaction with proper resources
-    // Use Groth16 proofs for on-chain verification (Succinct/STARK proofs can't be verified on-chain)
-    println!("Building test transaction...");
-    let mut tx = arm_tests::generate_test_transaction(n_actions, n_cus, ProofType::Groth16);
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run (compiling circuits)");
+    say!("  Subsequent runs will be faster (cached)");
+    say!();
 
-    println!("Generating ZK proofs...");
-    println!("  This may take several minutes on first run (compiling circuits)");
-    println!("  Subsequent runs will be faster (cached)");
-    println!();
-
-    // The test transaction generator already includes proofs
-    // but we need to generate the delta proof
-    tx = tx.generate_delta_proof()
-        .map_err(|e| anyhow!("Delta proof generation failed: {:?}", e))?;
+    let tx = build_test_transaction(n_actions, n_cus, mode)?;
 
     let elapsed = start.elapsed();
-    println!("\n✓ Proof generation complete!");
-    println!("  Time: {:.2}s", elapsed.as_secs_f64());
-
-    // Verify locally (clone because verify() takes ownership)
-    println!("\nVerifying proofs locally...");
-    tx.clone().verify()
-        .map_err(|e| anyhow!("Verification failed: {:?}", e))?;
-    println!("✓ Verification passed!");
-
-    // Convert to EVM format using the bindings
-    println!("\nConverting to EVM format...");
-    let evm_tx = ProtocolAdapter::Transaction::from(tx);
-    let abi_encoded = evm_tx.abi_encode();
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
 
-    // Build full calldata with function selector
-    let mut calldata = Vec::with_capacity(4 + abi_encoded.len());
-    calldata.extend_from_slice(&EXECUTE_SELECTOR);
-    calldata.extend_from_slice(&abi_encoded);
+    // Verify locally
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(tx)?;
+    say!("✓ Verification passed!");
 
     let output = ProofOutput {
         calldata: format!("0x{}", hex::encode(&calldata)),
-        to: PROTOCOL_ADAPTER.to_string(),
+        to: config.protocol_adapter.clone(),
         calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
         metadata: ProofMetadata {
-            proof_type: "Groth16".to_string(),
+            proof_type: format!("{:?}", mode),
             num_actions: n_actions,
             num_compliance_units: n_cus,
             generation_time_secs: elapsed.as_secs_f64(),
@@ -252,164 +595,104 @@ fn generate_test_proof(n_actions: usize, n_cus: usize) -> Result<()> {
     };
 
     // Save full calldata (with selector) to file
-    let output_path = format!("test_tx_{}_{}.bin", n_actions, n_cus);
+    let output_path = out.unwrap_or_else(|| output_file_name(format!("test_tx_{}_{}.bin", n_actions, n_cus), mode));
     std::fs::write(&output_path, &calldata)?;
 
-    println!("\n════════════════════════════════════════════");
-    println!("  TRANSACTION READY FOR ON-CHAIN EXECUTION");
-    println!("════════════════════════════════════════════");
-    println!();
-    println!("Target: {}", PROTOCOL_ADAPTER);
-    println!("Calldata: {} bytes (includes function selector)", calldata.len());
-    println!("Saved to: {}", output_path);
-    println!();
-    println!("To execute on Sepolia:");
-    println!("  # Using cast:");
-    println!("  cast send {} --data 0x$(xxd -p {} | tr -d '\\\\n') \\",
-             PROTOCOL_ADAPTER, output_path);
-    println!("    --rpc-url https://ethereum-sepolia-rpc.publicnode.com \\");
-    println!("    --private-key <YOUR_KEY> --gas-limit 1200000");
+    say!("\n════════════════════════════════════════════");
+    say!("  TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes (includes function selector)", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say_execute_hint(config, &output_path, output.estimated_gas);
 
     // Also output JSON for programmatic use
-    println!("\nJSON output:");
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    say!();
+    emit_json(&output)?;
 
-    println!("\n✓ LOCAL PROVING WORKS!");
-    println!("  You can now build custom transactions for shield/swap/unshield.");
+    say!("\n✓ LOCAL PROVING WORKS!");
+    say!("  You can now build custom transactions for shield/swap/unshield.");
 
     Ok(())
 }
 
+/// Gas limit to suggest for `calldata`: `--gas-limit` when given, else eth_estimateGas
+/// through RPC_URL when set, otherwise (or if estimation fails) a heuristic from the
+/// transaction's shape
+fn estimate_gas(adapter: &str, calldata: &[u8]) -> u64 {
+    let gas_limit = GAS_LIMIT.load(Ordering::Relaxed);
+    if gas_limit != 0 {
+        return gas_limit;
+    }
+    let heuristic = chain::decode_execute_bytes(calldata)
+        .map(|tx| chain::heuristic_execute_gas(&tx))
+        .unwrap_or_default();
+    let Some(rpc_url) = std::env::var("RPC_URL").ok().filter(|url| !url.is_empty()) else {
+        return heuristic;
+    };
+    let estimate = tokio::runtime::Runtime::new()
+        .map_err(anyhow::Error::from)
+        .and_then(|runtime| runtime.block_on(chain::estimate_execute_gas(&rpc_url, adapter, calldata)));
+    match estimate {
+        Ok(gas) => gas,
+        Err(e) => {
+            say!("⚠ {}; using the heuristic gas estimate", e);
+            heuristic
+        }
+    }
+}
+
+/// Print how to send a saved calldata file to the Protocol Adapter
+fn say_execute_hint(config: &Config, output_path: &str, gas_limit: u64) {
+    let rpc_url = if config.rpc_url.is_empty() { "<RPC_URL>" } else { &config.rpc_url };
+    say!("To execute on {}:", config.network);
+    say!("  # Using cast:");
+    say!("  cast send {} --data 0x$(xxd -p {} | tr -d '\\\\n') \\",
+         config.protocol_adapter, output_path);
+    say!("    --rpc-url {} \\", rpc_url);
+    say!("    --private-key <YOUR_KEY> --gas-limit {}", gas_limit);
+    say!("  # Or with local-prove:");
+    say!("  local-prove submit --file {} --rpc-url {} --private-key <YOUR_KEY>", output_path, rpc_url);
+}
+
 /// Generate a test proof using ephemeral resources that reference INITIAL_ROOT
 /// This transaction will verify on-chain because INITIAL_ROOT matches the deployed EMPTY_HASH
-fn generate_ephemeral_test_proof() -> Result<()> {
-    println!("Generating EPHEMERAL test proof...");
-    println!("  This uses ephemeral resources with quantity=0");
-    println!("  These reference INITIAL_ROOT which matches the on-chain EMPTY_HASH");
-    println!();
+fn generate_ephemeral_test_proof(config: &Config, out: Option<String>, nonce: Nonce, mode: ProofMode) -> Result<()> {
+    say!("Generating EPHEMERAL test proof...");
+    say!("  This uses ephemeral resources with quantity=0");
+    say!("  These reference INITIAL_ROOT which matches the on-chain EMPTY_HASH");
+    say!("  Nonce: 0x{}", hex::encode(nonce));
+    say!();
 
     let start = Instant::now();
+    let output_path = out.unwrap_or_else(|| output_file_name("ephemeral_test_tx.bin".to_string(), mode));
 
-    // Create ephemeral resources (is_ephemeral=true, quantity=0)
-    // These will use INITIAL_ROOT as the commitment tree root
-    println!("Building ephemeral transaction...");
-
-    // Create a nullifier key pair
-    let nf_key = NullifierKey::default();
-    let nf_key_cm = nf_key.commit();
-
-    // Create consumed ephemeral resource (is_ephemeral=true, quantity=0)
-    // TrivialLogicWitness expects: is_ephemeral=true, quantity=0
-    let mut consumed_resource = Resource {
-        logic_ref: TrivialLogicWitness::verifying_key(),  // Use trivial logic VK
-        nk_commitment: nf_key_cm,
-        quantity: 0,           // quantity=0 for ephemeral (required by TrivialLogicWitness)
-        is_ephemeral: true,    // CRITICAL: this makes the circuit use ephemeral_root
-        ..Default::default()
-    };
-    consumed_resource.nonce = [1u8; 32];  // Unique nonce
-
-    let consumed_resource_nf = consumed_resource.nullifier(&nf_key)
-        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
-
-    // Create created ephemeral resource (is_ephemeral=true, quantity=0)
-    let mut created_resource = consumed_resource.clone();
-    created_resource.set_nonce(consumed_resource_nf);
-
-    // Create the compliance witness
-    // with_fixed_rcv sets ephemeral_root to INITIAL_ROOT
-    // Since is_ephemeral=true, the circuit will use ephemeral_root (INITIAL_ROOT)
-    let compliance_witness = ComplianceWitness::with_fixed_rcv(
-        consumed_resource.clone(),
-        nf_key.clone(),
-        created_resource.clone(),
-    );
-
-    // Create a compliance unit from the witness with Groth16 proofs
-    let compliance_unit = ComplianceUnit::create(&compliance_witness, ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to create compliance unit: {:?}", e))?;
-
-    // Build the action tree for the merkle paths
-    let created_resource_cm = created_resource.commitment();
-    let mut action_tree = MerkleTree::new(vec![]);
-    action_tree.insert(consumed_resource_nf);
-    action_tree.insert(created_resource_cm);
-
-    let action_tree_root = action_tree.root()
-        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
-
-    // Create and prove TrivialLogic for consumed resource
-    // TrivialLogicWitness::new(resource, action_tree_root, nf_key, is_consumed)
-    let consumed_logic = TrivialLogicWitness::new(
-        consumed_resource.clone(),
-        action_tree_root,
-        nf_key.clone(),
-        true,  // is_consumed
-    );
-    let consumed_logic_proof = consumed_logic.prove(ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to prove consumed logic: {:?}", e))?;
-
-    // Create and prove TrivialLogic for created resource
-    let created_logic = TrivialLogicWitness::new(
-        created_resource.clone(),
-        action_tree_root,
-        nf_key.clone(),
-        false,  // is_consumed
-    );
-    let created_logic_proof = created_logic.prove(ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to prove created logic: {:?}", e))?;
-
-    // Create an action with this compliance unit and logic proofs
-    let action = Action::new(
-        vec![compliance_unit],
-        vec![consumed_logic_proof, created_logic_proof],
-    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
-
-    // Verify the action
-    action.clone().verify()
-        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
-
-    // Build the delta witness from the rcv values
-    let delta_witness = DeltaWitness::from_bytes_vec(&[compliance_witness.rcv.to_vec()])
-        .map_err(|e| anyhow!("Failed to create delta witness: {:?}", e))?;
-
-    let tx = Transaction::create(vec![action], Delta::Witness(delta_witness));
-
-    println!("Generating ZK proofs...");
-    println!("  This may take several minutes on first run (compiling circuits)");
-    println!("  Subsequent runs will be faster (cached)");
-    println!();
-
-    // Generate delta proof
-    let balanced_tx = tx.generate_delta_proof()
-        .map_err(|e| anyhow!("Delta proof generation failed: {:?}", e))?;
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run (compiling circuits)");
+    say!("  Subsequent runs will be faster (cached)");
+    say!();
+
+    let proven = prove_ephemeral_actions(nonce, mode)?;
+    let balanced_tx = balance_transaction(proven, mode, &output_path)?;
 
     let elapsed = start.elapsed();
-    println!("\n✓ Proof generation complete!");
-    println!("  Time: {:.2}s", elapsed.as_secs_f64());
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
 
     // Verify locally
-    println!("\nVerifying proofs locally...");
-    balanced_tx.clone().verify()
-        .map_err(|e| anyhow!("Verification failed: {:?}", e))?;
-    println!("✓ Verification passed!");
-
-    // Convert to EVM format
-    println!("\nConverting to EVM format...");
-    let evm_tx = ProtocolAdapter::Transaction::from(balanced_tx);
-    let abi_encoded = evm_tx.abi_encode();
-
-    // Build full calldata with function selector
-    let mut calldata = Vec::with_capacity(4 + abi_encoded.len());
-    calldata.extend_from_slice(&EXECUTE_SELECTOR);
-    calldata.extend_from_slice(&abi_encoded);
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
 
     let output = ProofOutput {
         calldata: format!("0x{}", hex::encode(&calldata)),
-        to: PROTOCOL_ADAPTER.to_string(),
+        to: config.protocol_adapter.clone(),
         calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
         metadata: ProofMetadata {
-            proof_type: "Groth16".to_string(),
+            proof_type: format!("{:?}", mode),
             num_actions: 1,
             num_compliance_units: 1,
             generation_time_secs: elapsed.as_secs_f64(),
@@ -417,211 +700,379 @@ fn generate_ephemeral_test_proof() -> Result<()> {
     };
 
     // Save full calldata (with selector) to file
-    let output_path = "ephemeral_test_tx.bin";
-    std::fs::write(output_path, &calldata)?;
-
-    println!("\n════════════════════════════════════════════");
-    println!("  EPHEMERAL TRANSACTION READY FOR ON-CHAIN EXECUTION");
-    println!("════════════════════════════════════════════");
-    println!();
-    println!("Target: {}", PROTOCOL_ADAPTER);
-    println!("Calldata: {} bytes (includes function selector)", calldata.len());
-    println!("Saved to: {}", output_path);
-    println!();
-    println!("This transaction uses INITIAL_ROOT: 0x{}", hex::encode(INITIAL_ROOT.as_bytes()));
-    println!("Which matches the on-chain EMPTY_HASH, so it WILL verify on-chain!");
-    println!();
-    println!("To execute on Sepolia:");
-    println!("  # Using cast:");
-    println!("  cast send {} --data 0x$(xxd -p {} | tr -d '\\\\n') \\",
-             PROTOCOL_ADAPTER, output_path);
-    println!("    --rpc-url https://ethereum-sepolia-rpc.publicnode.com \\");
-    println!("    --private-key <YOUR_KEY> --gas-limit 1200000");
+    std::fs::write(&output_path, &calldata)?;
+
+    say!("\n════════════════════════════════════════════");
+    say!("  EPHEMERAL TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes (includes function selector)", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say!("This transaction uses INITIAL_ROOT: 0x{}", hex::encode(INITIAL_ROOT.as_bytes()));
+    say!("Which matches the on-chain EMPTY_HASH, so it WILL verify on-chain!");
+    say!();
+    say_execute_hint(config, &output_path, output.estimated_gas);
 
     // Also output JSON
-    println!("\nJSON output:");
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    say!();
+    emit_json(&output)?;
 
-    println!("\n✓ EPHEMERAL TEST TRANSACTION READY!");
-    println!("  This transaction should verify on-chain because it uses INITIAL_ROOT.");
+    say!("\n✓ EPHEMERAL TEST TRANSACTION READY!");
+    say!("  This transaction should verify on-chain because it uses INITIAL_ROOT.");
 
     Ok(())
 }
 
-/// Parse a hex address string into a 20-byte array
-fn parse_address(addr: &str) -> Result<[u8; 20]> {
-    let addr = addr.trim_start_matches("0x");
-    let bytes = hex::decode(addr).map_err(|e| anyhow!("Invalid address: {}", e))?;
-    if bytes.len() != 20 {
-        return Err(anyhow!("Address must be 20 bytes, got {}", bytes.len()));
+/// Generate the delta proof for already-proven actions
+///
+/// If every attempt fails, the proven actions are written next to
+/// `output_path` so `local-prove rebalance` can finish the transaction later.
+fn balance_transaction(proven: ProvenActions, mode: ProofMode, output_path: &str) -> Result<Transaction> {
+    match proven.balance() {
+        Ok(balanced_tx) => Ok(balanced_tx),
+        Err(e) => {
+            let pending_path = format!("{}.pending.json", output_path);
+            let pending = PendingDelta {
+                proven,
+                mode,
+                output_path: output_path.to_string(),
+            };
+            std::fs::write(&pending_path, serde_json::to_vec(&pending)?)?;
+
+            say!("\nProven actions saved to: {}", pending_path);
+            say!("  Retry without re-proving: cargo run --release --bin local-prove -- rebalance --file {}", pending_path);
+
+            Err(e)
+        }
     }
-    let mut arr = [0u8; 20];
-    arr.copy_from_slice(&bytes);
-    Ok(arr)
 }
 
-/// Get the forwarder address for a token
-fn get_forwarder_address(token: &str) -> Result<[u8; 20]> {
-    match token.to_uppercase().as_str() {
-        "USDC" => parse_address(USDC_FORWARDER),
-        "WETH" => parse_address(WETH_FORWARDER),
-        _ => Err(anyhow!("Unknown token: {}. Supported: USDC, WETH", token)),
+/// Finish a transaction whose delta proof previously failed
+fn rebalance(adapter: &str, file: &str) -> Result<()> {
+    say!("Retrying delta proof from {}...", file);
+
+    let pending: PendingDelta = serde_json::from_slice(&std::fs::read(file)?)
+        .map_err(|e| anyhow!("Invalid pending delta file: {}", e))?;
+    let num_actions = pending.proven.actions.len();
+    let num_compliance_units = pending.proven.rcvs.len();
+    let mode = pending.mode;
+    if mode == ProofMode::Fake {
+        // Fake receipts only verify in dev mode, whatever --proof-type says
+        std::env::set_var("RISC0_DEV_MODE", "1");
     }
+
+    let start = Instant::now();
+    let balanced_tx = balance_transaction(pending.proven, mode, &pending.output_path)?;
+    let elapsed = start.elapsed();
+
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
+
+    std::fs::write(&pending.output_path, &calldata)?;
+    std::fs::remove_file(file)?;
+
+    let output = ProofOutput {
+        calldata: format!("0x{}", hex::encode(&calldata)),
+        to: adapter.to_string(),
+        calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(adapter, &calldata),
+        metadata: ProofMetadata {
+            proof_type: format!("{:?}", mode),
+            num_actions,
+            num_compliance_units,
+            generation_time_secs: elapsed.as_secs_f64(),
+        },
+    };
+
+    say!("\n✓ Transaction balanced!");
+    say!("Saved to: {}", pending.output_path);
+    say!();
+    emit_json(&output)?;
+
+    Ok(())
+}
+
+/// Print the delta balance of the operations in a spec file
+fn print_balance(spec: &str) -> Result<()> {
+    let operations: Vec<BalanceOperation> = serde_json::from_slice(&std::fs::read(spec)?)
+        .map_err(|e| anyhow!("Invalid balance spec: {}", e))?;
+    let report = balance_report(&operations)?;
+
+    say!("DELTA BALANCE ({} operations):", operations.len());
+    for delta in &report.labels {
+        say!("  {:<8} consumed {:>24}  created {:>24}  net {:>25}  {}",
+             delta.label, delta.consumed, delta.created, delta.net,
+             if delta.balanced { "✓" } else { "✗" });
+    }
+    say!();
+
+    if report.balanced {
+        say!("✓ Transaction balances.");
+    } else {
+        say!("✗ Transaction does not balance. Add resources so every label nets to zero.");
+    }
+
+    say!();
+    emit_json(&report)?;
+
+    Ok(())
+}
+
+/// Resolve a CLI amount argument into smallest token units
+///
+/// Raw integers are the default; with `--human` the value goes through the same
+/// `parse_token_amount` the HTTP service uses.
+fn resolve_amount(config: &Config, amount: &str, token: &str, human: bool) -> Result<u128> {
+    if human {
+        return parse_token_amount(amount, config.tokens.get(token)?);
+    }
+    let amount = amount.trim().parse::<u128>()
+        .map_err(|e| anyhow!("Invalid amount '{}' (expected smallest units, use --human for decimals): {}", amount, e))?;
+    ensure_nonzero(amount)
+}
+
+/// Check a `--sender`/`--recipient` address, including its EIP-55 checksum
+fn checksummed_address(s: &str) -> Result<String> {
+    parse_checksummed_address(s)?;
+    Ok(s.to_string())
+}
+
+/// Decode a calldata file, print its resources and check that it verifies
+fn verify_file(adapter: &str, file: &str, rpc_url: Option<&str>) -> Result<()> {
+    say!("Verifying {}...", file);
+
+    let calldata = std::fs::read(file)?;
+    let tx = chain::decode_execute_bytes(&calldata)?;
+    say!("  Calldata: {} bytes, {} action(s)", calldata.len(), tx.actions.len());
+    say!();
+
+    for (i, action) in tx.actions.iter().enumerate() {
+        say!("Action {}:", i);
+        for unit in &action.complianceVerifierInputs {
+            say!("  Nullifier:  {}", unit.instance.consumed.nullifier);
+            say!("  Commitment: {}", unit.instance.created.commitment);
+        }
+    }
+    say!("Delta proof: {} bytes (0x{})", tx.deltaProof.len(), hex::encode(&tx.deltaProof));
+    say!();
+
+    chain::check_resource_tags(&tx)?;
+    say!("✓ Every nullifier and commitment has a logic proof");
+
+    verify_execute_calldata(&calldata)?;
+    say!("✓ Compliance, logic and delta proofs verify and the transaction balances");
+
+    let Some(rpc_url) = rpc_url else {
+        say!();
+        say!("Not simulated on-chain: pass --rpc-url or set RPC_URL to also check the");
+        say!("Protocol Adapter's state (commitment tree root, nullifiers, approvals).");
+        return Ok(());
+    };
+
+    say!("\nSimulating execute on {}...", adapter);
+    tokio::runtime::Runtime::new()?
+        .block_on(chain::simulate_execute(rpc_url, adapter, &calldata))
+        .map_err(|e| anyhow!("Simulation failed: {}", e))?;
+    say!("✓ Verification passed!");
+
+    Ok(())
+}
+
+/// Send a calldata file to the Protocol Adapter and report the receipt
+fn submit_file(adapter: &str, file: &str, rpc_url: &str, private_key: &str, gas_limit: Option<u64>) -> Result<()> {
+    let calldata = std::fs::read(file)?;
+    // Refuse anything that is not execute calldata before spending gas on it
+    chain::decode_execute_bytes(&calldata)?;
+
+    say!("Submitting {} ({} bytes) to {}...", file, calldata.len(), adapter);
+    let submitted = tokio::runtime::Runtime::new()?
+        .block_on(chain::submit_execute(rpc_url, adapter, private_key, &calldata, gas_limit))?;
+
+    say!("  Tx hash: {}", submitted.tx_hash);
+    if let Some(block) = submitted.block_number {
+        say!("  Block: {}", block);
+    }
+    say!("  Gas used: {}", submitted.gas_used);
+    if !submitted.success {
+        return Err(anyhow!("Transaction {} reverted", submitted.tx_hash));
+    }
+    say!("✓ Transaction succeeded");
+
+    // The adapter appended every created commitment; keep the local tree in step
+    let tx = chain::decode_execute_bytes(&calldata)?;
+    let path = tree_file();
+    let mut tree = CommitmentTree::load(&path)?;
+    for commitment in chain::created_commitments(&tx) {
+        tree.push(commitment);
+    }
+    tree.save(&path)?;
+    say!("  Commitment tree: {} commitments, root {} ({})", tree.len(), tree.root(), path);
+
+    Ok(())
+}
+
+/// Print the local commitment tree, checking its root against the adapter when an RPC is given
+fn show_tree(adapter: &str, rpc_url: Option<&str>) -> Result<()> {
+    let path = tree_file();
+    let tree = CommitmentTree::load(&path)?;
+    say!("Local commitment tree ({}):", path);
+    say!("  Commitments: {}", tree.len());
+    say!("  Depth: {}", tree.depth());
+    say!("  Root: {}", tree.root());
+
+    let root_known_on_chain = match rpc_url {
+        Some(rpc_url) => {
+            let runtime = tokio::runtime::Runtime::new()?;
+            let (count, latest) = runtime.block_on(chain::commitment_tree_head(rpc_url, adapter))?;
+            let known = runtime.block_on(chain::commitment_root_contained(rpc_url, adapter, tree.root()))?;
+            say!("\nProtocol Adapter {}:", adapter);
+            say!("  Commitments: {}", count);
+            say!("  Latest root: {}", latest);
+            if latest == tree.root() {
+                say!("✓ Local tree is up to date");
+            } else if known {
+                say!("⚠ Local root is a past root; proofs against it still verify, but run `tree sync` to catch up");
+            } else {
+                say!("✗ Local root is unknown to the adapter; run `tree sync`");
+            }
+            Some(known)
+        }
+        None => None,
+    };
+
+    emit_json(&TreeReport {
+        file: path,
+        commitments: tree.len(),
+        depth: tree.depth(),
+        root: tree.root(),
+        root_known_on_chain,
+    })
+}
+
+/// Append hex commitments to the local tree
+fn add_to_tree(commitments: &[String]) -> Result<()> {
+    let path = tree_file();
+    let mut tree = CommitmentTree::load(&path)?;
+    for commitment in commitments {
+        let commitment: B256 = commitment.trim().parse().map_err(|e| anyhow!("Invalid commitment {}: {}", commitment, e))?;
+        let index = tree.push(commitment);
+        say!("  Leaf {}: {}", index, commitment);
+    }
+    tree.save(&path)?;
+    say!("✓ {} commitments, root {}", tree.len(), tree.root());
+    Ok(())
+}
+
+/// Replace the local tree with the commitments the adapter's events list
+fn sync_tree(adapter: &str, rpc_url: &str, from_block: u64) -> Result<()> {
+    say!("Reading TransactionExecuted events of {} from block {}...", adapter, from_block);
+    let runtime = tokio::runtime::Runtime::new()?;
+    let tree = CommitmentTree::from_leaves(runtime.block_on(chain::executed_commitments(rpc_url, adapter, from_block))?);
+    let (count, latest) = runtime.block_on(chain::commitment_tree_head(rpc_url, adapter))?;
+
+    if tree.len() as u64 != count || tree.root() != latest {
+        return Err(anyhow!(
+            "Events give {} commitments (root {}), but the adapter holds {} (root {}); try an earlier --from-block",
+            tree.len(), tree.root(), count, latest
+        ));
+    }
+
+    let path = tree_file();
+    tree.save(&path)?;
+    say!("✓ {} commitments, root {} ({})", tree.len(), tree.root(), path);
+    Ok(())
+}
+
+/// File name for proofs of `mode`
+///
+/// Proofs that do not verify on-chain get their mode in the name, so the
+/// server never mistakes them for cached Groth16 proofs.
+fn output_file_name(name: String, mode: ProofMode) -> String {
+    match (mode.verifies_on_chain(), name.strip_suffix(".bin")) {
+        (false, Some(stem)) => format!("{}.{}.bin", stem, mode.as_str()),
+        _ => name,
+    }
+}
+
+/// Parse a `--permit` JSON argument
+fn parse_permit(s: &str) -> Result<PermitData> {
+    let request: PermitRequest = serde_json::from_str(s).map_err(|e| anyhow!("Invalid permit JSON: {}", e))?;
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    request.to_permit_data(now)
 }
 
 /// Generate a shield proof with external_payload for forwarder call
 ///
-/// This creates a transaction that:
-/// 1. Creates a shielded resource (commitment goes on-chain)
-/// 2. Outputs external_payload encoding: transferFrom(sender, forwarder, amount)
-/// 3. The Protocol Adapter executes this forwarder call when processing the proof
-fn generate_shield_proof(token: &str, amount: u128, sender: &str) -> Result<()> {
-    println!("Generating SHIELD proof with forwarder call...");
-    println!("  Token: {}", token);
-    println!("  Amount: {}", amount);
-    println!("  Sender: {}", sender);
-    println!();
+/// See `shielded_prover::proofs::prove_shield_actions` for the transaction layout.
+#[allow(clippy::too_many_arguments)]
+fn generate_shield_proof(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    sender: &str,
+    deletion_criterion: DeletionCriterion,
+    permit: Option<PermitData>,
+    owner: Option<&NullifierKey>,
+    out: Option<String>,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<()> {
+    say!("Generating {}SHIELD proof with forwarder call...", if owner.is_some() { "PERSISTENT " } else { "" });
+    say!("  Token: {}", token);
+    say!("  Amount: {}", amount);
+    say!("  Sender: {}", sender);
+    say!("  Deletion criterion: {:?}", deletion_criterion);
+    say!("  Permit: {}", if permit.is_some() { "bundled" } else { "none (sender must approve first)" });
+    say!("  Nonce: 0x{}", hex::encode(nonce));
+    say!();
 
     let start = Instant::now();
 
-    let forwarder_address = get_forwarder_address(token)?;
-    let sender_address = parse_address(sender)?;
-
-    println!("  Forwarder: 0x{}", hex::encode(forwarder_address));
-    println!();
-
-    // Create nullifier key
-    let nf_key = NullifierKey::default();
-    let nf_key_cm = nf_key.commit();
-
-    // Get the verifying keys for the logic circuits
-    // Consumed resource uses TrivialLogic (no external call)
-    // Created resource uses ForwarderLogic (triggers transferFrom)
-    let trivial_vk = TrivialLogicWitness::verifying_key();
-    let forwarder_vk = ForwarderLogicWitness::verifying_key();
-
-    // Create consumed ephemeral resource (balance going in)
-    // Uses TrivialLogic since it doesn't trigger any external call
-    let mut consumed_resource = Resource {
-        logic_ref: trivial_vk,
-        nk_commitment: nf_key_cm,
-        quantity: 0,           // ephemeral
-        is_ephemeral: true,
-        ..Default::default()
-    };
-    consumed_resource.nonce = [1u8; 32];
-
-    let consumed_nf = consumed_resource.nullifier(&nf_key)
-        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
-
-    // Create the shielded resource (created, uses ForwarderLogic)
-    // This represents the shielded token balance and triggers the transferFrom call
-    let mut created_resource = Resource {
-        logic_ref: forwarder_vk,  // ForwarderLogic VK - this resource triggers the external call
-        nk_commitment: nf_key_cm,
-        quantity: 0,           // Still use 0 for ephemeral logic to work
-        is_ephemeral: true,    // Ephemeral for INITIAL_ROOT compatibility
-        ..Default::default()
+    let forwarder_address = get_forwarder_address(config, token)?;
+    say!("  Forwarder: 0x{}", hex::encode(forwarder_address));
+    say!();
+
+    // Persistent proofs get their own names so the server never serves them as cached shields
+    let output_path = out.unwrap_or_else(|| {
+        let name = shield_proof_file_name(token, amount, deletion_criterion, permit.as_ref());
+        let name = if owner.is_some() { format!("persistent_{}", name) } else { name };
+        output_file_name(name, mode)
+    });
+
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run (compiling circuits)");
+    say!();
+
+    let (proven, note) = match owner {
+        Some(nf_key) => {
+            let (proven, note) = prove_persistent_shield_actions(
+                config, token, amount, sender, nf_key, deletion_criterion, permit, nonce, mode,
+            )?;
+            (proven, Some(note))
+        }
+        None => (prove_shield_actions(config, token, amount, sender, deletion_criterion, permit, nonce, mode)?, None),
     };
-    created_resource.set_nonce(consumed_nf);
-
-    // Create the compliance witness
-    let compliance_witness = ComplianceWitness::with_fixed_rcv(
-        consumed_resource.clone(),
-        nf_key.clone(),
-        created_resource.clone(),
-    );
-
-    // Create compliance unit
-    let compliance_unit = ComplianceUnit::create(&compliance_witness, ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to create compliance unit: {:?}", e))?;
-
-    // Build action tree
-    let created_cm = created_resource.commitment();
-    let mut action_tree = MerkleTree::new(vec![]);
-    action_tree.insert(consumed_nf);
-    action_tree.insert(created_cm);
-
-    let action_tree_root = action_tree.root()
-        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
-
-    // Create ForwarderLogicWitness for the CREATED resource (triggers transferFrom)
-    // The created resource triggers the shield: transferFrom(sender, forwarder, amount)
-    let created_logic = ForwarderLogicWitness::new_shield(
-        created_resource.clone(),
-        action_tree_root,
-        nf_key.clone(),
-        false,  // is_consumed = false (this is the created resource)
-        forwarder_address,
-        sender_address,
-        amount,
-    );
-
-    // Create TrivialLogicWitness for the CONSUMED resource (no external call)
-    let consumed_logic = TrivialLogicWitness::new(
-        consumed_resource.clone(),
-        action_tree_root,
-        nf_key.clone(),
-        true,  // is_consumed = true
-    );
-
-    println!("Generating ZK proofs...");
-    println!("  This may take several minutes on first run (compiling circuits)");
-    println!();
-
-    // Prove both logic witnesses
-    let consumed_logic_proof = consumed_logic.prove(ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to prove consumed logic: {:?}", e))?;
-
-    let created_logic_proof = created_logic.prove(ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to prove created logic: {:?}", e))?;
-
-    // Create action
-    let action = Action::new(
-        vec![compliance_unit],
-        vec![consumed_logic_proof, created_logic_proof],
-    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
-
-    // Verify action
-    action.clone().verify()
-        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
-
-    // Build delta witness
-    let delta_witness = DeltaWitness::from_bytes_vec(&[compliance_witness.rcv.to_vec()])
-        .map_err(|e| anyhow!("Failed to create delta witness: {:?}", e))?;
-
-    let tx = Transaction::create(vec![action], Delta::Witness(delta_witness));
-
-    // Generate delta proof
-    let balanced_tx = tx.generate_delta_proof()
-        .map_err(|e| anyhow!("Delta proof generation failed: {:?}", e))?;
+    let balanced_tx = balance_transaction(proven, mode, &output_path)?;
 
     let elapsed = start.elapsed();
-    println!("\n✓ Proof generation complete!");
-    println!("  Time: {:.2}s", elapsed.as_secs_f64());
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
 
     // Verify locally
-    println!("\nVerifying proofs locally...");
-    balanced_tx.clone().verify()
-        .map_err(|e| anyhow!("Verification failed: {:?}", e))?;
-    println!("✓ Verification passed!");
-
-    // Convert to EVM format
-    println!("\nConverting to EVM format...");
-    let evm_tx = ProtocolAdapter::Transaction::from(balanced_tx);
-    let abi_encoded = evm_tx.abi_encode();
-
-    // Build full calldata
-    let mut calldata = Vec::with_capacity(4 + abi_encoded.len());
-    calldata.extend_from_slice(&EXECUTE_SELECTOR);
-    calldata.extend_from_slice(&abi_encoded);
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
 
     let output = ProofOutput {
         calldata: format!("0x{}", hex::encode(&calldata)),
-        to: PROTOCOL_ADAPTER.to_string(),
+        to: config.protocol_adapter.clone(),
         calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
         metadata: ProofMetadata {
-            proof_type: "Groth16".to_string(),
+            proof_type: format!("{:?}", mode),
             num_actions: 1,
             num_compliance_units: 1,
             generation_time_secs: elapsed.as_secs_f64(),
@@ -629,204 +1080,421 @@ fn generate_shield_proof(token: &str, amount: u128, sender: &str) -> Result<()>
     };
 
     // Save to file
-    let output_path = format!("shield_{}_{}.bin", token.to_lowercase(), amount);
     std::fs::write(&output_path, &calldata)?;
 
-    println!("\n════════════════════════════════════════════");
-    println!("  SHIELD TRANSACTION READY FOR ON-CHAIN EXECUTION");
-    println!("════════════════════════════════════════════");
-    println!();
-    println!("Target: {}", PROTOCOL_ADAPTER);
-    println!("Calldata: {} bytes", calldata.len());
-    println!("Saved to: {}", output_path);
-    println!();
-    println!("This transaction will:");
-    println!("  1. Call transferFrom({}, {}, {}) on {} forwarder",
-             sender, hex::encode(forwarder_address), amount, token);
-    println!("  2. Create a shielded resource commitment on-chain");
-    println!();
-    println!("IMPORTANT: Before executing, ensure:");
-    println!("  - Sender has approved the forwarder contract for {} tokens", token);
-    println!("  - Sender has sufficient {} balance", token);
-    println!();
-
-    // JSON output
-    println!("JSON output:");
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    say!("\n════════════════════════════════════════════");
+    say!("  SHIELD TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say!("This transaction will:");
+    if permit.is_some() {
+        say!("  0. Call permit({}, {}, ...) through the {} forwarder", sender, hex::encode(forwarder_address), token);
+    }
+    say!("  1. Call transferFrom({}, {}, {}) on {} forwarder",
+         sender, hex::encode(forwarder_address), amount, token);
+    say!("  2. Create a shielded resource commitment on-chain");
+    if let Some(note) = &note {
+        let note_path = format!("{}.note.json", output_path);
+        std::fs::write(&note_path, serde_json::to_vec_pretty(note)?)?;
+        say!();
+        say!("Note saved to: {}", note_path);
+        say!("  Commitment: {}", note.commitment);
+        say!("  Keep it with the nullifier key: `unshield --note {} --nullifier-key ...` withdraws it", note_path);
+        say!("  once the transaction is submitted (see `tree show`).");
+    }
+    say!();
+    say!("IMPORTANT: Before executing, ensure:");
+    if permit.is_none() {
+        say!("  - Sender has approved the forwarder contract for {} tokens", token);
+    }
+    say!("  - Sender has sufficient {} balance", token);
+    say!();
+
+    emit_json(&output)?;
 
     Ok(())
 }
 
 /// Generate an unshield proof with external_payload for forwarder call
 ///
-/// This creates a transaction that:
-/// 1. Consumes a shielded resource (nullifier goes on-chain)
-/// 2. Outputs external_payload encoding: transfer(recipient, amount)
-/// 3. The Protocol Adapter executes this forwarder call when processing the proof
-fn generate_unshield_proof(token: &str, amount: u128, recipient: &str) -> Result<()> {
-    println!("Generating UNSHIELD proof with forwarder call...");
-    println!("  Token: {}", token);
-    println!("  Amount: {}", amount);
-    println!("  Recipient: {}", recipient);
-    println!();
+/// See `shielded_prover::proofs::prove_unshield_actions` for the transaction layout.
+#[allow(clippy::too_many_arguments)]
+fn generate_unshield_proof(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    recipient: &str,
+    deletion_criterion: DeletionCriterion,
+    out: Option<String>,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<()> {
+    say!("Generating UNSHIELD proof with forwarder call...");
+    say!("  Token: {}", token);
+    say!("  Amount: {}", amount);
+    say!("  Recipient: {}", recipient);
+    say!("  Deletion criterion: {:?}", deletion_criterion);
+    say!("  Nonce: 0x{}", hex::encode(nonce));
+    say!();
 
     let start = Instant::now();
 
-    let forwarder_address = get_forwarder_address(token)?;
-    let recipient_address = parse_address(recipient)?;
-
-    println!("  Forwarder: 0x{}", hex::encode(forwarder_address));
-    println!();
-
-    // Create nullifier key
-    let nf_key = NullifierKey::default();
-    let nf_key_cm = nf_key.commit();
-
-    // For unshield: consumed resource uses ForwarderLogic (triggers transfer)
-    // Created resource uses TrivialLogic (no external call)
-    let trivial_vk = TrivialLogicWitness::verifying_key();
-    let forwarder_vk = ForwarderLogicWitness::verifying_key();
-
-    // Create consumed resource (the shielded balance being withdrawn)
-    // Uses ForwarderLogic since this triggers the transfer call
-    let mut consumed_resource = Resource {
-        logic_ref: forwarder_vk,
-        nk_commitment: nf_key_cm,
-        quantity: 0,
-        is_ephemeral: true,
-        ..Default::default()
+    check_recipient(allow_list_from_env()?.as_ref(), recipient)?;
+
+    let forwarder_address = get_forwarder_address(config, token)?;
+    say!("  Forwarder: 0x{}", hex::encode(forwarder_address));
+    say!();
+
+    let output_path = out.unwrap_or_else(|| output_file_name(proof_file_name("unshield", token, amount, deletion_criterion), mode));
+
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run");
+    say!();
+
+    let proven = prove_unshield_actions(config, token, amount, recipient, deletion_criterion, nonce, mode)?;
+    let balanced_tx = balance_transaction(proven, mode, &output_path)?;
+
+    let elapsed = start.elapsed();
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
+
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
+
+    let output = ProofOutput {
+        calldata: format!("0x{}", hex::encode(&calldata)),
+        to: config.protocol_adapter.clone(),
+        calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
+        metadata: ProofMetadata {
+            proof_type: format!("{:?}", mode),
+            num_actions: 1,
+            num_compliance_units: 1,
+            generation_time_secs: elapsed.as_secs_f64(),
+        },
     };
-    consumed_resource.nonce = [2u8; 32];  // Different nonce for unshield
-
-    let consumed_nf = consumed_resource.nullifier(&nf_key)
-        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
-
-    // Create output resource (ephemeral, represents the withdrawn value)
-    // Uses TrivialLogic (no external call)
-    let mut created_resource = Resource {
-        logic_ref: trivial_vk,
-        nk_commitment: nf_key_cm,
-        quantity: 0,
-        is_ephemeral: true,
-        ..Default::default()
+
+    std::fs::write(&output_path, &calldata)?;
+
+    say!("\n════════════════════════════════════════════");
+    say!("  UNSHIELD TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say!("This transaction will:");
+    say!("  1. Verify the shielded resource ownership via nullifier");
+    say!("  2. Call transfer({}, {}) on {} forwarder",
+         recipient, amount, token);
+    say!();
+    say!("IMPORTANT: The forwarder contract must hold sufficient {} tokens", token);
+    say!();
+
+    emit_json(&output)?;
+
+    Ok(())
+}
+
+/// Generate an unshield proof that withdraws the persistent resource of a note
+///
+/// See `shielded_prover::proofs::prove_persistent_unshield_actions` for the transaction layout.
+fn generate_note_unshield_proof(
+    config: &Config,
+    note_file: &str,
+    nf_key: &NullifierKey,
+    recipient: &str,
+    deletion_criterion: DeletionCriterion,
+    out: Option<String>,
+    mode: ProofMode,
+) -> Result<()> {
+    let note: ShieldedNote = serde_json::from_slice(&std::fs::read(note_file)?)
+        .map_err(|e| anyhow!("Invalid note file {}: {}", note_file, e))?;
+    let amount = note.resource.quantity;
+
+    say!("Generating PERSISTENT UNSHIELD proof with forwarder call...");
+    say!("  Note: {}", note_file);
+    say!("  Commitment: {}", note.commitment);
+    say!("  Token: {}", note.token);
+    say!("  Amount: {}", amount);
+    say!("  Recipient: {}", recipient);
+    say!("  Deletion criterion: {:?}", deletion_criterion);
+    say!();
+
+    let start = Instant::now();
+
+    check_recipient(allow_list_from_env()?.as_ref(), recipient)?;
+
+    let tree_path = tree_file();
+    let tree = CommitmentTree::load(&tree_path)?;
+    say!("  Commitment tree: {} commitments, root {} ({})", tree.len(), tree.root(), tree_path);
+
+    // A root the adapter has not seen makes the compliance proof worthless; check before proving
+    if let Some(rpc_url) = std::env::var("RPC_URL").ok().filter(|url| !url.is_empty()) {
+        let known = tokio::runtime::Runtime::new()?
+            .block_on(chain::commitment_root_contained(&rpc_url, &config.protocol_adapter, tree.root()))?;
+        if !known {
+            return Err(anyhow!("The Protocol Adapter has never had root {}; run `local-prove tree sync`", tree.root()));
+        }
+        say!("  ✓ Root is known to the Protocol Adapter");
+    }
+    say!();
+
+    let output_path = out.unwrap_or_else(|| {
+        output_file_name(format!("persistent_{}", proof_file_name("unshield", &note.token, amount, deletion_criterion)), mode)
+    });
+
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run");
+    say!();
+
+    let proven = prove_persistent_unshield_actions(config, &note, nf_key, &tree, recipient, deletion_criterion, mode)?;
+    let balanced_tx = balance_transaction(proven, mode, &output_path)?;
+
+    let elapsed = start.elapsed();
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
+
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
+
+    let output = ProofOutput {
+        calldata: format!("0x{}", hex::encode(&calldata)),
+        to: config.protocol_adapter.clone(),
+        calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
+        metadata: ProofMetadata {
+            proof_type: format!("{:?}", mode),
+            num_actions: 1,
+            num_compliance_units: 1,
+            generation_time_secs: elapsed.as_secs_f64(),
+        },
     };
-    created_resource.set_nonce(consumed_nf);
-
-    // Create compliance witness
-    let compliance_witness = ComplianceWitness::with_fixed_rcv(
-        consumed_resource.clone(),
-        nf_key.clone(),
-        created_resource.clone(),
-    );
-
-    let compliance_unit = ComplianceUnit::create(&compliance_witness, ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to create compliance unit: {:?}", e))?;
-
-    // Build action tree
-    let created_cm = created_resource.commitment();
-    let mut action_tree = MerkleTree::new(vec![]);
-    action_tree.insert(consumed_nf);
-    action_tree.insert(created_cm);
-
-    let action_tree_root = action_tree.root()
-        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
-
-    // Create ForwarderLogicWitness for the CONSUMED resource (triggers transfer)
-    // The consumed resource triggers the unshield: transfer(recipient, amount)
-    let consumed_logic = ForwarderLogicWitness::new_unshield(
-        consumed_resource.clone(),
-        action_tree_root,
-        nf_key.clone(),
-        true,  // is_consumed = true (this is the consumed resource)
-        forwarder_address,
-        recipient_address,
-        amount,
-    );
-
-    // Create TrivialLogicWitness for the created resource (no external call)
-    let created_logic = TrivialLogicWitness::new(
-        created_resource.clone(),
-        action_tree_root,
-        nf_key.clone(),
-        false,  // is_consumed = false
-    );
-
-    println!("Generating ZK proofs...");
-    println!("  This may take several minutes on first run");
-    println!();
-
-    let consumed_logic_proof = consumed_logic.prove(ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to prove consumed logic: {:?}", e))?;
-
-    let created_logic_proof = created_logic.prove(ProofType::Groth16)
-        .map_err(|e| anyhow!("Failed to prove created logic: {:?}", e))?;
-
-    let action = Action::new(
-        vec![compliance_unit],
-        vec![consumed_logic_proof, created_logic_proof],
-    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
-
-    action.clone().verify()
-        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
-
-    let delta_witness = DeltaWitness::from_bytes_vec(&[compliance_witness.rcv.to_vec()])
-        .map_err(|e| anyhow!("Failed to create delta witness: {:?}", e))?;
-
-    let tx = Transaction::create(vec![action], Delta::Witness(delta_witness));
-
-    let balanced_tx = tx.generate_delta_proof()
-        .map_err(|e| anyhow!("Delta proof generation failed: {:?}", e))?;
+
+    std::fs::write(&output_path, &calldata)?;
+
+    say!("\n════════════════════════════════════════════");
+    say!("  UNSHIELD TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say!("This transaction will:");
+    say!("  1. Consume the note's resource, proving it is in the commitment tree");
+    say!("  2. Call transfer({}, {}) on {} forwarder", recipient, amount, note.token);
+    say!();
+
+    emit_json(&output)?;
+
+    Ok(())
+}
+
+/// Generate a swap proof with external_payload for the Uniswap forwarder call
+///
+/// See `shielded_prover::proofs::prove_swap_actions` for the transaction layout.
+fn generate_swap_proof(
+    config: &Config,
+    order: &SwapOrder,
+    deletion_criterion: DeletionCriterion,
+    out: Option<String>,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<()> {
+    say!("Generating SWAP proof with Uniswap forwarder call...");
+    say!("  Sell: {} {}", order.amount_in, order.token_in);
+    say!("  Buy:  at least {} {}", order.min_amount_out, order.token_out);
+    say!("  Fee tier: {}", order.fee);
+    say!("  Deletion criterion: {:?}", deletion_criterion);
+    say!("  Nonce: 0x{}", hex::encode(nonce));
+    say!();
+
+    let start = Instant::now();
+
+    let params = order.swap_params(config)?;
+    say!("  Forwarder: {}", config.uniswap_forwarder);
+    say!("  Recipient: 0x{} ({} forwarder)", hex::encode(params.recipient), order.token_out);
+    say!();
+
+    // Dry delta check across the two tokens before spending minutes on proofs
+    let report = swap_balance(config, order, &params)?;
+    say!("Delta balance:");
+    for delta in &report.labels {
+        say!("  {:<6} consumed {} created {} net {}", delta.label, delta.consumed, delta.created, delta.net);
+    }
+    if !report.balanced {
+        return Err(anyhow!("Swap does not balance"));
+    }
+    say!("✓ Balanced");
+    say!();
+
+    let output_path = out.unwrap_or_else(|| output_file_name(swap_proof_file_name(order, deletion_criterion), mode));
+
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run");
+    say!();
+
+    let proven = prove_swap_actions(config, order, deletion_criterion, nonce, mode)?;
+    let balanced_tx = balance_transaction(proven, mode, &output_path)?;
 
     let elapsed = start.elapsed();
-    println!("\n✓ Proof generation complete!");
-    println!("  Time: {:.2}s", elapsed.as_secs_f64());
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
 
-    println!("\nVerifying proofs locally...");
-    balanced_tx.clone().verify()
-        .map_err(|e| anyhow!("Verification failed: {:?}", e))?;
-    println!("✓ Verification passed!");
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
 
-    println!("\nConverting to EVM format...");
-    let evm_tx = ProtocolAdapter::Transaction::from(balanced_tx);
-    let abi_encoded = evm_tx.abi_encode();
+    let output = ProofOutput {
+        calldata: format!("0x{}", hex::encode(&calldata)),
+        to: config.protocol_adapter.clone(),
+        calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
+        metadata: ProofMetadata {
+            proof_type: format!("{:?}", mode),
+            num_actions: 1,
+            num_compliance_units: 1,
+            generation_time_secs: elapsed.as_secs_f64(),
+        },
+    };
+
+    std::fs::write(&output_path, &calldata)?;
+
+    say!("\n════════════════════════════════════════════");
+    say!("  SWAP TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say!("This transaction will:");
+    say!("  1. Consume the input resource via its nullifier");
+    say!("  2. Call exactInputSingle on the Uniswap forwarder, selling {} {} for at least {} {}",
+         order.amount_in, order.token_in, order.min_amount_out, order.token_out);
+    say!();
+    say!("IMPORTANT: Before executing, ensure:");
+    say!("  - The Uniswap forwarder holds at least {} {}", order.amount_in, order.token_in);
+    say!("  - The pool fills at exactly the minimum output; the Protocol Adapter");
+    say!("    compares the returned amount with it byte for byte");
+    say!();
+
+    emit_json(&output)?;
+
+    Ok(())
+}
+
+/// Generate a proof carrying a raw forwarder call
+///
+/// See `shielded_prover::proofs::prove_raw_actions` for the transaction layout.
+fn generate_raw_proof(
+    config: &Config,
+    raw: &RawCall,
+    deletion_criterion: DeletionCriterion,
+    out: Option<String>,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<()> {
+    say!("Generating RAW forwarder call proof...");
+    say!("  Forwarder: 0x{}", hex::encode(raw.forwarder));
+    say!("  Call data: 0x{}", hex::encode(&raw.call_data));
+    say!("  Expected output: 0x{}", hex::encode(&raw.expected_output));
+    say!("  Deletion criterion: {:?}", deletion_criterion);
+    say!("  Nonce: 0x{}", hex::encode(nonce));
+    say!();
 
-    let mut calldata = Vec::with_capacity(4 + abi_encoded.len());
-    calldata.extend_from_slice(&EXECUTE_SELECTOR);
-    calldata.extend_from_slice(&abi_encoded);
+    let start = Instant::now();
+    let output_path = out.unwrap_or_else(|| output_file_name(raw.proof_file_name(deletion_criterion), mode));
+
+    say!("Generating ZK proofs...");
+    say!("  This may take several minutes on first run");
+    say!();
+
+    let proven = prove_raw_actions(raw, deletion_criterion, nonce, mode)?;
+    let balanced_tx = balance_transaction(proven, mode, &output_path)?;
+
+    let elapsed = start.elapsed();
+    say!("\n✓ Proof generation complete!");
+    say!("  Time: {:.2}s", elapsed.as_secs_f64());
+
+    say!("\nVerifying proofs locally...");
+    let calldata = verified_calldata(balanced_tx)?;
+    say!("✓ Verification passed!");
 
     let output = ProofOutput {
         calldata: format!("0x{}", hex::encode(&calldata)),
-        to: PROTOCOL_ADAPTER.to_string(),
+        to: config.protocol_adapter.clone(),
         calldata_length: calldata.len(),
+        estimated_gas: estimate_gas(&config.protocol_adapter, &calldata),
         metadata: ProofMetadata {
-            proof_type: "Groth16".to_string(),
+            proof_type: format!("{:?}", mode),
             num_actions: 1,
             num_compliance_units: 1,
             generation_time_secs: elapsed.as_secs_f64(),
         },
     };
 
-    let output_path = format!("unshield_{}_{}.bin", token.to_lowercase(), amount);
     std::fs::write(&output_path, &calldata)?;
 
-    println!("\n════════════════════════════════════════════");
-    println!("  UNSHIELD TRANSACTION READY FOR ON-CHAIN EXECUTION");
-    println!("════════════════════════════════════════════");
-    println!();
-    println!("Target: {}", PROTOCOL_ADAPTER);
-    println!("Calldata: {} bytes", calldata.len());
-    println!("Saved to: {}", output_path);
-    println!();
-    println!("This transaction will:");
-    println!("  1. Verify the shielded resource ownership via nullifier");
-    println!("  2. Call transfer({}, {}) on {} forwarder",
-             recipient, amount, token);
-    println!();
-    println!("IMPORTANT: The forwarder contract must hold sufficient {} tokens", token);
-    println!();
-
-    println!("JSON output:");
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    say!("\n════════════════════════════════════════════");
+    say!("  RAW CALL TRANSACTION READY FOR ON-CHAIN EXECUTION");
+    say!("════════════════════════════════════════════");
+    say!();
+    say!("Target: {}", config.protocol_adapter);
+    say!("Calldata: {} bytes", calldata.len());
+    say!("Saved to: {}", output_path);
+    say!();
+    say!("IMPORTANT: The call is not checked. It settles no resource, and the");
+    say!("  transaction reverts unless the forwarder returns exactly the expected output.");
+    say!();
+
+    emit_json(&output)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_amount_matches_server_parsing() {
+        let config = Config::default();
+        for (amount, token) in [("0.1", "USDC"), ("1.5", "WETH"), ("250", "USDC")] {
+            assert_eq!(
+                resolve_amount(&config, amount, token, true).unwrap(),
+                parse_token_amount(amount, config.tokens.get(token).unwrap()).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_file_name_marks_off_chain_proofs() {
+        assert_eq!(output_file_name("shield_usdc_5.bin".to_string(), ProofMode::Groth16), "shield_usdc_5.bin");
+        assert_eq!(output_file_name("shield_usdc_5.bin".to_string(), ProofMode::Fake), "shield_usdc_5.fake.bin");
+    }
+
+    #[test]
+    fn test_zero_amount_rejected() {
+        let config = Config::default();
+        assert!(resolve_amount(&config, "0", "USDC", false).is_err());
+        assert!(resolve_amount(&config, "0", "USDC", true).is_err());
+        assert!(resolve_amount(&config, "0.0000001", "USDC", true).is_err());
+        assert!(resolve_amount(&config, "-5", "WETH", false).is_err());
+    }
+
+    #[test]
+    fn test_raw_amount_is_default() {
+        let config = Config::default();
+        assert_eq!(resolve_amount(&config, "1000000", "USDC", false).unwrap(), 1_000_000);
+        assert!(resolve_amount(&config, "0.1", "USDC", false).is_err());
+    }
+}
@@ -1,47 +1,241 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::{get, post},
-    Json, Router,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Extension, Json, Router,
 };
+use futures::stream::{self, Stream};
+use metrics_exporter_prometheus::PrometheusHandle;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
-
-mod prover;
-
-use prover::{ProverService, ProofResponse};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::{debug, info, info_span, warn};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+mod jobstore;
+mod openapi;
+mod queue;
+
+use jobstore::{JobStore, JobUpdate};
+use queue::{Priority, ProofQueue};
+use shielded_prover::amount::{ensure_amount_bounds, ensure_nonzero, ensure_permit_supported, ensure_shieldable, parse_quantity, parse_token_amount};
+use shielded_prover::apikeys::{constant_time_eq, ApiKeyStore};
+use shielded_prover::idempotency::{self, IdempotencyStore};
+use shielded_prover::job::JobContext;
+use shielded_prover::proofs::{
+    generate_nullifier_keypair, logic_verifying_keys, nullifier_key_commitment, parse_checksummed_address, parse_nullifier_key,
+    verify_execute_calldata, BatchEntry, BatchOp,
+    ProvenActions, RawCall, SwapOrder, DEFAULT_SWAP_FEE, MAX_BATCH_ENTRIES,
+};
+use shielded_prover::prover::{prewarm_docker, prewarm_docker_from_env, resource_token};
+use shielded_prover::ratelimit::RateLimiter;
+use shielded_prover::secret::Secret;
+use shielded_prover::shield_logic::{encode_exact_input_single, SwapParams};
+use shielded_prover::{
+    allowlist, balance, chain, estimate, signature, telemetry, webhook, Config, DeletionCriterion, PermitData, ProofResponse,
+    ProverError, ProverService, TokenRegistry,
+};
 
 /// Job status for async proof generation
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct JobStatus {
     job_id: String,
-    status: String, // "pending", "generating", "completed", "failed"
+    status: String, // "pending", "generating", "completed", "failed", "cancelled"
+    priority: Priority,
     proof: Option<ProofResponse>,
     error: Option<String>,
+    /// Stable `ProverError` code of the failure, for client retry logic
+    #[serde(default)]
+    error_code: Option<String>,
     created_at: u64,
+    /// When the job left the queue and proving started
+    started_at: Option<u64>,
+    /// When the job completed
+    #[serde(default)]
+    completed_at: Option<u64>,
+    /// When the job failed or was cancelled
+    #[serde(default)]
+    failed_at: Option<u64>,
+    /// Whether the proof's nullifier is already spent on-chain (None if not checked)
+    nullifier_spent: Option<bool>,
+    /// eth_call outcome of the calldata, when the request asked for `simulate`
+    #[serde(default)]
+    simulation: Option<chain::Simulation>,
+    /// Suggested gas limit for executing the calldata
+    #[serde(default)]
+    estimated_gas: Option<u64>,
+    /// Webhook notified when the job finishes
+    #[serde(default)]
+    callback_url: Option<String>,
+    /// Whether the webhook accepted the final job JSON
+    #[serde(default)]
+    callback_delivered: bool,
+    /// Id of the API key that started the job (None when no key was configured)
+    #[serde(default)]
+    api_key_id: Option<String>,
+    /// Proving log (served by /api/job/{id}/logs), progress and cancellation; not persisted
+    #[serde(skip)]
+    context: JobContext,
+    /// Status change notifications (not persisted)
+    #[serde(skip)]
+    events: JobEvents,
+}
+
+impl JobStatus {
+    /// Stamp `completed_at` or `failed_at` on the job's first final status
+    fn stamp_finished(&mut self, now: u64) {
+        if self.completed_at.is_some() || self.failed_at.is_some() {
+            return;
+        }
+        match self.status.as_str() {
+            "completed" => self.completed_at = Some(now),
+            "failed" | "cancelled" => self.failed_at = Some(now),
+            _ => {}
+        }
+    }
+
+    /// Seconds from creation to the final status, once the job has one
+    fn duration_secs(&self) -> Option<u64> {
+        let finished_at = self.completed_at.or(self.failed_at)?;
+        Some(finished_at.saturating_sub(self.created_at))
+    }
+}
+
+/// Latest job JSON, watched by /api/job/{job_id}/ws subscribers
+#[derive(Clone)]
+struct JobEvents(Arc<watch::Sender<serde_json::Value>>);
+
+impl Default for JobEvents {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(serde_json::Value::Null).0))
+    }
+}
+
+impl JobEvents {
+    fn publish(&self, update: serde_json::Value) {
+        self.0.send_replace(update);
+    }
+
+    fn subscribe(&self) -> watch::Receiver<serde_json::Value> {
+        self.0.subscribe()
+    }
 }
 
 #[derive(Clone)]
 struct AppState {
-    prover: Arc<RwLock<ProverService>>,
-    jobs: Arc<RwLock<HashMap<String, JobStatus>>>,
+    /// Shared prover; its methods take &self and guard their own state
+    prover: Arc<ProverService>,
+    /// Job table, in memory or persisted per JOB_STORE
+    jobs: Arc<dyn JobStore>,
+    queue: Arc<ProofQueue>,
+    /// Bearer token for the /api/admin endpoints (admin API disabled when unset)
+    admin_api_key: Option<String>,
+    /// Bearer token for /api/admin/keys (key management disabled when unset)
+    master_api_key: Option<String>,
+    /// API keys, manageable at runtime through /api/admin/keys
+    api_keys: Arc<ApiKeyStore>,
+    /// Expected upper bound on a single proof generation
+    prove_timeout_secs: u64,
+    /// How long completed and failed jobs are kept
+    job_ttl_secs: u64,
+    /// Reject shield requests that are not signed by the sender
+    require_sender_sig: bool,
+    /// Sender signature nonces already accepted
+    used_nonces: Arc<signature::UsedNonces>,
+    /// HMAC key for webhook signatures (unsigned when unset)
+    webhook_secret: Option<String>,
+    /// Callback hosts allowed to resolve to non-public addresses (WEBHOOK_ALLOWED_HOSTS)
+    webhook_allowed_hosts: Arc<Vec<String>>,
+    /// Whether the service can prove yet (see PREWARM_DOCKER)
+    readiness: Arc<RwLock<Readiness>>,
+    /// Renders the Prometheus metrics served by /metrics
+    metrics: PrometheusHandle,
+    /// Per-IP limit on the proving routes (RATE_LIMIT_PER_MIN; unlimited when unset)
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Jobs started under an Idempotency-Key, kept for JOB_TTL_SECS
+    idempotency: Arc<IdempotencyStore>,
+}
+
+/// Startup readiness reported by /health
+#[derive(Clone)]
+enum Readiness {
+    /// Docker pre-warm still running
+    Warming,
+    Ready,
+    /// Docker pre-warm failed; real proofs will not work
+    Failed(String),
 }
 
 // Custom error type for proper axum responses
-struct AppError(anyhow::Error);
+struct AppError {
+    status: StatusCode,
+    error: anyhow::Error,
+    /// Seconds sent as Retry-After, for errors a client should retry later
+    retry_after: Option<u64>,
+}
+
+impl AppError {
+    fn with_status(status: StatusCode, error: impl Into<anyhow::Error>) -> Self {
+        Self {
+            status,
+            error: error.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Server error for a request that can be retried in `retry_after` seconds
+    fn unavailable(error: impl Into<anyhow::Error>, retry_after: u64) -> Self {
+        Self {
+            retry_after: Some(retry_after),
+            ..Self::with_status(StatusCode::SERVICE_UNAVAILABLE, error)
+        }
+    }
+
+    /// Client error for a request that fails validation
+    fn bad_request(error: impl Into<anyhow::Error>) -> Self {
+        Self::with_status(StatusCode::BAD_REQUEST, error)
+    }
+
+    /// Client error for an unknown job or other resource
+    fn not_found(error: impl Into<anyhow::Error>) -> Self {
+        Self::with_status(StatusCode::NOT_FOUND, error)
+    }
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": self.0.to_string()})),
-        )
-            .into_response()
+        // Prover failures carry their own status and code
+        match self.error.downcast::<ProverError>() {
+            Ok(e) => e.into_response(),
+            Err(error) => {
+                let mut response = (
+                    self.status,
+                    Json(serde_json::json!({"error": error.to_string()})),
+                )
+                    .into_response();
+                if let Some(secs) = self.retry_after {
+                    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+                }
+                response
+            }
+        }
     }
 }
 
@@ -50,7 +244,368 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: err.into(),
+            retry_after: None,
+        }
+    }
+}
+
+/// Check a finished proof's nullifiers against the chain when an RPC URL is configured
+///
+/// Reusing an already-executed proof reverts on-chain with PreExistingNullifier, so this
+/// lets clients know before they spend gas on the submission.
+async fn check_nullifier_spent(
+    state: &AppState,
+    result: &Result<ProofResponse, ProverError>,
+) -> Option<bool> {
+    let rpc_url = state.prover.rpc_url()?;
+    let calldata = result.as_ref().ok()?.calldata.as_ref()?;
+
+    let check = async {
+        let tx = chain::decode_execute_calldata(calldata)?;
+        chain::any_nullifier_spent(rpc_url, &state.prover.config().protocol_adapter, &chain::extract_nullifiers(&tx)).await
+    };
+
+    match check.await {
+        Ok(spent) => {
+            if spent {
+                warn!("Proof nullifier is already spent on-chain; submitting it will revert");
+            }
+            Some(spent)
+        }
+        Err(e) => {
+            warn!("Could not check nullifier status on-chain: {}", e);
+            None
+        }
+    }
+}
+
+/// Gas limit for a finished proof's calldata: eth_estimateGas through RPC_URL when
+/// configured, otherwise (or if estimation fails) a heuristic from the transaction's shape
+///
+/// None without calldata (mock and Bonsai proofs).
+async fn estimate_proof_gas(state: &AppState, result: &Result<ProofResponse, ProverError>) -> Option<u64> {
+    let calldata = hex::decode(result.as_ref().ok()?.calldata.as_ref()?.trim_start_matches("0x")).ok()?;
+    let heuristic = chain::heuristic_execute_gas(&chain::decode_execute_bytes(&calldata).ok()?);
+    let Some(rpc_url) = state.prover.rpc_url() else {
+        return Some(heuristic);
+    };
+
+    match chain::estimate_execute_gas(rpc_url, &state.prover.config().protocol_adapter, &calldata).await {
+        Ok(gas) => Some(gas),
+        Err(e) => {
+            warn!("{}; using the heuristic gas estimate", e);
+            Some(heuristic)
+        }
+    }
+}
+
+/// Simulating needs RPC_URL, so a `simulate` request without it is refused up front
+fn ensure_can_simulate(state: &AppState, simulate: bool) -> Result<(), AppError> {
+    if simulate && state.prover.rpc_url().is_none() {
+        return Err(AppError::with_status(
+            StatusCode::SERVICE_UNAVAILABLE,
+            anyhow::anyhow!("Simulation requires RPC_URL"),
+        ));
+    }
+    Ok(())
+}
+
+/// eth_call a finished proof's calldata on the Protocol Adapter, when `simulate` was requested
+///
+/// Catches reverts a local verification cannot, such as a missing approval or
+/// an underfunded forwarder. None when not requested, without calldata (mock
+/// and Bonsai proofs) or when the node could not be reached.
+async fn simulate_proof(
+    state: &AppState,
+    simulate: bool,
+    result: &Result<ProofResponse, ProverError>,
+) -> Option<chain::Simulation> {
+    if !simulate {
+        return None;
+    }
+    let rpc_url = state.prover.rpc_url()?;
+    let calldata = result.as_ref().ok()?.calldata.as_ref()?;
+
+    let simulation = async {
+        let calldata = hex::decode(calldata.trim_start_matches("0x"))?;
+        chain::simulate(rpc_url, &state.prover.config().protocol_adapter, &calldata).await
+    };
+
+    match simulation.await {
+        Ok(simulation) => {
+            if let Some(reason) = &simulation.revert_reason {
+                warn!("Proof calldata would revert on-chain: {}", reason);
+            }
+            Some(simulation)
+        }
+        Err(e) => {
+            warn!("Could not simulate proof calldata: {}", e);
+            None
+        }
+    }
+}
+
+/// Default for JOB_GC_INTERVAL_SECS, how often the job GC task runs
+const DEFAULT_JOB_GC_INTERVAL_SECS: u64 = 60;
+
+/// Default for JOB_TTL_SECS, how long finished jobs are kept
+const DEFAULT_JOB_TTL_SECS: u64 = 3600;
+
+/// Default for MAX_BODY_BYTES: generous for a batch of operations, small
+/// enough that an oversized `input_resource` cannot exhaust memory
+const DEFAULT_MAX_BODY_BYTES: usize = 256 * 1024;
+
+/// Largest request body accepted (MAX_BODY_BYTES); larger ones get 413 before they are parsed
+fn max_body_bytes() -> usize {
+    std::env::var("MAX_BODY_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or(DEFAULT_MAX_BODY_BYTES)
+}
+
+fn env_secs(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Spawn the background task that cleans up the job table
+fn spawn_job_gc(state: AppState) {
+    let interval_secs = env_secs("JOB_GC_INTERVAL_SECS", DEFAULT_JOB_GC_INTERVAL_SECS).max(1);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            fail_orphaned_jobs(&state).await;
+            let reaped = reap_expired_jobs(&state).await;
+            if reaped > 0 {
+                info!("Job GC reaped {} finished job(s) older than {}s", reaped, state.job_ttl_secs);
+            } else {
+                debug!("Job GC reaped no jobs");
+            }
+        }
+    });
+}
+
+/// Evict completed, failed and cancelled jobs created more than JOB_TTL_SECS ago
+///
+/// Pending and generating jobs are never evicted, however old.
+async fn reap_expired_jobs(state: &AppState) -> usize {
+    let now = get_timestamp();
+    let mut reaped = 0;
+    for job in list_local_jobs(state).await {
+        if !is_finished(&job.status) || now.saturating_sub(job.created_at) < state.job_ttl_secs {
+            continue;
+        }
+        match state.jobs.remove(&job.job_id).await {
+            Ok(_) => reaped += 1,
+            Err(e) => warn!("Failed to delete job {} from the job store: {}", job.job_id, e),
+        }
+    }
+    reaped
+}
+
+/// Pull the Groth16 prover image in the background, holding /health as not ready until done
+async fn spawn_docker_prewarm(state: AppState) {
+    *state.readiness.write().await = Readiness::Warming;
+    tokio::spawn(async move {
+        info!("Pre-warming Docker for Groth16 proving...");
+        let readiness = match tokio::task::spawn_blocking(prewarm_docker).await {
+            Ok(Ok(elapsed)) => {
+                info!("Docker pre-warm finished in {:.1}s", elapsed.as_secs_f64());
+                Readiness::Ready
+            }
+            Ok(Err(e)) => {
+                warn!("Docker pre-warm failed: {}", e);
+                Readiness::Failed(e.to_string())
+            }
+            Err(e) => {
+                warn!("Docker pre-warm task failed: {}", e);
+                Readiness::Failed(e.to_string())
+            }
+        };
+        *state.readiness.write().await = readiness;
+    });
+}
+
+/// Fail jobs stuck in "generating" whose task is no longer running
+///
+/// This catches tasks that died (e.g. panicked) without updating their status,
+/// which would otherwise leave clients polling forever.
+async fn fail_orphaned_jobs(state: &AppState) {
+    let cutoff = state.prove_timeout_secs.saturating_mul(2);
+    let now = get_timestamp();
+
+    for job in list_local_jobs(state).await {
+        if job.status != "generating" {
+            continue;
+        }
+        let started_at = job.started_at.unwrap_or(job.created_at);
+        if now.saturating_sub(started_at) < cutoff || state.queue.is_running(&job.job_id) {
+            continue;
+        }
+
+        warn!(
+            "Orphaned job {} has been generating for {}s with no running task; marking as failed",
+            job.job_id,
+            now.saturating_sub(started_at)
+        );
+        change_job(state, &job.job_id, Box::new(|job| {
+            // Finished since it was listed
+            if job.status != "generating" {
+                return false;
+            }
+            job.status = "failed".to_string();
+            job.error = Some("orphaned job".to_string());
+            true
+        })).await;
+    }
+}
+
+/// Notify a job's subscribers of its new status
+fn job_changed(state: &AppState, job: &JobStatus) {
+    job.events.publish(job_response(state, job));
+}
+
+async fn insert_job(state: &AppState, job: JobStatus) {
+    job_changed(state, &job);
+    let job_id = job.job_id.clone();
+    if let Err(e) = state.jobs.insert(job).await {
+        warn!("Failed to store job {}: {}", job_id, e);
+    }
+}
+
+/// Apply `update` through the job store and notify subscribers if it changed the job
+///
+/// Every transition goes through here, so this is where final statuses are timestamped.
+async fn change_job(state: &AppState, job_id: &str, update: JobUpdate) -> Option<JobStatus> {
+    let update: JobUpdate = Box::new(move |job| {
+        let changed = update(job);
+        if changed {
+            job.stamp_finished(get_timestamp());
+        }
+        changed
+    });
+    match state.jobs.update_status(job_id, update).await {
+        Ok(updated) => {
+            if let Some(job) = &updated {
+                job_changed(state, job);
+            }
+            updated
+        }
+        Err(e) => {
+            warn!("Failed to update job {}: {}", job_id, e);
+            None
+        }
+    }
+}
+
+/// Apply a status transition to a job and persist it
+///
+/// Cancelled jobs are final: a task finishing after its job was cancelled
+/// does not overwrite the status.
+async fn update_job(state: &AppState, job_id: &str, update: impl FnOnce(&mut JobStatus) + Send + 'static) {
+    change_job(state, job_id, Box::new(move |job| {
+        if job.status == "cancelled" {
+            return false;
+        }
+        update(job);
+        true
+    })).await;
+}
+
+async fn find_job(state: &AppState, job_id: &str) -> Option<JobStatus> {
+    state.jobs.get(job_id).await.unwrap_or_else(|e| {
+        warn!("Failed to read job {}: {}", job_id, e);
+        None
+    })
+}
+
+/// Jobs this replica started (see [`JobStore::list_local`])
+async fn list_local_jobs(state: &AppState) -> Vec<JobStatus> {
+    state.jobs.list_local().await.unwrap_or_else(|e| {
+        warn!("Failed to list jobs: {}", e);
+        Vec::new()
+    })
+}
+
+/// Prepare jobs loaded from a persistent job store on startup
+///
+/// Jobs that were pending or generating when the process stopped have no task
+/// any more, so they are marked as failed.
+async fn restore_jobs(state: &AppState) -> anyhow::Result<()> {
+    let jobs = state.jobs.list_local().await?;
+    if jobs.is_empty() {
+        return Ok(());
+    }
+    for job in &jobs {
+        let restored = change_job(state, &job.job_id, Box::new(|job| {
+            if job.status != "pending" && job.status != "generating" {
+                return false;
+            }
+            job.status = "failed".to_string();
+            job.error = Some("interrupted by prover restart".to_string());
+            true
+        })).await;
+        if restored.is_none() {
+            job_changed(state, job);
+        }
+    }
+    info!("Restored {} job(s) from the job store", jobs.len());
+    Ok(())
+}
+
+/// Header carrying the request id, taken from the client or generated, and echoed in the response
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Span every log line caused by a request is recorded in, jobs it queues included
+fn request_span(request: &Request) -> tracing::Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    info_span!("request", request_id, method = %request.method(), path = request.uri().path())
+}
+
+/// CORS policy from ALLOWED_ORIGINS (comma-separated)
+///
+/// Without ALLOWED_ORIGINS no cross-origin request is allowed, unless
+/// CORS_PERMISSIVE=1 allows any origin.
+fn cors_layer_from_env() -> anyhow::Result<CorsLayer> {
+    let cors = CorsLayer::new()
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)]);
+
+    let origins = std::env::var("ALLOWED_ORIGINS").ok().filter(|v| !v.trim().is_empty());
+    if let Some(origins) = origins {
+        let origins = origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| {
+                HeaderValue::from_str(origin).map_err(|e| anyhow::anyhow!("Invalid ALLOWED_ORIGINS entry '{}': {}", origin, e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        info!("CORS allowed for {} origin(s)", origins.len());
+        return Ok(cors.allow_origin(origins));
+    }
+
+    let permissive = std::env::var("CORS_PERMISSIVE")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false);
+    if permissive {
+        warn!("CORS_PERMISSIVE is set; allowing requests from any origin");
+        Ok(cors.allow_origin(Any))
+    } else {
+        warn!("ALLOWED_ORIGINS not set; cross-origin requests are not allowed");
+        Ok(cors)
     }
 }
 
@@ -69,6 +624,73 @@ fn generate_job_id() -> String {
     hex::encode(&hasher.finalize()[..8])
 }
 
+/// Every route and layer of the service, without the listener
+fn router(state: AppState, cors: CorsLayer) -> Router {
+    // Proving and job endpoints (rate limited, and require an API key once any is configured)
+    let proving = Router::new()
+        // Async endpoints - return job_id immediately
+        .route("/api/shield", post(start_shield_job))
+        .route("/api/swap", post(start_swap_job))
+        .route("/api/unshield", post(start_unshield_job))
+        // Legacy sync endpoints (for backwards compat with backend)
+        .route("/api/prove/shield", post(prove_shield_sync))
+        .route("/api/prove/swap", post(prove_swap_sync))
+        .route("/api/prove/unshield", post(prove_unshield_sync))
+        // Pre-built forwarder calldata (requires ALLOW_RAW_CALLS)
+        .route("/api/prove/raw", post(prove_raw_sync))
+        // Several operations proven as the actions of one transaction
+        .route("/api/prove/batch", post(prove_batch_sync))
+        // Delta proof over actions proven elsewhere
+        .route("/api/prove/delta", post(prove_delta_sync))
+        // Job listing, status, streams, cancellation and deletion, limited to the caller's own jobs
+        .route("/api/jobs", get(list_jobs))
+        .route("/api/job/{job_id}", get(get_job_status).delete(delete_job))
+        .route("/api/job/{job_id}/ws", get(job_ws))
+        .route("/api/job/{job_id}/events", get(job_events))
+        .route("/api/job/{job_id}/cancel", post(cancel_job))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_api_key))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit));
+
+    // Build router with async job pattern
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/ready", get(health_ready))
+        .route("/metrics", get(get_metrics))
+        .route("/api/info", get(api_info))
+        .route("/api/vk", get(get_verifying_keys))
+        .route("/api/generate-keypair", post(generate_keypair))
+        .route("/api/estimate", get(get_estimate))
+        .merge(proving)
+        // Proof status by deterministic proof id
+        .route("/api/proof/{proof_id}", get(get_proof_status))
+        // Proving log (requires ADMIN_API_KEY)
+        .route("/api/job/{job_id}/logs", get(get_job_logs))
+        // Calldata inspection and dry balance checks
+        .route("/api/decode", post(decode_calldata))
+        .route("/api/decode-calldata", post(decode_calldata))
+        .route("/api/merkle-root", post(merkle_root))
+        .route("/api/verify", post(verify_calldata))
+        .route("/api/balance", post(check_balance))
+        // Admin endpoints (require ADMIN_API_KEY)
+        .route(
+            "/api/admin/unshield-recipients",
+            get(get_unshield_recipients).put(set_unshield_recipients),
+        )
+        // Key management (require MASTER_API_KEY)
+        .route("/api/admin/keys", get(list_api_keys).post(create_api_key))
+        .route("/api/admin/keys/{key_id}", delete(revoke_api_key))
+        // OpenAPI document and Swagger UI
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi::ApiDoc::openapi()))
+        // Only the POST and PUT routes take a body; inside CORS so a 413 still carries its headers
+        .layer(RequestBodyLimitLayer::new(max_body_bytes()))
+        .layer(cors)
+        // Innermost first: echo the request id, log under it, and set it when the client did not
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .with_state(state)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -78,35 +700,44 @@ async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
 
     // Initialize prover service
-    let prover = ProverService::new()?;
+    let config = Config::from_env()?;
+    info!(
+        "Targeting {} deployment (chain {}, Protocol Adapter {})",
+        config.network, config.chain_id, config.protocol_adapter
+    );
+    let prover = ProverService::new(config)?;
+    let prove_timeout_secs = prover.prove_timeout().as_secs();
+    let job_ttl_secs = env_secs("JOB_TTL_SECS", DEFAULT_JOB_TTL_SECS);
+    let queue = ProofQueue::from_env();
+    queue.start();
     let state = AppState {
-        prover: Arc::new(RwLock::new(prover)),
-        jobs: Arc::new(RwLock::new(HashMap::new())),
+        prover: Arc::new(prover),
+        jobs: jobstore::from_env(std::time::Duration::from_secs(job_ttl_secs))?,
+        queue,
+        admin_api_key: std::env::var("ADMIN_API_KEY").ok().filter(|key| !key.is_empty()),
+        master_api_key: std::env::var("MASTER_API_KEY").ok().filter(|key| !key.is_empty()),
+        api_keys: Arc::new(ApiKeyStore::from_env()?),
+        prove_timeout_secs,
+        job_ttl_secs,
+        require_sender_sig: signature::sender_sig_required_from_env(),
+        used_nonces: Arc::new(signature::UsedNonces::new()),
+        webhook_secret: webhook::webhook_secret_from_env(),
+        webhook_allowed_hosts: Arc::new(webhook::allowed_hosts_from_env()),
+        readiness: Arc::new(RwLock::new(Readiness::Ready)),
+        metrics: telemetry::install()?,
+        rate_limiter: RateLimiter::from_env().map(Arc::new),
+        idempotency: Arc::new(IdempotencyStore::new(std::time::Duration::from_secs(job_ttl_secs))),
     };
+    if let Some(limiter) = &state.rate_limiter {
+        info!("Proving routes limited to {} requests per minute per client", limiter.per_min());
+    }
+    restore_jobs(&state).await?;
+    spawn_job_gc(state.clone());
+    if state.prover.uses_real_arm() && prewarm_docker_from_env() {
+        spawn_docker_prewarm(state.clone()).await;
+    }
 
-    // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    // Build router with async job pattern
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .route("/api/info", get(api_info))
-        .route("/api/generate-keypair", post(generate_keypair))
-        // Async endpoints - return job_id immediately
-        .route("/api/shield", post(start_shield_job))
-        .route("/api/swap", post(start_swap_job))
-        .route("/api/unshield", post(start_unshield_job))
-        // Job status polling
-        .route("/api/job/{job_id}", get(get_job_status))
-        // Legacy sync endpoints (for backwards compat with backend)
-        .route("/api/prove/shield", post(prove_shield_sync))
-        .route("/api/prove/swap", post(prove_swap_sync))
-        .route("/api/prove/unshield", post(prove_unshield_sync))
-        .layer(cors)
-        .with_state(state);
+    let app = router(state.clone(), cors_layer_from_env()?);
 
     // Get port from environment
     let port = std::env::var("PORT").unwrap_or_else(|_| "3002".to_string());
@@ -115,50 +746,227 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Shielded Prover service on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
-
+    // The rate limiter keys on the peer address
+    let stop = CancellationToken::new();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(stop.clone().cancelled_owned());
+    let mut server = tokio::spawn(async move { server.await });
+
+    tokio::select! {
+        result = &mut server => return Ok(result??),
+        _ = shutdown_signal() => {}
+    }
+    shut_down(&state, &stop, server).await;
     Ok(())
 }
 
+/// Default for SHUTDOWN_GRACE_SECS, how long shutdown waits for in-flight work
+const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 30;
+
+/// Longest shutdown waits for the webhooks of interrupted jobs
+const SHUTDOWN_CALLBACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Resolves on ctrl-c or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for ctrl-c: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Stop accepting connections and jobs, then wait up to SHUTDOWN_GRACE_SECS
+/// for running jobs and open requests
+///
+/// Jobs still pending or generating after that are marked as failed, so
+/// clients polling them see a final status.
+async fn shut_down(state: &AppState, stop: &CancellationToken, server: tokio::task::JoinHandle<std::io::Result<()>>) {
+    let grace = std::time::Duration::from_secs(env_secs("SHUTDOWN_GRACE_SECS", DEFAULT_SHUTDOWN_GRACE_SECS));
+    info!("Shutting down; waiting up to {}s for in-flight jobs", grace.as_secs());
+    stop.cancel();
+    state.queue.close();
+
+    let drained = async {
+        while state.queue.running_count() > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+        let _ = server.await;
+    };
+    if tokio::time::timeout(grace, drained).await.is_err() {
+        warn!("Shutdown grace period elapsed with work still in flight");
+    }
+
+    let mut interrupted = Vec::new();
+    for job in list_local_jobs(state).await {
+        let failed = change_job(state, &job.job_id, Box::new(|job| {
+            if job.status != "pending" && job.status != "generating" {
+                return false;
+            }
+            job.status = "failed".to_string();
+            job.error = Some("interrupted by shutdown".to_string());
+            true
+        })).await;
+        if let Some(job) = failed {
+            interrupted.push(job.job_id);
+        }
+    }
+    if !interrupted.is_empty() {
+        warn!("Marked {} unfinished job(s) as failed", interrupted.len());
+        let callbacks = futures::future::join_all(interrupted.iter().map(|job_id| notify_callback(state, job_id)));
+        if tokio::time::timeout(SHUTDOWN_CALLBACK_TIMEOUT, callbacks).await.is_err() {
+            warn!("Gave up on webhooks of interrupted jobs");
+        }
+    }
+}
+
 // Health check endpoint
-async fn health_check() -> Json<serde_json::Value> {
-    Json(serde_json::json!({
-        "status": "ok",
-        "service": "shielded-prover"
-    }))
+#[utoipa::path(
+    get, path = "/health", tag = "service",
+    responses(
+        (status = 200, description = "Ready to prove", body = openapi::Health),
+        (status = 503, description = "Docker pre-warm running or failed", body = openapi::Health),
+    )
+)]
+async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let (status, code, error) = match &*state.readiness.read().await {
+        Readiness::Ready => ("ok", StatusCode::OK, None),
+        Readiness::Warming => ("warming", StatusCode::SERVICE_UNAVAILABLE, None),
+        Readiness::Failed(e) => ("unavailable", StatusCode::SERVICE_UNAVAILABLE, Some(e.clone())),
+    };
+
+    (code, Json(serde_json::json!({
+        "status": status,
+        "service": "shielded-prover",
+        "error": error,
+        "nullifier_set_size": state.prover.nullifier_set_len(),
+    })))
+}
+
+// Readiness probe: 503 unless the configured backend can produce proofs
+#[utoipa::path(
+    get, path = "/health/ready", tag = "service",
+    responses(
+        (status = 200, description = "The backend can produce proofs", body = openapi::ReadinessReport),
+        (status = 503, description = "Not ready", body = openapi::ReadinessReport),
+    )
+)]
+async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let backend = state.prover.backend_status().await;
+    let warmed_up = matches!(&*state.readiness.read().await, Readiness::Ready);
+    let ready = backend.ready && warmed_up;
+    let code = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (code, Json(serde_json::json!({
+        "ready": ready,
+        "docker_available": backend.docker_available,
+        "bonsai_configured": backend.bonsai_configured,
+        "mock_mode": backend.mock_mode,
+    })))
+}
+
+// Prometheus metrics endpoint
+#[utoipa::path(
+    get, path = "/metrics", tag = "service",
+    responses((status = 200, description = "Prometheus text exposition", body = String, content_type = "text/plain"))
+)]
+async fn get_metrics(State(state): State<AppState>) -> String {
+    telemetry::set_job_gauges(state.queue.running_count(), state.queue.queued_count());
+    state.metrics.render()
 }
 
 // API info endpoint
-async fn api_info() -> Json<serde_json::Value> {
+#[utoipa::path(
+    get, path = "/api/info", tag = "service",
+    responses((status = 200, description = "Network, contracts, tokens and enabled features", body = serde_json::Value))
+)]
+async fn api_info(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let config = state.prover.config();
+    let mut contracts = serde_json::json!({
+        "protocol_adapter": config.protocol_adapter,
+        "uniswap_forwarder": config.uniswap_forwarder,
+    });
+    // `<symbol>_forwarder` for every token
+    for token in config.tokens.iter() {
+        contracts[format!("{}_forwarder", token.symbol.to_lowercase())] = token.forwarder_address.clone().into();
+    }
     Json(serde_json::json!({
         "name": "Shielded Actions Prover",
         "version": "0.2.0",
-        "network": "sepolia",
-        "contracts": {
-            "protocol_adapter": "0x08c3bdc46B115cDc71Df076d9De96EeEBaa98525",
-            "usdc_forwarder": "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE",
-            "weth_forwarder": "0xD5307D777dC60b763b74945BF5A42ba93ce44e4b",
-            "uniswap_forwarder": "0x9335Fa4A31E552378Ed29b94704c52b5635cd1AA"
-        },
+        "network": config.network,
+        "chain_id": config.chain_id,
+        "contracts": contracts,
+        "tokens": config.tokens,
         "features": {
             "async_proofs": true,
-            "polling_endpoint": "/api/job/:job_id"
-        }
+            "polling_endpoint": "/api/job/:job_id",
+            "proof_endpoint": "/api/proof/:proof_id",
+            "ids": {
+                "job_id": "Random per request, returned by /api/shield, /api/swap and /api/unshield. Poll /api/job/:job_id.",
+                "proof_id": "Hash of the request inputs and deployment, so identical requests share it. /api/proof/:proof_id serves proofs generated or served from the cache since startup, on every backend."
+            },
+            "decode_endpoint": "/api/decode",
+            "verify_endpoint": "/api/verify",
+            "balance_endpoint": "/api/balance",
+            "vk_endpoint": "/api/vk",
+            "estimate_endpoint": "/api/estimate",
+            "openapi": "/openapi.json",
+            "deletion_criteria": ["never", "immediately"],
+            "sender_signature_required": state.require_sender_sig,
+            "api_key_required": !state.api_keys.is_empty(),
+            "shield_permit": "Optional EIP-2612 {value, deadline, v, r, s}; bundles the approval into the shield",
+            "raw_calls": state.prover.raw_calls_enabled(),
+            "proof_cache": "Identical requests return the cached proof; set \"force\": true for a fresh one",
+            "idempotency_key": "Retries sending the same Idempotency-Key header get the job the first request started",
+            "simulate": state.prover.rpc_url().is_some(),
+            "priorities": ["high", "normal", "low"],
+            "max_concurrent_proofs": state.queue.max_concurrent(),
+            "max_pending_jobs": state.queue.max_pending()
+        },
+        "proof_cache": state.prover.cache_stats(),
     }))
 }
 
+// Verifying keys of the logic circuits, for on-chain verifier registration
+#[utoipa::path(
+    get, path = "/api/vk", tag = "service",
+    responses((status = 200, description = "Verifying keys of the logic circuits", body = serde_json::Value))
+)]
+async fn get_verifying_keys() -> Json<serde_json::Value> {
+    Json(serde_json::json!(logic_verifying_keys()))
+}
+
 // Generate keypair endpoint
+//
+// `private_key` is a nullifier key and `public_key` its commitment. The
+// commitment is what goes on-chain as a resource's `nk_commitment`; the key must
+// stay with the client.
+#[utoipa::path(
+    post, path = "/api/generate-keypair", tag = "service",
+    responses((status = 200, description = "A fresh nullifier key and its commitment", body = openapi::Keypair))
+)]
 async fn generate_keypair() -> Json<serde_json::Value> {
-    use sha2::{Sha256, Digest};
-
-    let mut hasher = Sha256::new();
-    hasher.update(get_timestamp().to_le_bytes());
-    hasher.update(rand::random::<[u8; 32]>());
-    let private_key = hex::encode(hasher.finalize());
-
-    let mut pub_hasher = Sha256::new();
-    pub_hasher.update(hex::decode(&private_key).unwrap_or_default());
-    let public_key = hex::encode(pub_hasher.finalize());
+    let (private_key, public_key) = generate_nullifier_keypair();
 
     Json(serde_json::json!({
         "private_key": private_key,
@@ -166,355 +974,1962 @@ async fn generate_keypair() -> Json<serde_json::Value> {
     }))
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct EstimateQuery {
+    /// shield, swap, unshield, raw, batch, delta or ephemeral
+    action: String,
+    /// local, bonsai or mock (default: the backend this service proves with)
+    backend: Option<String>,
+}
+
+// Expected proving time, for clients showing an ETA: the average of recent proofs, or a typical time
+#[utoipa::path(
+    get, path = "/api/estimate", tag = "service",
+    params(EstimateQuery),
+    responses(
+        (status = 200, description = "Estimated proving time", body = estimate::Estimate),
+        (status = 400, description = "Unknown action or backend", body = openapi::ErrorBody),
+    )
+)]
+async fn get_estimate(
+    State(state): State<AppState>,
+    Query(query): Query<EstimateQuery>,
+) -> Result<Json<estimate::Estimate>, AppError> {
+    Ok(Json(state.prover.estimate(&query.action, query.backend.as_deref())?))
+}
+
 // ============== ASYNC JOB ENDPOINTS ==============
 
-#[derive(Debug, Deserialize)]
+/// Client error naming the request field that failed validation
+fn invalid_field(field: &str, error: impl std::fmt::Display) -> AppError {
+    AppError::bad_request(anyhow::anyhow!("{}: {}", field, error))
+}
+
+/// Check a hex address field, rejecting a mixed-case one whose EIP-55 checksum is wrong
+fn validate_address(field: &str, address: &str) -> Result<(), AppError> {
+    parse_checksummed_address(address).map(|_| ()).map_err(|e| invalid_field(field, e))
+}
+
+fn validate_nullifier_key(nullifier_key: &str) -> Result<(), AppError> {
+    parse_nullifier_key(nullifier_key).map(|_| ()).map_err(|e| invalid_field("nullifier_key", e))
+}
+
+/// Amount of a request resource: a decimal string of smallest units, greater than zero
+fn validate_resource_amount(field: &str, resource: &serde_json::Value) -> Result<u128, AppError> {
+    let amount = resource.get("amount").ok_or_else(|| invalid_field(field, "missing"))?;
+    let parsed = amount.as_str()
+        .and_then(|s| s.trim().parse::<u128>().ok())
+        .ok_or_else(|| invalid_field(field, format!("expected a decimal string of smallest units, got {}", amount)))?;
+    ensure_nonzero(parsed).map_err(|e| invalid_field(field, e))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 struct ShieldProofRequest {
     token: String,
     amount: String,
     sender: String,
-    nullifier_key: String,
+    /// Private key material; masked in the request's Debug output
+    #[schema(value_type = String)]
+    nullifier_key: Secret,
+    #[serde(default)]
+    priority: Priority,
+    /// Deletion criterion of the forwarder call blob ("never" or "immediately")
+    #[schema(value_type = String, example = "never")]
+    #[serde(default)]
+    deletion_criterion: DeletionCriterion,
+    /// EIP-191 signature by `sender` over the shield request message
+    #[serde(default)]
+    signature: Option<String>,
+    /// Signed nonce, accepted once per sender (required with `signature`)
+    #[serde(default)]
+    signature_nonce: Option<String>,
+    /// Signed Unix timestamp after which the signature is invalid, at most an
+    /// hour away (required with `signature`)
+    #[serde(default)]
+    signature_expiry: Option<u64>,
+    /// EIP-2612 permit by `sender` for the forwarder, so no prior approve is needed
+    /// (omit for tokens without permit support)
+    #[serde(default)]
+    permit: Option<signature::PermitRequest>,
+    /// Bypass the proof cache, e.g. when a fresh nullifier is required
+    #[serde(default)]
+    force: bool,
+    /// eth_call the calldata on the Protocol Adapter and report the outcome as `simulation`
+    #[serde(default)]
+    simulate: bool,
+    /// Receives the final job JSON when the job completes or fails; must resolve to a
+    /// public address unless its host is in WEBHOOK_ALLOWED_HOSTS
+    #[serde(default)]
+    callback_url: Option<String>,
 }
 
-// Start a shield proof job asynchronously
-async fn start_shield_job(
-    State(state): State<AppState>,
-    Json(req): Json<ShieldProofRequest>,
-) -> Json<serde_json::Value> {
-    let job_id = generate_job_id();
-    info!("Starting shield job {}: {:?}", job_id, req);
-
-    // Create pending job
-    {
-        let mut jobs = state.jobs.write().await;
-        jobs.insert(job_id.clone(), JobStatus {
-            job_id: job_id.clone(),
-            status: "pending".to_string(),
-            proof: None,
-            error: None,
-            created_at: get_timestamp(),
-        });
+/// Reject shield requests for unknown or fee-on-transfer tokens, ones whose
+/// amount is zero, negative or below one token unit, malformed senders or
+/// nullifier keys, malformed or expired permits, and permits for forwarders
+/// that do not accept them
+///
+/// Runs before a job is queued, so bad input fails with 400 rather than
+/// minutes later in the proving task. Returns the parsed permit, if any.
+fn validate_shield_request(tokens: &TokenRegistry, req: &ShieldProofRequest) -> Result<Option<PermitData>, AppError> {
+    let token = tokens.get(&req.token).map_err(|e| invalid_field("token", e))?;
+    ensure_shieldable(token).map_err(|e| invalid_field("token", e))?;
+    parse_token_amount(&req.amount, token).map_err(|e| invalid_field("amount", e))?;
+    validate_address("sender", &req.sender)?;
+    validate_nullifier_key(&req.nullifier_key)?;
+    if req.permit.is_some() {
+        ensure_permit_supported(token).map_err(|e| invalid_field("permit", e))?;
     }
+    req.permit
+        .as_ref()
+        .map(|permit| permit.to_permit_data(get_timestamp()))
+        .transpose()
+        .map_err(|e| invalid_field("permit", e))
+}
 
-    // Spawn background task to generate proof
-    let state_clone = state.clone();
-    let job_id_clone = job_id.clone();
-    let req_token = req.token.clone();
-    let req_amount = req.amount.clone();
-    let req_sender = req.sender.clone();
-    let req_nullifier = req.nullifier_key.clone();
+/// Check the sender's signature, required when REQUIRE_SENDER_SIG is set, and
+/// consume its nonce
+///
+/// Without it anyone could request a transferFrom out of an address that has
+/// approved the forwarder. Runs after the other checks, so a request rejected
+/// for another reason does not use up the nonce.
+fn authenticate_shield_request(state: &AppState, req: &ShieldProofRequest, permit: Option<&PermitData>) -> Result<(), AppError> {
+    let nk_commitment = nullifier_key_commitment(&req.nullifier_key).map_err(|e| invalid_field("nullifier_key", e))?;
+    let auth = |nonce, expiry| signature::ShieldAuthorization {
+        chain_id: state.prover.config().chain_id,
+        protocol_adapter: &state.prover.config().protocol_adapter,
+        token: &req.token,
+        amount: &req.amount,
+        sender: &req.sender,
+        nk_commitment: Some(&nk_commitment),
+        permit,
+        nonce,
+        expiry,
+    };
+    authenticate_sender(state, "", req.signature.as_deref(), req.signature_nonce.as_deref(), req.signature_expiry, auth)
+}
 
-    tokio::spawn(async move {
-        // Update status to generating
-        {
-            let mut jobs = state_clone.jobs.write().await;
-            if let Some(job) = jobs.get_mut(&job_id_clone) {
-                job.status = "generating".to_string();
-            }
+/// Verify a shield signature, naming fields with `prefix` (e.g. "entries[0].")
+fn authenticate_sender<'a>(
+    state: &AppState,
+    prefix: &str,
+    sig: Option<&str>,
+    nonce: Option<&'a str>,
+    expiry: Option<u64>,
+    auth: impl FnOnce(&'a str, u64) -> signature::ShieldAuthorization<'a>,
+) -> Result<(), AppError> {
+    let Some(sig) = sig else {
+        if state.require_sender_sig {
+            return Err(AppError::with_status(
+                StatusCode::UNAUTHORIZED,
+                anyhow::anyhow!("{}signature: shield requests must be signed by the sender", prefix),
+            ));
         }
+        return Ok(());
+    };
+    let (Some(nonce), Some(expiry)) = (nonce, expiry) else {
+        return Err(invalid_field(
+            &format!("{}signature_nonce", prefix),
+            "signature_nonce and signature_expiry are required with a signature",
+        ));
+    };
 
-        // Generate the proof
-        let prover = state_clone.prover.read().await;
-        let result = prover
-            .create_shield_proof(&req_token, &req_amount, &req_sender, &req_nullifier)
-            .await;
-
-        // Update job with result
-        let mut jobs = state_clone.jobs.write().await;
-        if let Some(job) = jobs.get_mut(&job_id_clone) {
-            match result {
-                Ok(proof) => {
-                    job.status = "completed".to_string();
-                    job.proof = Some(proof);
-                }
-                Err(e) => {
-                    job.status = "failed".to_string();
-                    job.error = Some(e.to_string());
-                }
-            }
-        }
-    });
+    signature::verify_shield_authorization(&auth(nonce, expiry), sig, &state.used_nonces, get_timestamp())
+        .map_err(|e| AppError::with_status(StatusCode::UNAUTHORIZED, anyhow::anyhow!("{}signature: {}", prefix, e)))
+}
 
-    // Return immediately with job ID
-    Json(serde_json::json!({
-        "job_id": job_id,
-        "status": "pending",
-        "message": "Proof generation started. Poll /api/job/{} for status.".replace("{}", &job_id)
-    }))
+// Start a shield proof job asynchronously
+#[utoipa::path(
+    post, path = "/api/shield", tag = "jobs",
+    request_body = ShieldProofRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the job the first request started")),
+    responses(
+        (status = 200, description = "Job queued; poll /api/job/{job_id}", body = openapi::JobAccepted),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 401, description = "Missing or invalid sender signature", body = openapi::ErrorBody),
+        (status = 503, description = "Queue full, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn start_shield_job(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    headers: HeaderMap,
+    Json(req): Json<ShieldProofRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let idempotency_key = idempotency_key(&headers)?;
+    if let Some(existing) = idempotent_job(&state, "shield", idempotency_key).await {
+        return Ok(Json(existing));
+    }
+    let permit = validate_shield_request(&state.prover.config().tokens, &req)?;
+    let meta = JobMeta {
+        kind: "shield",
+        idempotency_key,
+        priority: req.priority,
+        simulate: req.simulate,
+        callback_url: req.callback_url.clone(),
+        caller,
+    };
+    meta.admit(&state).await?;
+    authenticate_shield_request(&state, &req, permit.as_ref())?;
+
+    let request = format!("{:?}", req);
+    let prover = state.prover.clone();
+    spawn_job(&state, meta, request, move |job| async move {
+        prover
+            .create_shield_proof(&req.token, &req.amount, &req.sender, &req.nullifier_key, req.deletion_criterion, permit, req.force, Some(&job))
+            .await
+    })
+    .await
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct SwapProofRequest {
     input_resource: serde_json::Value,
     output_token: String,
-    nullifier_key: String,
+    /// Private key material; masked in the request's Debug output
+    #[schema(value_type = String)]
+    nullifier_key: Secret,
     min_amount_out: String,
+    #[serde(default)]
+    priority: Priority,
+    /// Bypass the proof cache, e.g. when a fresh nullifier is required
+    #[serde(default)]
+    force: bool,
+    /// eth_call the calldata on the Protocol Adapter and report the outcome as `simulation`
+    #[serde(default)]
+    simulate: bool,
+    /// Receives the final job JSON when the job completes or fails; must resolve to a
+    /// public address unless its host is in WEBHOOK_ALLOWED_HOSTS
+    #[serde(default)]
+    callback_url: Option<String>,
 }
 
+/// Reject swaps between unregistered tokens, of a token for itself, into a
+/// token that cannot be shielded, with a zero or malformed input amount or
+/// minimum output, or with a malformed nullifier key
+///
+/// Returns the exactInputSingle params the Uniswap forwarder will be called with.
+fn validate_swap_request(config: &Config, req: &SwapProofRequest) -> Result<SwapParams, AppError> {
+    let tokens = &config.tokens;
+    let token_in = req.input_resource.get("token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid_field("input_resource.token", "missing"))?;
+    let token_in = tokens.get(token_in).map_err(|e| invalid_field("input_resource.token", e))?;
+    let amount_in = validate_resource_amount("input_resource.amount", &req.input_resource)?;
+    ensure_amount_bounds(amount_in, token_in).map_err(|e| invalid_field("input_resource.amount", e))?;
+    let token_out = tokens.get(&req.output_token).map_err(|e| invalid_field("output_token", e))?;
+    validate_nullifier_key(&req.nullifier_key)?;
+    let min_amount_out = parse_quantity(&req.min_amount_out)
+        .and_then(ensure_nonzero)
+        .map_err(|e| invalid_field("min_amount_out", e))?;
+
+    // The same order the prover builds from the request
+    let order = SwapOrder {
+        token_in: token_in.symbol.clone(),
+        amount_in,
+        token_out: token_out.symbol.clone(),
+        min_amount_out,
+        fee: DEFAULT_SWAP_FEE,
+    };
+    order.swap_params(config).map_err(|e| invalid_field("output_token", e))
+}
+
+#[utoipa::path(
+    post, path = "/api/swap", tag = "jobs",
+    request_body = SwapProofRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the job the first request started")),
+    responses(
+        (status = 200, description = "Job queued; poll /api/job/{job_id}", body = openapi::JobAccepted),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 503, description = "Queue full, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
 async fn start_swap_job(
     State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    headers: HeaderMap,
     Json(req): Json<SwapProofRequest>,
-) -> Json<serde_json::Value> {
-    let job_id = generate_job_id();
-    info!("Starting swap job {}: {:?}", job_id, req);
-
-    {
-        let mut jobs = state.jobs.write().await;
-        jobs.insert(job_id.clone(), JobStatus {
-            job_id: job_id.clone(),
-            status: "pending".to_string(),
-            proof: None,
-            error: None,
-            created_at: get_timestamp(),
-        });
+) -> Result<Json<serde_json::Value>, AppError> {
+    let idempotency_key = idempotency_key(&headers)?;
+    if let Some(existing) = idempotent_job(&state, "swap", idempotency_key).await {
+        return Ok(Json(existing));
     }
+    validate_swap_request(state.prover.config(), &req)?;
+    let meta = JobMeta {
+        kind: "swap",
+        idempotency_key,
+        priority: req.priority,
+        simulate: req.simulate,
+        callback_url: req.callback_url.clone(),
+        caller,
+    };
+    meta.admit(&state).await?;
+
+    let request = format!("{:?}", req);
+    let prover = state.prover.clone();
+    spawn_job(&state, meta, request, move |job| async move {
+        prover
+            .create_swap_proof(&req.input_resource, &req.output_token, &req.nullifier_key, &req.min_amount_out, req.force, Some(&job))
+            .await
+    })
+    .await
+}
 
-    let state_clone = state.clone();
-    let job_id_clone = job_id.clone();
+#[derive(Debug, Deserialize, ToSchema)]
+struct UnshieldProofRequest {
+    resource: serde_json::Value,
+    recipient: String,
+    /// Private key material; masked in the request's Debug output
+    #[schema(value_type = String)]
+    nullifier_key: Secret,
+    #[serde(default)]
+    priority: Priority,
+    /// Deletion criterion of the forwarder call blob ("never" or "immediately")
+    #[schema(value_type = String, example = "never")]
+    #[serde(default)]
+    deletion_criterion: DeletionCriterion,
+    /// Bypass the proof cache, e.g. when a fresh nullifier is required
+    #[serde(default)]
+    force: bool,
+    /// eth_call the calldata on the Protocol Adapter and report the outcome as `simulation`
+    #[serde(default)]
+    simulate: bool,
+    /// Receives the final job JSON when the job completes or fails; must resolve to a
+    /// public address unless its host is in WEBHOOK_ALLOWED_HOSTS
+    #[serde(default)]
+    callback_url: Option<String>,
+}
 
-    tokio::spawn(async move {
-        {
-            let mut jobs = state_clone.jobs.write().await;
-            if let Some(job) = jobs.get_mut(&job_id_clone) {
-                job.status = "generating".to_string();
-            }
+/// Reject unshield requests for an unregistered token, with a missing, zero or
+/// malformed amount, with a malformed recipient or nullifier key, or to a
+/// recipient that is not on the unshield allow-list
+///
+/// Returns the token's canonical symbol.
+fn validate_unshield_request(prover: &ProverService, req: &UnshieldProofRequest) -> Result<String, AppError> {
+    let token = resource_token(&req.resource).map_err(|e| invalid_field("resource", e))?;
+    let token = prover.config().tokens.get(&token).map_err(|e| invalid_field("resource.token", e))?;
+    let amount = validate_resource_amount("resource.amount", &req.resource)?;
+    ensure_amount_bounds(amount, token).map_err(|e| invalid_field("resource.amount", e))?;
+    validate_address("recipient", &req.recipient)?;
+    prover.check_unshield_recipient(&req.recipient).map_err(|e| invalid_field("recipient", e))?;
+    validate_nullifier_key(&req.nullifier_key)?;
+    Ok(token.symbol.clone())
+}
+
+#[utoipa::path(
+    post, path = "/api/unshield", tag = "jobs",
+    request_body = UnshieldProofRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the job the first request started")),
+    responses(
+        (status = 200, description = "Job queued; poll /api/job/{job_id}", body = openapi::JobAccepted),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 503, description = "Queue full, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn start_unshield_job(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    headers: HeaderMap,
+    Json(req): Json<UnshieldProofRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let idempotency_key = idempotency_key(&headers)?;
+    if let Some(existing) = idempotent_job(&state, "unshield", idempotency_key).await {
+        return Ok(Json(existing));
+    }
+    validate_unshield_request(&state.prover, &req)?;
+    let meta = JobMeta {
+        kind: "unshield",
+        idempotency_key,
+        priority: req.priority,
+        simulate: req.simulate,
+        callback_url: req.callback_url.clone(),
+        caller,
+    };
+    meta.admit(&state).await?;
+
+    let request = format!("{:?}", req);
+    let prover = state.prover.clone();
+    spawn_job(&state, meta, request, move |job| async move {
+        prover
+            .create_unshield_proof(&req.resource, &req.recipient, &req.nullifier_key, req.deletion_criterion, req.force, Some(&job))
+            .await
+    })
+    .await
+}
+
+// Get job status
+#[utoipa::path(
+    get, path = "/api/job/{job_id}", tag = "jobs",
+    params(("job_id" = String, Path, description = "Id returned when the job was started")),
+    responses(
+        (status = 200, description = "The job; includes the result once completed", body = openapi::JobView),
+        (status = 401, description = "Missing or invalid API key", body = openapi::ErrorBody),
+        (status = 404, description = "Unknown job, or one started with another API key", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn get_job_status(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    Path(job_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let job = find_owned_job(&state, &job_id, &caller).await?;
+    Ok(Json(job_response(&state, &job)))
+}
+
+/// The transaction in a proof's calldata, decoded into its commitments, nullifiers and deltas
+///
+/// Null when there is no calldata (mock and Bonsai proofs) or it does not decode.
+fn transaction_json(calldata: Option<&str>) -> serde_json::Value {
+    let Some(calldata) = calldata else {
+        return serde_json::Value::Null;
+    };
+    match chain::decode_execute_calldata(calldata).and_then(|tx| chain::transaction_details(&tx)) {
+        Ok(details) => serde_json::json!(details),
+        Err(e) => {
+            warn!("Could not decode proof calldata: {}", e);
+            serde_json::Value::Null
         }
+    }
+}
 
-        let prover = state_clone.prover.read().await;
-        let result = prover
-            .create_swap_proof(&req.input_resource, &req.output_token, &req.nullifier_key, &req.min_amount_out)
-            .await;
+/// Client-facing JSON for a job, as served by /api/job/{job_id}
+fn job_response(state: &AppState, job: &JobStatus) -> serde_json::Value {
+    let mut response = serde_json::json!({
+        "job_id": job.job_id,
+        "status": job.status,
+        "priority": job.priority,
+        "created_at": job.created_at,
+        "started_at": job.started_at,
+        "completed_at": job.completed_at,
+        "failed_at": job.failed_at,
+    });
+    if let Some(duration) = job.duration_secs() {
+        response["duration_secs"] = serde_json::json!(duration);
+    }
+
+    if let Some(position) = state.queue.position(&job.job_id) {
+        response["queue_position"] = serde_json::json!(position);
+    }
+
+    // Current proving stage, as a live indicator
+    if job.status == "generating" {
+        if let Some(progress) = job.context.progress.latest() {
+            response["progress"] = serde_json::json!(progress);
+        }
+    }
 
-        let mut jobs = state_clone.jobs.write().await;
-        if let Some(job) = jobs.get_mut(&job_id_clone) {
-            match result {
-                Ok(proof) => {
-                    job.status = "completed".to_string();
-                    job.proof = Some(proof);
+    if let Some(proof) = &job.proof {
+        // Include the calldata when proof is ready
+        response["calldata"] = serde_json::json!(proof.calldata);
+        response["proof_id"] = serde_json::json!(proof.proof_id);
+
+        // Build the full response the frontend expects
+        if let Some(calldata) = &proof.calldata {
+            response["result"] = serde_json::json!({
+                "transaction": transaction_json(Some(calldata)),
+                "calldata": calldata,
+                "forwarder_call": {
+                    "data": calldata
                 }
-                Err(e) => {
-                    job.status = "failed".to_string();
-                    job.error = Some(e.to_string());
+            });
+            if let Some(summary) = &proof.summary {
+                response["result"]["summary"] = serde_json::json!(summary);
+            }
+        }
+    }
+
+    if let Some(spent) = job.nullifier_spent {
+        response["nullifier_spent"] = serde_json::json!(spent);
+        if let Some(result) = response.get_mut("result") {
+            result["nullifier_spent"] = serde_json::json!(spent);
+        }
+    }
+
+    if let Some(gas) = job.estimated_gas {
+        response["estimated_gas"] = serde_json::json!(gas);
+        if let Some(result) = response.get_mut("result") {
+            result["estimated_gas"] = serde_json::json!(gas);
+        }
+    }
+
+    if let Some(simulation) = &job.simulation {
+        response["simulation"] = serde_json::json!(simulation);
+        if let Some(result) = response.get_mut("result") {
+            result["simulation"] = serde_json::json!(simulation);
+        }
+    }
+
+    if let Some(error) = &job.error {
+        response["error"] = serde_json::json!(error);
+    }
+    if let Some(code) = &job.error_code {
+        response["error_code"] = serde_json::json!(code);
+    }
+
+    if job.callback_url.is_some() {
+        response["callback_delivered"] = serde_json::json!(job.callback_delivered);
+    }
+
+    response
+}
+
+/// How often an idle SSE stream sends a heartbeat comment
+const SSE_HEARTBEAT_SECS: u64 = 15;
+
+/// Progress of a /api/job/{job_id}/events stream
+enum JobEventStream {
+    Watching {
+        events: watch::Receiver<serde_json::Value>,
+        first: bool,
+    },
+    Done,
+    End,
+}
+
+// Emit the job JSON as an SSE `data:` line on every status change, then `event: done`
+#[utoipa::path(
+    get, path = "/api/job/{job_id}/events", tag = "jobs",
+    params(("job_id" = String, Path, description = "Id returned when the job was started")),
+    responses(
+        (status = 200, description = "Server-sent events: the job JSON (`JobView`) as `data:` on every status change, then `event: done`", content_type = "text/event-stream"),
+        (status = 401, description = "Missing or invalid API key", body = openapi::ErrorBody),
+        (status = 404, description = "Unknown job, or one started with another API key", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn job_events(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    Path(job_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let events = find_owned_job(&state, &job_id, &caller).await?.events.subscribe();
+
+    let updates = stream::unfold(JobEventStream::Watching { events, first: true }, |progress| async move {
+        match progress {
+            JobEventStream::Watching { mut events, first } => {
+                // The job was removed from the map
+                if !first && events.changed().await.is_err() {
+                    return None;
                 }
+                let update = events.borrow_and_update().clone();
+                let next = if update["status"].as_str().is_some_and(is_finished) {
+                    JobEventStream::Done
+                } else {
+                    JobEventStream::Watching { events, first: false }
+                };
+                Some((Ok(Event::default().data(update.to_string())), next))
             }
+            JobEventStream::Done => Some((Ok(Event::default().event("done").data("")), JobEventStream::End)),
+            JobEventStream::End => None,
         }
     });
 
-    Json(serde_json::json!({
-        "job_id": job_id,
-        "status": "pending"
+    Ok(Sse::new(updates).keep_alive(
+        KeepAlive::new()
+            .interval(std::time::Duration::from_secs(SSE_HEARTBEAT_SECS))
+            .text("heartbeat"),
+    ))
+}
+
+/// Seconds a client turned away by a full queue is told to wait
+const QUEUE_FULL_RETRY_AFTER_SECS: u64 = 30;
+
+/// Idempotency-Key header of a job request, if sent
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<&str>, AppError> {
+    let Some(value) = headers.get("idempotency-key") else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| AppError::bad_request(anyhow::anyhow!("Idempotency-Key must be visible ASCII")))?;
+    idempotency::validate_key(key).map_err(AppError::bad_request)?;
+    Ok(Some(key))
+}
+
+/// Response for the job a former request with this key started on `scope`, if it is still kept
+async fn idempotent_job(state: &AppState, scope: &str, key: Option<&str>) -> Option<serde_json::Value> {
+    let key = key?;
+    let job_id = state.idempotency.get(scope, key, std::time::Instant::now())?;
+    let Some(job) = find_job(state, &job_id).await else {
+        // Deleted or reaped; the next request starts afresh
+        state.idempotency.forget(scope, key);
+        return None;
+    };
+    debug!("Idempotency-Key {} on {} replays job {}", key, scope, job_id);
+    Some(serde_json::json!({
+        "job_id": job.job_id,
+        "status": job.status,
+        "priority": job.priority,
+        "idempotent_replay": true
     }))
 }
 
-#[derive(Debug, Deserialize)]
-struct UnshieldProofRequest {
-    resource: serde_json::Value,
-    recipient: String,
-    nullifier_key: String,
+/// Map the request's key to `job_id`, or return the job of a concurrent request that mapped it first
+fn claim_idempotency_key(state: &AppState, scope: &str, key: Option<&str>, job_id: &str) -> Option<serde_json::Value> {
+    let winner = state.idempotency.claim(scope, key?, job_id, std::time::Instant::now());
+    (winner != job_id).then(|| {
+        serde_json::json!({
+            "job_id": winner,
+            "status": "pending",
+            "idempotent_replay": true
+        })
+    })
 }
 
-async fn start_unshield_job(
-    State(state): State<AppState>,
-    Json(req): Json<UnshieldProofRequest>,
-) -> Json<serde_json::Value> {
+async fn validate_callback(state: &AppState, callback_url: &Option<String>) -> Result<(), AppError> {
+    match callback_url {
+        Some(url) => webhook::validate_callback_url(url, &state.webhook_allowed_hosts)
+            .await
+            .map_err(AppError::bad_request),
+        None => Ok(()),
+    }
+}
+
+/// A job that was just queued, with no result yet
+fn new_job_status(
+    job_id: &str,
+    priority: Priority,
+    callback_url: Option<String>,
+    api_key_id: Option<String>,
+    context: JobContext,
+) -> JobStatus {
+    JobStatus {
+        job_id: job_id.to_string(),
+        status: "pending".to_string(),
+        priority,
+        proof: None,
+        error: None,
+        error_code: None,
+        created_at: get_timestamp(),
+        started_at: None,
+        completed_at: None,
+        failed_at: None,
+        nullifier_spent: None,
+        simulation: None,
+        estimated_gas: None,
+        callback_url,
+        callback_delivered: false,
+        api_key_id,
+        context,
+        events: JobEvents::default(),
+    }
+}
+
+/// What an async job request carries besides the operation it proves
+struct JobMeta<'a> {
+    /// "shield", "swap" or "unshield": the Idempotency-Key scope and metrics label
+    kind: &'static str,
+    idempotency_key: Option<&'a str>,
+    priority: Priority,
+    simulate: bool,
+    callback_url: Option<String>,
+    /// API key the job is started with; only it may manage the job
+    caller: ApiCaller,
+}
+
+impl JobMeta<'_> {
+    /// Checks shared by every job request: the callback URL and `simulate`
+    async fn admit(&self, state: &AppState) -> Result<(), AppError> {
+        validate_callback(state, &self.callback_url).await?;
+        ensure_can_simulate(state, self.simulate)
+    }
+}
+
+/// Queue a job whose proof `prove` generates and return the job's response
+///
+/// `request` is logged as the job starts. Once the job leaves the queue it is
+/// marked generating; its result is then checked against the chain, simulated
+/// when asked for, recorded and posted to the callback.
+///
+/// Fails with 503 and `Retry-After` when MAX_PENDING_JOBS jobs are already
+/// waiting; the job is then dropped along with its idempotency key.
+async fn spawn_job<F, Fut>(
+    state: &AppState,
+    meta: JobMeta<'_>,
+    request: String,
+    prove: F,
+) -> Result<Json<serde_json::Value>, AppError>
+where
+    F: FnOnce(JobContext) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<ProofResponse, ProverError>> + Send + 'static,
+{
     let job_id = generate_job_id();
-    info!("Starting unshield job {}: {:?}", job_id, req);
-
-    {
-        let mut jobs = state.jobs.write().await;
-        jobs.insert(job_id.clone(), JobStatus {
-            job_id: job_id.clone(),
-            status: "pending".to_string(),
-            proof: None,
-            error: None,
-            created_at: get_timestamp(),
-        });
+    if let Some(existing) = claim_idempotency_key(state, meta.kind, meta.idempotency_key, &job_id) {
+        return Ok(Json(existing));
     }
+    info!("Starting {} job {}: {}", meta.kind, job_id, request);
+
+    let job_context = JobContext::new();
+    insert_job(state, new_job_status(&job_id, meta.priority, meta.callback_url, meta.caller.0, job_context.clone())).await;
 
     let state_clone = state.clone();
     let job_id_clone = job_id.clone();
-
-    tokio::spawn(async move {
-        {
-            let mut jobs = state_clone.jobs.write().await;
-            if let Some(job) = jobs.get_mut(&job_id_clone) {
-                job.status = "generating".to_string();
-            }
+    let (kind, simulate) = (meta.kind, meta.simulate);
+    let queued = state.queue.push(job_id.clone(), meta.priority, async move {
+        // Cancelled while still queued
+        if job_context.is_cancelled() {
+            return;
         }
 
-        let prover = state_clone.prover.read().await;
-        let result = prover
-            .create_unshield_proof(&req.resource, &req.recipient, &req.nullifier_key)
-            .await;
-
-        let mut jobs = state_clone.jobs.write().await;
-        if let Some(job) = jobs.get_mut(&job_id_clone) {
-            match result {
-                Ok(proof) => {
-                    job.status = "completed".to_string();
-                    job.proof = Some(proof);
-                }
-                Err(e) => {
-                    job.status = "failed".to_string();
-                    job.error = Some(e.to_string());
-                }
+        update_job(&state_clone, &job_id_clone, |job| {
+            job.status = "generating".to_string();
+            job.started_at = Some(get_timestamp());
+        }).await;
+
+        let started = std::time::Instant::now();
+        let result = prove(job_context).await;
+        telemetry::record_proof(kind, &result, started.elapsed());
+        let nullifier_spent = check_nullifier_spent(&state_clone, &result).await;
+        let simulation = simulate_proof(&state_clone, simulate, &result).await;
+        let estimated_gas = estimate_proof_gas(&state_clone, &result).await;
+
+        update_job(&state_clone, &job_id_clone, move |job| match result {
+            Ok(proof) => {
+                job.status = "completed".to_string();
+                job.proof = Some(proof);
+                job.nullifier_spent = nullifier_spent;
+                job.simulation = simulation;
+                job.estimated_gas = estimated_gas;
             }
-        }
+            Err(e) => {
+                job.status = "failed".to_string();
+                job.error = Some(e.to_string());
+                job.error_code = Some(e.code().to_string());
+            }
+        }).await;
+        notify_callback(&state_clone, &job_id_clone).await;
     });
+    if let Err(full) = queued {
+        warn!("Turning away {} job {}: {}", kind, job_id, full);
+        if let Err(e) = state.jobs.remove(&job_id).await {
+            warn!("Failed to delete job {} from the job store: {}", job_id, e);
+        }
+        if let Some(key) = meta.idempotency_key {
+            state.idempotency.forget(kind, key);
+        }
+        return Err(AppError::unavailable(full, QUEUE_FULL_RETRY_AFTER_SECS));
+    }
 
-    Json(serde_json::json!({
+    Ok(Json(serde_json::json!({
         "job_id": job_id,
-        "status": "pending"
-    }))
+        "status": "pending",
+        "priority": meta.priority,
+        "message": format!("Proof generation started. Poll /api/job/{} for status.", job_id)
+    })))
 }
 
-// Get job status
-async fn get_job_status(
+/// POST a finished job to its callback_url, if it has one, and record delivery
+async fn notify_callback(state: &AppState, job_id: &str) {
+    let Some(job) = find_job(state, job_id).await else {
+        return;
+    };
+    let Some(url) = &job.callback_url else {
+        return;
+    };
+    if job.status != "completed" && job.status != "failed" {
+        return;
+    }
+
+    let delivered = webhook::deliver(
+        url,
+        &job_response(state, &job),
+        state.webhook_secret.as_deref(),
+        &state.webhook_allowed_hosts,
+    )
+    .await;
+    update_job(state, job_id, move |job| job.callback_delivered = delivered).await;
+}
+
+/// Whether a job status is final
+fn is_finished(status: &str) -> bool {
+    matches!(status, "completed" | "failed" | "cancelled")
+}
+
+// Push the job JSON on every status change over a WebSocket, closing once the job finishes
+#[utoipa::path(
+    get, path = "/api/job/{job_id}/ws", tag = "jobs",
+    params(("job_id" = String, Path, description = "Id returned when the job was started")),
+    responses(
+        (status = 101, description = "WebSocket sending the job JSON (`JobView`) on every status change, closed once the job finishes"),
+        (status = 401, description = "Missing or invalid API key", body = openapi::ErrorBody),
+        (status = 404, description = "Unknown job, or one started with another API key", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn job_ws(
     State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
     Path(job_id): Path<String>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    let jobs = state.jobs.read().await;
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let events = find_owned_job(&state, &job_id, &caller).await?.events.subscribe();
+
+    Ok(ws.on_upgrade(move |socket| stream_job_ws(socket, events)))
+}
+
+async fn stream_job_ws(mut socket: WebSocket, mut events: watch::Receiver<serde_json::Value>) {
+    loop {
+        let update = events.borrow_and_update().clone();
+        let finished = update["status"].as_str().is_some_and(is_finished);
+        if socket.send(Message::Text(update.to_string().into())).await.is_err() {
+            return;
+        }
+        // Stop once the job finishes, or when the job is removed from the map
+        if finished || events.changed().await.is_err() {
+            break;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
 
-    if let Some(job) = jobs.get(&job_id) {
-        let mut response = serde_json::json!({
+// Get a proof by its deterministic proof id
+#[utoipa::path(
+    get, path = "/api/proof/{proof_id}", tag = "jobs",
+    params(("proof_id" = String, Path, description = "Deterministic id derived from the request inputs")),
+    responses(
+        (status = 200, description = "The finished proof", body = ProofResponse),
+        (status = 404, description = "No proof with this id", body = openapi::ErrorBody),
+    )
+)]
+async fn get_proof_status(
+    State(state): State<AppState>,
+    Path(proof_id): Path<String>,
+) -> Result<Json<ProofResponse>, AppError> {
+    Ok(Json(state.prover.get_proof_status(&proof_id).await?))
+}
+
+/// Default and maximum page sizes for /api/jobs
+const DEFAULT_JOB_PAGE_SIZE: usize = 50;
+const MAX_JOB_PAGE_SIZE: usize = 500;
+
+const JOB_STATUSES: [&str; 5] = ["pending", "generating", "completed", "failed", "cancelled"];
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListJobsQuery {
+    /// Only jobs with this status
+    status: Option<String>,
+    /// Page size
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+// List known jobs, newest first
+#[utoipa::path(
+    get, path = "/api/jobs", tag = "jobs",
+    params(ListJobsQuery),
+    responses(
+        (status = 200, description = "Page of the caller's jobs, newest first", body = openapi::JobList),
+        (status = 400, description = "Unknown status filter", body = openapi::ErrorBody),
+        (status = 401, description = "Missing or invalid API key", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn list_jobs(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    Query(query): Query<ListJobsQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if let Some(status) = &query.status {
+        if !JOB_STATUSES.contains(&status.as_str()) {
+            return Err(AppError::bad_request(anyhow::anyhow!(
+                "Invalid status '{}'. Expected one of: {}",
+                status,
+                JOB_STATUSES.join(", ")
+            )));
+        }
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_JOB_PAGE_SIZE).min(MAX_JOB_PAGE_SIZE);
+
+    let mut matching: Vec<JobStatus> = state
+        .jobs
+        .list()
+        .await?
+        .into_iter()
+        .filter(|job| caller.may_access(job) && query.status.as_ref().is_none_or(|status| &job.status == status))
+        .collect();
+    matching.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.job_id.cmp(&b.job_id)));
+
+    let page: Vec<serde_json::Value> = matching
+        .iter()
+        .skip(query.offset)
+        .take(limit)
+        .map(|job| serde_json::json!({
             "job_id": job.job_id,
             "status": job.status,
-        });
+            "created_at": job.created_at,
+        }))
+        .collect();
 
-        if let Some(proof) = &job.proof {
-            // Include the calldata when proof is ready
-            response["calldata"] = serde_json::json!(proof.calldata);
-            response["proof_id"] = serde_json::json!(proof.proof_id);
-
-            // Build the full response the frontend expects
-            if let Some(calldata) = &proof.calldata {
-                response["result"] = serde_json::json!({
-                    "transaction": proof.proof_id,
-                    "resource_commitment": format!("0x{}", proof.proof_id),
-                    "calldata": calldata,
-                    "forwarder_call": {
-                        "data": calldata
-                    }
-                });
-            }
+    Ok(Json(serde_json::json!({
+        "jobs": page,
+        "total": matching.len(),
+        "limit": limit,
+        "offset": query.offset,
+    })))
+}
+
+// Cancel a queued or running job; a running proof is abandoned
+#[utoipa::path(
+    post, path = "/api/job/{job_id}/cancel", tag = "jobs",
+    params(("job_id" = String, Path, description = "Id returned when the job was started")),
+    responses(
+        (status = 200, description = "Job cancelled", body = openapi::CancelledJob),
+        (status = 401, description = "Missing or invalid API key", body = openapi::ErrorBody),
+        (status = 404, description = "Unknown job, or one started with another API key", body = openapi::ErrorBody),
+        (status = 409, description = "Job already finished", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn cancel_job(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    Path(job_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let job = find_owned_job(&state, &job_id, &caller).await?;
+    let already = |status: &str| {
+        AppError::with_status(StatusCode::CONFLICT, anyhow::anyhow!("Job {} is already {}", job_id, status))
+    };
+    if job.status != "pending" && job.status != "generating" {
+        return Err(already(&job.status));
+    }
+
+    let cancelled = change_job(&state, &job_id, Box::new(|job| {
+        // Finished since it was looked up
+        if job.status != "pending" && job.status != "generating" {
+            return false;
         }
+        job.context.cancel.cancel();
+        job.status = "cancelled".to_string();
+        job.error = Some("cancelled by client".to_string());
+        true
+    })).await;
+    if cancelled.is_none() {
+        let status = find_job(&state, &job_id).await.map(|job| job.status).unwrap_or_default();
+        return Err(already(&status));
+    }
+    info!("Cancelled job {}", job_id);
+
+    Ok(Json(serde_json::json!({
+        "job_id": job_id,
+        "status": "cancelled"
+    })))
+}
+
+// Forget a finished job once the client has its result
+#[utoipa::path(
+    delete, path = "/api/job/{job_id}", tag = "jobs",
+    params(("job_id" = String, Path, description = "Id returned when the job was started")),
+    responses(
+        (status = 204, description = "Job forgotten"),
+        (status = 401, description = "Missing or invalid API key", body = openapi::ErrorBody),
+        (status = 404, description = "Unknown job, or one started with another API key", body = openapi::ErrorBody),
+        (status = 409, description = "Job still pending or generating; cancel it first", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn delete_job(
+    State(state): State<AppState>,
+    Extension(caller): Extension<ApiCaller>,
+    Path(job_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let job = find_owned_job(&state, &job_id, &caller).await?;
+
+    // The running task still writes its result into this entry
+    if job.status == "pending" || job.status == "generating" {
+        return Err(AppError::with_status(
+            StatusCode::CONFLICT,
+            anyhow::anyhow!("Job {} is still {}", job_id, job.status),
+        ));
+    }
+
+    state.jobs.remove(&job_id).await?;
+    info!("Deleted job {}", job_id);
 
-        if let Some(error) = &job.error {
-            response["error"] = serde_json::json!(error);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Get the proving log for a job (requires ADMIN_API_KEY)
+#[utoipa::path(
+    get, path = "/api/job/{job_id}/logs", tag = "jobs",
+    params(("job_id" = String, Path, description = "Id returned when the job was started")),
+    responses(
+        (status = 200, description = "Proving log of the job", body = openapi::JobLogs),
+        (status = 404, description = "Unknown job", body = openapi::ErrorBody),
+    ),
+    security(("admin_key" = []))
+)]
+async fn get_job_logs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&state, &headers)?;
+
+    let job = find_job(&state, &job_id)
+        .await
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("Job not found: {}", job_id)))?;
+    let logs = job.context.log.snapshot();
+
+    Ok(Json(serde_json::json!({
+        "job_id": job.job_id,
+        "status": job.status,
+        "lines": logs.lines,
+        "truncated": logs.truncated
+    })))
+}
+
+// ============== CALLDATA INSPECTION ==============
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct DecodeRequest {
+    calldata: String,
+}
+
+// Decode arbitrary execute(Transaction) calldata into a readable summary
+#[utoipa::path(
+    post, path = "/api/decode", tag = "inspection",
+    request_body = DecodeRequest,
+    responses(
+        (status = 200, description = "Decoded transaction (also served at /api/decode-calldata)", body = chain::TransactionSummary),
+        (status = 400, description = "Not execute(Transaction) calldata", body = openapi::ErrorBody),
+    )
+)]
+async fn decode_calldata(
+    State(state): State<AppState>,
+    Json(req): Json<DecodeRequest>,
+) -> Result<Json<chain::TransactionSummary>, AppError> {
+    let tx = chain::decode_execute_calldata(&req.calldata).map_err(AppError::bad_request)?;
+    Ok(Json(chain::summarize_transaction(state.prover.config(), &tx)))
+}
+
+// Check that execute(Transaction) calldata verifies, by decoding it back into an
+// ARM transaction and verifying its proofs locally
+#[utoipa::path(
+    post, path = "/api/verify", tag = "inspection",
+    request_body = DecodeRequest,
+    responses(
+        (status = 200, description = "Whether the transaction's proofs and balance verify", body = openapi::Verification),
+    )
+)]
+async fn verify_calldata(Json(req): Json<DecodeRequest>) -> Result<Json<serde_json::Value>, AppError> {
+    // Malformed calldata is reported as invalid, like calldata that fails verification
+    let result = tokio::task::spawn_blocking(move || {
+        let calldata = hex::decode(req.calldata.trim_start_matches("0x"))
+            .map_err(|e| anyhow::anyhow!("Invalid calldata hex: {}", e))?;
+        verify_execute_calldata(&calldata)
+    })
+    .await?;
+
+    Ok(Json(match result {
+        Ok(()) => serde_json::json!({ "valid": true }),
+        Err(e) => serde_json::json!({ "valid": false, "error": e.to_string() }),
+    }))
+}
+
+/// Most leaves accepted by /api/merkle-root; an action has two per compliance unit
+const MAX_ACTION_TREE_LEAVES: usize = 1024;
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct MerkleRootRequest {
+    /// Nullifiers and commitments as 32-byte hex, in the order they are inserted
+    leaves: Vec<String>,
+}
+
+// Compute an action tree root and per-leaf paths, for clients building their own actions
+#[utoipa::path(
+    post, path = "/api/merkle-root", tag = "inspection",
+    request_body = MerkleRootRequest,
+    responses(
+        (status = 200, description = "Action tree root and the path of every leaf", body = chain::ActionTreeReport),
+        (status = 400, description = "No leaves, too many, or a malformed one", body = openapi::ErrorBody),
+    )
+)]
+async fn merkle_root(
+    Json(req): Json<MerkleRootRequest>,
+) -> Result<Json<chain::ActionTreeReport>, AppError> {
+    if req.leaves.len() > MAX_ACTION_TREE_LEAVES {
+        return Err(invalid_field("leaves", format!("at most {} leaves, got {}", MAX_ACTION_TREE_LEAVES, req.leaves.len())));
+    }
+    let leaves = req.leaves
+        .iter()
+        .enumerate()
+        .map(|(i, leaf)| leaf.trim().parse::<alloy::primitives::B256>().map_err(|e| invalid_field(&format!("leaves[{}]", i), e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let report = chain::action_tree(&leaves).map_err(AppError::bad_request)?;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct BalanceRequest {
+    operations: Vec<balance::BalanceOperation>,
+}
+
+// Report the per-label net quantity of a batch, without proving
+#[utoipa::path(
+    post, path = "/api/balance", tag = "inspection",
+    request_body = BalanceRequest,
+    responses(
+        (status = 200, description = "Net quantity per label", body = balance::BalanceReport),
+        (status = 400, description = "Malformed quantity", body = openapi::ErrorBody),
+    )
+)]
+async fn check_balance(
+    Json(req): Json<BalanceRequest>,
+) -> Result<Json<balance::BalanceReport>, AppError> {
+    let report = balance::balance_report(&req.operations).map_err(AppError::bad_request)?;
+    Ok(Json(report))
+}
+
+// ============== ADMIN ENDPOINTS ==============
+
+/// Token from an `Authorization: Bearer <token>` header
+fn bearer_token(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default()
+}
+
+/// API key a request was authenticated with, set by `require_api_key`
+///
+/// Holds the key's id, or None while no key is configured.
+#[derive(Clone, Debug, Default)]
+struct ApiCaller(Option<String>);
+
+impl ApiCaller {
+    /// Whether this caller may see and manage `job`
+    ///
+    /// A job started with a key belongs to that key. Jobs started while no key
+    /// was configured belong to everyone, and so does every job in dev mode.
+    fn may_access(&self, job: &JobStatus) -> bool {
+        self.0.is_none() || job.api_key_id.is_none() || self.0 == job.api_key_id
+    }
+}
+
+/// Require `Authorization: Bearer <key>` with a key from API_KEYS or /api/admin/keys
+///
+/// Applied to the proving and job routes, which get the key as an [`ApiCaller`].
+/// Every request passes while no key is configured (dev mode).
+async fn require_api_key(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, AppError> {
+    let caller = if state.api_keys.is_empty() {
+        ApiCaller(None)
+    } else {
+        let id = state.api_keys.identify(bearer_token(request.headers())).ok_or_else(|| {
+            AppError::with_status(StatusCode::UNAUTHORIZED, anyhow::anyhow!("Missing or invalid API key"))
+        })?;
+        ApiCaller(Some(id))
+    };
+    request.extensions_mut().insert(caller);
+    Ok(next.run(request).await)
+}
+
+/// The job `job_id`, unless another API key started it
+///
+/// Other keys' jobs are reported as unknown, so their ids cannot be probed.
+async fn find_owned_job(state: &AppState, job_id: &str, caller: &ApiCaller) -> Result<JobStatus, AppError> {
+    find_job(state, job_id)
+        .await
+        .filter(|job| caller.may_access(job))
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("Job not found: {}", job_id)))
+}
+
+/// Reject clients over RATE_LIMIT_PER_MIN with 429 and a Retry-After header
+///
+/// Runs before the API key check, so unauthenticated floods are limited too.
+async fn rate_limit(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(limiter) = &state.rate_limiter else {
+        return next.run(request).await;
+    };
+    match limiter.check(peer.ip(), std::time::Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                Json(serde_json::json!({
+                    "error": format!("Rate limit exceeded; retry in {} seconds", retry_after_secs),
+                })),
+            )
+                .into_response()
         }
+    }
+}
+
+/// Require `Authorization: Bearer <ADMIN_API_KEY>` on admin routes
+fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(admin_key) = &state.admin_api_key else {
+        return Err(AppError::with_status(
+            StatusCode::FORBIDDEN,
+            anyhow::anyhow!("Admin API disabled. Set ADMIN_API_KEY to enable it."),
+        ));
+    };
 
-        Ok(Json(response))
+    if constant_time_eq(bearer_token(headers).as_bytes(), admin_key.as_bytes()) {
+        Ok(())
     } else {
-        Err(anyhow::anyhow!("Job not found: {}", job_id).into())
+        Err(AppError::with_status(
+            StatusCode::UNAUTHORIZED,
+            anyhow::anyhow!("Invalid admin API key"),
+        ))
     }
 }
 
-// ============== SYNC ENDPOINTS (for backend compatibility) ==============
+/// Require `Authorization: Bearer <MASTER_API_KEY>` on the key management routes
+fn require_master(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(master_key) = &state.master_api_key else {
+        return Err(AppError::with_status(
+            StatusCode::FORBIDDEN,
+            anyhow::anyhow!("Key management disabled. Set MASTER_API_KEY to enable it."),
+        ));
+    };
 
-async fn prove_shield_sync(
+    if constant_time_eq(bearer_token(headers).as_bytes(), master_key.as_bytes()) {
+        Ok(())
+    } else {
+        Err(AppError::with_status(
+            StatusCode::UNAUTHORIZED,
+            anyhow::anyhow!("Invalid master API key"),
+        ))
+    }
+}
+
+#[utoipa::path(
+    get, path = "/api/admin/keys", tag = "admin",
+    responses((status = 200, description = "API keys, without the raw keys", body = serde_json::Value)),
+    security(("master_key" = []))
+)]
+async fn list_api_keys(
     State(state): State<AppState>,
-    Json(req): Json<ShieldProofRequest>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    info!("Shield proof request (sync): {:?}", req);
+    require_master(&state, &headers)?;
+    Ok(Json(serde_json::json!({ "keys": state.api_keys.list() })))
+}
 
-    let prover = state.prover.read().await;
-    let response = prover
-        .create_shield_proof(&req.token, &req.amount, &req.sender, &req.nullifier_key)
-        .await?;
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateApiKeyRequest {
+    label: String,
+}
 
-    let forwarder = match req.token.to_uppercase().as_str() {
-        "USDC" => "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE",
-        "WETH" => "0xD5307D777dC60b763b74945BF5A42ba93ce44e4b",
-        _ => "0x0000000000000000000000000000000000000000",
-    };
+// Create an API key; the raw key is only ever returned here
+#[utoipa::path(
+    post, path = "/api/admin/keys", tag = "admin",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "The new key's id, label, created_at and raw `key`, returned only here", body = serde_json::Value),
+        (status = 400, description = "Empty label", body = openapi::ErrorBody),
+    ),
+    security(("master_key" = []))
+)]
+async fn create_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_master(&state, &headers)?;
+    if req.label.trim().is_empty() {
+        return Err(AppError::bad_request(anyhow::anyhow!("Key label must not be empty")));
+    }
 
-    let resource = serde_json::json!({
-        "logic_ref": response.proof.as_ref().map(|p| &p.image_id).unwrap_or(&"".to_string()),
-        "label_ref": format!("0x{}", hex::encode(req.token.as_bytes())),
-        "quantity": req.amount.parse::<u64>().unwrap_or(0),
-        "value_ref": "0x0000000000000000000000000000000000000000000000000000000000000000",
-        "is_ephemeral": true,
-        "nonce": format!("0x{}", response.proof_id),
-        "nk_commitment": "0x0000000000000000000000000000000000000000000000000000000000000000",
-        "rand_seed": "0x0000000000000000000000000000000000000000000000000000000000000000"
-    });
+    let (info, key) = state.api_keys.create(req.label.trim())?;
+    info!("Created API key {} ({})", info.id, info.label);
+    Ok(Json(serde_json::json!({
+        "id": info.id,
+        "label": info.label,
+        "created_at": info.created_at,
+        "key": key,
+    })))
+}
+
+#[utoipa::path(
+    delete, path = "/api/admin/keys/{key_id}", tag = "admin",
+    params(("key_id" = String, Path)),
+    responses(
+        (status = 200, description = "`{\"revoked\": key_id}`", body = serde_json::Value),
+        (status = 404, description = "Unknown key", body = openapi::ErrorBody),
+    ),
+    security(("master_key" = []))
+)]
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(key_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_master(&state, &headers)?;
+    if !state.api_keys.revoke(&key_id)? {
+        return Err(AppError::not_found(anyhow::anyhow!("API key not found")));
+    }
+
+    info!("Revoked API key {}", key_id);
+    Ok(Json(serde_json::json!({ "revoked": key_id })))
+}
+
+#[utoipa::path(
+    get, path = "/api/admin/unshield-recipients", tag = "admin",
+    responses((status = 200, description = "`{\"recipients\": [...]}`, null when unrestricted", body = serde_json::Value)),
+    security(("admin_key" = []))
+)]
+async fn get_unshield_recipients(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&state, &headers)?;
+
+    let prover = &state.prover;
+    Ok(Json(serde_json::json!({
+        "recipients": prover.unshield_allow_list()?
+    })))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct UnshieldRecipientsRequest {
+    /// New allow-list; null removes the restriction
+    recipients: Option<Vec<String>>,
+}
+
+// Replace the unshield allow-list at runtime
+#[utoipa::path(
+    put, path = "/api/admin/unshield-recipients", tag = "admin",
+    request_body = UnshieldRecipientsRequest,
+    responses(
+        (status = 200, description = "The new allow-list", body = serde_json::Value),
+        (status = 400, description = "Malformed address", body = openapi::ErrorBody),
+    ),
+    security(("admin_key" = []))
+)]
+async fn set_unshield_recipients(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<UnshieldRecipientsRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&state, &headers)?;
+
+    let list = req.recipients
+        .map(|recipients| allowlist::parse_allow_list(recipients.iter().map(String::as_str)))
+        .transpose()
+        .map_err(AppError::bad_request)?;
+
+    let prover = &state.prover;
+    prover.set_unshield_allow_list(list)?;
 
     Ok(Json(serde_json::json!({
-        "transaction": response.proof_id,
-        "resource_commitment": format!("0x{}", response.proof_id),
-        "resource": resource,
+        "recipients": prover.unshield_allow_list()?
+    })))
+}
+
+// ============== SYNC ENDPOINTS (for backend compatibility) ==============
+
+#[utoipa::path(
+    post, path = "/api/prove/shield", tag = "sync",
+    request_body = ShieldProofRequest,
+    responses(
+        (status = 200, description = "Proven shield transaction", body = openapi::ShieldProofResult),
+        (status = 401, description = "Missing or invalid sender signature", body = openapi::ErrorBody),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 500, description = "Proving failed", body = openapi::ErrorBody),
+        (status = 503, description = "Docker unavailable, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+        (status = 504, description = "Proving timed out", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn prove_shield_sync(
+    State(state): State<AppState>,
+    Json(req): Json<ShieldProofRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Shield proof request (sync): {:?}", req);
+    let permit = validate_shield_request(&state.prover.config().tokens, &req)?;
+    let token = state.prover.config().tokens.get(&req.token).map_err(AppError::bad_request)?;
+    let quantity = parse_token_amount(&req.amount, token).map_err(AppError::bad_request)?;
+
+    ensure_can_simulate(&state, req.simulate)?;
+    authenticate_shield_request(&state, &req, permit.as_ref())?;
+
+    // Counts against MAX_CONCURRENT_PROOFS like queued jobs
+    let _permit = state.queue.acquire_permit().await;
+    let prover = &state.prover;
+    let started = std::time::Instant::now();
+    let result = prover
+        .create_shield_proof(&req.token, &req.amount, &req.sender, &req.nullifier_key, req.deletion_criterion, permit, req.force, None)
+        .await;
+    telemetry::record_proof("shield", &result, started.elapsed());
+    let simulation = simulate_proof(&state, req.simulate, &result).await;
+    let response = result?;
+
+    let forwarder = &token.forwarder_address;
+
+    // Commitments and nullifiers are read from the proven transaction (null
+    // without calldata); the resources themselves stay with the prover
+    Ok(Json(serde_json::json!({
+        "proof_id": response.proof_id,
+        "transaction": transaction_json(response.calldata.as_deref()),
+        "resource_commitment": response.commitment(),
+        // Decimal string: wei amounts do not fit in a JSON number
+        "quantity": quantity.to_string(),
         "forwarder_call": {
             "to": forwarder,
             "data": response.calldata.as_ref().map(|c| c.clone()).unwrap_or_default()
         },
-        "calldata": response.calldata
+        "calldata": response.calldata,
+        "simulation": simulation
     })))
 }
 
+#[utoipa::path(
+    post, path = "/api/prove/swap", tag = "sync",
+    request_body = SwapProofRequest,
+    responses(
+        (status = 200, description = "Proven swap transaction", body = openapi::SwapProofResult),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 500, description = "Proving failed", body = openapi::ErrorBody),
+        (status = 503, description = "Docker unavailable, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+        (status = 504, description = "Proving timed out", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
 async fn prove_swap_sync(
     State(state): State<AppState>,
     Json(req): Json<SwapProofRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
     info!("Swap proof request (sync): {:?}", req);
-
-    let prover = state.prover.read().await;
-    let response = prover
-        .create_swap_proof(&req.input_resource, &req.output_token, &req.nullifier_key, &req.min_amount_out)
-        .await?;
-
-    let new_resource = serde_json::json!({
-        "logic_ref": response.proof.as_ref().map(|p| &p.image_id).unwrap_or(&"".to_string()),
-        "label_ref": format!("0x{}", hex::encode(req.output_token.as_bytes())),
-        "quantity": req.min_amount_out.parse::<u64>().unwrap_or(0),
-        "value_ref": "0x0000000000000000000000000000000000000000000000000000000000000000",
-        "is_ephemeral": true,
-        "nonce": format!("0x{}", response.proof_id),
-        "nk_commitment": "0x0000000000000000000000000000000000000000000000000000000000000000",
-        "rand_seed": "0x0000000000000000000000000000000000000000000000000000000000000000"
-    });
+    let params = validate_swap_request(state.prover.config(), &req)?;
+    let swap_call = encode_exact_input_single(&params);
+
+    ensure_can_simulate(&state, req.simulate)?;
+
+    // Counts against MAX_CONCURRENT_PROOFS like queued jobs
+    let _permit = state.queue.acquire_permit().await;
+    let prover = &state.prover;
+    let started = std::time::Instant::now();
+    let result = prover
+        .create_swap_proof(&req.input_resource, &req.output_token, &req.nullifier_key, &req.min_amount_out, req.force, None)
+        .await;
+    telemetry::record_proof("swap", &result, started.elapsed());
+    let simulation = simulate_proof(&state, req.simulate, &result).await;
+    let response = result?;
 
     Ok(Json(serde_json::json!({
-        "transaction": response.proof_id,
-        "nullifier": format!("0x{}", response.proof_id),
-        "new_resource_commitment": format!("0x{}", response.proof_id),
-        "new_resource": new_resource,
+        "proof_id": response.proof_id,
+        "transaction": transaction_json(response.calldata.as_deref()),
+        "nullifier": response.nullifier(),
+        "new_resource_commitment": response.commitment(),
+        "min_amount_out": params.amount_out_minimum.to_string(),
+        // The call the Protocol Adapter makes through the Uniswap forwarder, and its decoded params
         "uniswap_call": {
-            "to": "0x9335Fa4A31E552378Ed29b94704c52b5635cd1AA",
-            "data": response.calldata.as_ref().map(|c| c.clone()).unwrap_or_default()
+            "to": state.prover.config().uniswap_forwarder,
+            "data": format!("0x{}", hex::encode(&swap_call))
         },
-        "calldata": response.calldata
+        "swap_params": chain::decode_call_args(&swap_call[..4], &swap_call[4..]),
+        "calldata": response.calldata,
+        "simulation": simulation
     })))
 }
 
+#[utoipa::path(
+    post, path = "/api/prove/unshield", tag = "sync",
+    request_body = UnshieldProofRequest,
+    responses(
+        (status = 200, description = "Proven unshield transaction", body = openapi::UnshieldProofResult),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 500, description = "Proving failed", body = openapi::ErrorBody),
+        (status = 503, description = "Docker unavailable, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+        (status = 504, description = "Proving timed out", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
 async fn prove_unshield_sync(
     State(state): State<AppState>,
     Json(req): Json<UnshieldProofRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
     info!("Unshield proof request (sync): {:?}", req);
+    let token = validate_unshield_request(&state.prover, &req)?;
+    let forwarder = &state.prover.config().tokens.get(&token).map_err(AppError::bad_request)?.forwarder_address;
+
+    ensure_can_simulate(&state, req.simulate)?;
+
+    // Counts against MAX_CONCURRENT_PROOFS like queued jobs
+    let _permit = state.queue.acquire_permit().await;
+    let prover = &state.prover;
+    let started = std::time::Instant::now();
+    let result = prover
+        .create_unshield_proof(&req.resource, &req.recipient, &req.nullifier_key, req.deletion_criterion, req.force, None)
+        .await;
+    telemetry::record_proof("unshield", &result, started.elapsed());
+    let simulation = simulate_proof(&state, req.simulate, &result).await;
+    let response = result?;
 
-    let prover = state.prover.read().await;
-    let response = prover
-        .create_unshield_proof(&req.resource, &req.recipient, &req.nullifier_key)
-        .await?;
+    Ok(Json(serde_json::json!({
+        "proof_id": response.proof_id,
+        "transaction": transaction_json(response.calldata.as_deref()),
+        "nullifier": response.nullifier(),
+        "forwarder_call": {
+            "to": forwarder,
+            "data": response.calldata.as_ref().map(|c| c.clone()).unwrap_or_default()
+        },
+        "calldata": response.calldata,
+        "simulation": simulation
+    })))
+}
 
-    let token = req.resource.get("label_ref")
-        .and_then(|v| v.as_str())
-        .map(|s| String::from_utf8_lossy(&hex::decode(s.trim_start_matches("0x")).unwrap_or_default()).to_string())
-        .unwrap_or_else(|| "USDC".to_string());
+#[derive(Debug, Deserialize, ToSchema)]
+struct RawProofRequest {
+    /// Forwarder contract address
+    forwarder: String,
+    /// Hex calldata sent verbatim, including the function selector
+    call_data: String,
+    /// Hex return data the Protocol Adapter requires from the call
+    #[serde(default)]
+    expected_output: String,
+    /// Deletion criterion of the forwarder call blob ("never" or "immediately")
+    #[schema(value_type = String, example = "never")]
+    #[serde(default)]
+    deletion_criterion: DeletionCriterion,
+    /// Bypass the proof cache, e.g. when a fresh nullifier is required
+    #[serde(default)]
+    force: bool,
+    /// eth_call the calldata on the Protocol Adapter and report the outcome as `simulation`
+    #[serde(default)]
+    simulate: bool,
+}
 
-    let forwarder = match token.to_uppercase().as_str() {
-        "USDC" => "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE",
-        "WETH" => "0xD5307D777dC60b763b74945BF5A42ba93ce44e4b",
-        _ => "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE",
-    };
+/// Prove pre-built forwarder calldata, for calls the typed endpoints do not cover
+///
+/// Disabled unless ALLOW_RAW_CALLS is set, since nothing checks what the call does.
+#[utoipa::path(
+    post, path = "/api/prove/raw", tag = "sync",
+    request_body = RawProofRequest,
+    responses(
+        (status = 200, description = "Proven transaction making the raw forwarder call", body = openapi::RawProofResult),
+        (status = 403, description = "ALLOW_RAW_CALLS is not set", body = openapi::ErrorBody),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 500, description = "Proving failed", body = openapi::ErrorBody),
+        (status = 503, description = "Docker unavailable, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+        (status = 504, description = "Proving timed out", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn prove_raw_sync(
+    State(state): State<AppState>,
+    Json(req): Json<RawProofRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if !state.prover.raw_calls_enabled() {
+        return Err(AppError::with_status(
+            StatusCode::FORBIDDEN,
+            anyhow::anyhow!("Raw forwarder calls are disabled; set ALLOW_RAW_CALLS=1 to enable them"),
+        ));
+    }
+    let raw = RawCall::parse(&req.forwarder, &req.call_data, &req.expected_output).map_err(AppError::bad_request)?;
+    info!("Raw proof request (sync): {:?}", req);
+
+    ensure_can_simulate(&state, req.simulate)?;
+
+    // Counts against MAX_CONCURRENT_PROOFS like queued jobs
+    let _permit = state.queue.acquire_permit().await;
+    let started = std::time::Instant::now();
+    let result = state.prover.create_raw_proof(&raw, req.deletion_criterion, req.force, None).await;
+    telemetry::record_proof("raw", &result, started.elapsed());
+    let simulation = simulate_proof(&state, req.simulate, &result).await;
+    let response = result?;
 
     Ok(Json(serde_json::json!({
-        "transaction": response.proof_id,
-        "nullifier": format!("0x{}", response.proof_id),
+        "proof_id": response.proof_id,
+        "transaction": transaction_json(response.calldata.as_deref()),
         "forwarder_call": {
-            "to": forwarder,
-            "data": response.calldata.as_ref().map(|c| c.clone()).unwrap_or_default()
+            "to": format!("0x{}", hex::encode(raw.forwarder)),
+            "data": format!("0x{}", hex::encode(&raw.call_data)),
+            "expected_output": format!("0x{}", hex::encode(&raw.expected_output))
         },
-        "calldata": response.calldata
+        "calldata": response.calldata,
+        "simulation": simulation
     })))
 }
+
+/// One operation of a batch, tagged by `action`; fields as on the single-operation endpoints
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum BatchEntryRequest {
+    Shield {
+        token: String,
+        /// Token units, as for /api/prove/shield
+        amount: String,
+        sender: String,
+        #[schema(value_type = String, example = "never")]
+        #[serde(default)]
+        deletion_criterion: DeletionCriterion,
+        /// Signed with `nullifier_key_commitment: none`, as batch resources have no client key
+        #[serde(default)]
+        signature: Option<String>,
+        #[serde(default)]
+        signature_nonce: Option<String>,
+        #[serde(default)]
+        signature_expiry: Option<u64>,
+        #[serde(default)]
+        permit: Option<signature::PermitRequest>,
+    },
+    Unshield {
+        resource: serde_json::Value,
+        recipient: String,
+        #[schema(value_type = String, example = "never")]
+        #[serde(default)]
+        deletion_criterion: DeletionCriterion,
+    },
+    Swap {
+        input_resource: serde_json::Value,
+        output_token: String,
+        min_amount_out: String,
+        /// Pool fee tier (defaults to the 0.3% pool)
+        #[serde(default)]
+        fee: Option<u32>,
+        #[schema(value_type = String, example = "never")]
+        #[serde(default)]
+        deletion_criterion: DeletionCriterion,
+    },
+    Raw {
+        forwarder: String,
+        call_data: String,
+        #[serde(default)]
+        expected_output: String,
+        #[schema(value_type = String, example = "never")]
+        #[serde(default)]
+        deletion_criterion: DeletionCriterion,
+    },
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct BatchProofRequest {
+    entries: Vec<BatchEntryRequest>,
+    /// Bypass the proof cache, e.g. when a fresh nullifier is required
+    #[serde(default)]
+    force: bool,
+    /// eth_call the calldata on the Protocol Adapter and report the outcome as `simulation`
+    #[serde(default)]
+    simulate: bool,
+}
+
+/// Validate one batch entry like its single-operation endpoint would, naming
+/// failing fields `entries[i].field`
+fn validate_batch_entry(state: &AppState, i: usize, req: &BatchEntryRequest) -> Result<BatchEntry, AppError> {
+    let tokens = &state.prover.config().tokens;
+    let field = |name: &str| format!("entries[{}].{}", i, name);
+
+    let (op, deletion_criterion) = match req {
+        BatchEntryRequest::Shield { token, amount, sender, deletion_criterion, signature: sig, signature_nonce, signature_expiry, permit } => {
+            let info = tokens.get(token).map_err(|e| invalid_field(&field("token"), e))?;
+            ensure_shieldable(info).map_err(|e| invalid_field(&field("token"), e))?;
+            let quantity = parse_token_amount(amount, info).map_err(|e| invalid_field(&field("amount"), e))?;
+            validate_address(&field("sender"), sender)?;
+            if permit.is_some() {
+                ensure_permit_supported(info).map_err(|e| invalid_field(&field("permit"), e))?;
+            }
+            let permit = permit
+                .as_ref()
+                .map(|permit| permit.to_permit_data(get_timestamp()))
+                .transpose()
+                .map_err(|e| invalid_field(&field("permit"), e))?;
+
+            // Same sender authentication as a single shield request
+            let config = state.prover.config();
+            let auth = |nonce, expiry| signature::ShieldAuthorization {
+                chain_id: config.chain_id,
+                protocol_adapter: &config.protocol_adapter,
+                token,
+                amount,
+                sender,
+                nk_commitment: None,
+                permit: permit.as_ref(),
+                nonce,
+                expiry,
+            };
+            authenticate_sender(state, &field(""), sig.as_deref(), signature_nonce.as_deref(), *signature_expiry, auth)?;
+
+            let op = BatchOp::Shield { token: info.symbol.clone(), amount: quantity, sender: sender.clone(), permit };
+            (op, *deletion_criterion)
+        }
+        BatchEntryRequest::Unshield { resource, recipient, deletion_criterion } => {
+            let token = resource_token(resource).map_err(|e| invalid_field(&field("resource"), e))?;
+            let token = tokens.get(&token).map_err(|e| invalid_field(&field("resource.token"), e))?;
+            let amount = validate_resource_amount(&field("resource.amount"), resource)?;
+            ensure_amount_bounds(amount, token).map_err(|e| invalid_field(&field("resource.amount"), e))?;
+            validate_address(&field("recipient"), recipient)?;
+            state.prover.check_unshield_recipient(recipient).map_err(|e| invalid_field(&field("recipient"), e))?;
+            (BatchOp::Unshield { token: token.symbol.clone(), amount, recipient: recipient.clone() }, *deletion_criterion)
+        }
+        BatchEntryRequest::Swap { input_resource, output_token, min_amount_out, fee, deletion_criterion } => {
+            let token_in = resource_token(input_resource).map_err(|e| invalid_field(&field("input_resource"), e))?;
+            let token_in = tokens.get(&token_in).map_err(|e| invalid_field(&field("input_resource.token"), e))?;
+            let amount_in = validate_resource_amount(&field("input_resource.amount"), input_resource)?;
+            ensure_amount_bounds(amount_in, token_in).map_err(|e| invalid_field(&field("input_resource.amount"), e))?;
+            let token_in = token_in.symbol.clone();
+            let token_out = tokens.get(output_token).map_err(|e| invalid_field(&field("output_token"), e))?.symbol.clone();
+            let min_amount_out = parse_quantity(min_amount_out)
+                .and_then(ensure_nonzero)
+                .map_err(|e| invalid_field(&field("min_amount_out"), e))?;
+            let order = SwapOrder { token_in, amount_in, token_out, min_amount_out, fee: fee.unwrap_or(DEFAULT_SWAP_FEE) };
+            order.swap_params(state.prover.config()).map_err(|e| invalid_field(&field("output_token"), e))?;
+            (BatchOp::Swap(order), *deletion_criterion)
+        }
+        BatchEntryRequest::Raw { forwarder, call_data, expected_output, deletion_criterion } => {
+            if !state.prover.raw_calls_enabled() {
+                return Err(AppError::with_status(
+                    StatusCode::FORBIDDEN,
+                    anyhow::anyhow!("{}: raw forwarder calls are disabled; set ALLOW_RAW_CALLS=1 to enable them", field("action")),
+                ));
+            }
+            let raw = RawCall::parse(forwarder, call_data, expected_output).map_err(|e| invalid_field(&field("forwarder"), e))?;
+            (BatchOp::Raw(raw), *deletion_criterion)
+        }
+    };
+    Ok(BatchEntry::new(op, deletion_criterion))
+}
+
+/// Prove several operations as the actions of one transaction, with a single
+/// delta proof over all of them
+///
+/// Either every operation executes or none does.
+#[utoipa::path(
+    post, path = "/api/prove/batch", tag = "sync",
+    request_body = BatchProofRequest,
+    responses(
+        (status = 200, description = "One transaction with an action per entry", body = openapi::BatchProofResult),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 500, description = "Proving failed", body = openapi::ErrorBody),
+        (status = 503, description = "Docker unavailable, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+        (status = 504, description = "Proving timed out", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn prove_batch_sync(
+    State(state): State<AppState>,
+    Json(req): Json<BatchProofRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Batch proof request (sync): {:?}", req);
+    if req.entries.is_empty() || req.entries.len() > MAX_BATCH_ENTRIES {
+        return Err(invalid_field("entries", format!("expected 1 to {} entries, got {}", MAX_BATCH_ENTRIES, req.entries.len())));
+    }
+    let entries = req.entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| validate_batch_entry(&state, i, entry))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    ensure_can_simulate(&state, req.simulate)?;
+
+    // Counts against MAX_CONCURRENT_PROOFS like queued jobs
+    let _permit = state.queue.acquire_permit().await;
+    let started = std::time::Instant::now();
+    let result = state.prover.create_batch_proof(&entries, req.force, None).await;
+    telemetry::record_proof("batch", &result, started.elapsed());
+    let simulation = simulate_proof(&state, req.simulate, &result).await;
+    let response = result?;
+
+    Ok(Json(serde_json::json!({
+        "proof_id": response.proof_id,
+        "transaction": transaction_json(response.calldata.as_deref()),
+        "entries": entries.len(),
+        "summary": response.summary,
+        "calldata": response.calldata,
+        "simulation": simulation
+    })))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct DeltaProofRequest {
+    /// Proven actions with their rcvs (`{"actions": [...], "rcvs": [...]}`), joined in order
+    #[schema(value_type = Vec<Object>)]
+    parts: Vec<ProvenActions>,
+    /// eth_call the calldata on the Protocol Adapter and report the outcome as `simulation`
+    #[serde(default)]
+    simulate: bool,
+}
+
+/// Generate only the delta proof for actions proven on other machines
+///
+/// Lets a relayer spread action proving across provers and combine the results
+/// into one balanced transaction.
+#[utoipa::path(
+    post, path = "/api/prove/delta", tag = "sync",
+    request_body = DeltaProofRequest,
+    responses(
+        (status = 200, description = "The parts joined into one balanced transaction", body = openapi::DeltaProofResult),
+        (status = 400, description = "A field failed validation", body = openapi::ErrorBody),
+        (status = 500, description = "Proving failed", body = openapi::ErrorBody),
+        (status = 503, description = "Docker unavailable, or `simulate` requested without RPC_URL", body = openapi::ErrorBody),
+        (status = 504, description = "Proving timed out", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = []))
+)]
+async fn prove_delta_sync(
+    State(state): State<AppState>,
+    Json(req): Json<DeltaProofRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if req.parts.is_empty() {
+        return Err(invalid_field("parts", "expected at least one part"));
+    }
+    let num_actions: usize = req.parts.iter().map(|part| part.actions.len()).sum();
+    let num_rcvs: usize = req.parts.iter().map(|part| part.rcvs.len()).sum();
+    info!("Delta proof request (sync): {} part(s), {} action(s), {} rcv(s)", req.parts.len(), num_actions, num_rcvs);
+
+    ensure_can_simulate(&state, req.simulate)?;
+
+    // Counts against MAX_CONCURRENT_PROOFS like queued jobs
+    let _permit = state.queue.acquire_permit().await;
+    let started = std::time::Instant::now();
+    let result = state.prover.create_delta_proof(req.parts, None).await;
+    telemetry::record_proof("delta", &result, started.elapsed());
+    let simulation = simulate_proof(&state, req.simulate, &result).await;
+    let response = result?;
+
+    Ok(Json(serde_json::json!({
+        "proof_id": response.proof_id,
+        "transaction": transaction_json(response.calldata.as_deref()),
+        "calldata": response.calldata,
+        "simulation": simulation
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use shielded_prover::backend::{BatchRequest, ProofBackend, RawRequest, ShieldRequest, SwapRequest, UnshieldRequest};
+    use shielded_prover::prover::run_prover;
+    use std::time::{Duration, Instant};
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    /// Proves shields like the local ARM backend, handing run_prover a builder
+    /// that blocks its thread for a second; the other operations are not stubbed
+    struct SlowBackend {
+        started: Arc<Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl ProofBackend for SlowBackend {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn prove_shield(
+            &self,
+            prover: &ProverService,
+            req: ShieldRequest<'_>,
+            job: Option<&JobContext>,
+        ) -> Result<ProofResponse, ProverError> {
+            let started = self.started.clone();
+            let build = move || {
+                started.notify_one();
+                std::thread::sleep(Duration::from_secs(1));
+                Ok(vec![0xab])
+            };
+            let calldata = run_prover(build, prover.prove_timeout(), job).await?;
+            Ok(ProofResponse {
+                proof_id: req.proof_id,
+                status: "completed".to_string(),
+                proof: None,
+                calldata: Some(format!("0x{}", hex::encode(calldata))),
+                summary: None,
+            })
+        }
+
+        async fn prove_swap(&self, _: &ProverService, _: SwapRequest<'_>, _: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+            Err(ProverError::invalid_request("not stubbed"))
+        }
+
+        async fn prove_unshield(&self, _: &ProverService, _: UnshieldRequest<'_>, _: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+            Err(ProverError::invalid_request("not stubbed"))
+        }
+
+        async fn prove_raw(&self, _: &ProverService, _: RawRequest<'_>, _: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+            Err(ProverError::invalid_request("not stubbed"))
+        }
+
+        async fn prove_batch(&self, _: &ProverService, _: BatchRequest<'_>, _: Option<&JobContext>) -> Result<ProofResponse, ProverError> {
+            Err(ProverError::invalid_request("not stubbed"))
+        }
+    }
+
+    fn test_state(prover: ProverService) -> AppState {
+        let queue = ProofQueue::new(1);
+        queue.start();
+        AppState {
+            prover: Arc::new(prover),
+            jobs: Arc::new(jobstore::MemoryJobStore::default()),
+            queue,
+            admin_api_key: None,
+            master_api_key: None,
+            api_keys: Arc::new(ApiKeyStore::default()),
+            prove_timeout_secs: 60,
+            job_ttl_secs: DEFAULT_JOB_TTL_SECS,
+            require_sender_sig: false,
+            used_nonces: Arc::new(signature::UsedNonces::new()),
+            webhook_secret: None,
+            webhook_allowed_hosts: Arc::new(Vec::new()),
+            readiness: Arc::new(RwLock::new(Readiness::Ready)),
+            metrics: metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder().handle(),
+            rate_limiter: None,
+            idempotency: Arc::new(IdempotencyStore::new(Duration::from_secs(DEFAULT_JOB_TTL_SECS))),
+        }
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_answers_while_proof_generates() {
+        let started = Arc::new(Notify::new());
+        let prover = ProverService::new(Config::default()).unwrap().with_backend(SlowBackend { started: started.clone() });
+        let app = router(test_state(prover), CorsLayer::new());
+
+        let shield = serde_json::json!({
+            "token": "USDC",
+            "amount": "1.5",
+            "sender": "0x000000000000000000000000000000000000dEaD",
+            "nullifier_key": hex::encode([7u8; 32]),
+            "force": true,
+        });
+        let mut request = axum::http::Request::post("/api/shield")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(shield.to_string()))
+            .unwrap();
+        // Set by into_make_service_with_connect_info when served; read by the rate limiter
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let job_id = json_body(response).await["job_id"].as_str().unwrap().to_string();
+
+        tokio::time::timeout(Duration::from_secs(5), started.notified())
+            .await
+            .expect("proof never started");
+
+        // The test runtime has a single worker. A builder run on it would stall
+        // /health until the proof is done, and the job would no longer be generating below.
+        let start = Instant::now();
+        let health = app
+            .clone()
+            .oneshot(axum::http::Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(health.status(), StatusCode::OK);
+        assert!(elapsed < Duration::from_millis(100), "/health took {:?} while a proof was generating", elapsed);
+
+        let mut request = axum::http::Request::get(format!("/api/job/{}", job_id)).body(Body::empty()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        let job = json_body(app.oneshot(request).await.unwrap()).await;
+        assert_eq!(job["status"], "generating");
+        assert_eq!(job["progress"], "Generating ZK proofs...");
+    }
+}
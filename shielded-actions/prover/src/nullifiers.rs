@@ -0,0 +1,116 @@
+//! Nullifiers of the proofs this service has handed out
+//!
+//! A transaction executes once; a second proof with the same nullifier reverts
+//! with `PreExistingNullifier` after minutes of proving. Every nullifier in
+//! returned calldata is recorded here, so a pre-generated proof file is only
+//! served once and a reused nullifier is caught before it reaches a client.
+//! Nullifiers are appended to `NULLIFIER_SET_FILE` (one hex nullifier per line,
+//! default `nullifiers.txt`) so the set survives a restart; an empty value
+//! keeps it in memory only.
+
+use alloy::primitives::B256;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// File the set is kept in unless NULLIFIER_SET_FILE is set
+pub const DEFAULT_NULLIFIER_SET_FILE: &str = "nullifiers.txt";
+
+/// Nullifiers already handed out, shared by all requests to a prover service
+pub struct NullifierSet {
+    inner: Mutex<HashSet<B256>>,
+    file: Option<PathBuf>,
+}
+
+impl NullifierSet {
+    /// Load the set from `file` (a missing file is an empty set), or keep it in memory only
+    pub fn new(file: Option<PathBuf>) -> Self {
+        let mut nullifiers = HashSet::new();
+        if let Some(contents) = file.as_ref().and_then(|path| std::fs::read_to_string(path).ok()) {
+            for line in contents.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                match line.parse::<B256>() {
+                    Ok(nullifier) => {
+                        nullifiers.insert(nullifier);
+                    }
+                    Err(e) => warn!("Skipping invalid nullifier {:?} in the nullifier set file: {}", line, e),
+                }
+            }
+        }
+        Self { inner: Mutex::new(nullifiers), file }
+    }
+
+    /// Persisted to NULLIFIER_SET_FILE, or [`DEFAULT_NULLIFIER_SET_FILE`]
+    pub fn from_env() -> Self {
+        let file = match std::env::var("NULLIFIER_SET_FILE") {
+            Ok(file) if file.is_empty() => None,
+            Ok(file) => Some(PathBuf::from(file)),
+            Err(_) => Some(PathBuf::from(DEFAULT_NULLIFIER_SET_FILE)),
+        };
+        Self::new(file)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether any of `nullifiers` was handed out before
+    pub fn contains_any(&self, nullifiers: &[B256]) -> bool {
+        let inner = self.inner.lock().unwrap();
+        nullifiers.iter().any(|nullifier| inner.contains(nullifier))
+    }
+
+    /// Record `nullifiers`, returning those that were already in the set
+    pub fn insert(&self, nullifiers: &[B256]) -> Vec<B256> {
+        let mut inner = self.inner.lock().unwrap();
+        let (reused, new): (Vec<B256>, Vec<B256>) = nullifiers.iter().partition(|nullifier| inner.contains(*nullifier));
+        inner.extend(new.iter().copied());
+
+        if let (Some(path), false) = (&self.file, new.is_empty()) {
+            let lines: String = new.iter().map(|nullifier| format!("{}\n", nullifier)).collect();
+            let appended = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(lines.as_bytes()));
+            if let Err(e) = appended {
+                warn!("Failed to persist nullifiers to {}: {}", path.display(), e);
+            }
+        }
+        reused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reused_nullifiers_are_reported_and_persisted() {
+        let path = std::env::temp_dir().join(format!("nullifiers-{}.txt", hex::encode(rand::random::<[u8; 4]>())));
+        let (a, b) = (B256::repeat_byte(0x01), B256::repeat_byte(0x02));
+
+        let set = NullifierSet::new(Some(path.clone()));
+        assert!(set.is_empty());
+        assert!(set.insert(&[a]).is_empty());
+        assert!(set.contains_any(&[b, a]));
+        assert!(!set.contains_any(&[b]));
+        assert_eq!(set.insert(&[a, b]), vec![a]);
+        assert_eq!(set.len(), 2);
+
+        // A restart reads the set back
+        let reloaded = NullifierSet::new(Some(path.clone()));
+        assert_eq!(reloaded.len(), 2);
+        assert!(reloaded.contains_any(&[b]));
+        std::fs::remove_file(&path).unwrap();
+
+        let in_memory = NullifierSet::new(None);
+        in_memory.insert(&[a]);
+        assert_eq!(in_memory.len(), 1);
+    }
+}
@@ -0,0 +1,322 @@
+//! OpenAPI 3 document for the HTTP API, served at /openapi.json (Swagger UI at /docs)
+//!
+//! Handlers are annotated with `#[utoipa::path]` next to their definitions.
+//! Most of them build their JSON with `serde_json::json!`, so the shapes they
+//! return are described by the schema-only types below; keep each in step with
+//! the handler it names.
+
+// Schema-only types are described, never constructed
+#![allow(dead_code)]
+
+use crate::queue::Priority;
+use serde_json::Value;
+use shielded_prover::chain::Simulation;
+use shielded_prover::OperationSummary;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Shielded Actions Prover",
+        description = "Proves shield, swap and unshield operations as Protocol Adapter transactions. \
+                       The async endpoints queue a job: poll /api/job/{job_id}, subscribe to its \
+                       events or WebSocket until it is completed, failed or cancelled."
+    ),
+    paths(
+        crate::health_check,
+        crate::health_ready,
+        crate::get_metrics,
+        crate::api_info,
+        crate::get_verifying_keys,
+        crate::generate_keypair,
+        crate::get_estimate,
+        crate::start_shield_job,
+        crate::start_swap_job,
+        crate::start_unshield_job,
+        crate::list_jobs,
+        crate::get_job_status,
+        crate::delete_job,
+        crate::job_ws,
+        crate::job_events,
+        crate::cancel_job,
+        crate::get_job_logs,
+        crate::get_proof_status,
+        crate::prove_shield_sync,
+        crate::prove_swap_sync,
+        crate::prove_unshield_sync,
+        crate::prove_raw_sync,
+        crate::prove_batch_sync,
+        crate::prove_delta_sync,
+        crate::decode_calldata,
+        crate::verify_calldata,
+        crate::merkle_root,
+        crate::check_balance,
+        crate::get_unshield_recipients,
+        crate::set_unshield_recipients,
+        crate::list_api_keys,
+        crate::create_api_key,
+        crate::revoke_api_key,
+    ),
+    modifiers(&BearerKeys),
+    tags(
+        (name = "service", description = "Health, metrics and service configuration"),
+        (name = "jobs", description = "Async proving: start a job, then follow it until it finishes"),
+        (name = "sync", description = "Proving in the request; the response arrives when the proof is done"),
+        (name = "inspection", description = "Calldata decoding, verification and dry balance checks"),
+        (name = "admin", description = "Allow-list and API key management"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Bearer schemes: `api_key` for the proving and job routes (once any key is
+/// configured), `admin_key` (ADMIN_API_KEY) and `master_key` (MASTER_API_KEY)
+struct BearerKeys;
+
+impl Modify for BearerKeys {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        for name in ["api_key", "admin_key", "master_key"] {
+            components.add_security_scheme(name, SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()));
+        }
+    }
+}
+
+/// Error response; `code` is the stable `ProverError` code for proving failures
+#[derive(ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+    /// e.g. "invalid_amount", "docker_unavailable" or "timeout"
+    pub code: Option<String>,
+}
+
+/// Job queued by /api/shield, /api/swap or /api/unshield
+#[derive(ToSchema)]
+pub struct JobAccepted {
+    pub job_id: String,
+    /// Always "pending" for a new job; a repeated Idempotency-Key returns the earlier job instead
+    pub status: String,
+    pub priority: Priority,
+    pub message: Option<String>,
+}
+
+/// A job, as served by /api/job/{job_id} and pushed by its events and WebSocket streams
+///
+/// `status` moves from "pending" to "generating", then to one of the final
+/// statuses "completed", "failed" or "cancelled".
+#[derive(ToSchema)]
+pub struct JobView {
+    pub job_id: String,
+    pub status: String,
+    pub priority: Priority,
+    /// Unix seconds
+    pub created_at: u64,
+    /// When the job left the queue and proving started
+    pub started_at: Option<u64>,
+    pub completed_at: Option<u64>,
+    /// When the job failed or was cancelled
+    pub failed_at: Option<u64>,
+    /// Seconds from creation to the final status
+    pub duration_secs: Option<u64>,
+    /// Jobs ahead of this one, while it is queued
+    pub queue_position: Option<usize>,
+    /// Current proving stage, while generating
+    pub progress: Option<String>,
+    pub proof_id: Option<String>,
+    pub calldata: Option<String>,
+    /// Present once a completed proof has calldata
+    pub result: Option<JobResult>,
+    /// Whether the proof's nullifier is already spent on-chain (needs RPC_URL)
+    pub nullifier_spent: Option<bool>,
+    pub estimated_gas: Option<u64>,
+    pub simulation: Option<Simulation>,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    /// Whether the callback_url accepted the final job JSON, for jobs that set one
+    pub callback_delivered: Option<bool>,
+}
+
+/// Result of a completed job
+#[derive(ToSchema)]
+pub struct JobResult {
+    /// The proven transaction's commitments, nullifiers and deltas
+    pub transaction: Value,
+    pub calldata: String,
+    pub forwarder_call: CallTarget,
+    pub summary: Option<Vec<OperationSummary>>,
+    pub nullifier_spent: Option<bool>,
+    pub estimated_gas: Option<u64>,
+    pub simulation: Option<Simulation>,
+}
+
+/// Contract call a client sends, or the forwarder call a transaction makes
+#[derive(ToSchema)]
+pub struct CallTarget {
+    pub to: Option<String>,
+    pub data: String,
+    /// Return data the Protocol Adapter requires (raw calls only)
+    pub expected_output: Option<String>,
+}
+
+/// Page of jobs, newest first
+#[derive(ToSchema)]
+pub struct JobList {
+    pub jobs: Vec<JobListEntry>,
+    /// Jobs matching the filter, on all pages
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+#[derive(ToSchema)]
+pub struct JobListEntry {
+    pub job_id: String,
+    pub status: String,
+    pub created_at: u64,
+}
+
+#[derive(ToSchema)]
+pub struct CancelledJob {
+    pub job_id: String,
+    /// "cancelled"
+    pub status: String,
+}
+
+/// Proving log of a job
+#[derive(ToSchema)]
+pub struct JobLogs {
+    pub job_id: String,
+    pub status: String,
+    pub lines: Vec<String>,
+    /// Whether older lines were dropped
+    pub truncated: bool,
+}
+
+#[derive(ToSchema)]
+pub struct ShieldProofResult {
+    pub proof_id: String,
+    pub transaction: Value,
+    pub resource_commitment: Option<String>,
+    /// Amount in the token's smallest units (decimal string)
+    pub quantity: String,
+    pub forwarder_call: CallTarget,
+    pub calldata: Option<String>,
+    pub simulation: Option<Simulation>,
+}
+
+#[derive(ToSchema)]
+pub struct SwapProofResult {
+    pub proof_id: String,
+    pub transaction: Value,
+    pub nullifier: Option<String>,
+    pub new_resource_commitment: Option<String>,
+    /// Decimal string of smallest units
+    pub min_amount_out: String,
+    /// exactInputSingle call made through the Uniswap forwarder
+    pub uniswap_call: CallTarget,
+    /// Its decoded params: tokenIn, tokenOut, fee, recipient, amountIn, amountOutMinimum and sqrtPriceLimitX96
+    pub swap_params: Value,
+    pub calldata: Option<String>,
+    pub simulation: Option<Simulation>,
+}
+
+#[derive(ToSchema)]
+pub struct UnshieldProofResult {
+    pub proof_id: String,
+    pub transaction: Value,
+    pub nullifier: Option<String>,
+    pub forwarder_call: CallTarget,
+    pub calldata: Option<String>,
+    pub simulation: Option<Simulation>,
+}
+
+#[derive(ToSchema)]
+pub struct RawProofResult {
+    pub proof_id: String,
+    pub transaction: Value,
+    pub forwarder_call: CallTarget,
+    pub calldata: Option<String>,
+    pub simulation: Option<Simulation>,
+}
+
+#[derive(ToSchema)]
+pub struct BatchProofResult {
+    pub proof_id: String,
+    pub transaction: Value,
+    /// Number of operations in the transaction
+    pub entries: usize,
+    pub summary: Option<Vec<OperationSummary>>,
+    pub calldata: Option<String>,
+    pub simulation: Option<Simulation>,
+}
+
+#[derive(ToSchema)]
+pub struct DeltaProofResult {
+    pub proof_id: String,
+    pub transaction: Value,
+    pub calldata: Option<String>,
+    pub simulation: Option<Simulation>,
+}
+
+#[derive(ToSchema)]
+pub struct Health {
+    /// "ok", "warming" or "unavailable"
+    pub status: String,
+    pub service: String,
+    /// Why the Docker pre-warm failed
+    pub error: Option<String>,
+    pub nullifier_set_size: usize,
+}
+
+#[derive(ToSchema)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub docker_available: bool,
+    pub bonsai_configured: bool,
+    pub mock_mode: bool,
+}
+
+#[derive(ToSchema)]
+pub struct Keypair {
+    /// Nullifier key; stays with the client
+    pub private_key: String,
+    /// Its commitment, a resource's `nk_commitment`
+    pub public_key: String,
+}
+
+#[derive(ToSchema)]
+pub struct Verification {
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_job_lifecycle_and_sync_endpoints() {
+        let spec = ApiDoc::openapi();
+        for path in [
+            "/api/shield",
+            "/api/swap",
+            "/api/unshield",
+            "/api/job/{job_id}",
+            "/api/job/{job_id}/events",
+            "/api/job/{job_id}/cancel",
+            "/api/prove/shield",
+            "/api/prove/swap",
+            "/api/prove/unshield",
+            "/api/prove/batch",
+            "/api/prove/delta",
+        ] {
+            assert!(spec.paths.paths.contains_key(path), "{} missing from the spec", path);
+        }
+
+        let schemas = &spec.components.as_ref().unwrap().schemas;
+        for schema in ["ShieldProofRequest", "SwapProofRequest", "UnshieldProofRequest", "ProofResponse", "JobView"] {
+            assert!(schemas.contains_key(schema), "{} missing from the spec", schema);
+        }
+        assert!(serde_json::to_string(&spec).unwrap().contains("\"openapi\":\"3."));
+    }
+}
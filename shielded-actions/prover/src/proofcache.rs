@@ -0,0 +1,207 @@
+//! Proof results keyed on their input parameters
+//!
+//! Identical requests (same action, token, amount, counterparty, ...) get the
+//! response of the first one instead of a new proof. Entries live in memory,
+//! and also as `<key>.json` files under `PROOF_CACHE_DIR` when that is set, so
+//! they survive a restart.
+
+use crate::prover::ProofResponse;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Maximum number of responses kept in memory; the oldest is evicted first
+pub const MAX_CACHED_PROOFS: usize = 1024;
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<String, ProofResponse>,
+    order: VecDeque<String>,
+}
+
+/// Cache of proof responses, shared by all requests to a prover service
+pub struct ProofCache {
+    inner: Mutex<CacheInner>,
+    dir: Option<PathBuf>,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Counters reported by /api/info
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl ProofCache {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self::with_capacity(dir, MAX_CACHED_PROOFS)
+    }
+
+    pub fn with_capacity(dir: Option<PathBuf>, max_entries: usize) -> Self {
+        Self {
+            inner: Mutex::default(),
+            dir,
+            max_entries,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache in memory, and on disk under PROOF_CACHE_DIR when set
+    pub fn from_env() -> Self {
+        let dir = std::env::var("PROOF_CACHE_DIR").ok().filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        if let Some(dir) = &dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                warn!("Could not create PROOF_CACHE_DIR {}: {}", dir.display(), e);
+            }
+        }
+        Self::new(dir)
+    }
+
+    /// Cache key of a request: a hash of its action and parameters
+    pub fn key(action: &str, params: &serde_json::Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(action.as_bytes());
+        hasher.update(params.to_string().as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Look up a response, counting the hit or miss
+    pub fn get(&self, key: &str) -> Option<ProofResponse> {
+        let found = self.inner.lock().unwrap().entries.get(key).cloned();
+        let found = found.or_else(|| {
+            let response = self.read_file(key)?;
+            self.remember(key, response.clone());
+            Some(response)
+        });
+
+        let counter = if found.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// Store a response, replacing any previous one for `key`
+    pub fn insert(&self, key: &str, response: &ProofResponse) {
+        self.remember(key, response.clone());
+        if let Some(path) = self.path(key) {
+            let written = serde_json::to_vec(response)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| std::fs::write(&path, json).map_err(anyhow::Error::from));
+            if let Err(e) = written {
+                warn!("Failed to write cached proof {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Drop a response that can no longer be used
+    pub fn remove(&self, key: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.remove(key);
+        inner.order.retain(|k| k != key);
+        drop(inner);
+        if let Some(path) = self.path(key) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.inner.lock().unwrap().entries.len(),
+        }
+    }
+
+    fn remember(&self, key: &str, response: ProofResponse) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.insert(key.to_string(), response).is_none() {
+            inner.order.push_back(key.to_string());
+        }
+        while inner.entries.len() > self.max_entries {
+            let Some(oldest) = inner.order.pop_front() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.json", key)))
+    }
+
+    fn read_file(&self, key: &str) -> Option<ProofResponse> {
+        let json = std::fs::read(self.path(key)?).ok()?;
+        serde_json::from_slice(&json)
+            .map_err(|e| warn!("Ignoring unreadable cached proof {}: {}", key, e))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(proof_id: &str) -> ProofResponse {
+        ProofResponse {
+            proof_id: proof_id.to_string(),
+            status: "completed".to_string(),
+            proof: None,
+            calldata: Some("0xed3cf91f".to_string()),
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_key_depends_on_every_parameter() {
+        let key = ProofCache::key("shield", &serde_json::json!({"token": "USDC", "amount": "1000000"}));
+        assert_eq!(key, ProofCache::key("shield", &serde_json::json!({"token": "USDC", "amount": "1000000"})));
+        assert_ne!(key, ProofCache::key("unshield", &serde_json::json!({"token": "USDC", "amount": "1000000"})));
+        assert_ne!(key, ProofCache::key("shield", &serde_json::json!({"token": "USDC", "amount": "2000000"})));
+    }
+
+    #[test]
+    fn test_hits_and_misses_are_counted() {
+        let cache = ProofCache::new(None);
+        assert!(cache.get("a").is_none());
+        cache.insert("a", &response("1"));
+        assert_eq!(cache.get("a").unwrap().proof_id, "1");
+        cache.remove("a");
+        assert!(cache.get("a").is_none());
+
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2, entries: 0 });
+    }
+
+    #[test]
+    fn test_oldest_entry_is_evicted() {
+        let cache = ProofCache::with_capacity(None, 2);
+        cache.insert("a", &response("1"));
+        cache.insert("b", &response("2"));
+        cache.insert("a", &response("3"));
+        cache.insert("c", &response("4"));
+
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("b").unwrap().proof_id, "2");
+        assert_eq!(cache.get("c").unwrap().proof_id, "4");
+    }
+
+    #[test]
+    fn test_entries_survive_restart_on_disk() {
+        let dir = std::env::temp_dir().join(format!("proof_cache_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        ProofCache::new(Some(dir.clone())).insert("a", &response("1"));
+        let restarted = ProofCache::new(Some(dir.clone()));
+        assert_eq!(restarted.get("a").unwrap().proof_id, "1");
+        assert_eq!(restarted.stats().entries, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
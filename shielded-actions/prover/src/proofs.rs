@@ -0,0 +1,1844 @@
+//! Transaction builders for shield/unshield and test proofs
+//!
+//! These build fully proven ARM transactions in-process, so other crates can
+//! embed proving without shelling out to the local-prove binary.
+//!
+//! The `prove_*` builders take a [`ProofMode`]; the `build_*` wrappers always
+//! generate Groth16 proofs, the only kind the Protocol Adapter verifies. The
+//! prover service calls the `*_calldata` wrappers, which also verify the
+//! transaction and return its execute(Transaction) calldata.
+
+use alloy::primitives::{Address, B256};
+use alloy::sol_types::SolValue;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::time::Instant;
+use tracing::{info, warn};
+
+// ARM-RISC0 imports for real proving
+use arm::action::Action;
+use arm::action_tree::MerkleTree;
+use arm::compliance::{ComplianceInstance, ComplianceWitness};
+use arm::compliance_unit::ComplianceUnit;
+use arm::delta_proof::{DeltaProof, DeltaWitness};
+use arm::logic_instance::{AppData, ExpirableBlob};
+use arm::logic_proof::{LogicProver, LogicVerifier, LogicVerifierInputs};  // Trait needed for .prove() and .verifying_key()
+use arm::merkle_path::MerklePath;
+use arm::nullifier_key::NullifierKey;
+use arm::proving_system::ProofType;
+use arm::resource::Resource;
+use arm::resource_logic::TrivialLogicWitness;  // For ephemeral resources
+use arm::transaction::{Delta, Transaction};
+use arm::utils::{bytes_to_words, words_to_bytes};
+use risc0_zkvm::sha::Digest;
+
+// Forwarder logic witness for shield/unshield with external_payload
+use forwarder_logic_witness::{token_label, DeletionCriterion, ForwarderLogicWitness, PermitData, SwapParams};
+
+// EVM Protocol Adapter bindings
+use evm_protocol_adapter_bindings::contract::ProtocolAdapter;
+
+use crate::amount::{ensure_permit_supported, ensure_shieldable};
+use crate::balance::{balance_report, BalanceOperation, BalanceReport, Side};
+use crate::chain::{self, EXECUTE_SELECTOR};
+use crate::commitment_tree::CommitmentTree;
+use crate::config::Config;
+
+/// Number of delta proof attempts before giving up
+pub const DELTA_PROOF_ATTEMPTS: u32 = 3;
+
+/// Uniswap pool fee tier used when none is given (0.3%)
+pub const DEFAULT_SWAP_FEE: u32 = 3000;
+
+/// Which proofs to generate
+///
+/// Only Groth16 proofs verify on-chain. Succinct (STARK) proofs verify locally and
+/// need no Docker; fake proofs (RISC Zero dev mode) skip proving entirely.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofMode {
+    #[default]
+    Groth16,
+    Succinct,
+    Fake,
+}
+
+impl ProofMode {
+    /// ARM proof type; fake proofs are succinct receipts produced in dev mode
+    pub fn proof_type(self) -> ProofType {
+        match self {
+            ProofMode::Groth16 => ProofType::Groth16,
+            ProofMode::Succinct | ProofMode::Fake => ProofType::Succinct,
+        }
+    }
+
+    /// Whether the Protocol Adapter can verify the proofs
+    pub fn verifies_on_chain(self) -> bool {
+        self == ProofMode::Groth16
+    }
+
+    /// Name accepted by `FromStr`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProofMode::Groth16 => "groth16",
+            ProofMode::Succinct => "succinct",
+            ProofMode::Fake => "fake",
+        }
+    }
+}
+
+impl std::str::FromStr for ProofMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "groth16" => Ok(ProofMode::Groth16),
+            "succinct" => Ok(ProofMode::Succinct),
+            "fake" => Ok(ProofMode::Fake),
+            _ => Err(format!("Unknown proof type: {}. Supported: groth16, succinct, fake", s)),
+        }
+    }
+}
+
+/// Default for PROOF_PARALLELISM
+const DEFAULT_PROOF_PARALLELISM: usize = 2;
+
+/// A proof that can run on its own thread
+pub type ProveTask<'a, T> = Box<dyn FnOnce() -> Result<T> + Send + 'a>;
+
+/// How many independent proofs of one transaction run at once (PROOF_PARALLELISM, min 1)
+///
+/// Each proof is itself multi-threaded, so keep this low on small machines.
+pub fn proof_parallelism() -> usize {
+    std::env::var("PROOF_PARALLELISM")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PROOF_PARALLELISM)
+        .max(1)
+}
+
+/// Run independent proving tasks, at most `parallelism` at a time
+///
+/// Results are returned in task order; the first failing task's error is returned.
+pub fn prove_parallel<T: Send>(tasks: Vec<ProveTask<'_, T>>, parallelism: usize) -> Result<Vec<T>> {
+    let mut results = Vec::with_capacity(tasks.len());
+    let mut tasks = tasks.into_iter().peekable();
+
+    while tasks.peek().is_some() {
+        let batch: Vec<_> = tasks.by_ref().take(parallelism.max(1)).collect();
+        let batch_results: Vec<Result<T>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch.into_iter().map(|task| scope.spawn(task)).collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(anyhow!("Proving thread panicked"))))
+                .collect()
+        });
+        for result in batch_results {
+            results.push(result?);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Prove the compliance units of an action
+pub fn prove_compliance_units<'a>(witnesses: &[&'a ComplianceWitness], mode: ProofMode) -> Result<Vec<ComplianceUnit>> {
+    let start = Instant::now();
+    let parallelism = proof_parallelism();
+    let tasks: Vec<ProveTask<'a, ComplianceUnit>> = witnesses
+        .iter()
+        .map(|&witness| -> ProveTask<'a, ComplianceUnit> {
+            Box::new(move || {
+                ComplianceUnit::create(witness, mode.proof_type())
+                    .map_err(|e| anyhow!("Failed to create compliance unit: {:?}", e))
+            })
+        })
+        .collect();
+
+    let units = prove_parallel(tasks, parallelism)?;
+    info!("Proved {} compliance unit(s) in {:.2}s (parallelism {})",
+          units.len(), start.elapsed().as_secs_f64(), parallelism);
+    Ok(units)
+}
+
+/// Prove the consumed and created logic witnesses of an action, returned in that order
+fn prove_logic_pair<C, R>(consumed: C, created: R, mode: ProofMode) -> Result<Vec<LogicVerifier>>
+where
+    C: LogicProver + Send,
+    R: LogicProver + Send,
+{
+    let start = Instant::now();
+    let parallelism = proof_parallelism();
+    let tasks: Vec<ProveTask<'_, LogicVerifier>> = vec![
+        Box::new(move || consumed.prove(mode.proof_type())
+            .map_err(|e| anyhow!("Failed to prove consumed logic: {:?}", e))),
+        Box::new(move || created.prove(mode.proof_type())
+            .map_err(|e| anyhow!("Failed to prove created logic: {:?}", e))),
+    ];
+    let proofs = prove_parallel(tasks, parallelism)?;
+    info!("Proved 2 logic witnesses in {:.2}s (parallelism {})",
+          start.elapsed().as_secs_f64(), parallelism);
+    Ok(proofs)
+}
+
+/// Actions whose compliance and logic proofs are done, awaiting the delta proof
+///
+/// The delta proof is cheap compared to the rest, so keeping these around lets a
+/// failed delta proof be retried without re-proving.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProvenActions {
+    pub actions: Vec<Action>,
+    /// Compliance unit rcv values for the delta witness
+    pub rcvs: Vec<Vec<u8>>,
+}
+
+impl ProvenActions {
+    /// Generate the delta proof, retrying transient failures
+    pub fn balance(&self) -> Result<Transaction> {
+        let mut last_error = String::new();
+
+        for attempt in 1..=DELTA_PROOF_ATTEMPTS {
+            let delta_witness = DeltaWitness::from_bytes_vec(&self.rcvs)
+                .map_err(|e| anyhow!("Failed to create delta witness: {:?}", e))?;
+            let tx = Transaction::create(self.actions.clone(), Delta::Witness(delta_witness));
+
+            match tx.generate_delta_proof() {
+                Ok(balanced_tx) => return Ok(balanced_tx),
+                Err(e) => {
+                    last_error = format!("{:?}", e);
+                    warn!("Delta proof attempt {}/{} failed: {}", attempt, DELTA_PROOF_ATTEMPTS, last_error);
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "Delta proof generation failed after {} attempts: {}",
+            DELTA_PROOF_ATTEMPTS, last_error
+        ))
+    }
+}
+
+/// What local-prove prints for a generated proof (the only stdout line with `--json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofOutput {
+    /// execute(Transaction) calldata, 0x-prefixed hex
+    pub calldata: String,
+    /// Protocol Adapter the calldata targets
+    pub to: String,
+    pub calldata_length: usize,
+    /// Suggested gas limit: eth_estimateGas through RPC_URL, or a heuristic from the proof counts
+    #[serde(default)]
+    pub estimated_gas: u64,
+    pub metadata: ProofMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofMetadata {
+    pub proof_type: String,
+    pub num_actions: usize,
+    pub num_compliance_units: usize,
+    pub generation_time_secs: f64,
+}
+
+/// Encode a balanced transaction as execute(Transaction) calldata, including the selector
+pub fn encode_execute_calldata(tx: Transaction) -> Vec<u8> {
+    let evm_tx = ProtocolAdapter::Transaction::from(tx);
+    let abi_encoded = evm_tx.abi_encode();
+
+    let mut calldata = Vec::with_capacity(4 + abi_encoded.len());
+    calldata.extend_from_slice(&EXECUTE_SELECTOR);
+    calldata.extend_from_slice(&abi_encoded);
+    calldata
+}
+
+/// Length of the verifier selector the EVM encoding puts ahead of each seal
+const SEAL_SELECTOR_LEN: usize = 4;
+
+/// Decode execute(Transaction) calldata back into an ARM transaction: the
+/// reverse of [`encode_execute_calldata`], so it can be verified locally
+pub fn decode_execute_transaction(calldata: &[u8]) -> Result<Transaction> {
+    let evm_tx = chain::decode_execute_bytes(calldata)?;
+    let digest = |word: B256| Digest::from_bytes(word.0);
+    // Proofs are empty in the EVM encoding when absent
+    let seal = |proof: &[u8]| (proof.len() > SEAL_SELECTOR_LEN).then(|| proof[SEAL_SELECTOR_LEN..].to_vec());
+    macro_rules! blobs {
+        ($payload:expr) => {
+            $payload
+                .iter()
+                .map(|blob| ExpirableBlob { blob: bytes_to_words(&blob.blob), deletion_criterion: blob.deletionCriterion as u32 })
+                .collect()
+        };
+    }
+
+    let actions = evm_tx
+        .actions
+        .iter()
+        .map(|action| -> Result<Action> {
+            let compliance_units = action
+                .complianceVerifierInputs
+                .iter()
+                .map(|unit| -> Result<ComplianceUnit> {
+                    let delta = |word: B256| -> Result<[u32; 8]> {
+                        bytes_to_words(word.as_slice()).try_into().map_err(|_| anyhow!("Invalid unit delta"))
+                    };
+                    let instance = ComplianceInstance {
+                        consumed_nullifier: digest(unit.instance.consumed.nullifier),
+                        consumed_logic_ref: digest(unit.instance.consumed.logicRef),
+                        consumed_commitment_tree_root: digest(unit.instance.consumed.commitmentTreeRoot),
+                        created_commitment: digest(unit.instance.created.commitment),
+                        created_logic_ref: digest(unit.instance.created.logicRef),
+                        delta_x: delta(unit.instance.unitDeltaX)?,
+                        delta_y: delta(unit.instance.unitDeltaY)?,
+                    };
+                    // The instance is the compliance circuit's journal
+                    let journal = risc0_zkvm::serde::to_vec(&instance)
+                        .map_err(|e| anyhow!("Failed to encode compliance instance: {}", e))?;
+                    Ok(ComplianceUnit { proof: seal(&unit.proof), instance: words_to_bytes(&journal).to_vec() })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let logic_verifier_inputs = action
+                .logicVerifierInputs
+                .iter()
+                .map(|input| LogicVerifierInputs {
+                    tag: digest(input.tag),
+                    verifying_key: digest(input.verifyingKey),
+                    app_data: AppData {
+                        resource_payload: blobs!(input.appData.resourcePayload),
+                        discovery_payload: blobs!(input.appData.discoveryPayload),
+                        external_payload: blobs!(input.appData.externalPayload),
+                        application_payload: blobs!(input.appData.applicationPayload),
+                    },
+                    proof: seal(&input.proof),
+                })
+                .collect();
+
+            Ok(Action { compliance_units, logic_verifier_inputs })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let delta_proof = DeltaProof::from_bytes(&evm_tx.deltaProof)
+        .map_err(|e| anyhow!("Invalid delta proof: {:?}", e))?;
+    Ok(Transaction {
+        actions,
+        delta_proof: Delta::Proof(delta_proof),
+        expected_balance: None,
+        aggregation_proof: seal(&evm_tx.aggregationProof),
+    })
+}
+
+/// Check execute(Transaction) calldata the way [`verified_calldata`] checks a
+/// transaction before encoding it: every compliance and logic proof, the
+/// delta proof and the balance
+pub fn verify_execute_calldata(calldata: &[u8]) -> Result<()> {
+    decode_execute_transaction(calldata)?
+        .verify()
+        .map_err(|e| anyhow!("Verification failed: {:?}", e))?;
+    Ok(())
+}
+
+/// Verify a balanced transaction locally and encode it as execute(Transaction) calldata
+pub fn verified_calldata(tx: Transaction) -> Result<Vec<u8>> {
+    // verify() takes ownership
+    tx.clone().verify().map_err(|e| anyhow!("Verification failed: {:?}", e))?;
+    Ok(encode_execute_calldata(tx))
+}
+
+/// Verifying keys (RISC Zero image IDs) of the logic circuits, as 0x-prefixed hex
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogicVerifyingKeys {
+    pub forwarder_logic: String,
+    pub trivial_logic: String,
+}
+
+/// Verifying key of the forwarder logic, the `logic_ref` of shield, unshield, swap and raw resources
+pub fn forwarder_logic_vk() -> String {
+    format!("0x{}", hex::encode(ForwarderLogicWitness::verifying_key().as_bytes()))
+}
+
+/// Verifying key of the trivial logic used by ephemeral resources
+pub fn trivial_logic_vk() -> String {
+    format!("0x{}", hex::encode(TrivialLogicWitness::verifying_key().as_bytes()))
+}
+
+pub fn logic_verifying_keys() -> LogicVerifyingKeys {
+    LogicVerifyingKeys { forwarder_logic: forwarder_logic_vk(), trivial_logic: trivial_logic_vk() }
+}
+
+/// Parse a hex address string into a 20-byte array
+pub fn parse_address(addr: &str) -> Result<[u8; 20]> {
+    let addr = addr.trim_start_matches("0x");
+    let bytes = hex::decode(addr).map_err(|e| anyhow!("Invalid address: {}", e))?;
+    if bytes.len() != 20 {
+        return Err(anyhow!("Address must be 20 bytes, got {}", bytes.len()));
+    }
+    let mut arr = [0u8; 20];
+    arr.copy_from_slice(&bytes);
+    Ok(arr)
+}
+
+/// Parse a user-supplied hex address, verifying its EIP-55 checksum if it has one
+///
+/// Mixed-case input must match the checksum, so a mistyped character is caught
+/// instead of baked into the proof. All-lowercase and all-uppercase input carries
+/// no checksum and is accepted as is.
+pub fn parse_checksummed_address(addr: &str) -> Result<[u8; 20]> {
+    let bytes = parse_address(addr)?;
+    let digits = addr.trim_start_matches("0x");
+    let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        let checksummed = Address::from(bytes).to_checksum(None);
+        if checksummed[2..] != *digits {
+            return Err(anyhow!("Address {} has an invalid EIP-55 checksum (expected {})", addr, checksummed));
+        }
+    }
+    Ok(bytes)
+}
+
+/// Get the forwarder address for a token
+pub fn get_forwarder_address(config: &Config, token: &str) -> Result<[u8; 20]> {
+    parse_address(&config.tokens.get(token)?.forwarder_address)
+}
+
+/// File name local-prove writes a shield/unshield proof's calldata to
+pub fn proof_file_name(op: &str, token: &str, amount: u128, deletion_criterion: DeletionCriterion) -> String {
+    match deletion_criterion {
+        DeletionCriterion::Never => format!("{}_{}_{}.bin", op, token.to_lowercase(), amount),
+        DeletionCriterion::Immediately => format!("{}_{}_{}_pruned.bin", op, token.to_lowercase(), amount),
+    }
+}
+
+/// File name local-prove writes a shield proof's calldata to
+///
+/// A bundled permit is part of the proof, so it is keyed on the permit's signature.
+pub fn shield_proof_file_name(
+    token: &str,
+    amount: u128,
+    deletion_criterion: DeletionCriterion,
+    permit: Option<&PermitData>,
+) -> String {
+    let name = proof_file_name("shield", token, amount, deletion_criterion);
+    match (permit, name.strip_suffix(".bin")) {
+        (Some(permit), Some(stem)) => format!("{}_permit_{}.bin", stem, hex::encode(&permit.r[..4])),
+        _ => name,
+    }
+}
+
+/// File name local-prove writes a swap proof's calldata to
+pub fn swap_proof_file_name(order: &SwapOrder, deletion_criterion: DeletionCriterion) -> String {
+    let stem = format!(
+        "swap_{}_{}_{}_{}",
+        order.token_in.to_lowercase(), order.amount_in, order.token_out.to_lowercase(), order.min_amount_out
+    );
+    match deletion_criterion {
+        DeletionCriterion::Never => format!("{}.bin", stem),
+        DeletionCriterion::Immediately => format!("{}_pruned.bin", stem),
+    }
+}
+
+/// A swap of one registered token for another through the Uniswap forwarder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapOrder {
+    pub token_in: String,
+    /// Amount sold, in token_in's smallest units
+    pub amount_in: u128,
+    pub token_out: String,
+    /// Floor on the amount bought, in token_out's smallest units
+    pub min_amount_out: u128,
+    /// Pool fee tier, see [`DEFAULT_SWAP_FEE`]
+    pub fee: u32,
+}
+
+impl SwapOrder {
+    /// exactInputSingle params: sell from the Uniswap forwarder, buy into token_out's forwarder
+    pub fn swap_params(&self, config: &Config) -> Result<SwapParams> {
+        let token_in = config.tokens.get(&self.token_in)?;
+        let token_out = config.tokens.get(&self.token_out)?;
+        if token_in.symbol == token_out.symbol {
+            return Err(anyhow!("Cannot swap {} for itself", token_in.symbol));
+        }
+        if self.fee >= 1 << 24 {
+            return Err(anyhow!("Invalid fee tier {} (must fit in uint24)", self.fee));
+        }
+        // The bought tokens are sent to the forwarder, which must be credited in full
+        ensure_shieldable(token_out)?;
+
+        Ok(SwapParams {
+            token_in: parse_address(&token_in.erc20_address)?,
+            token_out: parse_address(&token_out.erc20_address)?,
+            fee: self.fee,
+            recipient: parse_address(&token_out.forwarder_address)?,
+            amount_in: self.amount_in,
+            amount_out_minimum: self.min_amount_out,
+        })
+    }
+}
+
+/// Per-token delta of a swap
+///
+/// The consumed input and created output resources must be settled exactly by the
+/// legs of the swap call: `amount_in` of token_in leaves the pool and at least
+/// `amount_out_minimum` of token_out enters it. Token labels of the call legs are
+/// resolved from their ERC20 addresses, so a call that trades the wrong tokens or
+/// amounts does not balance.
+pub fn swap_balance(config: &Config, order: &SwapOrder, params: &SwapParams) -> Result<BalanceReport> {
+    let label = |erc20: [u8; 20]| {
+        config.tokens.by_erc20(&erc20.into())
+            .map(|token| token.symbol.clone())
+            .ok_or_else(|| anyhow!("Swap call trades an unregistered token 0x{}", hex::encode(erc20)))
+    };
+    let op = |label: String, quantity: u128, side| BalanceOperation { label, quantity: quantity.to_string(), side };
+
+    balance_report(&[
+        op(order.token_in.clone(), order.amount_in, Side::Consumed),
+        op(order.token_out.clone(), order.min_amount_out, Side::Created),
+        op(label(params.token_in)?, params.amount_in, Side::Created),
+        op(label(params.token_out)?, params.amount_out_minimum, Side::Consumed),
+    ])
+}
+
+/// A forwarder call given as pre-built calldata, for calls without a typed builder
+///
+/// Nothing checks that the call settles any resource or that the forwarder
+/// returns `expected_output`, so a wrong payload only fails on-chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawCall {
+    pub forwarder: [u8; 20],
+    /// Calldata sent verbatim, including the function selector
+    pub call_data: Vec<u8>,
+    /// Return data the Protocol Adapter requires, byte for byte
+    pub expected_output: Vec<u8>,
+}
+
+impl RawCall {
+    /// Parse the hex forwarder address, calldata and expected output
+    pub fn parse(forwarder: &str, call_data: &str, expected_output: &str) -> Result<Self> {
+        let forwarder = parse_address(forwarder)?;
+        let call_data = parse_hex_bytes(call_data).map_err(|e| anyhow!("Invalid call_data: {}", e))?;
+        if call_data.len() < 4 {
+            return Err(anyhow!("call_data must start with a 4-byte function selector"));
+        }
+        let expected_output =
+            parse_hex_bytes(expected_output).map_err(|e| anyhow!("Invalid expected_output: {}", e))?;
+        Ok(Self { forwarder, call_data, expected_output })
+    }
+
+    /// File name local-prove writes the proof's calldata to, keyed on a hash of the call
+    pub fn proof_file_name(&self, deletion_criterion: DeletionCriterion) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.forwarder);
+        hasher.update((self.call_data.len() as u64).to_be_bytes());
+        hasher.update(&self.call_data);
+        hasher.update(&self.expected_output);
+        let stem = format!("raw_{}", hex::encode(&hasher.finalize()[..8]));
+        match deletion_criterion {
+            DeletionCriterion::Never => format!("{}.bin", stem),
+            DeletionCriterion::Immediately => format!("{}_pruned.bin", stem),
+        }
+    }
+}
+
+/// Decode a hex byte string, with or without 0x (empty is allowed)
+pub fn parse_hex_bytes(s: &str) -> Result<Vec<u8>> {
+    hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| anyhow!("{}", e))
+}
+
+/// Generate a random nullifier key and its commitment, both hex encoded
+///
+/// The key stays with the user and authorizes consuming their resources; only
+/// the commitment (a resource's `nk_commitment`) is published on-chain.
+pub fn generate_nullifier_keypair() -> (String, String) {
+    let nf_key = NullifierKey::from_bytes(&rand::random::<[u8; 32]>());
+    (hex::encode(nf_key.inner()), hex::encode(nf_key.commit().inner()))
+}
+
+/// Parse a 32-byte hex nullifier key, as returned by [`generate_nullifier_keypair`]
+pub fn parse_nullifier_key(s: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| anyhow!("Invalid nullifier key: {}", e))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| anyhow!("Nullifier key must be 32 bytes, got {}", bytes.len()))
+}
+
+/// Hex commitment of a nullifier key: the `public_key` of [`generate_nullifier_keypair`]
+pub fn nullifier_key_commitment(s: &str) -> Result<String> {
+    let nf_key = NullifierKey::from_bytes(&parse_nullifier_key(s)?);
+    Ok(hex::encode(nf_key.commit().inner()))
+}
+
+/// Nonce of a transaction's consumed resource, which determines its nullifier
+pub type Nonce = [u8; 32];
+
+/// A random nonce, so every generated transaction has a nullifier of its own
+pub fn fresh_nonce() -> Nonce {
+    rand::random()
+}
+
+/// Parse a 32-byte hex nonce (e.g. from `--nonce`)
+pub fn parse_nonce(s: &str) -> Result<Nonce> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| anyhow!("Invalid nonce: {}", e))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| anyhow!("Nonce must be 32 bytes, got {}", bytes.len()))
+}
+
+/// Build a test transaction using ARM's test transaction generator
+///
+/// Use [`ProofMode::Groth16`] for on-chain verification (Succinct/STARK proofs can't be verified on-chain).
+pub fn build_test_transaction(n_actions: usize, n_cus: usize, mode: ProofMode) -> Result<Transaction> {
+    let tx = arm_tests::generate_test_transaction(n_actions, n_cus, mode.proof_type());
+
+    // The test transaction generator already includes proofs
+    // but we need to generate the delta proof
+    tx.generate_delta_proof()
+        .map_err(|e| anyhow!("Delta proof generation failed: {:?}", e))
+}
+
+/// Prove an ephemeral transaction that references INITIAL_ROOT
+/// It verifies on-chain because INITIAL_ROOT matches the deployed EMPTY_HASH
+///
+/// `nonce` determines the nullifier, so it must not repeat one already spent on-chain.
+pub fn prove_ephemeral_actions(nonce: Nonce, mode: ProofMode) -> Result<ProvenActions> {
+    info!("Building ephemeral transaction");
+
+    // Create a nullifier key pair
+    let nf_key = NullifierKey::default();
+    let nf_key_cm = nf_key.commit();
+
+    // Create consumed ephemeral resource (is_ephemeral=true, quantity=0)
+    // TrivialLogicWitness expects: is_ephemeral=true, quantity=0
+    let mut consumed_resource = Resource {
+        logic_ref: TrivialLogicWitness::verifying_key(),  // Use trivial logic VK
+        nk_commitment: nf_key_cm,
+        quantity: 0,           // quantity=0 for ephemeral (required by TrivialLogicWitness)
+        is_ephemeral: true,    // CRITICAL: this makes the circuit use ephemeral_root
+        ..Default::default()
+    };
+    consumed_resource.nonce = nonce;
+
+    let consumed_resource_nf = consumed_resource.nullifier(&nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    // Create created ephemeral resource (is_ephemeral=true, quantity=0)
+    let mut created_resource = consumed_resource.clone();
+    created_resource.set_nonce(consumed_resource_nf);
+
+    // Create the compliance witness
+    // with_fixed_rcv sets ephemeral_root to INITIAL_ROOT
+    // Since is_ephemeral=true, the circuit will use ephemeral_root (INITIAL_ROOT)
+    let compliance_witness = ComplianceWitness::with_fixed_rcv(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        created_resource.clone(),
+    );
+
+    // Create a compliance unit from the witness with Groth16 proofs
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    // Build the action tree for the merkle paths
+    let created_resource_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_resource_nf);
+    action_tree.insert(created_resource_cm);
+
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    // Create and prove TrivialLogic for consumed resource
+    // TrivialLogicWitness::new(resource, action_tree_root, nf_key, is_consumed)
+    let consumed_logic = TrivialLogicWitness::new(
+        consumed_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        true,  // is_consumed
+    );
+
+    // Create and prove TrivialLogic for created resource
+    let created_logic = TrivialLogicWitness::new(
+        created_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        false,  // is_consumed
+    );
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    // Create an action with this compliance unit and logic proofs
+    let action = Action::new(
+        compliance_units,
+        logic_proofs,
+    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+
+    // Verify the action
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    Ok(ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    })
+}
+
+/// Prove a shield action with external_payload for the forwarder call
+///
+/// The resulting transaction:
+/// 1. Creates a shielded resource (commitment goes on-chain)
+/// 2. Outputs external_payload encoding: transferFrom(sender, forwarder, amount)
+/// 3. The Protocol Adapter executes this forwarder call when processing the proof
+///
+/// With a `permit`, a permit(sender, forwarder, ...) call runs first, so the sender
+/// needs no prior approve. `nonce` determines the consumed resource's nullifier;
+/// see [`fresh_nonce`].
+#[allow(clippy::too_many_arguments)]
+pub fn prove_shield_actions(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    sender: &str,
+    deletion_criterion: DeletionCriterion,
+    permit: Option<PermitData>,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<ProvenActions> {
+    let token_info = config.tokens.get(token)?;
+    ensure_shieldable(token_info)?;
+    if permit.is_some() {
+        ensure_permit_supported(token_info)?;
+    }
+    let forwarder_address = get_forwarder_address(config, token)?;
+    let sender_address = parse_checksummed_address(sender)?;
+
+    info!("Building shield transaction: token={}, amount={}, forwarder=0x{}, deletion_criterion={:?}, permit={}",
+          token, amount, hex::encode(forwarder_address), deletion_criterion, permit.is_some());
+
+    // Create nullifier key
+    let nf_key = NullifierKey::default();
+    let nf_key_cm = nf_key.commit();
+
+    // Get the verifying keys for the logic circuits
+    // Consumed resource uses TrivialLogic (no external call)
+    // Created resource uses ForwarderLogic (triggers transferFrom)
+    let trivial_vk = TrivialLogicWitness::verifying_key();
+    let forwarder_vk = ForwarderLogicWitness::verifying_key();
+
+    // Create consumed ephemeral resource (balance going in)
+    // Uses TrivialLogic since it doesn't trigger any external call
+    let mut consumed_resource = Resource {
+        logic_ref: trivial_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,           // ephemeral
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    consumed_resource.nonce = nonce;
+
+    let consumed_nf = consumed_resource.nullifier(&nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    // Create the shielded resource (created, uses ForwarderLogic)
+    // This represents the shielded token balance and triggers the transferFrom call
+    let mut created_resource = Resource {
+        logic_ref: forwarder_vk,  // ForwarderLogic VK - this resource triggers the external call
+        nk_commitment: nf_key_cm,
+        quantity: 0,           // Still use 0 for ephemeral logic to work
+        is_ephemeral: true,    // Ephemeral for INITIAL_ROOT compatibility
+        ..Default::default()
+    };
+    created_resource.set_nonce(consumed_nf);
+
+    // Create the compliance witness
+    let compliance_witness = ComplianceWitness::with_fixed_rcv(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        created_resource.clone(),
+    );
+
+    // Create compliance unit
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    // Build action tree
+    let created_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_nf);
+    action_tree.insert(created_cm);
+
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    // Create ForwarderLogicWitness for the CREATED resource (triggers transferFrom)
+    // The created resource triggers the shield: transferFrom(sender, forwarder, amount)
+    let created_logic = ForwarderLogicWitness::new_shield(
+        created_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        false,  // is_consumed = false (this is the created resource)
+        forwarder_address,
+        sender_address,
+        amount,
+    ).with_deletion_criterion(deletion_criterion).with_permit(permit);
+
+    // Create TrivialLogicWitness for the CONSUMED resource (no external call)
+    let consumed_logic = TrivialLogicWitness::new(
+        consumed_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        true,  // is_consumed = true
+    );
+
+    // Prove both logic witnesses (concurrently, see PROOF_PARALLELISM)
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    // Create action
+    let action = Action::new(
+        compliance_units,
+        logic_proofs,
+    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+
+    // Verify action
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    Ok(ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    })
+}
+
+/// Prove an unshield action with external_payload for the forwarder call
+///
+/// The resulting transaction:
+/// 1. Consumes a shielded resource (nullifier goes on-chain)
+/// 2. Outputs external_payload encoding: transfer(recipient, amount)
+/// 3. The Protocol Adapter executes this forwarder call when processing the proof
+///
+/// `nonce` determines the consumed resource's nullifier; see [`fresh_nonce`].
+pub fn prove_unshield_actions(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    recipient: &str,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<ProvenActions> {
+    let forwarder_address = get_forwarder_address(config, token)?;
+    let recipient_address = parse_checksummed_address(recipient)?;
+
+    info!("Building unshield transaction: token={}, amount={}, forwarder=0x{}, deletion_criterion={:?}",
+          token, amount, hex::encode(forwarder_address), deletion_criterion);
+
+    // Create nullifier key
+    let nf_key = NullifierKey::default();
+    let nf_key_cm = nf_key.commit();
+
+    // For unshield: consumed resource uses ForwarderLogic (triggers transfer)
+    // Created resource uses TrivialLogic (no external call)
+    let trivial_vk = TrivialLogicWitness::verifying_key();
+    let forwarder_vk = ForwarderLogicWitness::verifying_key();
+
+    // Create consumed resource (the shielded balance being withdrawn)
+    // Uses ForwarderLogic since this triggers the transfer call
+    let mut consumed_resource = Resource {
+        logic_ref: forwarder_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    consumed_resource.nonce = nonce;
+
+    let consumed_nf = consumed_resource.nullifier(&nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    // Create output resource (ephemeral, represents the withdrawn value)
+    // Uses TrivialLogic (no external call)
+    let mut created_resource = Resource {
+        logic_ref: trivial_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    created_resource.set_nonce(consumed_nf);
+
+    // Create compliance witness
+    let compliance_witness = ComplianceWitness::with_fixed_rcv(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        created_resource.clone(),
+    );
+
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    // Build action tree
+    let created_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_nf);
+    action_tree.insert(created_cm);
+
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    // Create ForwarderLogicWitness for the CONSUMED resource (triggers transfer)
+    // The consumed resource triggers the unshield: transfer(recipient, amount)
+    let consumed_logic = ForwarderLogicWitness::new_unshield(
+        consumed_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        true,  // is_consumed = true (this is the consumed resource)
+        forwarder_address,
+        recipient_address,
+        amount,
+    ).with_deletion_criterion(deletion_criterion);
+
+    // Create TrivialLogicWitness for the created resource (no external call)
+    let created_logic = TrivialLogicWitness::new(
+        created_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        false,  // is_consumed = false
+    );
+
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    let action = Action::new(
+        compliance_units,
+        logic_proofs,
+    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    Ok(ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    })
+}
+
+/// A persistent resource created by [`prove_persistent_shield_actions`]
+///
+/// Its owner keeps it, together with the nullifier key, to unshield it later;
+/// only the commitment is published on-chain.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShieldedNote {
+    pub token: String,
+    /// Commitment the Protocol Adapter appends to its tree, 0x-prefixed hex
+    pub commitment: String,
+    pub resource: Resource,
+}
+
+impl ShieldedNote {
+    fn new(token: &str, resource: Resource) -> Self {
+        Self {
+            token: token.to_string(),
+            commitment: format!("0x{}", hex::encode(resource.commitment().as_bytes())),
+            resource,
+        }
+    }
+}
+
+/// Prove a shield that creates a persistent resource holding `amount`
+///
+/// The transaction:
+/// 1. Consumes an ephemeral wrapper of quantity `amount`, whose ForwarderLogic
+///    calls transferFrom(sender, forwarder, amount) (after `permit`, if any)
+/// 2. Creates a persistent resource of quantity `amount`, owned by `nf_key`
+///
+/// Both resources carry the forwarder's [`token_label`], so they balance in the
+/// delta proof and the commitment goes into the adapter's tree for a later
+/// [`prove_persistent_unshield_actions`]. Needs the forwarder logic build that
+/// accepts funded ephemeral wrappers.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_persistent_shield_actions(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    sender: &str,
+    nf_key: &NullifierKey,
+    deletion_criterion: DeletionCriterion,
+    permit: Option<PermitData>,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<(ProvenActions, ShieldedNote)> {
+    let token_info = config.tokens.get(token)?;
+    ensure_shieldable(token_info)?;
+    if permit.is_some() {
+        ensure_permit_supported(token_info)?;
+    }
+    let forwarder_address = get_forwarder_address(config, token)?;
+    let sender_address = parse_checksummed_address(sender)?;
+
+    info!("Building persistent shield transaction: token={}, amount={}, forwarder=0x{}, deletion_criterion={:?}, permit={}",
+          token, amount, hex::encode(forwarder_address), deletion_criterion, permit.is_some());
+
+    let nf_key_cm = nf_key.commit();
+    let forwarder_vk = ForwarderLogicWitness::verifying_key();
+    let label_ref = token_label(forwarder_address);
+
+    // Ephemeral wrapper paid for by the transferFrom
+    let mut consumed_resource = Resource {
+        logic_ref: forwarder_vk,
+        label_ref,
+        nk_commitment: nf_key_cm,
+        quantity: amount,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    consumed_resource.nonce = nonce;
+
+    let consumed_nf = consumed_resource.nullifier(nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    // The shielded balance itself
+    let mut created_resource = Resource {
+        logic_ref: forwarder_vk,
+        label_ref,
+        nk_commitment: nf_key_cm,
+        quantity: amount,
+        is_ephemeral: false,
+        ..Default::default()
+    };
+    created_resource.set_nonce(consumed_nf);
+
+    // The consumed wrapper is ephemeral, so the unit still references INITIAL_ROOT
+    let compliance_witness = ComplianceWitness::with_fixed_rcv(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        created_resource.clone(),
+    );
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    let created_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_nf);
+    action_tree.insert(created_cm);
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    let consumed_logic = ForwarderLogicWitness::new_deposit(
+        consumed_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        forwarder_address,
+        sender_address,
+        amount,
+    ).with_deletion_criterion(deletion_criterion).with_permit(permit);
+    let created_logic = ForwarderLogicWitness::new_no_call(
+        created_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        false,
+    );
+
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    let action = Action::new(compliance_units, logic_proofs)
+        .map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    let proven = ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    };
+    Ok((proven, ShieldedNote::new(token, created_resource)))
+}
+
+/// Check that `nf_key` owns `note` and find its commitment in `tree`
+///
+/// Returns the forwarder of the note's token and the commitment's Merkle path.
+fn locate_note(
+    config: &Config,
+    note: &ShieldedNote,
+    nf_key: &NullifierKey,
+    tree: &CommitmentTree,
+) -> Result<([u8; 20], MerklePath)> {
+    if note.resource.is_ephemeral || note.resource.quantity == 0 {
+        return Err(anyhow!("Note is not a persistent resource with a balance"));
+    }
+    if note.resource.nk_commitment.inner() != nf_key.commit().inner() {
+        return Err(anyhow!("Nullifier key does not own this note"));
+    }
+    let forwarder_address = get_forwarder_address(config, &note.token)?;
+    if note.resource.label_ref != token_label(forwarder_address) {
+        return Err(anyhow!("Note is not a {} resource of the {} deployment", note.token, config.network));
+    }
+
+    let commitment = B256::from_slice(note.resource.commitment().as_bytes());
+    let index = tree.position(&commitment).ok_or_else(|| {
+        anyhow!("Commitment {} is not in the local commitment tree; sync it first", commitment)
+    })?;
+    let path: Vec<(Digest, bool)> = tree
+        .path(index)?
+        .into_iter()
+        .map(|(sibling, sibling_is_left)| (Digest::from_bytes(sibling.0), sibling_is_left))
+        .collect();
+    Ok((forwarder_address, MerklePath::from_path(&path)))
+}
+
+/// Prove an unshield that consumes the persistent resource of `note`
+///
+/// The transaction:
+/// 1. Consumes the note's resource, proving membership under the current root
+///    of `tree`, which must mirror the Protocol Adapter's commitment tree
+/// 2. Creates an ephemeral wrapper of the same quantity, whose ForwarderLogic
+///    calls transfer(recipient, quantity)
+///
+/// The whole note is withdrawn; there is no change resource.
+pub fn prove_persistent_unshield_actions(
+    config: &Config,
+    note: &ShieldedNote,
+    nf_key: &NullifierKey,
+    tree: &CommitmentTree,
+    recipient: &str,
+    deletion_criterion: DeletionCriterion,
+    mode: ProofMode,
+) -> Result<ProvenActions> {
+    let (forwarder_address, merkle_path) = locate_note(config, note, nf_key, tree)?;
+    let recipient_address = parse_checksummed_address(recipient)?;
+    let amount = note.resource.quantity;
+
+    info!("Building persistent unshield transaction: token={}, amount={}, forwarder=0x{}, root={}, deletion_criterion={:?}",
+          note.token, amount, hex::encode(forwarder_address), tree.root(), deletion_criterion);
+
+    let consumed_resource = note.resource.clone();
+    let consumed_nf = consumed_resource.nullifier(nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    // Ephemeral wrapper whose creation pays out the note
+    let mut created_resource = Resource {
+        logic_ref: consumed_resource.logic_ref,
+        label_ref: consumed_resource.label_ref,
+        nk_commitment: consumed_resource.nk_commitment,
+        quantity: amount,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    created_resource.set_nonce(consumed_nf);
+
+    let compliance_witness = ComplianceWitness::from_resources_with_path(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        merkle_path,
+        created_resource.clone(),
+    );
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    let created_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_nf);
+    action_tree.insert(created_cm);
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    let consumed_logic = ForwarderLogicWitness::new_no_call(
+        consumed_resource,
+        action_tree_root,
+        nf_key.clone(),
+        true,
+    );
+    let created_logic = ForwarderLogicWitness::new_withdrawal(
+        created_resource,
+        action_tree_root,
+        nf_key.clone(),
+        forwarder_address,
+        recipient_address,
+        amount,
+    ).with_deletion_criterion(deletion_criterion);
+
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    let action = Action::new(compliance_units, logic_proofs)
+        .map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    Ok(ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    })
+}
+
+/// Prove a swap action with external_payload for the Uniswap forwarder call
+///
+/// The resulting transaction:
+/// 1. Consumes the input resource (nullifier goes on-chain)
+/// 2. Creates the output resource, whose external_payload encodes
+///    exactInputSingle(token_in, token_out, fee, token_out forwarder, amount_in, min_amount_out)
+/// 3. The Protocol Adapter executes this call on the Uniswap forwarder, which reverts
+///    below `min_amount_out` and must return exactly abi.encode(min_amount_out)
+///
+/// Like shield/unshield, both resources are ephemeral with quantity 0, so the delta proof
+/// cannot see the token amounts; [`swap_balance`] checks them before proving.
+pub fn prove_swap_actions(
+    config: &Config,
+    order: &SwapOrder,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<ProvenActions> {
+    let params = order.swap_params(config)?;
+    let report = swap_balance(config, order, &params)?;
+    if !report.balanced {
+        return Err(anyhow!("Swap does not balance: {}", serde_json::to_string(&report.labels)?));
+    }
+    let forwarder_address = parse_address(&config.uniswap_forwarder)?;
+
+    info!("Building swap transaction: {} {} -> min {} {}, fee={}, forwarder=0x{}, deletion_criterion={:?}",
+          order.amount_in, order.token_in, order.min_amount_out, order.token_out, order.fee,
+          hex::encode(forwarder_address), deletion_criterion);
+
+    // Create nullifier key
+    let nf_key = NullifierKey::default();
+    let nf_key_cm = nf_key.commit();
+
+    // Consumed input resource uses TrivialLogic (no external call)
+    // Created output resource uses ForwarderLogic (triggers exactInputSingle)
+    let trivial_vk = TrivialLogicWitness::verifying_key();
+    let forwarder_vk = ForwarderLogicWitness::verifying_key();
+
+    let mut consumed_resource = Resource {
+        logic_ref: trivial_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    consumed_resource.nonce = nonce;
+
+    let consumed_nf = consumed_resource.nullifier(&nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    let mut created_resource = Resource {
+        logic_ref: forwarder_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    created_resource.set_nonce(consumed_nf);
+
+    // Create compliance witness
+    let compliance_witness = ComplianceWitness::with_fixed_rcv(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        created_resource.clone(),
+    );
+
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    // Build action tree
+    let created_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_nf);
+    action_tree.insert(created_cm);
+
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    // Create ForwarderLogicWitness for the CREATED resource (triggers the swap)
+    let created_logic = ForwarderLogicWitness::new_swap(
+        created_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        false,  // is_consumed = false (this is the created resource)
+        forwarder_address,
+        params,
+    ).with_deletion_criterion(deletion_criterion);
+
+    // Create TrivialLogicWitness for the CONSUMED resource (no external call)
+    let consumed_logic = TrivialLogicWitness::new(
+        consumed_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        true,  // is_consumed = true
+    );
+
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    let action = Action::new(
+        compliance_units,
+        logic_proofs,
+    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    Ok(ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    })
+}
+
+/// Prove an action whose created resource sends a [`RawCall`] to its forwarder
+///
+/// Built like a shield: a TrivialLogic consumed resource and a ForwarderLogic
+/// created resource, both ephemeral with quantity 0. Prefer the typed builders
+/// when one fits; they derive the calldata and expected output from the amounts.
+pub fn prove_raw_actions(
+    raw: &RawCall,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+    mode: ProofMode,
+) -> Result<ProvenActions> {
+    info!("Building raw forwarder call: forwarder=0x{}, selector=0x{}, call_data={} bytes, deletion_criterion={:?}",
+          hex::encode(raw.forwarder), hex::encode(&raw.call_data[..4.min(raw.call_data.len())]),
+          raw.call_data.len(), deletion_criterion);
+
+    let nf_key = NullifierKey::default();
+    let nf_key_cm = nf_key.commit();
+
+    let trivial_vk = TrivialLogicWitness::verifying_key();
+    let forwarder_vk = ForwarderLogicWitness::verifying_key();
+
+    let mut consumed_resource = Resource {
+        logic_ref: trivial_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    consumed_resource.nonce = nonce;
+
+    let consumed_nf = consumed_resource.nullifier(&nf_key)
+        .map_err(|e| anyhow!("Failed to compute nullifier: {:?}", e))?;
+
+    let mut created_resource = Resource {
+        logic_ref: forwarder_vk,
+        nk_commitment: nf_key_cm,
+        quantity: 0,
+        is_ephemeral: true,
+        ..Default::default()
+    };
+    created_resource.set_nonce(consumed_nf);
+
+    let compliance_witness = ComplianceWitness::with_fixed_rcv(
+        consumed_resource.clone(),
+        nf_key.clone(),
+        created_resource.clone(),
+    );
+
+    let compliance_units = prove_compliance_units(&[&compliance_witness], mode)?;
+
+    let created_cm = created_resource.commitment();
+    let mut action_tree = MerkleTree::new(vec![]);
+    action_tree.insert(consumed_nf);
+    action_tree.insert(created_cm);
+
+    let action_tree_root = action_tree.root()
+        .map_err(|e| anyhow!("Failed to compute action tree root: {:?}", e))?;
+
+    // The created resource carries the call verbatim
+    let created_logic = ForwarderLogicWitness::new_raw(
+        created_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        false,  // is_consumed = false (this is the created resource)
+        raw.forwarder,
+        raw.call_data.clone(),
+        raw.expected_output.clone(),
+        true,
+    ).with_deletion_criterion(deletion_criterion);
+
+    let consumed_logic = TrivialLogicWitness::new(
+        consumed_resource.clone(),
+        action_tree_root,
+        nf_key.clone(),
+        true,  // is_consumed = true
+    );
+
+    let logic_proofs = prove_logic_pair(consumed_logic, created_logic, mode)?;
+
+    let action = Action::new(
+        compliance_units,
+        logic_proofs,
+    ).map_err(|e| anyhow!("Failed to create action: {:?}", e))?;
+
+    action.clone().verify()
+        .map_err(|e| anyhow!("Action verification failed: {:?}", e))?;
+
+    Ok(ProvenActions {
+        actions: vec![action],
+        rcvs: vec![compliance_witness.rcv.to_vec()],
+    })
+}
+
+/// Most entries proved into one batch transaction
+///
+/// Every entry adds a compliance proof and two logic proofs, and the gas and
+/// proving time grow with them.
+pub const MAX_BATCH_ENTRIES: usize = 8;
+
+/// One operation of a batch transaction, proved as its own action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// See [`prove_shield_actions`]
+    Shield { token: String, amount: u128, sender: String, permit: Option<PermitData> },
+    /// See [`prove_unshield_actions`]
+    Unshield { token: String, amount: u128, recipient: String },
+    /// See [`prove_swap_actions`]
+    Swap(SwapOrder),
+    /// See [`prove_raw_actions`]
+    Raw(RawCall),
+}
+
+impl BatchOp {
+    /// "shield", "unshield", "swap" or "raw"
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchOp::Shield { .. } => "shield",
+            BatchOp::Unshield { .. } => "unshield",
+            BatchOp::Swap(_) => "swap",
+            BatchOp::Raw(_) => "raw",
+        }
+    }
+}
+
+/// A batch operation with the deletion criterion and nonce of its action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchEntry {
+    pub op: BatchOp,
+    pub deletion_criterion: DeletionCriterion,
+    pub nonce: Nonce,
+}
+
+impl BatchEntry {
+    /// An entry with a fresh nonce
+    pub fn new(op: BatchOp, deletion_criterion: DeletionCriterion) -> Self {
+        Self { op, deletion_criterion, nonce: fresh_nonce() }
+    }
+
+    /// The entry's parameters as JSON, without the nonce (for cache keys and journals)
+    pub fn describe(&self) -> serde_json::Value {
+        let mut json = match &self.op {
+            BatchOp::Shield { token, amount, sender, permit } => serde_json::json!({
+                "token": token.to_uppercase(),
+                "amount": amount.to_string(),
+                "sender": sender.to_lowercase(),
+                "permit": permit.is_some(),
+            }),
+            BatchOp::Unshield { token, amount, recipient } => serde_json::json!({
+                "token": token.to_uppercase(),
+                "amount": amount.to_string(),
+                "recipient": recipient.to_lowercase(),
+            }),
+            BatchOp::Swap(order) => serde_json::json!({
+                "token_in": order.token_in.to_uppercase(),
+                "amount_in": order.amount_in.to_string(),
+                "token_out": order.token_out.to_uppercase(),
+                "min_amount_out": order.min_amount_out.to_string(),
+                "fee": order.fee,
+            }),
+            BatchOp::Raw(raw) => serde_json::json!({
+                "forwarder": format!("0x{}", hex::encode(raw.forwarder)),
+                "call_data": format!("0x{}", hex::encode(&raw.call_data)),
+                "expected_output": format!("0x{}", hex::encode(&raw.expected_output)),
+            }),
+        };
+        json["action"] = self.op.name().into();
+        json["deletion_criterion"] = self.deletion_criterion.as_str().into();
+        json
+    }
+}
+
+impl ProvenActions {
+    /// Join proven actions into those of one transaction, in order
+    ///
+    /// Each action keeps its own compliance units; the rcvs are concatenated so a
+    /// single delta proof covers them all.
+    pub fn merge(parts: impl IntoIterator<Item = ProvenActions>) -> Self {
+        let mut merged = ProvenActions { actions: vec![], rcvs: vec![] };
+        for part in parts {
+            merged.actions.extend(part.actions);
+            merged.rcvs.extend(part.rcvs);
+        }
+        merged
+    }
+}
+
+/// Check a batch before any proving: 1 to [`MAX_BATCH_ENTRIES`] entries with distinct nonces
+pub fn check_batch(entries: &[BatchEntry]) -> Result<()> {
+    if entries.is_empty() || entries.len() > MAX_BATCH_ENTRIES {
+        return Err(anyhow!("A batch takes 1 to {} entries, got {}", MAX_BATCH_ENTRIES, entries.len()));
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        if entries[..i].iter().any(|earlier| earlier.nonce == entry.nonce) {
+            return Err(anyhow!("Batch entry {} reuses the nonce of an earlier entry", i));
+        }
+    }
+    Ok(())
+}
+
+/// Prove every entry of a batch as its own action, in order
+///
+/// The result is balanced with one delta proof over every entry's rcv.
+pub fn prove_batch_actions(config: &Config, entries: &[BatchEntry], mode: ProofMode) -> Result<ProvenActions> {
+    check_batch(entries)?;
+    info!("Building batch transaction: {} entries ({})",
+          entries.len(), entries.iter().map(|entry| entry.op.name()).collect::<Vec<_>>().join(", "));
+
+    let parts = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let (deletion_criterion, nonce) = (entry.deletion_criterion, entry.nonce);
+            let proven = match &entry.op {
+                BatchOp::Shield { token, amount, sender, permit } => {
+                    prove_shield_actions(config, token, *amount, sender, deletion_criterion, *permit, nonce, mode)
+                }
+                BatchOp::Unshield { token, amount, recipient } => {
+                    prove_unshield_actions(config, token, *amount, recipient, deletion_criterion, nonce, mode)
+                }
+                BatchOp::Swap(order) => prove_swap_actions(config, order, deletion_criterion, nonce, mode),
+                BatchOp::Raw(raw) => prove_raw_actions(raw, deletion_criterion, nonce, mode),
+            };
+            proven.map_err(|e| anyhow!("Batch entry {} ({}): {}", i, entry.op.name(), e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ProvenActions::merge(parts))
+}
+
+/// Build a balanced ephemeral test transaction with a fresh nonce
+pub fn build_ephemeral_transaction() -> Result<Transaction> {
+    prove_ephemeral_actions(fresh_nonce(), ProofMode::Groth16)?.balance()
+}
+
+/// Build a balanced shield transaction (transferFrom sender to the token forwarder)
+pub fn build_shield_transaction(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    sender: &str,
+    deletion_criterion: DeletionCriterion,
+    permit: Option<PermitData>,
+    nonce: Nonce,
+) -> Result<Transaction> {
+    let op = BatchOp::Shield { token: token.to_string(), amount, sender: sender.to_string(), permit };
+    build_batch_transaction(config, &[BatchEntry { op, deletion_criterion, nonce }])
+}
+
+/// Build a balanced unshield transaction (transfer from the token forwarder to recipient)
+pub fn build_unshield_transaction(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    recipient: &str,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+) -> Result<Transaction> {
+    let op = BatchOp::Unshield { token: token.to_string(), amount, recipient: recipient.to_string() };
+    build_batch_transaction(config, &[BatchEntry { op, deletion_criterion, nonce }])
+}
+
+/// Build a balanced swap transaction (exactInputSingle through the Uniswap forwarder)
+pub fn build_swap_transaction(
+    config: &Config,
+    order: &SwapOrder,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+) -> Result<Transaction> {
+    build_batch_transaction(config, &[BatchEntry { op: BatchOp::Swap(order.clone()), deletion_criterion, nonce }])
+}
+
+/// Build a balanced transaction sending a raw forwarder call
+pub fn build_raw_transaction(raw: &RawCall, deletion_criterion: DeletionCriterion, nonce: Nonce) -> Result<Transaction> {
+    prove_raw_actions(raw, deletion_criterion, nonce, ProofMode::Groth16)?.balance()
+}
+
+/// Build a balanced transaction with one action per batch entry
+///
+/// The single-operation builders above are the one-entry case.
+pub fn build_batch_transaction(config: &Config, entries: &[BatchEntry]) -> Result<Transaction> {
+    prove_batch_actions(config, entries, ProofMode::Groth16)?.balance()
+}
+
+/// Verified execute calldata for a fresh ephemeral test transaction
+pub fn ephemeral_calldata() -> Result<Vec<u8>> {
+    verified_calldata(build_ephemeral_transaction()?)
+}
+
+/// Verified execute calldata for a shield transaction
+pub fn shield_calldata(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    sender: &str,
+    deletion_criterion: DeletionCriterion,
+    permit: Option<PermitData>,
+    nonce: Nonce,
+) -> Result<Vec<u8>> {
+    verified_calldata(build_shield_transaction(config, token, amount, sender, deletion_criterion, permit, nonce)?)
+}
+
+/// Verified execute calldata for an unshield transaction
+pub fn unshield_calldata(
+    config: &Config,
+    token: &str,
+    amount: u128,
+    recipient: &str,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+) -> Result<Vec<u8>> {
+    verified_calldata(build_unshield_transaction(config, token, amount, recipient, deletion_criterion, nonce)?)
+}
+
+/// Verified execute calldata for a swap transaction
+pub fn swap_calldata(
+    config: &Config,
+    order: &SwapOrder,
+    deletion_criterion: DeletionCriterion,
+    nonce: Nonce,
+) -> Result<Vec<u8>> {
+    verified_calldata(build_swap_transaction(config, order, deletion_criterion, nonce)?)
+}
+
+/// Verified execute calldata for a batch transaction
+///
+/// Verification checks the delta proof against the sum of every unit's value
+/// commitment, so it fails unless the batch as a whole balances to zero.
+pub fn batch_calldata(config: &Config, entries: &[BatchEntry]) -> Result<Vec<u8>> {
+    verified_calldata(build_batch_transaction(config, entries)?)
+}
+
+/// Delta-prove actions proven elsewhere and verify them as one transaction
+///
+/// Each part is a [`ProvenActions`] as serialized by another prover (or a
+/// `local-prove` pending delta file). The parts are joined in order with
+/// [`ProvenActions::merge`], so every part lists its rcvs in the order of its
+/// compliance units. Only the delta proof is generated here; the compliance
+/// and logic proofs are checked by the final verification.
+pub fn delta_calldata(parts: Vec<ProvenActions>) -> Result<Vec<u8>> {
+    let proven = ProvenActions::merge(parts);
+    if let Some(i) = proven.rcvs.iter().position(|rcv| rcv.len() != 32) {
+        return Err(anyhow!("rcv {} must be 32 bytes, got {}", i, proven.rcvs[i].len()));
+    }
+    if proven.actions.is_empty() {
+        return Err(anyhow!("No proven actions to balance"));
+    }
+    verified_calldata(proven.balance()?)
+}
+
+/// Verified execute calldata for a raw forwarder call transaction
+pub fn raw_calldata(raw: &RawCall, deletion_criterion: DeletionCriterion, nonce: Nonce) -> Result<Vec<u8>> {
+    verified_calldata(build_raw_transaction(raw, deletion_criterion, nonce)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forwarder_logic_witness::FORWARDER_LOGIC_IMAGE_ID;
+
+    #[test]
+    fn test_verify_rejects_calldata_that_is_not_execute() {
+        assert!(verify_execute_calldata(&[0x12, 0x34]).is_err());
+        let mut calldata = EXECUTE_SELECTOR.to_vec();
+        calldata.extend_from_slice(&[0xff; 31]);
+        assert!(decode_execute_transaction(&calldata).unwrap_err().to_string().contains("decode"));
+    }
+
+    #[test]
+    fn test_logic_verifying_keys_are_image_ids() {
+        let keys = logic_verifying_keys();
+        assert_eq!(keys.forwarder_logic, format!("0x{}", hex::encode(FORWARDER_LOGIC_IMAGE_ID.as_bytes())));
+        assert_eq!(keys.forwarder_logic.len(), 66);
+        assert_eq!(keys.trivial_logic.len(), 66);
+        assert_ne!(keys.forwarder_logic, keys.trivial_logic);
+    }
+
+    #[test]
+    fn test_prove_parallel_keeps_order() {
+        let tasks: Vec<ProveTask<'static, usize>> = (0..5)
+            .map(|i| -> ProveTask<'static, usize> { Box::new(move || Ok(i * 10)) })
+            .collect();
+        assert_eq!(prove_parallel(tasks, 2).unwrap(), [0, 10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_prove_parallel_surfaces_errors() {
+        let tasks: Vec<ProveTask<'static, ()>> = vec![
+            Box::new(|| Ok(())),
+            Box::new(|| Err(anyhow!("Failed to prove created logic"))),
+            Box::new(|| panic!("prover crashed")),
+        ];
+        let err = prove_parallel(tasks, 3).unwrap_err();
+        assert!(err.to_string().contains("created logic"));
+
+        let tasks: Vec<ProveTask<'static, ()>> = vec![Box::new(|| panic!("prover crashed"))];
+        assert!(prove_parallel(tasks, 1).unwrap_err().to_string().contains("panicked"));
+    }
+
+    #[test]
+    fn test_persistent_unshield_needs_the_owner_and_the_commitment() {
+        let config = Config::default();
+        let owner = NullifierKey::from_bytes(&[7u8; 32]);
+        let forwarder = get_forwarder_address(&config, "USDC").unwrap();
+        let note = ShieldedNote::new("USDC", Resource {
+            logic_ref: ForwarderLogicWitness::verifying_key(),
+            label_ref: token_label(forwarder),
+            nk_commitment: owner.commit(),
+            quantity: 5,
+            is_ephemeral: false,
+            ..Default::default()
+        });
+
+        let mut tree = CommitmentTree::new();
+        let err = locate_note(&config, &note, &owner, &tree).unwrap_err();
+        assert!(err.to_string().contains("not in the local commitment tree"), "{}", err);
+
+        tree.push(B256::repeat_byte(0x01));
+        tree.push(note.commitment.parse().unwrap());
+        assert!(locate_note(&config, &note, &owner, &tree).is_ok());
+
+        let stranger = NullifierKey::from_bytes(&[8u8; 32]);
+        assert!(locate_note(&config, &note, &stranger, &tree).unwrap_err().to_string().contains("does not own"));
+        let relabelled = ShieldedNote { token: "WETH".to_string(), ..note.clone() };
+        assert!(locate_note(&config, &relabelled, &owner, &tree).is_err());
+    }
+
+    #[test]
+    fn test_batch_entries_are_checked_and_merged_in_order() {
+        let shield = BatchOp::Shield {
+            token: "usdc".to_string(),
+            amount: 5,
+            sender: "0x000000000000000000000000000000000000dEaD".to_string(),
+            permit: None,
+        };
+        let entry = BatchEntry::new(shield, DeletionCriterion::Never);
+        let described = entry.describe();
+        assert_eq!(described["action"], "shield");
+        assert_eq!(described["token"], "USDC");
+        assert_eq!(described["amount"], "5");
+        assert!(described.get("nonce").is_none());
+
+        assert!(check_batch(&[entry.clone()]).is_ok());
+        assert!(check_batch(&[]).is_err());
+        assert!(check_batch(&vec![BatchEntry::new(entry.op.clone(), DeletionCriterion::Never); MAX_BATCH_ENTRIES + 1]).is_err());
+        let err = check_batch(&[entry.clone(), entry.clone()]).unwrap_err();
+        assert!(err.to_string().contains("reuses the nonce"), "{}", err);
+
+        let part = |rcv: u8| ProvenActions { actions: vec![], rcvs: vec![vec![rcv; 32]] };
+        let merged = ProvenActions::merge([part(1), part(2), part(3)]);
+        assert_eq!(merged.rcvs, vec![vec![1; 32], vec![2; 32], vec![3; 32]]);
+    }
+
+    #[test]
+    fn test_delta_needs_actions_and_32_byte_rcvs() {
+        assert!(delta_calldata(vec![]).is_err());
+        let empty = ProvenActions { actions: vec![], rcvs: vec![vec![0; 32]] };
+        assert!(delta_calldata(vec![empty]).unwrap_err().to_string().contains("No proven actions"));
+
+        // rcvs are numbered across the merged parts
+        let part = |len: usize| ProvenActions { actions: vec![], rcvs: vec![vec![0; len]] };
+        let err = delta_calldata(vec![part(32), part(31)]).unwrap_err();
+        assert!(err.to_string().contains("rcv 1 must be 32 bytes, got 31"));
+    }
+
+    fn usdc_to_weth() -> SwapOrder {
+        SwapOrder {
+            token_in: "USDC".to_string(),
+            amount_in: 1_000_000,
+            token_out: "weth".to_string(),
+            min_amount_out: 250_000_000_000_000,
+            fee: DEFAULT_SWAP_FEE,
+        }
+    }
+
+    #[test]
+    fn test_swap_params_and_balance() {
+        let config = Config::default();
+        let order = usdc_to_weth();
+        let params = order.swap_params(&config).unwrap();
+        let weth = config.tokens.get("WETH").unwrap();
+        assert_eq!(params.token_out, parse_address(&weth.erc20_address).unwrap());
+        assert_eq!(params.recipient, parse_address(&weth.forwarder_address).unwrap());
+        assert_eq!(params.amount_out_minimum, order.min_amount_out);
+
+        let report = swap_balance(&config, &order, &params).unwrap();
+        assert!(report.balanced);
+        assert_eq!(report.labels.len(), 2);
+
+        // A call that buys less than the output resource claims does not balance
+        let short = SwapParams { amount_out_minimum: order.min_amount_out - 1, ..params };
+        let report = swap_balance(&config, &order, &short).unwrap();
+        assert!(!report.balanced);
+        assert_eq!(report.labels[1].net, "-1");
+    }
+
+    #[test]
+    fn test_swap_rejects_same_or_unknown_token() {
+        let config = Config::default();
+        let same = SwapOrder { token_out: "usdc".to_string(), ..usdc_to_weth() };
+        assert!(same.swap_params(&config).unwrap_err().to_string().contains("itself"));
+        let unknown = SwapOrder { token_out: "DAI".to_string(), ..usdc_to_weth() };
+        assert!(unknown.swap_params(&config).unwrap_err().to_string().contains("Unknown token"));
+        let fee = SwapOrder { fee: 1 << 24, ..usdc_to_weth() };
+        assert!(fee.swap_params(&config).unwrap_err().to_string().contains("uint24"));
+    }
+
+    #[test]
+    fn test_shield_file_name_keys_on_permit() {
+        let permit = PermitData { value: 1, deadline: 1, v: 27, r: [0xab; 32], s: [0; 32] };
+        assert_eq!(shield_proof_file_name("USDC", 5, DeletionCriterion::Never, None), "shield_usdc_5.bin");
+        assert_eq!(
+            shield_proof_file_name("USDC", 5, DeletionCriterion::Immediately, Some(&permit)),
+            "shield_usdc_5_pruned_permit_abababab.bin"
+        );
+    }
+
+    #[test]
+    fn test_raw_call_parse_and_file_name() {
+        let forwarder = "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE";
+        let raw = RawCall::parse(forwarder, "0xa9059cbb00", "").unwrap();
+        assert_eq!(raw.call_data, [0xa9, 0x05, 0x9c, 0xbb, 0x00]);
+        assert!(raw.expected_output.is_empty());
+
+        let name = raw.proof_file_name(DeletionCriterion::Never);
+        assert!(name.starts_with("raw_") && name.ends_with(".bin"));
+        let other = RawCall { expected_output: vec![1], ..raw.clone() };
+        assert_ne!(other.proof_file_name(DeletionCriterion::Never), name);
+
+        assert!(RawCall::parse(forwarder, "0xa905", "").unwrap_err().to_string().contains("selector"));
+        assert!(RawCall::parse(forwarder, "0xa9059cbb", "0xzz").unwrap_err().to_string().contains("expected_output"));
+        assert!(RawCall::parse("0x1234", "0xa9059cbb", "").is_err());
+    }
+
+    #[test]
+    fn test_parse_checksummed_address() {
+        let checksummed = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let bytes = parse_checksummed_address(checksummed).unwrap();
+        assert_eq!(parse_checksummed_address(&checksummed.to_lowercase()).unwrap(), bytes);
+        assert_eq!(parse_checksummed_address(&format!("0x{}", checksummed[2..].to_uppercase())).unwrap(), bytes);
+
+        // One character flipped to lowercase
+        let err = parse_checksummed_address("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap_err().to_string();
+        assert!(err.contains("EIP-55") && err.contains(checksummed), "{}", err);
+    }
+
+    #[test]
+    fn test_nullifier_keypair_commits_to_key() {
+        let (key, commitment) = generate_nullifier_keypair();
+        assert_eq!(hex::decode(&key).unwrap().len(), 32);
+        let nf_key = NullifierKey::from_bytes(&hex::decode(&key).unwrap());
+        assert_eq!(hex::encode(nf_key.commit().inner()), commitment);
+        assert_ne!(generate_nullifier_keypair().0, key);
+
+        assert!(parse_nullifier_key(&format!("0x{}", key)).is_ok());
+        assert!(parse_nullifier_key(&key[..62]).unwrap_err().to_string().contains("must be 32 bytes"));
+        assert!(parse_nullifier_key("not hex").is_err());
+    }
+
+    #[test]
+    fn test_proof_mode() {
+        assert_eq!("Groth16".parse::<ProofMode>().unwrap(), ProofMode::default());
+        assert_eq!("fake".parse::<ProofMode>().unwrap().as_str(), "fake");
+        assert!("stark".parse::<ProofMode>().unwrap_err().contains("Supported"));
+        assert!(ProofMode::Groth16.verifies_on_chain());
+        assert!(!ProofMode::Succinct.verifies_on_chain());
+    }
+
+    #[test]
+    fn test_nonces() {
+        assert_ne!(fresh_nonce(), fresh_nonce());
+        assert_eq!(parse_nonce(&format!("0x{}", "ab".repeat(32))).unwrap(), [0xab; 32]);
+        assert!(parse_nonce("0x1234").unwrap_err().to_string().contains("32 bytes"));
+        assert!(parse_nonce("zz").is_err());
+    }
+}
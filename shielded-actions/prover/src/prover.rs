@@ -1,11 +1,40 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Mutex;
-use tracing::{info, warn};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, instrument, warn, Span};
+use utoipa::ToSchema;
+
+use crate::allowlist::{allow_list_from_env, check_recipient, RecipientAllowList};
+use crate::amount::{ensure_permit_supported, ensure_shieldable};
+use crate::backend::{
+    BatchRequest, BonsaiBackend, LocalArmBackend, MockBackend, ProofBackend, RawRequest, ShieldRequest, SwapRequest, UnshieldRequest,
+};
+use crate::chain;
+use crate::config::Config;
+use crate::error::ProverError;
+use crate::estimate::{self, Estimate, ProvingTimes};
+use crate::job::JobContext;
+use crate::nullifiers::NullifierSet;
+use crate::proofcache::{CacheStats, ProofCache};
+use crate::proofs::{
+    batch_calldata, check_batch, delta_calldata, ephemeral_calldata, forwarder_logic_vk, fresh_nonce, proof_file_name, raw_calldata, shield_calldata,
+    shield_proof_file_name, swap_calldata, swap_proof_file_name, trivial_logic_vk, unshield_calldata, BatchEntry, BatchOp, ProvenActions,
+    RawCall, SwapOrder,
+};
+use crate::signature::PermitRequest;
+use forwarder_logic_witness::{DeletionCriterion, PermitData, FORWARDER_LOGIC_ELF, FORWARDER_LOGIC_IMAGE_ID};
 
 // For proof ID generation
-use sha2::{Sha256, Digest};
+use sha2::{Digest as _, Sha256};
+
+// Fake receipts for mock mode
+use risc0_zkvm::sha::{Digest, Digestible};
+use risc0_zkvm::{FakeReceipt, ReceiptClaim};
+
+/// Verifier selector used by RISC Zero for seals of fake receipts
+const FAKE_RECEIPT_SELECTOR: [u8; 4] = [0xff; 4];
 
 /// Get Docker binary path, checking common locations on macOS/Linux
 fn get_docker_path() -> Option<String> {
@@ -49,6 +78,135 @@ fn is_docker_available() -> bool {
     }
 }
 
+/// is_docker_available off the runtime: `docker info` can take seconds
+async fn docker_available() -> bool {
+    tokio::task::spawn_blocking(is_docker_available).await.unwrap_or(false)
+}
+
+/// RISC Zero Groth16 prover image pulled by the Docker pre-warm
+pub const DEFAULT_GROTH16_PROVER_IMAGE: &str = "risczero/risc0-groth16-prover:v2025-04-03.1";
+
+/// Whether PREWARM_DOCKER asks for the Docker pre-warm at startup
+pub fn prewarm_docker_from_env() -> bool {
+    std::env::var("PREWARM_DOCKER")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Make sure Docker is running and the Groth16 prover image is pulled
+///
+/// Without this the first real proof pays for the image pull and usually
+/// times out. Override the image with GROTH16_PROVER_IMAGE.
+pub fn prewarm_docker() -> Result<Duration> {
+    let start = Instant::now();
+    let docker = get_docker_path().ok_or_else(|| anyhow!("Docker binary not found"))?;
+    if !is_docker_available() {
+        return Err(anyhow!("Docker is installed but not running"));
+    }
+
+    let image = std::env::var("GROTH16_PROVER_IMAGE")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| DEFAULT_GROTH16_PROVER_IMAGE.to_string());
+    info!("Pulling Groth16 prover image {}...", image);
+
+    let output = std::process::Command::new(&docker)
+        .args(["pull", &image])
+        .env("PATH", get_path_with_docker())
+        .output()
+        .map_err(|e| anyhow!("Failed to run docker pull: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "docker pull {} failed: {}",
+            image,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(start.elapsed())
+}
+
+/// Default for PROVE_TIMEOUT_SECS (a fresh Groth16 proof takes ~7 minutes)
+pub const DEFAULT_PROVE_TIMEOUT_SECS: u64 = 900;
+
+/// A unique path to write one generated proof to before it is cached
+///
+/// Concurrent jobs for the same proof would otherwise write the same file at
+/// once. The file is removed on drop unless it was published.
+struct ScratchFile {
+    path: String,
+}
+
+impl ScratchFile {
+    fn for_proof(proof_file: &str) -> Self {
+        let suffix: [u8; 8] = rand::random();
+        Self { path: format!("{}.{}.tmp", proof_file, hex::encode(suffix)) }
+    }
+
+    /// Move the finished proof to `proof_file`, where later requests find it cached
+    fn publish(self, proof_file: &str) {
+        if let Err(e) = std::fs::rename(&self.path, proof_file) {
+            warn!("Failed to move {} to {}: {}", self.path, proof_file, e);
+        }
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Save generated calldata as `proof_file`, for [`ProverService::load_cached_proof`]
+fn cache_proof(proof_file: &str, calldata: &[u8]) {
+    let scratch = ScratchFile::for_proof(proof_file);
+    match std::fs::write(&scratch.path, calldata) {
+        Ok(()) => scratch.publish(proof_file),
+        Err(e) => warn!("Failed to cache proof as {}: {}", proof_file, e),
+    }
+}
+
+/// Run a blocking proof builder (see [`crate::proofs`]) on the blocking thread pool
+///
+/// Proving takes minutes, so it must not hold a runtime worker. An in-process
+/// prover cannot be interrupted: once the job is cancelled or `timeout` passes,
+/// the job fails straight away and the builder's result is discarded when it
+/// eventually finishes. Every in-process backend proves through here, custom
+/// [`ProofBackend`]s included.
+pub async fn run_prover<F>(prove: F, timeout: Duration, job: Option<&JobContext>) -> Result<Vec<u8>, ProverError>
+where
+    F: FnOnce() -> Result<Vec<u8>> + Send + 'static,
+{
+    if let Some(job) = job {
+        job.progress.set("Generating ZK proofs...");
+    }
+    let cancelled = async {
+        match job {
+            Some(job) => job.cancel.cancelled().await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let result = tokio::select! {
+        joined = tokio::time::timeout(timeout, tokio::task::spawn_blocking(in_current_span(prove))) => match joined {
+            Ok(Ok(result)) => result.map_err(ProverError::ProvingFailed),
+            Ok(Err(e)) => Err(ProverError::ProvingFailed(anyhow!("Proving task failed: {}", e))),
+            Err(_) => Err(ProverError::Timeout(format!("Proof generation timed out after {} seconds", timeout.as_secs()))),
+        },
+        _ = cancelled => Err(ProverError::ProvingFailed(anyhow!("Proof cancelled"))),
+    };
+    if let (Some(job), Err(e)) = (job, &result) {
+        job.log.push(format!("[error] {}", e));
+    }
+    result
+}
+
+/// Run `f` in the caller's tracing span, so logs from the blocking pool keep the job and proof ids
+fn in_current_span<T>(f: impl FnOnce() -> T) -> impl FnOnce() -> T {
+    let span = Span::current();
+    move || span.in_scope(f)
+}
+
 /// Get PATH with Docker added
 fn get_path_with_docker() -> String {
     let current_path = std::env::var("PATH").unwrap_or_default();
@@ -69,61 +227,241 @@ fn get_path_with_docker() -> String {
     new_path
 }
 
-/// Convert human-readable token amount to smallest units
-/// USDC has 6 decimals, WETH has 18 decimals
-fn parse_token_amount(amount: &str, token: &str) -> Result<u128> {
-    let decimals: u32 = match token.to_uppercase().as_str() {
-        "USDC" => 6,
-        "WETH" => 18,
-        _ => 18, // Default to 18 decimals
-    };
-
-    // Try parsing as u128 first (already in smallest units)
-    if let Ok(val) = amount.parse::<u128>() {
-        // If it's a large number, assume it's already in smallest units
-        if val > 1_000_000_000 {
-            return Ok(val);
-        }
+/// Token symbol of a request resource: its `token` field, or else its hex `label_ref`
+///
+/// There is no default token; a resource naming neither is rejected.
+pub fn resource_token(resource: &serde_json::Value) -> Result<String> {
+    if let Some(token) = resource.get("token").and_then(|v| v.as_str()) {
+        return Ok(token.to_string());
     }
-
-    // Parse as float and convert to smallest units
-    let amount_str = amount.trim();
-
-    // Handle amounts like ".1" by prepending "0"
-    let normalized = if amount_str.starts_with('.') {
-        format!("0{}", amount_str)
-    } else {
-        amount_str.to_string()
-    };
-
-    let float_val: f64 = normalized.parse()
-        .map_err(|e| anyhow!("Invalid amount '{}': {}", amount, e))?;
-
-    // Convert to smallest units
-    let multiplier = 10u128.pow(decimals);
-    let smallest_units = (float_val * multiplier as f64).round() as u128;
-
-    info!("Parsed amount '{}' for {} -> {} smallest units ({} decimals)",
-          amount, token, smallest_units, decimals);
-
-    Ok(smallest_units)
+    let label_ref = resource.get("label_ref")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Resource names no token (expected 'token' or 'label_ref')"))?;
+    let bytes = hex::decode(label_ref.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Invalid resource label_ref: {}", e))?;
+    String::from_utf8(bytes).map_err(|_| anyhow!("Resource label_ref is not a token symbol"))
 }
 
 /// Proof response structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ProofResponse {
     pub proof_id: String,
     pub status: String,
     pub proof: Option<ProofData>,
     /// Full calldata with function selector for on-chain execution
     pub calldata: Option<String>,
+    /// Decoded operations the transaction performs, for display by clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Vec<OperationSummary>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ProofResponse {
+    /// Commitment of the resource created by the transaction's first compliance unit
+    ///
+    /// Read from the calldata, so None for mock and Bonsai proofs.
+    pub fn commitment(&self) -> Option<String> {
+        self.first_unit_tags().map(|(_, commitment)| commitment)
+    }
+
+    /// Nullifier of the resource consumed by the transaction's first compliance unit
+    pub fn nullifier(&self) -> Option<String> {
+        self.first_unit_tags().map(|(nullifier, _)| nullifier)
+    }
+
+    /// (nullifier, commitment) of the first compliance unit in the calldata
+    fn first_unit_tags(&self) -> Option<(String, String)> {
+        let tx = chain::decode_execute_calldata(self.calldata.as_deref()?).ok()?;
+        let unit = tx.actions.first()?.complianceVerifierInputs.first()?;
+        Some((unit.instance.consumed.nullifier.to_string(), unit.instance.created.commitment.to_string()))
+    }
+}
+
+/// One token movement performed by a proven transaction
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OperationSummary {
+    /// "shield", "unshield" or "swap"
+    pub op: String,
+    /// Token moved (the token sold, for a swap)
+    pub token: String,
+    /// Amount in the token's smallest units (decimal string, as it may exceed 2^53)
+    pub amount: String,
+    /// Sender for shield, recipient for unshield, the bought token's forwarder for swap
+    pub counterparty: String,
+    pub forwarder: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ProofData {
     pub journal: String,
     pub seal: String,
     pub image_id: String,
+    /// True when the seal comes from a RISC Zero FakeReceipt and will not verify
+    #[serde(default)]
+    pub fake: bool,
+}
+
+/// Polling and retry schedule for Bonsai sessions
+#[derive(Debug, Clone, Copy)]
+pub struct BonsaiPollConfig {
+    /// Longest delay between status checks or retries (BONSAI_POLL_INTERVAL_MS)
+    pub max_interval: Duration,
+    /// Give up waiting after this long (BONSAI_MAX_WAIT_SECS)
+    pub max_wait: Duration,
+    /// Tries per Bonsai API call before a transient error fails the proof (BONSAI_MAX_ATTEMPTS)
+    pub max_attempts: u32,
+}
+
+impl Default for BonsaiPollConfig {
+    fn default() -> Self {
+        Self {
+            max_interval: Duration::from_millis(10_000),
+            max_wait: Duration::from_secs(1_800),
+            max_attempts: 5,
+        }
+    }
+}
+
+impl BonsaiPollConfig {
+    /// Delay before the first status check; doubled after each check
+    const INITIAL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_u64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_interval: env_u64("BONSAI_POLL_INTERVAL_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_interval),
+            max_wait: env_u64("BONSAI_MAX_WAIT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(default.max_wait),
+            max_attempts: env_u64("BONSAI_MAX_ATTEMPTS")
+                .map(|n| n.clamp(1, u32::MAX as u64) as u32)
+                .unwrap_or(default.max_attempts),
+        }
+    }
+
+    /// Delay before status check number `attempt` (zero-based)
+    pub fn interval(&self, attempt: u32) -> Duration {
+        Self::INITIAL_INTERVAL
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_interval)
+    }
+
+    /// Delay before retry number `attempt` (zero-based) of a failed Bonsai call
+    ///
+    /// Half the backoff interval plus a random part of the other half, so jobs
+    /// failing together do not retry in lockstep.
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        let interval = self.interval(attempt);
+        interval / 2 + interval.mul_f64(rand::random::<f64>() / 2.0)
+    }
+}
+
+/// Build the Bonsai client shared by all requests
+///
+/// The client reports the `risc0_zkvm` version this binary is built with.
+/// When Bonsai is reachable and does not list that version, startup fails
+/// here instead of at every proof; an unreachable Bonsai only logs a warning.
+/// The blocking client runs a runtime of its own, which cannot be started from
+/// within the server's, so it is built on a thread of its own.
+fn bonsai_client(api_url: String, api_key: String) -> Result<bonsai_sdk::blocking::Client> {
+    std::thread::spawn(move || {
+        let client = bonsai_sdk::blocking::Client::from_parts(api_url, api_key, risc0_zkvm::VERSION)
+            .map_err(|e| anyhow!("Failed to create the Bonsai client: {}", e))?;
+        match client.version() {
+            Ok(info) if !bonsai_supports_version(&info.risc0_zkvm, risc0_zkvm::VERSION) => Err(anyhow!(
+                "Bonsai does not support risc0-zkvm {} (supported: {}); build against a supported version",
+                risc0_zkvm::VERSION, info.risc0_zkvm.join(", ")
+            )),
+            Ok(_) => Ok(client),
+            Err(e) => {
+                warn!("Could not check Bonsai's supported risc0-zkvm versions: {}", e);
+                Ok(client)
+            }
+        }
+    })
+    .join()
+    .map_err(|_| anyhow!("Bonsai client setup panicked"))?
+}
+
+/// Whether `version` is among Bonsai's supported versions, which may list only major.minor
+fn bonsai_supports_version(supported: &[String], version: &str) -> bool {
+    let major_minor = |v: &str| v.split('.').take(2).collect::<Vec<_>>().join(".");
+    supported.iter().any(|v| v == version || major_minor(v) == major_minor(version))
+}
+
+/// Whether a failed Bonsai API call may succeed if repeated
+///
+/// Connection failures, timeouts, 5xx and 429 are transient. The SDK reports
+/// any other unsuccessful response as `InternalServerErr` with only its body,
+/// so those are retried unless the body names a client error. Missing
+/// credentials and local file errors are permanent.
+pub fn bonsai_error_is_retryable(e: &bonsai_sdk::SdkErr) -> bool {
+    use bonsai_sdk::SdkErr;
+
+    match e {
+        SdkErr::HttpErr(e) => match e.status() {
+            Some(status) => status.is_server_error() || status.as_u16() == 429,
+            None => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+        },
+        SdkErr::InternalServerErr(body) => {
+            let body = body.to_lowercase();
+            !["unauthorized", "forbidden", "invalid", "not found", "bad request"]
+                .iter()
+                .any(|permanent| body.contains(permanent))
+        }
+        _ => false,
+    }
+}
+
+/// Make a Bonsai API call, retrying transient errors up to `max_attempts` times
+///
+/// Blocks while backing off; only call it inside `with_bonsai_client`. A
+/// retried `create_session` whose response was lost can leave an extra
+/// session behind, which does no harm beyond its cost.
+fn retry_bonsai<T>(
+    poll: &BonsaiPollConfig,
+    what: &str,
+    mut call: impl FnMut() -> Result<T, bonsai_sdk::SdkErr>,
+) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        match call() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt + 1 < poll.max_attempts && bonsai_error_is_retryable(&e) => {
+                let delay = poll.retry_delay(attempt);
+                warn!("Bonsai {} failed (attempt {}/{}), retrying in {:?}: {:#}",
+                      what, attempt + 1, poll.max_attempts, delay, anyhow::Error::new(e));
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(anyhow::Error::new(e).context(format!("Bonsai {} failed after {} attempt(s)", what, attempt + 1)));
+            }
+        }
+    }
+}
+
+/// Whether the configured proving backend can produce proofs, as reported by /health/ready
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BackendStatus {
+    pub ready: bool,
+    pub docker_available: bool,
+    pub bonsai_configured: bool,
+    pub mock_mode: bool,
+}
+
+impl BackendStatus {
+    fn new(use_real_arm: bool, bonsai_configured: bool, docker_available: bool) -> Self {
+        Self {
+            // Only local ARM proving needs Docker; Bonsai and mock proofs do not
+            ready: !use_real_arm || docker_available,
+            docker_available,
+            bonsai_configured,
+            mock_mode: !use_real_arm && !bonsai_configured,
+        }
+    }
 }
 
 /// Session tracking for async proof generation
@@ -132,223 +470,570 @@ struct ProofSession {
     session_id: String,
     status: String,
     proof: Option<ProofData>,
+    calldata: Option<String>,
+    summary: Option<Vec<OperationSummary>>,
 }
 
 /// Prover service that interfaces with Bonsai
 pub struct ProverService {
-    // Bonsai API configuration
-    bonsai_api_key: Option<String>,
-    bonsai_api_url: String,
+    // Bonsai API client, built once when BONSAI_API_KEY is set
+    bonsai: Option<Arc<bonsai_sdk::blocking::Client>>,
 
     // In-memory proof cache
     proofs: Mutex<HashMap<String, ProofSession>>,
 
-    // Use mock mode if no API key is set
-    mock_mode: bool,
+    // Generates the proofs: local ARM, Bonsai or mock
+    backend: Box<dyn ProofBackend>,
+
+    // Optional unshield recipient allow-list (reloadable at runtime)
+    unshield_allow_list: RwLock<Option<RecipientAllowList>>,
+
+    // Polling schedule for Bonsai sessions
+    bonsai_poll: BonsaiPollConfig,
+
+    // Deployed contract addresses
+    config: Config,
+
+    // Ethereum RPC used to check cached proofs against on-chain state
+    rpc_url: Option<String>,
+
+    // Whether proofs of arbitrary forwarder calldata may be requested
+    raw_calls_enabled: bool,
+
+    // Longest a proof may take before its job fails
+    prove_timeout: Duration,
 
-    // Use real ARM proving (requires Docker for Groth16)
-    use_real_arm: bool,
+    // Responses of earlier requests with the same parameters
+    cache: ProofCache,
+
+    // Nullifiers of every real proof handed out
+    nullifiers: NullifierSet,
+
+    // Durations of recent proofs, for proving time estimates
+    proving_times: ProvingTimes,
 }
 
 impl ProverService {
-    pub fn new() -> Result<Self> {
-        let bonsai_api_key = std::env::var("BONSAI_API_KEY").ok();
+    pub fn new(config: Config) -> Result<Self> {
         let bonsai_api_url = std::env::var("BONSAI_API_URL")
             .unwrap_or_else(|_| "https://api.bonsai.xyz".to_string());
+        let bonsai = std::env::var("BONSAI_API_KEY")
+            .ok()
+            .map(|api_key| bonsai_client(bonsai_api_url.clone(), api_key).map(Arc::new))
+            .transpose()?;
 
         // USE_REAL_ARM=1 enables real ARM-RISC0 proving (requires Docker)
         let use_real_arm = std::env::var("USE_REAL_ARM")
             .map(|v| v == "1" || v.to_lowercase() == "true")
             .unwrap_or(false);
 
-        let mock_mode = bonsai_api_key.is_none() && !use_real_arm;
+        let unshield_allow_list = allow_list_from_env()?;
+        if let Some(list) = &unshield_allow_list {
+            info!("Unshield recipients restricted to {} allow-listed address(es)", list.len());
+        }
 
-        if use_real_arm {
+        let backend: Box<dyn ProofBackend> = if use_real_arm {
             info!("Real ARM-RISC0 proving enabled (requires Docker for Groth16)");
-        } else if mock_mode {
-            warn!("Running in mock mode. Set USE_REAL_ARM=1 for real proofs or BONSAI_API_KEY for Bonsai");
-        } else {
+            Box::new(LocalArmBackend)
+        } else if bonsai.is_some() {
             info!("Bonsai API configured at {}, real proofs enabled", bonsai_api_url);
-        }
+            Box::new(BonsaiBackend)
+        } else {
+            warn!("Running in mock mode. Set USE_REAL_ARM=1 for real proofs or BONSAI_API_KEY for Bonsai");
+            Box::new(MockBackend)
+        };
 
         Ok(Self {
-            bonsai_api_key,
-            bonsai_api_url,
+            bonsai,
             proofs: Mutex::new(HashMap::new()),
-            mock_mode,
-            use_real_arm,
+            backend,
+            unshield_allow_list: RwLock::new(unshield_allow_list),
+            bonsai_poll: BonsaiPollConfig::from_env(),
+            config,
+            rpc_url: std::env::var("RPC_URL").ok().filter(|url| !url.is_empty()),
+            raw_calls_enabled: std::env::var("ALLOW_RAW_CALLS")
+                .map(|v| v == "1" || v.to_lowercase() == "true")
+                .unwrap_or(false),
+            prove_timeout: Duration::from_secs(
+                std::env::var("PROVE_TIMEOUT_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_PROVE_TIMEOUT_SECS),
+            ),
+            cache: ProofCache::from_env(),
+            nullifiers: NullifierSet::from_env(),
+            proving_times: ProvingTimes::new(),
         })
     }
 
-    /// Create a shield proof
+    /// Replace the proving backend chosen from the environment, e.g. with
+    /// [`MockBackend`] in tests
+    pub fn with_backend(mut self, backend: impl ProofBackend + 'static) -> Self {
+        info!("Using the {} proving backend", backend.name());
+        self.backend = Box::new(backend);
+        self
+    }
+
+    /// Contract addresses proofs are generated for
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Ethereum RPC used to check proofs against on-chain state (RPC_URL)
+    pub fn rpc_url(&self) -> Option<&str> {
+        self.rpc_url.as_deref()
+    }
+
+    /// Whether `/api/prove/raw` is enabled (ALLOW_RAW_CALLS)
+    pub fn raw_calls_enabled(&self) -> bool {
+        self.raw_calls_enabled
+    }
+
+    /// Longest a proof may take before its job fails (PROVE_TIMEOUT_SECS)
+    pub fn prove_timeout(&self) -> Duration {
+        self.prove_timeout
+    }
+
+    /// Proof cache hit and miss counters
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Number of nullifiers handed out in real proofs (see [`NullifierSet`])
+    pub fn nullifier_set_len(&self) -> usize {
+        self.nullifiers.len()
+    }
+
+    /// Whether proofs are generated locally with ARM (and so need Docker)
+    pub fn uses_real_arm(&self) -> bool {
+        self.backend.uses_real_arm()
+    }
+
+    /// Expected proving time of `action` on `backend`, the active backend by default
+    pub fn estimate(&self, action: &str, backend: Option<&str>) -> Result<Estimate, ProverError> {
+        let action = estimate::action_name(action).map_err(ProverError::invalid_request)?;
+        let backend = match backend {
+            Some(backend) => estimate::backend_name(backend).map_err(ProverError::invalid_request)?,
+            None => self.backend.name(),
+        };
+        Ok(self.proving_times.estimate(action, backend))
+    }
+
+    /// Probe the proving backend; runs `docker info`, so it is done on the blocking pool
+    pub async fn backend_status(&self) -> BackendStatus {
+        BackendStatus::new(self.uses_real_arm(), self.bonsai.is_some(), docker_available().await)
+    }
+
+    /// Create a shield proof, bundling the sender's permit when given
+    ///
+    /// A cached response for the same parameters is returned unless `force` is set.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(name = "shield_proof", skip_all, fields(token = %token, amount = %amount, proof_id))]
     pub async fn create_shield_proof(
         &self,
         token: &str,
         amount: &str,
         sender: &str,
         nullifier_key: &str,
-    ) -> Result<ProofResponse> {
-        let proof_id = self.generate_proof_id("shield", &[token, amount, sender]);
-
-        // Use real ARM proving with forwarder logic if enabled
-        if self.use_real_arm {
-            // Parse amount, handling both decimal strings like "0.1" and raw u128 values
-            let amount_u128 = parse_token_amount(amount, token)?;
-            return self.create_shield_proof_with_forwarder(proof_id, token, amount_u128, sender);
+        deletion_criterion: DeletionCriterion,
+        permit: Option<PermitData>,
+        force: bool,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let token_info = self.config.tokens.get(token).map_err(ProverError::unknown_token)?;
+        ensure_shieldable(token_info).map_err(ProverError::invalid_request)?;
+        if permit.is_some() {
+            ensure_permit_supported(token_info).map_err(ProverError::invalid_request)?;
         }
-
-        let journal_data = serde_json::json!({
-            "action": "shield",
-            "token": token,
+        let key = ProofCache::key("shield", &serde_json::json!({
+            "token": token_info.symbol,
             "amount": amount,
-            "sender": sender,
-            "nullifier_key_commitment": self.hash_nullifier_key(nullifier_key),
-        });
-
-        if self.mock_mode {
-            return self.create_mock_proof(proof_id, "shield", journal_data);
-        }
+            "sender": sender.to_lowercase(),
+            "deletion_criterion": deletion_criterion,
+            "permit": permit.as_ref().map(PermitRequest::from),
+            "nullifier_key_commitment": self.hash_nullifier_key(nullifier_key)?,
+        }));
+        self.cached("shield", &key, force, |proof_id| self.generate_shield_proof(proof_id, token, amount, sender, nullifier_key, deletion_criterion, permit, job))
+            .await
+    }
 
-        self.submit_bonsai_proof(proof_id, journal_data).await
+    #[allow(clippy::too_many_arguments)]
+    async fn generate_shield_proof(
+        &self,
+        proof_id: String,
+        token: &str,
+        amount: &str,
+        sender: &str,
+        nullifier_key: &str,
+        deletion_criterion: DeletionCriterion,
+        permit: Option<PermitData>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let req = ShieldRequest { proof_id, token, amount, sender, nullifier_key, deletion_criterion, permit };
+        self.backend.prove_shield(self, req, job).await
     }
 
     /// Create a swap proof
+    ///
+    /// A cached response for the same parameters is returned unless `force` is set.
+    #[instrument(name = "swap_proof", skip_all, fields(output_token = %output_token, proof_id))]
     pub async fn create_swap_proof(
         &self,
         input_resource: &serde_json::Value,
         output_token: &str,
         nullifier_key: &str,
         min_amount_out: &str,
-    ) -> Result<ProofResponse> {
-        let proof_id = self.generate_proof_id("swap", &[output_token, min_amount_out]);
-
-        // Use real ARM proving if enabled
-        if self.use_real_arm {
-            return self.create_real_ephemeral_proof(proof_id);
-        }
-
-        let journal_data = serde_json::json!({
-            "action": "swap",
+        force: bool,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let key = ProofCache::key("swap", &serde_json::json!({
             "input_resource": input_resource,
-            "output_token": output_token,
+            "output_token": output_token.to_uppercase(),
             "min_amount_out": min_amount_out,
-            "nullifier_key_commitment": self.hash_nullifier_key(nullifier_key),
-        });
+            "nullifier_key_commitment": self.hash_nullifier_key(nullifier_key)?,
+        }));
+        self.cached("swap", &key, force, |proof_id| self.generate_swap_proof(proof_id, input_resource, output_token, nullifier_key, min_amount_out, job))
+            .await
+    }
+
+    async fn generate_swap_proof(
+        &self,
+        proof_id: String,
+        input_resource: &serde_json::Value,
+        output_token: &str,
+        nullifier_key: &str,
+        min_amount_out: &str,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let req = SwapRequest { proof_id, input_resource, output_token, nullifier_key, min_amount_out };
+        self.backend.prove_swap(self, req, job).await
+    }
+
+    /// Create a proof of a raw forwarder call
+    ///
+    /// The calldata is not checked; callers are expected to gate this behind
+    /// [`raw_calls_enabled`](Self::raw_calls_enabled). A cached response for
+    /// the same call is returned unless `force` is set.
+    #[instrument(name = "raw_proof", skip_all, fields(proof_id))]
+    pub async fn create_raw_proof(
+        &self,
+        raw: &RawCall,
+        deletion_criterion: DeletionCriterion,
+        force: bool,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let key = ProofCache::key("raw", &serde_json::json!({
+            "forwarder": hex::encode(raw.forwarder),
+            "call_data": hex::encode(&raw.call_data),
+            "expected_output": hex::encode(&raw.expected_output),
+            "deletion_criterion": deletion_criterion,
+        }));
+        self.cached("raw", &key, force, |proof_id| self.generate_raw_proof(proof_id, raw, deletion_criterion, job)).await
+    }
 
-        if self.mock_mode {
-            return self.create_mock_proof(proof_id, "swap", journal_data);
+    async fn generate_raw_proof(
+        &self,
+        proof_id: String,
+        raw: &RawCall,
+        deletion_criterion: DeletionCriterion,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        self.backend.prove_raw(self, RawRequest { proof_id, raw, deletion_criterion }, job).await
+    }
+
+    /// Create one proof for several operations, each its own action of a single transaction
+    ///
+    /// The entries are checked with [`check_batch`] first. A cached response
+    /// for the same entries is returned unless `force` is set.
+    #[instrument(name = "batch_proof", skip_all, fields(entries = entries.len(), proof_id))]
+    pub async fn create_batch_proof(
+        &self,
+        entries: &[BatchEntry],
+        force: bool,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        check_batch(entries).map_err(ProverError::invalid_request)?;
+        for entry in entries {
+            if let BatchOp::Unshield { recipient, .. } = &entry.op {
+                self.check_unshield_recipient(recipient)?;
+            }
         }
+        let key = ProofCache::key("batch", &serde_json::json!({
+            "entries": entries.iter().map(BatchEntry::describe).collect::<Vec<_>>(),
+        }));
+        self.cached("batch", &key, force, |proof_id| self.generate_batch_proof(proof_id, entries, job)).await
+    }
 
-        self.submit_bonsai_proof(proof_id, journal_data).await
+    async fn generate_batch_proof(
+        &self,
+        proof_id: String,
+        entries: &[BatchEntry],
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        self.backend.prove_batch(self, BatchRequest { proof_id, entries }, job).await
+    }
+
+    /// Balance actions proven elsewhere into one transaction's calldata
+    ///
+    /// Only the delta proof is generated, so neither Docker nor a backend is
+    /// involved; see [`delta_calldata`]. Parts that do not balance or verify are
+    /// the client's to fix and fail as invalid requests. Never cached.
+    #[instrument(name = "delta_proof", skip_all, fields(parts = parts.len(), proof_id))]
+    pub async fn create_delta_proof(
+        &self,
+        parts: Vec<ProvenActions>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let num_actions: usize = parts.iter().map(|part| part.actions.len()).sum();
+        let parts_json = serde_json::to_value(&parts).map_err(|e| anyhow!("Failed to encode actions: {}", e))?;
+        let proof_id = self.proof_id(&format!("{}_{}", self.config.fingerprint(), ProofCache::key("delta", &parts_json)));
+        Span::current().record("proof_id", proof_id.as_str());
+        info!("Balancing {} proven action(s) from {} part(s): {}", num_actions, parts.len(), proof_id);
+
+        let started = Instant::now();
+        let calldata = run_prover(move || delta_calldata(parts), self.prove_timeout, job)
+            .await
+            .map_err(|e| match e {
+                ProverError::ProvingFailed(e) => ProverError::invalid_request(e),
+                e => e,
+            })?;
+        info!("Balanced transaction: {} bytes", calldata.len());
+        self.proving_times.record("delta", self.backend.name(), started.elapsed());
+
+        let response = ProofResponse {
+            proof_id,
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal: format!("delta_{}", num_actions),
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: forwarder_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(format!("0x{}", hex::encode(&calldata))),
+            summary: None,
+        };
+        self.record_nullifiers(&response)?;
+        self.remember_proof(&response)?;
+        Ok(response)
     }
 
     /// Create an unshield proof
+    ///
+    /// Recipients not on the unshield allow-list are refused whichever backend
+    /// proves. A cached response for the same parameters is returned unless
+    /// `force` is set.
+    #[instrument(name = "unshield_proof", skip_all, fields(recipient = %recipient, proof_id))]
     pub async fn create_unshield_proof(
         &self,
         resource: &serde_json::Value,
         recipient: &str,
         nullifier_key: &str,
-    ) -> Result<ProofResponse> {
-        let proof_id = self.generate_proof_id("unshield", &[recipient]);
-
-        // Use real ARM proving with forwarder logic if enabled
-        if self.use_real_arm {
-            // Extract token and amount from resource
-            let token = resource.get("token")
-                .and_then(|v| v.as_str())
-                .unwrap_or("USDC");
-            let amount: u128 = resource.get("amount")
-                .and_then(|v| v.as_str())
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(0);
-            return self.create_unshield_proof_with_forwarder(proof_id, token, amount, recipient);
-        }
-
-        let journal_data = serde_json::json!({
-            "action": "unshield",
+        deletion_criterion: DeletionCriterion,
+        force: bool,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        // Checked before the cache and the backend, so no backend proves to a denied recipient
+        self.check_unshield_recipient(recipient)?;
+        let key = ProofCache::key("unshield", &serde_json::json!({
             "resource": resource,
-            "recipient": recipient,
-            "nullifier_key_commitment": self.hash_nullifier_key(nullifier_key),
-        });
+            "recipient": recipient.to_lowercase(),
+            "deletion_criterion": deletion_criterion,
+            "nullifier_key_commitment": self.hash_nullifier_key(nullifier_key)?,
+        }));
+        self.cached("unshield", &key, force, |proof_id| self.generate_unshield_proof(proof_id, resource, recipient, nullifier_key, deletion_criterion, job))
+            .await
+    }
+
+    async fn generate_unshield_proof(
+        &self,
+        proof_id: String,
+        resource: &serde_json::Value,
+        recipient: &str,
+        nullifier_key: &str,
+        deletion_criterion: DeletionCriterion,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let req = UnshieldRequest { proof_id, resource, recipient, nullifier_key, deletion_criterion };
+        self.backend.prove_unshield(self, req, job).await
+    }
+
+    /// Return the cached response for `key`, or else await `generate` with the
+    /// proof id of `key` and cache its result
+    ///
+    /// A cached response whose nullifier is already spent on-chain is dropped
+    /// and regenerated, since its calldata would only revert. A generated proof's
+    /// duration is recorded for [`Self::estimate`], and every response is kept
+    /// for [`Self::get_proof_status`].
+    async fn cached<F, Fut>(&self, action: &str, key: &str, force: bool, generate: F) -> Result<ProofResponse, ProverError>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: std::future::Future<Output = Result<ProofResponse, ProverError>>,
+    {
+        // Proofs only execute against the contracts they were generated for
+        let key = &format!("{}_{}", self.config.fingerprint(), key);
+        let proof_id = self.proof_id(key);
+        Span::current().record("proof_id", proof_id.as_str());
+        if !force {
+            if let Some(response) = self.cache.get(key) {
+                if !self.response_spent(&response).await {
+                    info!("Proof cache hit for {}", response.proof_id);
+                    self.remember_proof(&response)?;
+                    return Ok(response);
+                }
+                self.cache.remove(key);
+            }
+        }
 
-        if self.mock_mode {
-            return self.create_mock_proof(proof_id, "unshield", journal_data);
+        let started = Instant::now();
+        let response = generate(proof_id).await?;
+        self.record_nullifiers(&response)?;
+        if response.status == "completed" {
+            self.proving_times.record(action, self.backend.name(), started.elapsed());
+            self.cache.insert(key, &response);
+            self.remember_proof(&response)?;
         }
+        Ok(response)
+    }
 
-        self.submit_bonsai_proof(proof_id, journal_data).await
+    /// Keep a completed response so [`Self::get_proof_status`] can serve it by id
+    fn remember_proof(&self, response: &ProofResponse) -> Result<(), ProverError> {
+        let mut proofs = self.proofs.lock().map_err(|e| anyhow!("Lock error: {}", e))?;
+        proofs.insert(response.proof_id.clone(), ProofSession {
+            session_id: response.proof_id.clone(),
+            status: response.status.clone(),
+            proof: response.proof.clone(),
+            calldata: response.calldata.clone(),
+            summary: response.summary.clone(),
+        });
+        Ok(())
     }
 
-    /// Get proof status
-    pub async fn get_proof_status(&self, proof_id: &str) -> Result<ProofResponse> {
-        let proofs = self.proofs.lock().map_err(|e| anyhow!("Lock error: {}", e))?;
+    /// Record the nullifiers of a newly produced response, refusing one whose nullifier was handed out before
+    ///
+    /// Pre-generated files already in the set are regenerated by
+    /// [`Self::load_cached_proof`], so this only trips if fresh proving repeats a nullifier.
+    fn record_nullifiers(&self, response: &ProofResponse) -> Result<(), ProverError> {
+        let Some(calldata) = &response.calldata else {
+            return Ok(());
+        };
+        let tx = chain::decode_execute_calldata(calldata).map_err(ProverError::ProvingFailed)?;
+        match self.nullifiers.insert(&chain::extract_nullifiers(&tx)).first() {
+            Some(nullifier) => Err(ProverError::ProvingFailed(anyhow!(
+                "Proof {} reuses nullifier {} of a proof already handed out; request it again with force",
+                response.proof_id, nullifier
+            ))),
+            None => Ok(()),
+        }
+    }
 
-        if let Some(session) = proofs.get(proof_id) {
-            return Ok(ProofResponse {
-                proof_id: proof_id.to_string(),
-                status: session.status.clone(),
-                proof: session.proof.clone(),
-                calldata: None,
-            });
+    /// Whether a response's calldata spends an already spent nullifier (mock and Bonsai responses carry none)
+    async fn response_spent(&self, response: &ProofResponse) -> bool {
+        let Some(calldata) = &response.calldata else {
+            return false;
+        };
+        match hex::decode(calldata.trim_start_matches("0x")) {
+            Ok(calldata) => self.nullifier_spent(&calldata, &format!("cached proof {}", response.proof_id)).await,
+            Err(_) => true,
         }
+    }
 
-        // If not in cache and we have Bonsai configured, check status
-        if !self.mock_mode && !self.use_real_arm {
-            drop(proofs); // Release lock before async call
-            return self.check_bonsai_status(proof_id).await;
+    /// Get proof status
+    ///
+    /// Covers every backend: proofs this service generated or served from its
+    /// cache since it started, and Bonsai sessions still running. Fails with
+    /// NotFound for other proof ids.
+    pub async fn get_proof_status(&self, proof_id: &str) -> Result<ProofResponse, ProverError> {
+        let session = self.proofs.lock().map_err(|e| anyhow!("Lock error: {}", e))?
+            .get(proof_id)
+            .cloned()
+            .ok_or_else(|| ProverError::NotFound(format!("Proof not found: {}", proof_id)))?;
+
+        // Sessions still proving on Bonsai report their live status
+        if session.status == "running" && self.bonsai.is_some() {
+            return Ok(self.check_bonsai_status(proof_id, &session.session_id).await?);
         }
 
-        Err(anyhow!("Proof not found: {}", proof_id))
+        Ok(ProofResponse {
+            proof_id: proof_id.to_string(),
+            status: session.status,
+            proof: session.proof,
+            calldata: session.calldata,
+            summary: session.summary,
+        })
     }
 
-    // Helper functions
+    /// Current unshield allow-list as hex addresses (None when unrestricted)
+    pub fn unshield_allow_list(&self) -> Result<Option<Vec<String>>, ProverError> {
+        let list = self.unshield_allow_list.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(list.as_ref().map(|list| {
+            let mut addrs: Vec<String> = list.iter().map(|a| format!("0x{}", hex::encode(a))).collect();
+            addrs.sort();
+            addrs
+        }))
+    }
 
-    fn generate_proof_id(&self, proof_type: &str, inputs: &[&str]) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(proof_type.as_bytes());
-        for input in inputs {
-            hasher.update(input.as_bytes());
+    /// Reject unshield recipients that are not on the allow-list, when one is set
+    pub fn check_unshield_recipient(&self, recipient: &str) -> Result<(), ProverError> {
+        let list = self.unshield_allow_list.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        check_recipient(list.as_ref(), recipient).map_err(ProverError::invalid_request)
+    }
+
+    /// Replace the unshield allow-list without restarting (None removes the restriction)
+    pub fn set_unshield_allow_list(&self, list: Option<RecipientAllowList>) -> Result<(), ProverError> {
+        let mut current = self.unshield_allow_list.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        match &list {
+            Some(list) => info!("Unshield allow-list updated: {} address(es)", list.len()),
+            None => info!("Unshield allow-list cleared, all recipients allowed"),
         }
-        hasher.update(std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos()
-            .to_le_bytes());
+        *current = list;
+        Ok(())
+    }
 
-        hex::encode(hasher.finalize())[..16].to_string()
+    // Helper functions
+
+    /// Proof id of a deployment-scoped cache key, so identical requests share it
+    fn proof_id(&self, key: &str) -> String {
+        hex::encode(Sha256::digest(key.as_bytes()))[..16].to_string()
     }
 
-    fn hash_nullifier_key(&self, key: &str) -> String {
-        let key_bytes = hex::decode(key.trim_start_matches("0x")).unwrap_or_default();
+    /// SHA-256 of the key bytes, so cache keys and journals never hold the key itself
+    pub(crate) fn hash_nullifier_key(&self, key: &str) -> Result<String, ProverError> {
+        let key_bytes = hex::decode(key.trim_start_matches("0x"))
+            .map_err(|e| ProverError::invalid_request(format!("Invalid nullifier key hex: {}", e)))?;
         let mut hasher = Sha256::new();
         hasher.update(&key_bytes);
-        hex::encode(hasher.finalize())
+        Ok(hex::encode(hasher.finalize()))
     }
 
-    fn create_mock_proof(
+    pub(crate) fn create_mock_proof(
         &self,
         proof_id: String,
         proof_type: &str,
         journal_data: serde_json::Value,
-    ) -> Result<ProofResponse> {
+    ) -> Result<ProofResponse, ProverError> {
         info!("Creating mock {} proof: {}", proof_type, proof_id);
 
         // Generate mock proof data
-        let journal = serde_json::to_string(&journal_data)?;
+        let journal = journal_data.to_string();
         let journal_hex = hex::encode(journal.as_bytes());
 
-        // Mock seal (in real implementation, this would be the ZK proof)
-        let mut seal_hasher = Sha256::new();
-        seal_hasher.update(journal.as_bytes());
-        seal_hasher.update(proof_id.as_bytes());
-        let seal = hex::encode(seal_hasher.finalize());
-
         // Mock image ID (would be the actual guest program ID)
         let image_id = "mock_shielded_actions_guest_v1";
+        let image_digest = Digest::from_bytes(Sha256::digest(image_id.as_bytes()).into());
+
+        // Build a real (but unverifiable) FakeReceipt over the journal so the seal has
+        // the same structure as a real one: 4-byte verifier selector followed by the
+        // proof bytes. Fake receipts use the 0xFFFFFFFF mock verifier selector and the
+        // claim digest as the proof, matching risc0-ethereum's encode_seal.
+        let receipt = FakeReceipt::<ReceiptClaim>::new(ReceiptClaim::ok(image_digest, journal.as_bytes().to_vec()));
+        let mut seal = FAKE_RECEIPT_SELECTOR.to_vec();
+        seal.extend_from_slice(receipt.claim.digest().as_bytes());
 
         let proof_data = ProofData {
             journal: journal_hex,
-            seal,
+            seal: hex::encode(seal),
             image_id: image_id.to_string(),
+            fake: true,
         };
 
         // Store in cache
@@ -357,6 +1042,8 @@ impl ProverService {
             session_id: proof_id.clone(),
             status: "completed".to_string(),
             proof: Some(proof_data.clone()),
+            calldata: None,
+            summary: None,
         });
 
         Ok(ProofResponse {
@@ -364,26 +1051,100 @@ impl ProverService {
             status: "completed".to_string(),
             proof: Some(proof_data),
             calldata: None, // Mock mode doesn't produce real calldata
+            summary: None,
         })
     }
 
-    /// Load a pre-generated proof from disk or generate by calling the local-prove binary
+    /// Read a pre-generated proof, unless its nullifier is already spent on-chain
+    ///
+    /// Every proof gets a fresh nonce, so a spent file is regenerated
+    /// (and overwritten) rather than handed out to revert with PreExistingNullifier.
+    /// So is a file this service already handed out (see [`NullifierSet`]),
+    /// which may yet execute. Without RPC_URL the file is returned unchecked
+    /// on-chain. With `own_forwarders`, a file calling forwarders outside this
+    /// deployment (generated for another network) is regenerated too.
+    async fn load_cached_proof(&self, path: &str, own_forwarders: bool) -> Option<Vec<u8>> {
+        let calldata = std::fs::read(path).ok()?;
+        if own_forwarders {
+            let foreign = chain::decode_execute_bytes(&calldata)
+                .map(|tx| chain::foreign_forwarders(&self.config, &tx))
+                .unwrap_or_default();
+            if !foreign.is_empty() {
+                warn!(
+                    "{} calls forwarder(s) {} outside the {} deployment, generating a fresh proof",
+                    path,
+                    foreign.join(", "),
+                    self.config.network
+                );
+                return None;
+            }
+        }
+        let handed_out = chain::decode_execute_bytes(&calldata)
+            .map(|tx| self.nullifiers.contains_any(&chain::extract_nullifiers(&tx)))
+            .unwrap_or(false);
+        if handed_out {
+            info!("{} was already handed out, generating a fresh proof", path);
+            return None;
+        }
+        (!self.nullifier_spent(&calldata, path).await).then_some(calldata)
+    }
+
+    /// Whether `calldata` (described as `what` in logs) spends a nullifier already spent on-chain
+    ///
+    /// False when that cannot be checked, so the proof is reused.
+    async fn nullifier_spent(&self, calldata: &[u8], what: &str) -> bool {
+        let Some(rpc_url) = &self.rpc_url else {
+            warn!("RPC_URL not set; reusing {} without checking whether its nullifier is spent", what);
+            return false;
+        };
+
+        let check = async {
+            let tx = chain::decode_execute_calldata(&hex::encode(calldata))?;
+            chain::any_nullifier_spent(rpc_url, &self.config.protocol_adapter, &chain::extract_nullifiers(&tx)).await
+        };
+        match check.await {
+            Ok(false) => false,
+            Ok(true) => {
+                info!("Nullifier of {} is already spent on-chain, generating a fresh proof", what);
+                true
+            }
+            Err(e) => {
+                warn!("Could not check {} against on-chain state, reusing it: {}", what, e);
+                false
+            }
+        }
+    }
+
+    /// Load a pre-generated proof from disk or generate one in-process
     /// The proof uses INITIAL_ROOT and is valid for on-chain execution
     ///
-    /// NOTE: The pre-generated proof has a fixed nullifier. Once used on-chain, it cannot be
-    /// reused (PreExistingNullifier error). To generate a fresh proof with a new nullifier,
-    /// Docker must be running for Groth16 proof generation.
-    pub fn create_real_ephemeral_proof(&self, proof_id: String) -> Result<ProofResponse> {
-        info!("Looking for pre-generated proof or calling local-prove...");
+    /// NOTE: A pre-generated proof can only execute once (its nullifier is then spent).
+    /// With RPC_URL set, a spent proof is regenerated with a fresh nonce, which needs
+    /// Docker for Groth16 proof generation. A cached response is returned unless
+    /// `force` is set.
+    pub async fn create_real_ephemeral_proof(
+        &self,
+        force: bool,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let key = ProofCache::key("ephemeral", &serde_json::Value::Null);
+        self.cached("ephemeral", &key, force, |proof_id| self.generate_real_ephemeral_proof(proof_id, job)).await
+    }
+
+    async fn generate_real_ephemeral_proof(
+        &self,
+        proof_id: String,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        info!("Looking for pre-generated proof...");
 
         // Try to load pre-generated calldata from file
         // This file is generated by: cargo run --release --bin local-prove -- test-ephemeral
         let proof_file = "ephemeral_test_tx.bin";
 
-        if let Ok(calldata) = std::fs::read(proof_file) {
+        if let Some(calldata) = self.load_cached_proof(proof_file, true).await {
             let calldata_hex = format!("0x{}", hex::encode(&calldata));
             info!("Loaded pre-generated proof: {} bytes", calldata.len());
-            warn!("NOTE: Pre-generated proof has fixed nullifier. If 'PreExistingNullifier' error occurs, generate fresh proof with Docker.");
 
             return Ok(ProofResponse {
                 proof_id,
@@ -391,76 +1152,70 @@ impl ProverService {
                 proof: Some(ProofData {
                     journal: "ephemeral_proof_pregenerated".to_string(),
                     seal: hex::encode(&calldata[..64.min(calldata.len())]),
-                    image_id: "arm_trivial_logic_v0.13.0".to_string(),
+                    image_id: trivial_logic_vk(),
+                    fake: false,
                 }),
                 calldata: Some(calldata_hex),
+                summary: None,
             });
         }
 
         // Check if Docker is available before trying to generate
-        if !is_docker_available() {
-            return Err(anyhow!(
-                "Docker not available. Please ensure Docker Desktop is running. \
-                 Proof generation requires Docker for Groth16 proving."
-            ));
+        if !docker_available().await {
+            return Err(ProverError::DockerUnavailable);
         }
 
-        // Generate proof using local-prove with Docker-aware PATH
         info!("Generating fresh ephemeral proof with Docker (this will take ~8 minutes)...");
 
-        let output = std::process::Command::new("cargo")
-            .args(["run", "--release", "--bin", "local-prove", "--", "test-ephemeral"])
-            .env("PATH", get_path_with_docker())
-            .current_dir(std::env::current_dir().unwrap_or_default())
-            .output();
-
-        match output {
-            Ok(out) => {
-                if out.status.success() {
-                    // Try to load the generated file
-                    if let Ok(calldata) = std::fs::read(proof_file) {
-                        let calldata_hex = format!("0x{}", hex::encode(&calldata));
-                        info!("Generated proof: {} bytes", calldata.len());
-
-                        return Ok(ProofResponse {
-                            proof_id,
-                            status: "completed".to_string(),
-                            proof: Some(ProofData {
-                                journal: "ephemeral_proof_generated".to_string(),
-                                seal: hex::encode(&calldata[..64.min(calldata.len())]),
-                                image_id: "arm_trivial_logic_v0.13.0".to_string(),
-                            }),
-                            calldata: Some(calldata_hex),
-                        });
-                    }
-                }
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                Err(anyhow!("local-prove failed: {}", stderr))
-            }
-            Err(e) => {
-                Err(anyhow!("Failed to run local-prove: {}. Generate proof manually with: cargo run --release --bin local-prove -- test-ephemeral", e))
-            }
-        }
+        let calldata = run_prover(ephemeral_calldata, self.prove_timeout, job).await?;
+        cache_proof(proof_file, &calldata);
+        let calldata_hex = format!("0x{}", hex::encode(&calldata));
+        info!("Generated proof: {} bytes", calldata.len());
+
+        Ok(ProofResponse {
+            proof_id,
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal: "ephemeral_proof_generated".to_string(),
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: trivial_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(calldata_hex),
+            summary: None,
+        })
     }
 
     /// Generate a shield proof with forwarder call for real token transfers
-    /// This proof includes external_payload that triggers transferFrom on the forwarder contract
-    pub fn create_shield_proof_with_forwarder(
+    /// This proof includes external_payload that triggers transferFrom on the forwarder contract,
+    /// preceded by permit when the sender signed one
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_shield_proof_with_forwarder(
         &self,
         proof_id: String,
         token: &str,
         amount: u128,
         sender: &str,
-    ) -> Result<ProofResponse> {
+        deletion_criterion: DeletionCriterion,
+        permit: Option<PermitData>,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
         info!("Generating shield proof with forwarder call: token={}, amount={}, sender={}", token, amount, sender);
 
+        let summary = vec![OperationSummary {
+            op: "shield".to_string(),
+            token: token.to_uppercase(),
+            amount: amount.to_string(),
+            counterparty: sender.to_string(),
+            forwarder: self.config.tokens.get(token).map_err(ProverError::unknown_token)?.forwarder_address.clone(),
+        }];
+
         // Check if we have a pre-generated proof for this exact parameters
-        let proof_file = format!("shield_{}_{}.bin", token.to_lowercase(), amount);
+        let proof_file = shield_proof_file_name(token, amount, deletion_criterion, permit.as_ref());
 
-        if let Ok(calldata) = std::fs::read(&proof_file) {
+        if let Some(calldata) = self.load_cached_proof(&proof_file, true).await {
             let calldata_hex = format!("0x{}", hex::encode(&calldata));
             info!("Loaded pre-generated shield proof: {} bytes", calldata.len());
-            warn!("NOTE: Pre-generated proof has fixed nullifier. For production, generate fresh proof.");
 
             return Ok(ProofResponse {
                 proof_id,
@@ -468,77 +1223,71 @@ impl ProverService {
                 proof: Some(ProofData {
                     journal: format!("shield_{}_{}", token, amount),
                     seal: hex::encode(&calldata[..64.min(calldata.len())]),
-                    image_id: "forwarder_logic_v0.1.0".to_string(),
+                    image_id: forwarder_logic_vk(),
+                    fake: false,
                 }),
                 calldata: Some(calldata_hex),
+                summary: Some(summary.clone()),
             });
         }
 
         // Check if Docker is available
-        if !is_docker_available() {
-            return Err(anyhow!(
-                "Docker not available. Please ensure Docker Desktop is running. \
-                 Proof generation requires Docker for Groth16 proving."
-            ));
+        if !docker_available().await {
+            return Err(ProverError::DockerUnavailable);
         }
 
-        // Generate proof using local-prove with Docker-aware PATH
         info!("Generating fresh proof with Docker (this will take ~7 minutes)...");
 
-        let output = std::process::Command::new("cargo")
-            .args([
-                "run", "--release", "--bin", "local-prove", "--",
-                "shield",
-                "--token", token,
-                "--amount", &amount.to_string(),
-                "--sender", sender,
-            ])
-            .env("PATH", get_path_with_docker())
-            .current_dir(std::env::current_dir().unwrap_or_default())
-            .output();
-
-        match output {
-            Ok(out) => {
-                if out.status.success() {
-                    if let Ok(calldata) = std::fs::read(&proof_file) {
-                        let calldata_hex = format!("0x{}", hex::encode(&calldata));
-                        info!("Generated shield proof: {} bytes", calldata.len());
-
-                        return Ok(ProofResponse {
-                            proof_id,
-                            status: "completed".to_string(),
-                            proof: Some(ProofData {
-                                journal: format!("shield_{}_{}", token, amount),
-                                seal: hex::encode(&calldata[..64.min(calldata.len())]),
-                                image_id: "forwarder_logic_v0.1.0".to_string(),
-                            }),
-                            calldata: Some(calldata_hex),
-                        });
-                    }
-                }
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                Err(anyhow!("local-prove shield failed: {}", stderr))
-            }
-            Err(e) => {
-                Err(anyhow!("Failed to run local-prove: {}", e))
-            }
-        }
+        let config = self.config.clone();
+        let (token_arg, sender_arg) = (token.to_string(), sender.to_string());
+        let calldata = run_prover(
+            move || shield_calldata(&config, &token_arg, amount, &sender_arg, deletion_criterion, permit, fresh_nonce()),
+            self.prove_timeout,
+            job,
+        )
+        .await?;
+        cache_proof(&proof_file, &calldata);
+        let calldata_hex = format!("0x{}", hex::encode(&calldata));
+        info!("Generated shield proof: {} bytes", calldata.len());
+
+        Ok(ProofResponse {
+            proof_id,
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal: format!("shield_{}_{}", token, amount),
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: forwarder_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(calldata_hex),
+            summary: Some(summary),
+        })
     }
 
     /// Generate an unshield proof with forwarder call for real token transfers
     /// This proof includes external_payload that triggers transfer on the forwarder contract
-    pub fn create_unshield_proof_with_forwarder(
+    pub async fn create_unshield_proof_with_forwarder(
         &self,
         proof_id: String,
         token: &str,
         amount: u128,
         recipient: &str,
-    ) -> Result<ProofResponse> {
+        deletion_criterion: DeletionCriterion,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
         info!("Generating unshield proof with forwarder call: token={}, amount={}, recipient={}", token, amount, recipient);
 
-        let proof_file = format!("unshield_{}_{}.bin", token.to_lowercase(), amount);
+        let summary = vec![OperationSummary {
+            op: "unshield".to_string(),
+            token: token.to_uppercase(),
+            amount: amount.to_string(),
+            counterparty: recipient.to_string(),
+            forwarder: self.config.tokens.get(token).map_err(ProverError::unknown_token)?.forwarder_address.clone(),
+        }];
+
+        let proof_file = proof_file_name("unshield", token, amount, deletion_criterion);
 
-        if let Ok(calldata) = std::fs::read(&proof_file) {
+        if let Some(calldata) = self.load_cached_proof(&proof_file, true).await {
             let calldata_hex = format!("0x{}", hex::encode(&calldata));
             info!("Loaded pre-generated unshield proof: {} bytes", calldata.len());
 
@@ -548,127 +1297,632 @@ impl ProverService {
                 proof: Some(ProofData {
                     journal: format!("unshield_{}_{}", token, amount),
                     seal: hex::encode(&calldata[..64.min(calldata.len())]),
-                    image_id: "forwarder_logic_v0.1.0".to_string(),
+                    image_id: forwarder_logic_vk(),
+                    fake: false,
                 }),
                 calldata: Some(calldata_hex),
+                summary: Some(summary.clone()),
             });
         }
 
         // Check if Docker is available
-        if !is_docker_available() {
-            return Err(anyhow!(
-                "Docker not available. Please ensure Docker Desktop is running. \
-                 Proof generation requires Docker for Groth16 proving."
-            ));
+        if !docker_available().await {
+            return Err(ProverError::DockerUnavailable);
         }
 
-        // Generate proof using local-prove with Docker-aware PATH
         info!("Generating fresh unshield proof with Docker (this will take ~7 minutes)...");
 
-        let output = std::process::Command::new("cargo")
-            .args([
-                "run", "--release", "--bin", "local-prove", "--",
-                "unshield",
-                "--token", token,
-                "--amount", &amount.to_string(),
-                "--recipient", recipient,
-            ])
-            .env("PATH", get_path_with_docker())
-            .current_dir(std::env::current_dir().unwrap_or_default())
-            .output();
-
-        match output {
-            Ok(out) => {
-                if out.status.success() {
-                    if let Ok(calldata) = std::fs::read(&proof_file) {
-                        let calldata_hex = format!("0x{}", hex::encode(&calldata));
-                        info!("Generated unshield proof: {} bytes", calldata.len());
-
-                        return Ok(ProofResponse {
-                            proof_id,
-                            status: "completed".to_string(),
-                            proof: Some(ProofData {
-                                journal: format!("unshield_{}_{}", token, amount),
-                                seal: hex::encode(&calldata[..64.min(calldata.len())]),
-                                image_id: "forwarder_logic_v0.1.0".to_string(),
-                            }),
-                            calldata: Some(calldata_hex),
-                        });
-                    }
-                }
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                Err(anyhow!("local-prove unshield failed: {}", stderr))
-            }
-            Err(e) => {
-                Err(anyhow!("Failed to run local-prove: {}", e))
-            }
+        let config = self.config.clone();
+        let (token_arg, recipient_arg) = (token.to_string(), recipient.to_string());
+        let calldata = run_prover(
+            move || unshield_calldata(&config, &token_arg, amount, &recipient_arg, deletion_criterion, fresh_nonce()),
+            self.prove_timeout,
+            job,
+        )
+        .await?;
+        cache_proof(&proof_file, &calldata);
+        let calldata_hex = format!("0x{}", hex::encode(&calldata));
+        info!("Generated unshield proof: {} bytes", calldata.len());
+
+        Ok(ProofResponse {
+            proof_id,
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal: format!("unshield_{}_{}", token, amount),
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: forwarder_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(calldata_hex),
+            summary: Some(summary),
+        })
+    }
+
+    /// Generate a swap proof with a Uniswap forwarder call
+    /// This proof includes external_payload that triggers exactInputSingle on the Uniswap forwarder
+    pub async fn create_swap_proof_with_forwarder(
+        &self,
+        proof_id: String,
+        order: &SwapOrder,
+        deletion_criterion: DeletionCriterion,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        info!("Generating swap proof with forwarder call: {} {} -> min {} {}",
+              order.amount_in, order.token_in, order.min_amount_out, order.token_out);
+
+        let summary = vec![OperationSummary {
+            op: "swap".to_string(),
+            token: order.token_in.to_uppercase(),
+            amount: order.amount_in.to_string(),
+            counterparty: self.config.tokens.get(&order.token_out).map_err(ProverError::unknown_token)?.forwarder_address.clone(),
+            forwarder: self.config.uniswap_forwarder.clone(),
+        }];
+        let journal = format!("swap_{}_{}_{}_{}", order.token_in, order.amount_in, order.token_out, order.min_amount_out);
+
+        let proof_file = swap_proof_file_name(order, deletion_criterion);
+
+        if let Some(calldata) = self.load_cached_proof(&proof_file, true).await {
+            let calldata_hex = format!("0x{}", hex::encode(&calldata));
+            info!("Loaded pre-generated swap proof: {} bytes", calldata.len());
+
+            return Ok(ProofResponse {
+                proof_id,
+                status: "completed".to_string(),
+                proof: Some(ProofData {
+                    journal,
+                    seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                    image_id: forwarder_logic_vk(),
+                    fake: false,
+                }),
+                calldata: Some(calldata_hex),
+                summary: Some(summary.clone()),
+            });
+        }
+
+        // Check if Docker is available
+        if !docker_available().await {
+            return Err(ProverError::DockerUnavailable);
         }
+
+        info!("Generating fresh swap proof with Docker (this will take ~7 minutes)...");
+
+        let config = self.config.clone();
+        let order_arg = order.clone();
+        let calldata = run_prover(
+            move || swap_calldata(&config, &order_arg, deletion_criterion, fresh_nonce()),
+            self.prove_timeout,
+            job,
+        )
+        .await?;
+        cache_proof(&proof_file, &calldata);
+        let calldata_hex = format!("0x{}", hex::encode(&calldata));
+        info!("Generated swap proof: {} bytes", calldata.len());
+
+        Ok(ProofResponse {
+            proof_id,
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal,
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: forwarder_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(calldata_hex),
+            summary: Some(summary),
+        })
     }
 
-    async fn submit_bonsai_proof(
+    /// Generate a proof whose external_payload carries a raw forwarder call
+    pub async fn create_raw_proof_with_forwarder(
         &self,
         proof_id: String,
-        input_data: serde_json::Value,
-    ) -> Result<ProofResponse> {
-        let api_key = self.bonsai_api_key.as_ref()
-            .ok_or_else(|| anyhow!("Bonsai API key not configured"))?;
+        raw: &RawCall,
+        deletion_criterion: DeletionCriterion,
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        let forwarder = format!("0x{}", hex::encode(raw.forwarder));
+        info!("Generating raw forwarder call proof: forwarder={}, {} bytes of calldata", forwarder, raw.call_data.len());
+
+        let journal = format!("raw_{}_{}", forwarder, hex::encode(&raw.call_data[..4.min(raw.call_data.len())]));
+        let proof_file = raw.proof_file_name(deletion_criterion);
+
+        let response = |calldata: Vec<u8>| ProofResponse {
+            proof_id: proof_id.clone(),
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal: journal.clone(),
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: forwarder_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(format!("0x{}", hex::encode(&calldata))),
+            summary: None,
+        };
 
-        info!("Submitting proof to Bonsai: {}", proof_id);
+        if let Some(calldata) = self.load_cached_proof(&proof_file, false).await {
+            info!("Loaded pre-generated raw call proof: {} bytes", calldata.len());
+            return Ok(response(calldata));
+        }
 
-        // Use the blocking Bonsai SDK client
-        // The SDK requires a risc0_zkvm version string
-        let risc0_version = "1.4.0"; // Match the bonsai-sdk version
+        if !docker_available().await {
+            return Err(ProverError::DockerUnavailable);
+        }
 
-        let client = bonsai_sdk::blocking::Client::from_parts(
-            self.bonsai_api_url.clone(),
-            api_key.clone(),
-            risc0_version,
-        )?;
+        info!("Generating fresh raw call proof with Docker (this will take ~7 minutes)...");
+
+        let raw_arg = raw.clone();
+        let calldata = run_prover(
+            move || raw_calldata(&raw_arg, deletion_criterion, fresh_nonce()),
+            self.prove_timeout,
+            job,
+        )
+        .await?;
+        cache_proof(&proof_file, &calldata);
+        info!("Generated raw call proof: {} bytes", calldata.len());
+        Ok(response(calldata))
+    }
 
-        // Serialize input
-        let input_bytes = serde_json::to_vec(&input_data)?;
+    /// Generate one proof for every entry of a batch, each entry its own action
+    ///
+    /// Batches are not cached as proof files: every entry carries a fresh nonce.
+    pub async fn create_batch_proof_with_forwarder(
+        &self,
+        proof_id: String,
+        entries: &[BatchEntry],
+        job: Option<&JobContext>,
+    ) -> Result<ProofResponse, ProverError> {
+        info!("Generating batch proof with {} action(s): {:?}", entries.len(),
+              entries.iter().map(|entry| entry.op.name()).collect::<Vec<_>>());
+
+        let mut summary = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let forwarder = |token: &str| {
+                self.config.tokens.get(token).map(|info| info.forwarder_address.clone()).map_err(ProverError::unknown_token)
+            };
+            match &entry.op {
+                BatchOp::Shield { token, amount, sender, .. } => summary.push(OperationSummary {
+                    op: "shield".to_string(),
+                    token: token.to_uppercase(),
+                    amount: amount.to_string(),
+                    counterparty: sender.clone(),
+                    forwarder: forwarder(token)?,
+                }),
+                BatchOp::Unshield { token, amount, recipient } => summary.push(OperationSummary {
+                    op: "unshield".to_string(),
+                    token: token.to_uppercase(),
+                    amount: amount.to_string(),
+                    counterparty: recipient.clone(),
+                    forwarder: forwarder(token)?,
+                }),
+                BatchOp::Swap(order) => summary.push(OperationSummary {
+                    op: "swap".to_string(),
+                    token: order.token_in.to_uppercase(),
+                    amount: order.amount_in.to_string(),
+                    counterparty: forwarder(&order.token_out)?,
+                    forwarder: self.config.uniswap_forwarder.clone(),
+                }),
+                // Raw calls move no token the service knows about
+                BatchOp::Raw(_) => {}
+            }
+        }
+
+        if !docker_available().await {
+            return Err(ProverError::DockerUnavailable);
+        }
+
+        info!("Generating fresh batch proof with Docker (this will take ~7 minutes per action)...");
 
-        // Upload input data
-        let input_id = client.upload_input(input_bytes)?;
-        info!("Uploaded input to Bonsai: {}", input_id);
+        let config = self.config.clone();
+        let entries_arg = entries.to_vec();
+        let calldata = run_prover(move || batch_calldata(&config, &entries_arg), self.prove_timeout, job).await?;
+        info!("Generated batch proof: {} bytes", calldata.len());
 
-        // For a complete implementation, we would need:
-        // 1. A compiled guest program (ELF) that implements the shielded action verification
-        // 2. Upload that program with client.upload_img()
-        // 3. Create a session with client.create_session()
-        // 4. Poll for completion with session.status()
-        // 5. Download the receipt
+        Ok(ProofResponse {
+            proof_id,
+            status: "completed".to_string(),
+            proof: Some(ProofData {
+                journal: format!("batch_{}", entries.len()),
+                seal: hex::encode(&calldata[..64.min(calldata.len())]),
+                image_id: forwarder_logic_vk(),
+                fake: false,
+            }),
+            calldata: Some(format!("0x{}", hex::encode(&calldata))),
+            summary: Some(summary),
+        })
+    }
 
-        // For now, store as pending and return
+    /// Run `f` with the shared Bonsai client on the blocking thread pool
+    ///
+    /// The blocking client must not be used on the async runtime.
+    async fn with_bonsai_client<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&bonsai_sdk::blocking::Client) -> Result<T> + Send + 'static,
+    {
+        let client = self.bonsai.clone()
+            .ok_or_else(|| anyhow!("Bonsai API key not configured"))?;
+
+        tokio::task::spawn_blocking(in_current_span(move || f(&client)))
+            .await
+            .map_err(|e| anyhow!("Bonsai task failed: {}", e))?
+    }
+
+    fn set_session(&self, proof_id: &str, session_id: &str, status: &str, proof: Option<ProofData>) -> Result<()> {
         let mut proofs = self.proofs.lock().map_err(|e| anyhow!("Lock error: {}", e))?;
-        proofs.insert(proof_id.clone(), ProofSession {
-            session_id: input_id.clone(),
-            status: "pending".to_string(),
-            proof: None,
+        proofs.insert(proof_id.to_string(), ProofSession {
+            session_id: session_id.to_string(),
+            status: status.to_string(),
+            proof,
+            calldata: None,
+            summary: None,
         });
+        Ok(())
+    }
+
+    /// Prove the forwarder logic guest on Bonsai and wrap the result in a Groth16 SNARK
+    pub(crate) async fn submit_bonsai_proof(
+        &self,
+        proof_id: String,
+        input_data: serde_json::Value,
+    ) -> Result<ProofResponse, ProverError> {
+        info!("Submitting proof to Bonsai: {}", proof_id);
+
+        let input_bytes = input_data.to_string().into_bytes();
+        let image_id = FORWARDER_LOGIC_IMAGE_ID.to_string();
+        let poll = self.bonsai_poll;
+        let session_id = self.with_bonsai_client(move |client| {
+            // Returns false without re-uploading when the image already exists
+            retry_bonsai(&poll, "image upload", || client.upload_img(&image_id, FORWARDER_LOGIC_ELF.to_vec()))?;
+            let input_id = retry_bonsai(&poll, "input upload", || client.upload_input(input_bytes.clone()))?;
+            let session = retry_bonsai(&poll, "session creation", || {
+                client.create_session(image_id.clone(), input_id.clone(), vec![], false)
+            })?;
+            Ok(session.uuid)
+        }).await?;
+        info!("Started Bonsai session {} for proof {}", session_id, proof_id);
+        self.set_session(&proof_id, &session_id, "running", None)?;
+
+        let status = match self.wait_for_bonsai_session(&session_id).await {
+            Ok(status) => status,
+            Err(e) => {
+                self.set_session(&proof_id, &session_id, "failed", None)?;
+                return Err(e);
+            }
+        };
+        if status.status != "SUCCEEDED" {
+            self.set_session(&proof_id, &session_id, "failed", None)?;
+            return Err(ProverError::ProvingFailed(anyhow!(
+                "Bonsai session {} {}: {}",
+                session_id, status.status, status.error_msg.unwrap_or_default()
+            )));
+        }
+
+        let proof = match self.prove_bonsai_snark(&session_id).await {
+            Ok(proof) => proof,
+            Err(e) => {
+                self.set_session(&proof_id, &session_id, "failed", None)?;
+                return Err(e.into());
+            }
+        };
+        self.set_session(&proof_id, &session_id, "completed", Some(proof.clone()))?;
+        info!("Bonsai proof {} completed", proof_id);
 
         Ok(ProofResponse {
             proof_id,
-            status: "pending".to_string(),
-            proof: None,
+            status: "completed".to_string(),
+            proof: Some(proof),
             calldata: None,
+            summary: None,
+        })
+    }
+
+    /// Convert a finished session's receipt to Groth16 so it verifies on-chain
+    async fn prove_bonsai_snark(&self, session_id: &str) -> Result<ProofData> {
+        let session_id = session_id.to_string();
+        let poll = self.bonsai_poll;
+
+        let receipt_bytes = self.with_bonsai_client(move |client| {
+            let snark = retry_bonsai(&poll, "SNARK creation", || client.create_snark(session_id.clone()))?;
+            info!("Started Bonsai SNARK {} for session {}", snark.uuid, session_id);
+
+            let started = Instant::now();
+            let mut attempt = 0;
+            loop {
+                let status = retry_bonsai(&poll, "SNARK status", || snark.status(client))?;
+                match status.status.as_str() {
+                    "RUNNING" => {}
+                    "SUCCEEDED" => {
+                        let url = status.output
+                            .ok_or_else(|| anyhow!("Bonsai SNARK {} succeeded without a receipt", snark.uuid))?;
+                        return retry_bonsai(&poll, "receipt download", || client.download(&url));
+                    }
+                    other => {
+                        return Err(anyhow!(
+                            "Bonsai SNARK for session {} {}: {}",
+                            session_id, other, status.error_msg.unwrap_or_default()
+                        ));
+                    }
+                }
+
+                if started.elapsed() >= poll.max_wait {
+                    return Err(anyhow!(
+                        "Timed out after {}s waiting for Bonsai SNARK {}",
+                        started.elapsed().as_secs(), snark.uuid
+                    ));
+                }
+                std::thread::sleep(poll.interval(attempt));
+                attempt += 1;
+            }
+        }).await?;
+
+        let receipt: risc0_zkvm::Receipt = bincode::deserialize(&receipt_bytes)
+            .map_err(|e| anyhow!("Failed to decode Bonsai receipt: {}", e))?;
+        let groth16 = receipt.inner.groth16()
+            .map_err(|e| anyhow!("Bonsai receipt is not a Groth16 receipt: {}", e))?;
+
+        // On-chain verifiers expect the 4-byte verifier selector ahead of the seal
+        let mut seal = groth16.verifier_parameters.as_bytes()[..4].to_vec();
+        seal.extend_from_slice(&groth16.seal);
+
+        Ok(ProofData {
+            journal: hex::encode(&receipt.journal.bytes),
+            seal: hex::encode(seal),
+            image_id: forwarder_logic_vk(),
+            fake: false,
         })
     }
 
-    async fn check_bonsai_status(&self, proof_id: &str) -> Result<ProofResponse> {
-        let proofs = self.proofs.lock().map_err(|e| anyhow!("Lock error: {}", e))?;
+    /// Poll a Bonsai session with exponential backoff until it leaves RUNNING
+    ///
+    /// Fails once BONSAI_MAX_WAIT_SECS has elapsed. The session itself is not
+    /// stopped, so it can still be inspected in Bonsai afterwards.
+    pub async fn wait_for_bonsai_session(
+        &self,
+        session_id: &str,
+    ) -> Result<bonsai_sdk::responses::SessionStatusRes, ProverError> {
+        let started = Instant::now();
+        let mut attempt = 0;
+
+        loop {
+            let session = bonsai_sdk::blocking::SessionId::new(session_id.to_string());
+            let poll = self.bonsai_poll;
+            let status = self.with_bonsai_client(move |client| {
+                retry_bonsai(&poll, "session status", || session.status(client))
+            }).await?;
+
+            if status.status != "RUNNING" {
+                return Ok(status);
+            }
 
-        if let Some(session) = proofs.get(proof_id) {
-            // In a full implementation, we would poll Bonsai for the session status
-            // using client.session_status(&session.session_id)
-            return Ok(ProofResponse {
-                proof_id: proof_id.to_string(),
-                status: session.status.clone(),
-                proof: session.proof.clone(),
-                calldata: None,
-            });
+            let elapsed = started.elapsed();
+            if elapsed >= self.bonsai_poll.max_wait {
+                warn!("Bonsai session {} still running after {}s; leaving it for manual inspection",
+                      session_id, elapsed.as_secs());
+                return Err(ProverError::Timeout(format!(
+                    "Timed out after {}s waiting for Bonsai session {}",
+                    elapsed.as_secs(), session_id
+                )));
+            }
+
+            let delay = self.bonsai_poll.interval(attempt).min(self.bonsai_poll.max_wait - elapsed);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
+    }
+
+    /// Live status of a proof whose Bonsai session is still running
+    async fn check_bonsai_status(&self, proof_id: &str, session_id: &str) -> Result<ProofResponse> {
+        let session = bonsai_sdk::blocking::SessionId::new(session_id.to_string());
+        let poll = self.bonsai_poll;
+        let status = self.with_bonsai_client(move |client| {
+            retry_bonsai(&poll, "session status", || session.status(client))
+        }).await?;
+
+        // A succeeded session is still being wrapped into a SNARK by submit_bonsai_proof
+        let status = match status.status.as_str() {
+            "RUNNING" | "SUCCEEDED" => "running",
+            _ => "failed",
+        };
+
+        Ok(ProofResponse {
+            proof_id: proof_id.to_string(),
+            status: status.to_string(),
+            proof: None,
+            calldata: None,
+            summary: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_file_is_unique_and_published() {
+        let dir = std::env::temp_dir().join(format!("scratch_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let proof_file = dir.join("unshield_usdc_1000000.bin").to_string_lossy().into_owned();
+
+        let first = ScratchFile::for_proof(&proof_file);
+        let second = ScratchFile::for_proof(&proof_file);
+        assert_ne!(first.path, second.path);
+
+        std::fs::write(&first.path, b"first").unwrap();
+        std::fs::write(&second.path, b"second").unwrap();
+        let second_path = second.path.clone();
+        drop(second);
+        assert!(!std::path::Path::new(&second_path).exists());
+
+        first.publish(&proof_file);
+        assert_eq!(std::fs::read(&proof_file).unwrap(), b"first");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_handed_out_nullifier_is_refused() {
+        use alloy::primitives::B256;
+        use alloy::sol_types::SolValue;
+        use evm_protocol_adapter_bindings::contract::ProtocolAdapter;
 
-        Err(anyhow!("Proof not found: {}", proof_id))
+        let mut prover = ProverService::new(Config::default()).unwrap();
+        prover.nullifiers = NullifierSet::new(None);
+
+        let mut tx = ProtocolAdapter::Transaction::default();
+        tx.actions.push(Default::default());
+        tx.actions[0].complianceVerifierInputs.push(Default::default());
+        tx.actions[0].complianceVerifierInputs[0].instance.consumed.nullifier = B256::repeat_byte(0x0f);
+        let mut calldata = chain::EXECUTE_SELECTOR.to_vec();
+        calldata.extend(tx.abi_encode());
+
+        let response = ProofResponse {
+            proof_id: "proof_1".to_string(),
+            status: "completed".to_string(),
+            proof: None,
+            calldata: Some(format!("0x{}", hex::encode(&calldata))),
+            summary: None,
+        };
+        prover.record_nullifiers(&response).unwrap();
+        assert_eq!(prover.nullifier_set_len(), 1);
+
+        let err = prover.record_nullifiers(&response).unwrap_err().to_string();
+        assert!(err.contains("already handed out"), "{}", err);
+
+        // Mock responses carry no calldata
+        prover.record_nullifiers(&ProofResponse { calldata: None, ..response }).unwrap();
+        assert_eq!(prover.nullifier_set_len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_nullifier_key_hex_is_rejected() {
+        let prover = ProverService::new(Config::default()).unwrap();
+        let err = prover.hash_nullifier_key("0xZZ").unwrap_err();
+        assert!(matches!(err, ProverError::InvalidRequest(_)), "{:?}", err);
+        assert!(err.to_string().contains("Invalid nullifier key hex"), "{}", err);
+
+        let empty = prover.hash_nullifier_key("").unwrap();
+        let key = prover.hash_nullifier_key(&format!("0x{}", hex::encode([7u8; 32]))).unwrap();
+        assert_ne!(key, empty);
+        assert_eq!(key, prover.hash_nullifier_key(&hex::encode([7u8; 32])).unwrap());
+    }
+
+    #[test]
+    fn test_resource_token() {
+        assert_eq!(resource_token(&serde_json::json!({"token": "WETH", "label_ref": "0x55534443"})).unwrap(), "WETH");
+        assert_eq!(resource_token(&serde_json::json!({"label_ref": "0x55534443"})).unwrap(), "USDC");
+        assert!(resource_token(&serde_json::json!({"amount": "5"})).unwrap_err().to_string().contains("names no token"));
+
+        // Unknown tokens are rejected by the registry instead of falling back to a default
+        let token = resource_token(&serde_json::json!({"token": "DOGE"})).unwrap();
+        let err = Config::default().tokens.get(&token).unwrap_err().to_string();
+        assert!(err.contains("Unknown token: DOGE"), "{}", err);
+    }
+
+    #[test]
+    fn test_backend_readiness() {
+        // Real ARM proving is ready only with Docker
+        assert!(BackendStatus::new(true, false, true).ready);
+        assert!(!BackendStatus::new(true, true, false).ready);
+
+        let bonsai = BackendStatus::new(false, true, false);
+        assert!(bonsai.ready && !bonsai.mock_mode);
+        let mock = BackendStatus::new(false, false, false);
+        assert!(mock.ready && mock.mock_mode);
+    }
+
+    #[test]
+    fn test_bonsai_poll_backoff_is_capped() {
+        let config = BonsaiPollConfig {
+            max_interval: Duration::from_secs(3),
+            max_wait: Duration::from_secs(60),
+            max_attempts: 5,
+        };
+        assert_eq!(config.interval(0), Duration::from_millis(500));
+        assert_eq!(config.interval(1), Duration::from_secs(1));
+        assert_eq!(config.interval(2), Duration::from_secs(2));
+        assert_eq!(config.interval(3), Duration::from_secs(3));
+        assert_eq!(config.interval(40), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_bonsai_calls_retry_only_transient_errors() {
+        use bonsai_sdk::SdkErr;
+
+        let poll = BonsaiPollConfig { max_interval: Duration::ZERO, max_wait: Duration::ZERO, max_attempts: 3 };
+        let calls = &std::cell::Cell::new(0);
+        let flaky = |fail_times: u32, err: fn() -> SdkErr| {
+            calls.set(0);
+            move || {
+                calls.set(calls.get() + 1);
+                if calls.get() <= fail_times { Err(err()) } else { Ok(calls.get()) }
+            }
+        };
+        let gateway = || SdkErr::InternalServerErr("502 Bad Gateway".to_string());
+
+        assert_eq!(retry_bonsai(&poll, "test", flaky(2, gateway)).unwrap(), 3);
+        let err = retry_bonsai(&poll, "test", flaky(3, gateway)).unwrap_err();
+        assert!(err.to_string().contains("after 3 attempt(s)"), "{}", err);
+        assert_eq!(calls.get(), 3);
+
+        assert!(retry_bonsai(&poll, "test", flaky(1, || SdkErr::MissingApiKey)).is_err());
+        assert_eq!(calls.get(), 1);
+        assert!(!bonsai_error_is_retryable(&SdkErr::InternalServerErr("Unauthorized".to_string())));
+        assert!(bonsai_error_is_retryable(&SdkErr::InternalServerErr("Too Many Requests".to_string())));
+
+        let delay = BonsaiPollConfig::default().retry_delay(1);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1), "{:?}", delay);
+    }
+
+    #[test]
+    fn test_bonsai_version_check() {
+        let supported = vec!["1.1.4".to_string(), "1.2".to_string()];
+        assert!(bonsai_supports_version(&supported, "1.1.4"));
+        assert!(bonsai_supports_version(&supported, "1.2.6"));
+        assert!(!bonsai_supports_version(&supported, "1.3.0"));
+        assert!(!bonsai_supports_version(&[], risc0_zkvm::VERSION));
+    }
+
+    #[tokio::test]
+    async fn test_proving_does_not_block_runtime() {
+        let prove = || {
+            std::thread::sleep(Duration::from_secs(1));
+            Ok(vec![1])
+        };
+        let proving = tokio::spawn(run_prover(prove, Duration::from_secs(5), None));
+
+        // The test runtime has a single worker, which a prover run in place would hold for the whole second
+        let start = Instant::now();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(start.elapsed() < Duration::from_millis(100), "runtime blocked for {:?}", start.elapsed());
+
+        assert_eq!(proving.await.unwrap().unwrap(), [1]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_fails_proof() {
+        let job = JobContext::new();
+        job.cancel.cancel();
+        let prove = || {
+            std::thread::sleep(Duration::from_secs(3));
+            Ok(vec![])
+        };
+        let result = tokio::time::timeout(Duration::from_secs(1), run_prover(prove, Duration::from_secs(60), Some(&job)))
+            .await
+            .unwrap();
+
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+        assert_eq!(job.progress.latest().as_deref(), Some("Generating ZK proofs..."));
+        assert_eq!(job.log.snapshot().lines, ["[error] Proof cancelled"]);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_fails_proof() {
+        let prove = || {
+            std::thread::sleep(Duration::from_secs(3));
+            Ok(vec![])
+        };
+        let err = run_prover(prove, Duration::from_secs(1), None).await.unwrap_err();
+        assert_eq!(err.to_string(), "Proof generation timed out after 1 seconds");
+        assert_eq!(err.code(), "timeout");
     }
 }
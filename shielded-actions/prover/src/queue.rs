@@ -0,0 +1,352 @@
+//! Priority work queue for proof generation jobs
+//!
+//! Jobs are queued instead of spawned immediately. A dispatcher task hands the
+//! next job to a worker whenever a proving permit frees up, always choosing the
+//! highest-priority job and falling back to FIFO order within a priority.
+//!
+//! `MAX_PENDING_JOBS` bounds how many jobs may wait for a permit; pushing onto
+//! a full queue fails, and the server turns the job away.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tracing::{info, info_span, Instrument};
+use utoipa::ToSchema;
+
+/// Scheduling priority of a proof request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Returned by [`ProofQueue::push`] when MAX_PENDING_JOBS jobs are already waiting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull {
+    pub pending: usize,
+}
+
+impl fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Proof queue is full ({} pending jobs); retry later", self.pending)
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+type JobTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct QueuedJob {
+    priority: Priority,
+    seq: u64,
+    job_id: String,
+    task: JobTask,
+}
+
+impl QueuedJob {
+    /// Whether this job is dispatched before `other`
+    fn runs_before(&self, other: &QueuedJob) -> bool {
+        self.cmp(other) == Ordering::Greater
+    }
+}
+
+impl PartialEq for QueuedJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for QueuedJob {}
+
+impl PartialOrd for QueuedJob {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedJob {
+    // BinaryHeap is a max-heap: higher priority first, then the earliest sequence number
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct QueueInner {
+    heap: BinaryHeap<QueuedJob>,
+    next_seq: u64,
+}
+
+/// Priority queue feeding the proving semaphore
+pub struct ProofQueue {
+    inner: Mutex<QueueInner>,
+    /// Jobs whose task has been dispatched and has not finished or panicked
+    running: Mutex<HashSet<String>>,
+    notify: Notify,
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+    /// Most jobs allowed to wait for a permit (unbounded when None)
+    max_pending: Option<usize>,
+    /// Set on shutdown; queued jobs are no longer started
+    closed: AtomicBool,
+}
+
+impl ProofQueue {
+    pub fn new(max_concurrent: usize) -> Arc<Self> {
+        Self::with_max_pending(max_concurrent, None)
+    }
+
+    pub fn with_max_pending(max_concurrent: usize, max_pending: Option<usize>) -> Arc<Self> {
+        let max_concurrent = max_concurrent.max(1);
+        Arc::new(Self {
+            inner: Mutex::new(QueueInner::default()),
+            running: Mutex::new(HashSet::new()),
+            notify: Notify::new(),
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            max_pending,
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Read the concurrency limit from MAX_CONCURRENT_PROOFS (default: half the available CPUs)
+    /// and the queue bound from MAX_PENDING_JOBS (default: unbounded)
+    pub fn from_env() -> Arc<Self> {
+        let default = std::thread::available_parallelism()
+            .map(|n| (n.get() / 2).max(1))
+            .unwrap_or(1);
+        let max_concurrent = std::env::var("MAX_CONCURRENT_PROOFS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default);
+        let max_pending = std::env::var("MAX_PENDING_JOBS").ok().and_then(|v| v.parse().ok());
+        Self::with_max_pending(max_concurrent, max_pending)
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    pub fn max_pending(&self) -> Option<usize> {
+        self.max_pending
+    }
+
+    /// Wait for a proving slot outside the queue
+    ///
+    /// Used by the synchronous endpoints, which prove inline but must still
+    /// count against MAX_CONCURRENT_PROOFS.
+    pub async fn acquire_permit(&self) -> OwnedSemaphorePermit {
+        self.permits.clone().acquire_owned().await.expect("proof semaphore closed")
+    }
+
+    /// Spawn the dispatcher that starts queued jobs as permits become available
+    pub fn start(self: &Arc<Self>) {
+        info!("Proof queue started with {} concurrent proof slot(s)", self.max_concurrent);
+        let queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let permit = queue.permits.clone().acquire_owned().await
+                    .expect("proof semaphore closed");
+
+                // Choose the job only once a permit is held, so a high-priority job
+                // queued while we were waiting still goes first
+                let job = loop {
+                    if let Some(job) = queue.pop() {
+                        break job;
+                    }
+                    queue.notify.notified().await;
+                };
+                if queue.closed.load(AtomicOrdering::SeqCst) {
+                    info!("Proof queue closed, not starting job {}", job.job_id);
+                    break;
+                }
+
+                info!("Dispatching job {} (priority {:?})", job.job_id, job.priority);
+                let running = RunningGuard::new(&queue, job.job_id);
+                tokio::spawn(async move {
+                    job.task.await;
+                    drop(running);
+                    drop(permit);
+                });
+            }
+        });
+    }
+
+    /// Stop starting queued jobs; running jobs are left to finish
+    pub fn close(&self) {
+        self.closed.store(true, AtomicOrdering::SeqCst);
+    }
+
+    /// Queue a job's proving task, or fail if MAX_PENDING_JOBS jobs are already waiting
+    ///
+    /// The bound is checked under the same lock the job is queued under, so
+    /// concurrent pushes cannot overshoot it.
+    ///
+    /// The task runs in a `job` span carrying the job id, under the span of the
+    /// request that queued it, so everything it logs can be traced to both.
+    pub fn push<F>(&self, job_id: String, priority: Priority, task: F) -> Result<(), QueueFull>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = task.instrument(info_span!("job", job_id = %job_id));
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let pending = inner.heap.len();
+            if self.max_pending.is_some_and(|max| pending >= max) {
+                return Err(QueueFull { pending });
+            }
+            let seq = inner.next_seq;
+            inner.next_seq += 1;
+            inner.heap.push(QueuedJob {
+                priority,
+                seq,
+                job_id,
+                task: Box::pin(task),
+            });
+        }
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    fn pop(&self) -> Option<QueuedJob> {
+        self.inner.lock().unwrap().heap.pop()
+    }
+
+    /// Whether a job's task is currently running
+    pub fn is_running(&self, job_id: &str) -> bool {
+        self.running.lock().unwrap().contains(job_id)
+    }
+
+    /// Number of jobs whose task is running
+    pub fn running_count(&self) -> usize {
+        self.running.lock().unwrap().len()
+    }
+
+    /// Number of jobs waiting to be dispatched
+    pub fn queued_count(&self) -> usize {
+        self.inner.lock().unwrap().heap.len()
+    }
+
+    /// Zero-based position of a job in the queue, or None once it has been dispatched
+    pub fn position(&self, job_id: &str) -> Option<usize> {
+        let inner = self.inner.lock().unwrap();
+        let job = inner.heap.iter().find(|j| j.job_id == job_id)?;
+        Some(inner.heap.iter().filter(|other| other.runs_before(job)).count())
+    }
+}
+
+/// Marks a job as running until dropped, including when its task panics
+struct RunningGuard {
+    queue: Arc<ProofQueue>,
+    job_id: String,
+}
+
+impl RunningGuard {
+    fn new(queue: &Arc<ProofQueue>, job_id: String) -> Self {
+        queue.running.lock().unwrap().insert(job_id.clone());
+        Self {
+            queue: queue.clone(),
+            job_id,
+        }
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.queue.running.lock() {
+            running.remove(&self.job_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_priority_jumps_queue() {
+        let queue = ProofQueue::new(1);
+        queue.push("low".to_string(), Priority::Low, async {}).unwrap();
+        queue.push("normal-1".to_string(), Priority::Normal, async {}).unwrap();
+        queue.push("normal-2".to_string(), Priority::Normal, async {}).unwrap();
+        queue.push("high".to_string(), Priority::High, async {}).unwrap();
+
+        assert_eq!(queue.position("high"), Some(0));
+        assert_eq!(queue.position("normal-1"), Some(1));
+        assert_eq!(queue.position("low"), Some(3));
+
+        let order: Vec<String> = std::iter::from_fn(|| queue.pop().map(|j| j.job_id)).collect();
+        assert_eq!(order, ["high", "normal-1", "normal-2", "low"]);
+        assert_eq!(queue.position("high"), None);
+    }
+
+    #[tokio::test]
+    async fn test_panicked_job_is_not_running() {
+        let queue = ProofQueue::new(1);
+        queue.start();
+        queue.push("panics".to_string(), Priority::Normal, async { panic!("prover bug") }).unwrap();
+
+        for _ in 0..100 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            if queue.position("panics").is_none() && !queue.is_running("panics") {
+                return;
+            }
+        }
+        panic!("panicked job still marked as running");
+    }
+
+    #[test]
+    fn test_push_fails_while_queue_is_full() {
+        let queue = ProofQueue::with_max_pending(1, Some(2));
+        queue.push("first".to_string(), Priority::Normal, async {}).unwrap();
+        queue.push("second".to_string(), Priority::Normal, async {}).unwrap();
+        assert_eq!(
+            queue.push("third".to_string(), Priority::High, async {}),
+            Err(QueueFull { pending: 2 })
+        );
+        assert_eq!(queue.position("third"), None);
+
+        // Dispatching a job (it starts generating) frees its place
+        queue.pop();
+        queue.push("third".to_string(), Priority::Normal, async {}).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_pushes_stay_within_bound() {
+        let queue = ProofQueue::with_max_pending(1, Some(5));
+        let accepted: usize = std::thread::scope(|s| {
+            let pushers: Vec<_> = (0..32)
+                .map(|i| {
+                    let queue = &queue;
+                    s.spawn(move || queue.push(format!("job-{}", i), Priority::Normal, async {}).is_ok())
+                })
+                .collect();
+            pushers.into_iter().map(|p| p.join().unwrap() as usize).sum()
+        });
+        assert_eq!(accepted, 5);
+        assert_eq!(queue.queued_count(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_sync_permit_counts_against_limit() {
+        let queue = ProofQueue::new(1);
+        let permit = queue.acquire_permit().await;
+
+        let waiting = tokio::time::timeout(std::time::Duration::from_millis(50), queue.acquire_permit()).await;
+        assert!(waiting.is_err(), "second permit granted past the limit");
+
+        drop(permit);
+        let _permit = queue.acquire_permit().await;
+    }
+}
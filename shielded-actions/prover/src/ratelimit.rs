@@ -0,0 +1,99 @@
+//! Per-client rate limit for the proving endpoints
+//!
+//! Each client IP gets a token bucket holding `RATE_LIMIT_PER_MIN` requests,
+//! refilled continuously at that rate. Without `RATE_LIMIT_PER_MIN` nothing is
+//! limited.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets kept before full (idle) ones are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets keyed on client IP
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_min: u32,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_min: u32) -> Self {
+        Self {
+            per_min: per_min.max(1),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limiter configured by RATE_LIMIT_PER_MIN, or None when unset or zero
+    pub fn from_env() -> Option<Self> {
+        std::env::var("RATE_LIMIT_PER_MIN")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|&per_min| per_min > 0)
+            .map(Self::new)
+    }
+
+    pub fn per_min(&self) -> u32 {
+        self.per_min
+    }
+
+    /// Take one request from `client`'s bucket, or return how long until one is available
+    pub fn check(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(self.per_min);
+        let per_sec = capacity / 60.0;
+        let refilled = |bucket: &Bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            (bucket.tokens + elapsed * per_sec).min(capacity)
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| refilled(bucket) < capacity);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = refilled(bucket);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_over_the_limit_is_rejected() {
+        let limiter = RateLimiter::new(3);
+        let client: IpAddr = "192.0.2.1".parse().unwrap();
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check(client, start).is_ok());
+        }
+        let retry_after = limiter.check(client, start).unwrap_err();
+        assert_eq!(retry_after.as_secs_f64().round(), 20.0);
+
+        // Other clients have buckets of their own
+        assert!(limiter.check("192.0.2.2".parse().unwrap(), start).is_ok());
+
+        // One request's worth refills every 60s / 3
+        let later = start + Duration::from_secs(21);
+        assert!(limiter.check(client, later).is_ok());
+        assert!(limiter.check(client, later).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! Secret request fields that must not reach the logs
+//!
+//! Request structs derive `Debug` and are logged whole; a field of type
+//! [`Secret`] formats as `***` there while still dereferencing to the value.
+
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::ops::Deref;
+
+/// A string, such as a nullifier key, whose `Debug` output is masked
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
+
+impl Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Request {
+        token: String,
+        nullifier_key: Secret,
+    }
+
+    #[test]
+    fn test_secret_is_masked_in_debug_output() {
+        let key = "4f1c2a9e8b7d6c5b4a3f2e1d0c9b8a7f6e5d4c3b2a1f0e9d8c7b6a5f4e3d2c1b";
+        let req: Request = serde_json::from_value(serde_json::json!({"token": "USDC", "nullifier_key": key})).unwrap();
+
+        let logged = format!("{:?}", req);
+        assert!(!logged.contains(key), "{}", logged);
+        assert!(logged.contains(r#"nullifier_key: "***""#), "{}", logged);
+        assert!(logged.contains("USDC"));
+        assert_eq!(&*req.nullifier_key, key);
+    }
+}
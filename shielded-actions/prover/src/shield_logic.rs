@@ -1,197 +1,40 @@
-//! Shield Logic - A custom resource logic circuit for ERC20 token shielding
+//! Shield Logic - the resource logic used for ERC20 token shielding
 //!
-//! This circuit allows resources to include external payloads that trigger
-//! forwarder contract calls when the proof is verified on-chain.
+//! Resources include external payloads that trigger forwarder contract calls
+//! when the proof is verified on-chain.
 //!
 //! For shield operations, the external_payload encodes:
 //! - Forwarder address
 //! - transferFrom(sender, forwarder, amount) call data
 //! - Expected output (abi.encode(true))
+//!
+//! Swaps encode exactInputSingle(params) to the Uniswap forwarder instead. It
+//! also returns abi.encode(true), whatever amount the swap fills at.
+//!
+//! The witness is the guest's `ForwarderLogicWitness`, so the payload the host
+//! builds is exactly the one the guest proves.
 
-use arm::error::ArmError;
-use arm::logic_instance::{AppData, ExpirableBlob, LogicInstance};
-use arm::nullifier_key::NullifierKey;
-use arm::resource::Resource;
-use arm::resource_logic::LogicCircuit;
-use arm::utils::bytes_to_words;
-use alloy::primitives::{Address, U256};
-use alloy::sol_types::SolValue;
-use risc0_zkvm::sha::Digest;
-use serde::{Deserialize, Serialize};
-
-/// Contract addresses on Sepolia
-pub mod contracts {
-    pub const USDC_FORWARDER: &str = "0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE";
-    pub const WETH_FORWARDER: &str = "0xD5307D777dC60b763b74945BF5A42ba93ce44e4b";
-    pub const UNISWAP_FORWARDER: &str = "0x9335Fa4A31E552378Ed29b94704c52b5635cd1AA";
-}
-
-/// DeletionCriterion::Never = 1 (persists after transaction)
-const DELETION_CRITERION_NEVER: u32 = 1;
-
-/// Shield Logic Witness - witness data for shield operations
-#[derive(Clone, Default, Serialize, Deserialize)]
-pub struct ShieldLogicWitness {
-    /// The resource being created (for shield) or consumed (for unshield)
-    pub resource: Resource,
-    /// The action tree root for merkle path verification
-    pub action_tree_root: Digest,
-    /// Whether this is a consumed (true) or created (false) resource
-    pub is_consumed: bool,
-    /// The nullifier key for computing tags
-    pub nf_key: NullifierKey,
-    /// The forwarder contract address
-    pub forwarder_address: [u8; 20],
-    /// The user/sender address for transfers
-    pub user_address: [u8; 20],
-    /// Amount to transfer (in token base units)
-    pub amount: u128,
-    /// True for shield (transferFrom), false for unshield (transfer)
-    pub is_shield: bool,
-}
-
-impl LogicCircuit for ShieldLogicWitness {
-    fn constrain(&self) -> Result<LogicInstance, ArmError> {
-        // Compute the resource tag
-        let tag = self.resource.tag(self.is_consumed, &self.nf_key)?;
-
-        // Build the external payload for the forwarder call
-        let external_payload = self.build_external_payload();
-
-        // Build the app data with the external payload
-        let app_data = AppData {
-            resource_payload: vec![],
-            discovery_payload: vec![],
-            external_payload,
-            application_payload: vec![],
-        };
-
-        Ok(LogicInstance {
-            tag,
-            is_consumed: self.is_consumed,
-            root: self.action_tree_root,
-            app_data,
-        })
-    }
-}
-
-impl ShieldLogicWitness {
-    /// Create a new shield witness for depositing tokens
-    pub fn new_shield(
-        resource: Resource,
-        action_tree_root: Digest,
-        nf_key: NullifierKey,
-        is_consumed: bool,
-        forwarder_address: [u8; 20],
-        user_address: [u8; 20],
-        amount: u128,
-    ) -> Self {
-        Self {
-            resource,
-            action_tree_root,
-            is_consumed,
-            nf_key,
-            forwarder_address,
-            user_address,
-            amount,
-            is_shield: true,
-        }
-    }
-
-    /// Create a new unshield witness for withdrawing tokens
-    pub fn new_unshield(
-        resource: Resource,
-        action_tree_root: Digest,
-        nf_key: NullifierKey,
-        is_consumed: bool,
-        forwarder_address: [u8; 20],
-        recipient_address: [u8; 20],
-        amount: u128,
-    ) -> Self {
-        Self {
-            resource,
-            action_tree_root,
-            is_consumed,
-            nf_key,
-            forwarder_address,
-            user_address: recipient_address,
-            amount,
-            is_shield: false,
-        }
-    }
-
-    /// Build the external payload for the forwarder call
-    /// Format: abi.encode(forwarderAddress, input, expectedOutput)
-    fn build_external_payload(&self) -> Vec<ExpirableBlob> {
-        // Only include external payload for created resources (not consumed)
-        // The shield operation creates a new resource, the unshield consumes it
-        if self.is_consumed && self.is_shield {
-            // Consumed resources in shield don't need forwarder calls
-            return vec![];
-        }
-        if !self.is_consumed && !self.is_shield {
-            // Created resources in unshield don't need forwarder calls
-            return vec![];
-        }
-
-        let forwarder = Address::from_slice(&self.forwarder_address);
-        let user = Address::from_slice(&self.user_address);
-        let amount = U256::from(self.amount);
-
-        // Build the ERC20 call data
-        let call_data = if self.is_shield {
-            // transferFrom(from, to, amount) - shield deposits tokens TO the forwarder
-            Self::encode_transfer_from(user, forwarder, amount)
-        } else {
-            // transfer(to, amount) - unshield withdraws tokens FROM the forwarder
-            Self::encode_transfer(user, amount)
-        };
-
-        // Expected output: abi.encode(true) for successful transfers
-        let expected_output = true.abi_encode();
-
-        // Full blob: abi.encode(forwarderAddress, input, expectedOutput)
-        let blob_data = (forwarder, call_data.clone(), expected_output.clone()).abi_encode();
-
-        let blob = ExpirableBlob {
-            blob: bytes_to_words(&blob_data),
-            deletion_criterion: DELETION_CRITERION_NEVER,
-        };
-
-        vec![blob]
-    }
+pub use forwarder_logic_witness::{encode_exact_input_single, encode_forwarder_call, Call, SwapParams};
 
-    /// Encode transferFrom(from, to, amount) call
-    fn encode_transfer_from(from: Address, to: Address, amount: U256) -> Vec<u8> {
-        // Function selector for transferFrom(address,address,uint256)
-        let selector: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
-        let mut data = selector.to_vec();
-        data.extend_from_slice(&(from, to, amount).abi_encode());
-        data
-    }
+/// Shield Logic Witness - the forwarder logic witness under its historical name
+pub type ShieldLogicWitness = forwarder_logic_witness::ForwarderLogicWitness;
 
-    /// Encode transfer(to, amount) call
-    fn encode_transfer(to: Address, amount: U256) -> Vec<u8> {
-        // Function selector for transfer(address,uint256)
-        let selector: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
-        let mut data = selector.to_vec();
-        data.extend_from_slice(&(to, amount).abi_encode());
-        data
-    }
-}
-
-/// Helper to parse address from hex string
+/// Helper to parse address from hex string, verifying a mixed-case EIP-55 checksum
+///
+/// Panics on invalid input; see [`crate::proofs::parse_checksummed_address`].
 pub fn parse_address(s: &str) -> [u8; 20] {
-    let s = s.strip_prefix("0x").unwrap_or(s);
-    let bytes = hex::decode(s).expect("Invalid hex address");
-    let mut arr = [0u8; 20];
-    arr.copy_from_slice(&bytes);
-    arr
+    crate::proofs::parse_checksummed_address(s).expect("Invalid hex address")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chain::decode_forwarder_call;
+    use crate::config::Config;
+    use arm::nullifier_key::NullifierKey;
+    use arm::resource::Resource;
+    use forwarder_logic_witness::DeletionCriterion;
+    use risc0_zkvm::sha::Digest;
 
     #[test]
     fn test_parse_address() {
@@ -200,12 +43,74 @@ mod tests {
     }
 
     #[test]
-    fn test_encode_transfer_from() {
-        let from = Address::from_slice(&parse_address("0x1234567890123456789012345678901234567890"));
-        let to = Address::from_slice(&parse_address("0x5256b82cB889f8845570b3a2f1C2af7d2F1567fE"));
-        let amount = U256::from(1000000u64);
+    fn test_payload_decodes_as_forwarder_call() {
+        let config = Config::default();
+        let witness = ShieldLogicWitness::new_shield(
+            Resource::default(),
+            Digest::default(),
+            NullifierKey::default(),
+            false,
+            parse_address(&config.tokens.get("USDC").unwrap().forwarder_address),
+            parse_address("0x1234567890123456789012345678901234567890"),
+            1000000,
+        );
+
+        let payload_call = witness.call.unwrap();
+        let blob = encode_forwarder_call(
+            witness.forwarder_address,
+            &payload_call.call_data(witness.forwarder_address),
+            &payload_call.expected_output(),
+        );
+        let call = decode_forwarder_call(&config, &blob).unwrap();
+        assert_eq!(call.forwarder_name.as_deref(), Some("USDC"));
+        assert_eq!(call.function, Some("transferFrom(address,address,uint256)"));
+    }
 
-        let encoded = ShieldLogicWitness::encode_transfer_from(from, to, amount);
-        assert_eq!(&encoded[..4], &[0x23, 0xb8, 0x72, 0xdd]);
+    #[test]
+    fn test_swap_payload_targets_uniswap_forwarder() {
+        let config = Config::default();
+        let order = crate::proofs::SwapOrder {
+            token_in: "USDC".to_string(),
+            amount_in: 1000000,
+            token_out: "WETH".to_string(),
+            min_amount_out: 250,
+            fee: crate::proofs::DEFAULT_SWAP_FEE,
+        };
+        let params = order.swap_params(&config).unwrap();
+        let witness = ShieldLogicWitness::new_swap(
+            Resource::default(),
+            Digest::default(),
+            NullifierKey::default(),
+            false,
+            parse_address(&config.uniswap_forwarder),
+            params,
+        );
+        assert_eq!(witness.build_external_payload().len(), 1);
+
+        let payload_call = witness.call.unwrap();
+        let call_data = payload_call.call_data(witness.forwarder_address);
+        assert_eq!(call_data, encode_exact_input_single(&params));
+        let blob = encode_forwarder_call(witness.forwarder_address, &call_data, &payload_call.expected_output());
+        let call = decode_forwarder_call(&config, &blob).unwrap();
+        assert_eq!(call.forwarder_name.as_deref(), Some("Uniswap"));
+        assert_eq!(call.selector, "0x04e45aaf");
+        assert!(call.function.unwrap().starts_with("exactInputSingle("));
+    }
+
+    #[test]
+    fn test_deletion_criterion_propagates() {
+        let witness = ShieldLogicWitness::new_shield(
+            Resource::default(),
+            Digest::default(),
+            NullifierKey::default(),
+            false,
+            parse_address(&Config::default().tokens.get("USDC").unwrap().forwarder_address),
+            parse_address("0x1234567890123456789012345678901234567890"),
+            1000000,
+        );
+        assert_eq!(witness.build_external_payload()[0].deletion_criterion, 1);
+
+        let witness = witness.with_deletion_criterion(DeletionCriterion::Immediately);
+        assert_eq!(witness.build_external_payload()[0].deletion_criterion, 0);
     }
 }
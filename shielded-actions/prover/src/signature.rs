@@ -0,0 +1,316 @@
+//! Sender signatures for shield requests
+//!
+//! A shield proof makes the Protocol Adapter call `transferFrom(sender, ...)`, so
+//! the service can require the request to be signed (EIP-191 personal_sign) by
+//! `sender` before it builds one. Set `REQUIRE_SENDER_SIG` to enforce this.
+//!
+//! The signed message binds everything the proof depends on: the deployment
+//! (chain id and Protocol Adapter), token, amount, sender, the commitment of the
+//! nullifier key that will own the shielded resource, and the permit. It also
+//! carries a nonce and an expiry. Each nonce is accepted once per sender, so a
+//! captured signature cannot be replayed, not even with another nullifier key.
+//!
+//! A shield may also carry the sender's EIP-2612 permit for the forwarder, which
+//! the proof bundles ahead of transferFrom so no separate approve is needed.
+
+use alloy::primitives::{Address, Signature};
+use anyhow::{anyhow, bail, Result};
+use forwarder_logic_witness::PermitData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+/// Longest a signature may stay valid; bounds how long used nonces are kept
+pub const MAX_SIGNATURE_LIFETIME_SECS: u64 = 3600;
+
+/// Longest accepted signature nonce
+pub const MAX_NONCE_LEN: usize = 128;
+
+/// EIP-2612 permit as sent by clients: `permit(sender, forwarder, value, deadline, v, r, s)`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PermitRequest {
+    /// Approved amount in smallest units (decimal string)
+    pub value: String,
+    /// Unix timestamp after which the permit is invalid
+    pub deadline: u64,
+    pub v: u8,
+    /// 32-byte hex
+    pub r: String,
+    /// 32-byte hex
+    pub s: String,
+}
+
+impl PermitRequest {
+    /// Parse into witness form, rejecting malformed or expired permits
+    pub fn to_permit_data(&self, now: u64) -> Result<PermitData> {
+        let word = |name: &str, hex_str: &str| -> Result<[u8; 32]> {
+            hex::decode(hex_str.trim().trim_start_matches("0x"))
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| anyhow!("Invalid permit {}: expected 32-byte hex", name))
+        };
+        let value = self.value.trim().parse()
+            .map_err(|e| anyhow!("Invalid permit value '{}': {}", self.value, e))?;
+        if self.deadline <= now {
+            return Err(anyhow!("Permit expired at {}", self.deadline));
+        }
+        if !matches!(self.v, 27 | 28) {
+            return Err(anyhow!("Invalid permit v {} (expected 27 or 28)", self.v));
+        }
+
+        Ok(PermitData {
+            value,
+            deadline: self.deadline,
+            v: self.v,
+            r: word("r", &self.r)?,
+            s: word("s", &self.s)?,
+        })
+    }
+}
+
+impl From<&PermitData> for PermitRequest {
+    fn from(permit: &PermitData) -> Self {
+        Self {
+            value: permit.value.to_string(),
+            deadline: permit.deadline,
+            v: permit.v,
+            r: format!("0x{}", hex::encode(permit.r)),
+            s: format!("0x{}", hex::encode(permit.s)),
+        }
+    }
+}
+
+/// Whether REQUIRE_SENDER_SIG is set to a truthy value
+pub fn sender_sig_required_from_env() -> bool {
+    std::env::var("REQUIRE_SENDER_SIG")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// A shield request as authorized by its sender's signature
+#[derive(Debug, Clone)]
+pub struct ShieldAuthorization<'a> {
+    pub chain_id: u64,
+    pub protocol_adapter: &'a str,
+    pub token: &'a str,
+    pub amount: &'a str,
+    pub sender: &'a str,
+    /// Commitment of the nullifier key owning the shielded resource; None for
+    /// batch entries, whose resources are not owned by a client key
+    pub nk_commitment: Option<&'a str>,
+    pub permit: Option<&'a PermitData>,
+    /// Chosen by the client; accepted once per sender
+    pub nonce: &'a str,
+    /// Unix timestamp after which the signature is invalid
+    pub expiry: u64,
+}
+
+impl ShieldAuthorization<'_> {
+    /// Message the sender signs
+    pub fn message(&self) -> String {
+        let permit = match self.permit {
+            Some(permit) => format!(
+                "{} {} {} 0x{} 0x{}",
+                permit.value,
+                permit.deadline,
+                permit.v,
+                hex::encode(permit.r),
+                hex::encode(permit.s)
+            ),
+            None => "none".to_string(),
+        };
+        format!(
+            "Shielded Actions shield request\n\
+             chain_id: {}\n\
+             protocol_adapter: {}\n\
+             token: {}\n\
+             amount: {}\n\
+             sender: {}\n\
+             nullifier_key_commitment: {}\n\
+             permit: {}\n\
+             nonce: {}\n\
+             expiry: {}",
+            self.chain_id,
+            self.protocol_adapter.trim().to_lowercase(),
+            self.token.to_uppercase(),
+            self.amount.trim(),
+            self.sender.trim().to_lowercase(),
+            self.nk_commitment.map(|cm| cm.trim_start_matches("0x").to_lowercase()).unwrap_or_else(|| "none".to_string()),
+            permit,
+            self.nonce,
+            self.expiry
+        )
+    }
+}
+
+/// Signature nonces already accepted, per sender, kept until their expiry
+#[derive(Debug, Default)]
+pub struct UsedNonces {
+    entries: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl UsedNonces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark `nonce` used by `sender` until `expiry`, failing if it already is
+    pub fn claim(&self, sender: &str, nonce: &str, expiry: u64, now: u64) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, expires| *expires >= now);
+        let key = (sender.trim().to_lowercase(), nonce.to_string());
+        if entries.contains_key(&key) {
+            bail!("Signature nonce '{}' was already used", nonce);
+        }
+        entries.insert(key, expiry);
+        Ok(())
+    }
+}
+
+/// Verify a signed shield authorization and consume its nonce
+///
+/// Rejects malformed nonces, expired signatures and ones valid for longer than
+/// [`MAX_SIGNATURE_LIFETIME_SECS`], signatures not made by the sender, and
+/// nonces the sender has already used.
+pub fn verify_shield_authorization(auth: &ShieldAuthorization, signature: &str, used: &UsedNonces, now: u64) -> Result<()> {
+    if auth.nonce.is_empty() || auth.nonce.len() > MAX_NONCE_LEN || !auth.nonce.bytes().all(|b| b.is_ascii_graphic()) {
+        bail!("Signature nonce must be 1 to {} visible ASCII characters", MAX_NONCE_LEN);
+    }
+    if auth.expiry <= now {
+        bail!("Signature expired at {}", auth.expiry);
+    }
+    if auth.expiry > now + MAX_SIGNATURE_LIFETIME_SECS {
+        bail!("Signature expiry must be at most {} seconds away", MAX_SIGNATURE_LIFETIME_SECS);
+    }
+    verify_sender_signature(&auth.message(), signature, auth.sender)?;
+    used.claim(auth.sender, auth.nonce, auth.expiry, now)
+}
+
+/// Check that `signature` is an EIP-191 signature of `message` by `sender`
+pub fn verify_sender_signature(message: &str, signature: &str, sender: &str) -> Result<()> {
+    let sender: Address = sender.trim().parse()
+        .map_err(|e| anyhow!("Invalid sender address: {}", e))?;
+    let signature: Signature = signature.trim().parse()
+        .map_err(|e| anyhow!("Invalid signature: {}", e))?;
+
+    let signer = signature.recover_address_from_msg(message)
+        .map_err(|e| anyhow!("Could not recover signer: {}", e))?;
+
+    if signer != sender {
+        return Err(anyhow!("Signature was made by {}, not sender {}", signer, sender));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::signers::SignerSync;
+
+    fn sign(signer: &PrivateKeySigner, message: &str) -> String {
+        let signature = signer.sign_message_sync(message.as_bytes()).unwrap();
+        format!("0x{}", hex::encode(signature.as_bytes()))
+    }
+
+    const NOW: u64 = 1_000;
+
+    fn authorization<'a>(sender: &'a str, nk_commitment: &'a str) -> ShieldAuthorization<'a> {
+        ShieldAuthorization {
+            chain_id: 11155111,
+            protocol_adapter: "0x08c3bdc46B115cDc71Df076d9De96EeEBaa98525",
+            token: "USDC",
+            amount: "1.5",
+            sender,
+            nk_commitment: Some(nk_commitment),
+            permit: None,
+            nonce: "order-1",
+            expiry: NOW + 600,
+        }
+    }
+
+    #[test]
+    fn test_valid_signature() {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address().to_string();
+        let auth = authorization(&sender, "aa");
+
+        let signature = sign(&signer, &auth.message());
+        assert!(verify_shield_authorization(&auth, &signature, &UsedNonces::new(), NOW).is_ok());
+    }
+
+    #[test]
+    fn test_forged_signature_rejected() {
+        let victim = PrivateKeySigner::random().address().to_string();
+        let attacker = PrivateKeySigner::random();
+        let auth = authorization(&victim, "aa");
+
+        let err = verify_shield_authorization(&auth, &sign(&attacker, &auth.message()), &UsedNonces::new(), NOW).unwrap_err();
+        assert!(err.to_string().contains("not sender"));
+    }
+
+    #[test]
+    fn test_signature_bound_to_request() {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address().to_string();
+        let auth = authorization(&sender, "aa");
+        let signature = sign(&signer, &auth.message());
+        let used = UsedNonces::new();
+
+        // Replaying it with another amount, nullifier key, deployment or permit fails
+        let permit = PermitData { value: 1, deadline: NOW + 60, v: 27, r: [1; 32], s: [2; 32] };
+        for tampered in [
+            ShieldAuthorization { amount: "1000", ..auth.clone() },
+            ShieldAuthorization { nk_commitment: Some("bb"), ..auth.clone() },
+            ShieldAuthorization { chain_id: 1, ..auth.clone() },
+            ShieldAuthorization { protocol_adapter: "0x0000000000000000000000000000000000000001", ..auth.clone() },
+            ShieldAuthorization { permit: Some(&permit), ..auth.clone() },
+        ] {
+            assert!(verify_shield_authorization(&tampered, &signature, &used, NOW).is_err());
+        }
+    }
+
+    #[test]
+    fn test_nonce_used_once_and_expiry_enforced() {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address().to_string();
+        let auth = authorization(&sender, "aa");
+        let signature = sign(&signer, &auth.message());
+        let used = UsedNonces::new();
+
+        verify_shield_authorization(&auth, &signature, &used, NOW).unwrap();
+        let err = verify_shield_authorization(&auth, &signature, &used, NOW + 1).unwrap_err();
+        assert!(err.to_string().contains("already used"));
+        // The nonce is per sender
+        assert!(used.claim("0x0000000000000000000000000000000000000002", "order-1", NOW + 600, NOW).is_ok());
+
+        let err = verify_shield_authorization(&auth, &signature, &UsedNonces::new(), auth.expiry).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+        let long_lived = ShieldAuthorization { expiry: NOW + MAX_SIGNATURE_LIFETIME_SECS + 1, ..auth.clone() };
+        let signature = sign(&signer, &long_lived.message());
+        assert!(verify_shield_authorization(&long_lived, &signature, &UsedNonces::new(), NOW).is_err());
+        let no_nonce = ShieldAuthorization { nonce: "", ..auth };
+        let signature = sign(&signer, &no_nonce.message());
+        assert!(verify_shield_authorization(&no_nonce, &signature, &UsedNonces::new(), NOW).is_err());
+    }
+
+    #[test]
+    fn test_permit_round_trips_and_rejects_bad_fields() {
+        let request = PermitRequest {
+            value: "1000000".to_string(),
+            deadline: 2_000,
+            v: 27,
+            r: format!("0x{}", "aa".repeat(32)),
+            s: "bb".repeat(32),
+        };
+        let permit = request.to_permit_data(1_000).unwrap();
+        assert_eq!((permit.value, permit.r, permit.s), (1_000_000, [0xaa; 32], [0xbb; 32]));
+        assert_eq!(PermitRequest::from(&permit).to_permit_data(1_000).unwrap(), permit);
+
+        assert!(request.to_permit_data(2_000).unwrap_err().to_string().contains("expired"));
+        assert!(PermitRequest { v: 1, ..request.clone() }.to_permit_data(1_000).is_err());
+        assert!(PermitRequest { r: "0x1234".to_string(), ..request.clone() }.to_permit_data(1_000).is_err());
+        assert!(PermitRequest { value: "1.5".to_string(), ..request }.to_permit_data(1_000).is_err());
+    }
+}
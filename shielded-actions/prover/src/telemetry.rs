@@ -0,0 +1,69 @@
+//! Prometheus metrics served by /metrics
+//!
+//! - `proofs_total{action,status}`: finished proofs, `status` being "completed" or "failed"
+//! - `proof_duration_seconds{action}`: histogram of proof generation time
+//! - `jobs_active`: jobs whose proving task is running
+//! - `jobs_in_queue`: jobs waiting for a proving slot
+
+use anyhow::{anyhow, Result};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle, PrometheusRecorder};
+use std::time::Duration;
+
+pub const PROOFS_TOTAL: &str = "proofs_total";
+pub const PROOF_DURATION_SECONDS: &str = "proof_duration_seconds";
+pub const JOBS_ACTIVE: &str = "jobs_active";
+pub const JOBS_IN_QUEUE: &str = "jobs_in_queue";
+
+/// Histogram buckets in seconds: mock proofs finish in milliseconds, Groth16 in ~7 minutes
+const PROOF_DURATION_BUCKETS: [f64; 11] = [0.1, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1800.0];
+
+fn build_recorder() -> Result<PrometheusRecorder> {
+    Ok(PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(PROOF_DURATION_SECONDS.to_string()), &PROOF_DURATION_BUCKETS)
+        .map_err(|e| anyhow!("Invalid proof duration buckets: {}", e))?
+        .build_recorder())
+}
+
+/// Install the global metrics recorder; the handle renders the /metrics page
+pub fn install() -> Result<PrometheusHandle> {
+    let recorder = build_recorder()?;
+    let handle = recorder.handle();
+    metrics::set_global_recorder(recorder).map_err(|e| anyhow!("Failed to install metrics recorder: {}", e))?;
+    Ok(handle)
+}
+
+/// Count a finished proof of `action` and observe how long it took
+pub fn record_proof<T, E>(action: &'static str, result: &Result<T, E>, duration: Duration) {
+    let status = if result.is_ok() { "completed" } else { "failed" };
+    metrics::counter!(PROOFS_TOTAL, "action" => action, "status" => status).increment(1);
+    metrics::histogram!(PROOF_DURATION_SECONDS, "action" => action).record(duration.as_secs_f64());
+}
+
+/// Set the job gauges, right before the metrics are rendered
+pub fn set_job_gauges(active: usize, queued: usize) {
+    metrics::gauge!(JOBS_ACTIVE).set(active as f64);
+    metrics::gauge!(JOBS_IN_QUEUE).set(queued as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proofs_are_counted_and_timed() {
+        let recorder = build_recorder().unwrap();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            record_proof("shield", &Ok::<_, ()>(()), Duration::from_secs(420));
+            record_proof("shield", &Err::<(), _>(()), Duration::from_secs(2));
+            set_job_gauges(1, 3);
+        });
+
+        let rendered = handle.render();
+        assert!(rendered.contains(r#"proofs_total{action="shield",status="completed"} 1"#), "{}", rendered);
+        assert!(rendered.contains(r#"proofs_total{action="shield",status="failed"} 1"#), "{}", rendered);
+        assert!(rendered.contains(r#"proof_duration_seconds_bucket{action="shield",le="600"} 2"#), "{}", rendered);
+        assert!(rendered.contains(r#"proof_duration_seconds_bucket{action="shield",le="300"} 1"#), "{}", rendered);
+        assert!(rendered.contains("jobs_in_queue 3"), "{}", rendered);
+    }
+}
@@ -0,0 +1,213 @@
+//! Supported tokens
+//!
+//! Every token the prover can shield or unshield is looked up here, keyed on
+//! its uppercase symbol. Deployments ship with USDC and WETH; `TOKEN_CONFIG`
+//! adds tokens or overrides fields of the built-in ones as a JSON map, so
+//! supporting another token is a config change:
+//!
+//! `{"DAI": {"decimals": 18, "forwarder_address": "0x…", "erc20_address": "0x…"}}`
+
+use alloy::primitives::Address;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A token and the forwarder that moves it for the Protocol Adapter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub forwarder_address: String,
+    pub erc20_address: String,
+    pub decimals: u32,
+    /// The token takes a fee on transfer, so the amount credited is less than the amount sent
+    pub fee_on_transfer: bool,
+    /// The forwarder accepts the EIP-2612 `permit` call; forwarders deployed
+    /// before permit support revert it with `UnsupportedSelector`
+    pub forwarder_permit: bool,
+    /// Largest amount one request may move, in smallest units (None: any that fits in a u128)
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_decimal")]
+    pub max_amount: Option<u128>,
+}
+
+/// Amounts are serialized as decimal strings, as they may exceed 2^53
+fn serialize_decimal<S: serde::Serializer>(amount: &Option<u128>, serializer: S) -> Result<S::Ok, S::Error> {
+    match amount {
+        Some(amount) => serializer.serialize_str(&amount.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl TokenInfo {
+    pub fn new(symbol: &str, forwarder_address: &str, erc20_address: &str, decimals: u32) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            forwarder_address: forwarder_address.to_string(),
+            erc20_address: erc20_address.to_string(),
+            decimals,
+            fee_on_transfer: false,
+            forwarder_permit: false,
+            max_amount: None,
+        }
+    }
+}
+
+/// One TOKEN_CONFIG entry; unset fields keep the built-in value
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenOverride {
+    decimals: Option<u32>,
+    fee_on_transfer: Option<bool>,
+    forwarder_permit: Option<bool>,
+    forwarder_address: Option<String>,
+    erc20_address: Option<String>,
+    /// Decimal string of smallest units, as amounts may exceed 2^53
+    max_amount: Option<String>,
+}
+
+/// Tokens by uppercase symbol
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<String, TokenInfo>,
+}
+
+impl TokenRegistry {
+    pub fn new(tokens: impl IntoIterator<Item = TokenInfo>) -> Self {
+        Self {
+            tokens: tokens.into_iter().map(|token| (token.symbol.clone(), token)).collect(),
+        }
+    }
+
+    /// Look up a token by symbol (case-insensitive)
+    pub fn get(&self, symbol: &str) -> Result<&TokenInfo> {
+        self.tokens.get(&symbol.to_uppercase()).ok_or_else(|| {
+            anyhow!("Unknown token: {}. Supported: {}", symbol, self.symbols().collect::<Vec<_>>().join(", "))
+        })
+    }
+
+    /// Symbols of every registered token, sorted
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.tokens.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TokenInfo> {
+        self.tokens.values()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut TokenInfo> {
+        self.tokens.values_mut()
+    }
+
+    /// The token whose forwarder is at `address`
+    pub fn by_forwarder(&self, address: &Address) -> Option<&TokenInfo> {
+        let address = address.to_string();
+        self.iter().find(|token| token.forwarder_address.eq_ignore_ascii_case(&address))
+    }
+
+    /// The token whose ERC20 contract is at `address`
+    pub fn by_erc20(&self, address: &Address) -> Option<&TokenInfo> {
+        let address = address.to_string();
+        self.iter().find(|token| token.erc20_address.eq_ignore_ascii_case(&address))
+    }
+
+    /// Apply a TOKEN_CONFIG JSON map
+    ///
+    /// New tokens must set `decimals`, `forwarder_address` and `erc20_address`;
+    /// `max_amount` is optional for any token.
+    pub fn apply_overrides(&mut self, raw: &str) -> Result<()> {
+        let overrides: HashMap<String, TokenOverride> =
+            serde_json::from_str(raw).map_err(|e| anyhow!("Invalid TOKEN_CONFIG: {}", e))?;
+
+        for (symbol, entry) in overrides {
+            let symbol = symbol.to_uppercase();
+            let token = match self.tokens.remove(&symbol) {
+                Some(mut token) => {
+                    if let Some(decimals) = entry.decimals {
+                        token.decimals = decimals;
+                    }
+                    if let Some(forwarder_address) = entry.forwarder_address {
+                        token.forwarder_address = forwarder_address;
+                    }
+                    if let Some(erc20_address) = entry.erc20_address {
+                        token.erc20_address = erc20_address;
+                    }
+                    token
+                }
+                None => {
+                    let missing = |field: &str| anyhow!("TOKEN_CONFIG entry for new token {} must set {}", symbol, field);
+                    TokenInfo::new(
+                        &symbol,
+                        &entry.forwarder_address.ok_or_else(|| missing("forwarder_address"))?,
+                        &entry.erc20_address.ok_or_else(|| missing("erc20_address"))?,
+                        entry.decimals.ok_or_else(|| missing("decimals"))?,
+                    )
+                }
+            };
+            let fee_on_transfer = entry.fee_on_transfer.unwrap_or(token.fee_on_transfer);
+            let forwarder_permit = entry.forwarder_permit.unwrap_or(token.forwarder_permit);
+            let max_amount = match entry.max_amount {
+                Some(max) => Some(max.trim().parse::<u128>().ok().filter(|max| *max > 0).ok_or_else(|| {
+                    anyhow!("TOKEN_CONFIG max_amount for {} must be a positive decimal string of smallest units, got '{}'", symbol, max)
+                })?),
+                None => token.max_amount,
+            };
+            self.tokens.insert(symbol, TokenInfo { fee_on_transfer, forwarder_permit, max_amount, ..token });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORWARDER: &str = "0x1111111111111111111111111111111111111111";
+    const ERC20: &str = "0x2222222222222222222222222222222222222222";
+
+    fn registry() -> TokenRegistry {
+        TokenRegistry::new([TokenInfo::new("usdc", FORWARDER, ERC20, 6)])
+    }
+
+    #[test]
+    fn test_get_is_case_insensitive_and_rejects_unknown() {
+        let registry = registry();
+        assert_eq!(registry.get("Usdc").unwrap().decimals, 6);
+        let err = registry.get("DAI").unwrap_err().to_string();
+        assert!(err.contains("Unknown token: DAI") && err.contains("USDC"));
+        assert_eq!(registry.by_forwarder(&FORWARDER.parse().unwrap()).unwrap().symbol, "USDC");
+    }
+
+    #[test]
+    fn test_overrides_add_and_update_tokens() {
+        let mut registry = registry();
+        registry
+            .apply_overrides(&format!(
+                r#"{{"usdc": {{"fee_on_transfer": true, "forwarder_permit": true}}, "DAI": {{"decimals": 18, "forwarder_address": "{}", "erc20_address": "{}"}}}}"#,
+                FORWARDER, ERC20
+            ))
+            .unwrap();
+
+        let usdc = registry.get("USDC").unwrap();
+        assert_eq!(usdc.decimals, 6);
+        assert!(usdc.fee_on_transfer);
+        assert!(usdc.forwarder_permit);
+        assert_eq!(registry.get("dai").unwrap().decimals, 18);
+        assert!(!registry.get("dai").unwrap().forwarder_permit);
+        assert_eq!(usdc.max_amount, None);
+
+        registry.apply_overrides(r#"{"USDC": {"max_amount": "1000000000000"}}"#).unwrap();
+        assert_eq!(registry.get("USDC").unwrap().max_amount, Some(1_000_000_000_000));
+        assert!(registry.get("USDC").unwrap().fee_on_transfer);
+        for bad in ["0", "-1", "1.5", "lots"] {
+            assert!(registry.apply_overrides(&format!(r#"{{"USDC": {{"max_amount": "{}"}}}}"#, bad)).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_new_token_needs_addresses() {
+        let mut registry = registry();
+        let err = registry.apply_overrides(r#"{"DAI": {"decimals": 18}}"#).unwrap_err();
+        assert!(err.to_string().contains("forwarder_address"));
+        assert!(registry.apply_overrides(r#"{"USDC": {"decimal": 6}}"#).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Job completion webhooks
+//!
+//! A proof request can carry a `callback_url` that receives the final job JSON
+//! as a POST. When WEBHOOK_SECRET is set, the body is signed with HMAC-SHA256
+//! and the signature sent as `X-Signature: sha256=<hex>`, so receivers can
+//! check it came from this prover.
+//!
+//! Callbacks may only reach public addresses, checked after DNS resolution, so
+//! a request cannot point the prover at loopback, private or link-local
+//! services. Hosts listed in WEBHOOK_ALLOWED_HOSTS are exempt. Redirects are
+//! not followed, and delivery connects to the addresses that were checked.
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Header carrying the body signature
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Delivery attempts before giving up
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled after each failure
+const WEBHOOK_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Timeout of a single delivery attempt
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Secret used to sign webhook bodies (WEBHOOK_SECRET)
+pub fn webhook_secret_from_env() -> Option<String> {
+    std::env::var("WEBHOOK_SECRET").ok().filter(|v| !v.is_empty())
+}
+
+/// Callback hosts that may resolve to non-public addresses (comma-separated WEBHOOK_ALLOWED_HOSTS)
+pub fn allowed_hosts_from_env() -> Vec<String> {
+    std::env::var("WEBHOOK_ALLOWED_HOSTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|host| host.trim().to_ascii_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// Reject callback URLs that are not absolute http(s) URLs of a public host
+pub async fn validate_callback_url(url: &str, allowed_hosts: &[String]) -> Result<()> {
+    resolve_callback(url, allowed_hosts).await.map(|_| ())
+}
+
+/// Parse a callback URL and resolve its host
+///
+/// Every resolved address must be public unless the host is allow-listed.
+async fn resolve_callback(url: &str, allowed_hosts: &[String]) -> Result<(Url, Vec<SocketAddr>)> {
+    let parsed = Url::parse(url).map_err(|e| anyhow!("Invalid callback_url '{}': {}", url, e))?;
+    match parsed.scheme() {
+        "http" | "https" => {}
+        scheme => return Err(anyhow!("callback_url must use http or https, got '{}'", scheme)),
+    }
+    let host = bare_host(&parsed).ok_or_else(|| anyhow!("callback_url has no host"))?;
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| anyhow!("Could not resolve callback_url host '{}': {}", host, e))?
+            .collect(),
+    };
+    if addrs.is_empty() {
+        return Err(anyhow!("callback_url host '{}' did not resolve", host));
+    }
+
+    if !allowed_hosts.iter().any(|allowed| allowed == host) {
+        if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            return Err(anyhow!(
+                "callback_url host '{}' resolves to non-public address {}; add it to WEBHOOK_ALLOWED_HOSTS to allow it",
+                host,
+                addr.ip()
+            ));
+        }
+    }
+    Ok((parsed, addrs))
+}
+
+/// Host of a URL, without the brackets of an IPv6 literal
+fn bare_host(url: &Url) -> Option<&str> {
+    url.host_str().map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+}
+
+/// Whether `ip` is routable on the public internet
+///
+/// Rejects loopback, RFC 1918 and unique-local, link-local (cloud metadata
+/// endpoints live at 169.254.169.254), carrier-grade NAT, unspecified and
+/// broadcast addresses.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// `sha256=<hex HMAC-SHA256 of body>`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POST `payload` to `url`, retrying with exponential backoff
+///
+/// The host is resolved and checked again, and the client pinned to the
+/// checked addresses, so a DNS change since the request cannot redirect it.
+/// Returns whether the receiver answered with a 2xx status.
+pub async fn deliver(url: &str, payload: &serde_json::Value, secret: Option<&str>, allowed_hosts: &[String]) -> bool {
+    let (url, addrs) = match resolve_callback(url, allowed_hosts).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            warn!("Not delivering webhook: {}", e);
+            return false;
+        }
+    };
+    let mut builder = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    // Connect to the addresses just checked rather than resolving the host again
+    if let Some(domain) = bare_host(&url).filter(|host| host.parse::<IpAddr>().is_err()) {
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to build webhook client: {}", e);
+            return false;
+        }
+    };
+
+    let body = payload.to_string();
+    let mut backoff = WEBHOOK_INITIAL_BACKOFF;
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let mut request = client
+            .post(url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(secret) = secret {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, body.as_bytes()));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                info!("Delivered webhook to {} (attempt {})", url, attempt);
+                return true;
+            }
+            Ok(response) => warn!("Webhook to {} returned {} (attempt {})", url, response.status(), attempt),
+            Err(e) => warn!("Webhook to {} failed (attempt {}): {}", url, attempt, e),
+        }
+
+        if attempt < WEBHOOK_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_callback_url() {
+        assert!(validate_callback_url("https://8.8.8.8/hooks/proof", &[]).await.is_ok());
+        assert!(validate_callback_url("ftp://example.com/cb", &[]).await.is_err());
+        assert!(validate_callback_url("not a url", &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_callback_url_must_be_public() {
+        for url in [
+            "http://localhost:8080/cb",
+            "http://127.0.0.1/cb",
+            "http://10.0.0.5/cb",
+            "http://192.168.1.1/cb",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://0.0.0.0:3000/cb",
+            "http://[::1]/cb",
+            "http://[::ffff:127.0.0.1]/cb",
+            "http://[fd00::1]/cb",
+        ] {
+            assert!(validate_callback_url(url, &[]).await.is_err(), "{} accepted", url);
+        }
+
+        let allowed = vec!["localhost".to_string(), "::1".to_string()];
+        assert!(validate_callback_url("http://localhost:8080/cb", &allowed).await.is_ok());
+        assert!(validate_callback_url("http://[::1]/cb", &allowed).await.is_ok());
+        assert!(validate_callback_url("http://127.0.0.1/cb", &allowed).await.is_err());
+    }
+}
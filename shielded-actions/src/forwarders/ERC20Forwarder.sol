@@ -2,6 +2,7 @@
 pragma solidity ^0.8.30;
 
 import {IERC20} from "@openzeppelin-contracts/token/ERC20/IERC20.sol";
+import {IERC20Permit} from "@openzeppelin-contracts/token/ERC20/extensions/IERC20Permit.sol";
 import {SafeERC20} from "@openzeppelin-contracts/token/ERC20/utils/SafeERC20.sol";
 import {Ownable} from "@openzeppelin-contracts/access/Ownable.sol";
 
@@ -34,6 +35,9 @@ contract ERC20Forwarder is IForwarder, Ownable {
     /// @notice Error thrown when recipient validation fails
     error InvalidRecipient(address expected, address actual);
 
+    /// @notice Error thrown when a permit is rejected and no equivalent allowance exists
+    error PermitFailed(address owner);
+
     constructor(address _protocolAdapter, address _token) Ownable(_protocolAdapter) {
         protocolAdapter = _protocolAdapter;
         token = IERC20(_token);
@@ -41,7 +45,8 @@ contract ERC20Forwarder is IForwarder, Ownable {
 
     /// @inheritdoc IForwarder
     /// @notice Forward a call - only callable by Protocol Adapter
-    /// @param input The encoded function call (transferFrom for deposit, transfer for withdraw)
+    /// @param input The encoded function call (transferFrom for deposit, transfer for withdraw,
+    ///              permit to approve a deposit without a separate transaction)
     /// @return output The call result (encoded bool)
     function forwardCall(
         bytes32, /* logicRef */
@@ -74,6 +79,23 @@ contract ERC20Forwarder is IForwarder, Ownable {
             token.safeTransfer(to, amount);
             emit Withdrawn(to, amount);
 
+            output = abi.encode(true);
+        } else if (selector == IERC20Permit.permit.selector) {
+            // EIP-2612 approval of this forwarder, bundled ahead of a deposit
+            (address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) =
+                abi.decode(input[4:], (address, address, uint256, uint256, uint8, bytes32, bytes32));
+
+            if (spender != address(this)) {
+                revert InvalidRecipient(address(this), spender);
+            }
+
+            // A front-run permit still leaves the approval in place, so only fail without it
+            try IERC20Permit(address(token)).permit(owner, spender, value, deadline, v, r, s) {}
+            catch {
+                if (token.allowance(owner, address(this)) < value) {
+                    revert PermitFailed(owner);
+                }
+            }
             output = abi.encode(true);
         } else {
             revert UnsupportedSelector(selector);
@@ -72,8 +72,12 @@ contract UniswapV3Forwarder is IForwarder {
 
     /// @inheritdoc IForwarder
     /// @notice Execute a swap via Uniswap V3
+    /// @dev The Protocol Adapter compares the output byte for byte with the one the
+    ///      resource committed to, which cannot know the filled amount in advance.
+    ///      Like ERC20 forwarder calls, a swap therefore returns abi.encode(true) once
+    ///      its bound (amountOutMinimum or amountInMaximum) holds; amounts are in SwapExecuted.
     /// @param input Encoded swap parameters
-    /// @return output The amount of tokens received (encoded)
+    /// @return output abi.encode(true)
     function forwardCall(
         bytes32, /* logicRef */
         bytes calldata input
@@ -94,10 +98,13 @@ contract UniswapV3Forwarder is IForwarder {
 
             // Execute swap
             uint256 amountOut = swapRouter.exactInputSingle(params);
+            if (amountOut < params.amountOutMinimum) {
+                revert InsufficientOutputAmount(params.amountOutMinimum, amountOut);
+            }
 
             emit SwapExecuted(params.tokenIn, params.tokenOut, params.amountIn, amountOut, params.recipient);
 
-            output = abi.encode(amountOut);
+            output = abi.encode(true);
         } else if (selector == this.exactOutputSingle.selector) {
             // Decode ExactOutputSingleParams
             ISwapRouter02.ExactOutputSingleParams memory params =
@@ -114,7 +121,7 @@ contract UniswapV3Forwarder is IForwarder {
 
             emit SwapExecuted(params.tokenIn, params.tokenOut, amountIn, params.amountOut, params.recipient);
 
-            output = abi.encode(amountIn);
+            output = abi.encode(true);
         } else {
             revert UnsupportedSelector(selector);
         }
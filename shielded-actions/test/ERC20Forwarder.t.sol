@@ -4,6 +4,8 @@ pragma solidity ^0.8.30;
 import {Test, console} from "forge-std/Test.sol";
 import {IERC20} from "@openzeppelin-contracts/token/ERC20/IERC20.sol";
 import {ERC20} from "@openzeppelin-contracts/token/ERC20/ERC20.sol";
+import {ERC20Permit} from "@openzeppelin-contracts/token/ERC20/extensions/ERC20Permit.sol";
+import {IERC20Permit} from "@openzeppelin-contracts/token/ERC20/extensions/IERC20Permit.sol";
 
 import {ERC20Forwarder} from "../src/forwarders/ERC20Forwarder.sol";
 
@@ -16,6 +18,15 @@ contract MockERC20 is ERC20 {
     }
 }
 
+/// @notice Mock EIP-2612 token for testing
+contract MockERC20Permit is ERC20Permit {
+    constructor(string memory name, string memory symbol) ERC20(name, symbol) ERC20Permit(name) {}
+
+    function mint(address to, uint256 amount) external {
+        _mint(to, amount);
+    }
+}
+
 contract ERC20ForwarderTest is Test {
     ERC20Forwarder public forwarder;
     MockERC20 public token;
@@ -160,4 +171,65 @@ contract ERC20ForwarderTest is Test {
         assertEq(abi.decode(output, (bool)), true);
         assertEq(token.balanceOf(address(forwarder)), amount);
     }
+
+    function _signPermit(MockERC20Permit permitToken, uint256 ownerKey, address spender, uint256 value, uint256 deadline)
+        internal
+        view
+        returns (bytes memory input)
+    {
+        address owner = vm.addr(ownerKey);
+        bytes32 structHash = keccak256(
+            abi.encode(
+                keccak256("Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)"),
+                owner,
+                spender,
+                value,
+                permitToken.nonces(owner),
+                deadline
+            )
+        );
+        bytes32 digest = keccak256(abi.encodePacked("\x19\x01", permitToken.DOMAIN_SEPARATOR(), structHash));
+        (uint8 v, bytes32 r, bytes32 s) = vm.sign(ownerKey, digest);
+        input = abi.encodeWithSelector(IERC20Permit.permit.selector, owner, spender, value, deadline, v, r, s);
+    }
+
+    function test_permit_then_deposit() public {
+        MockERC20Permit permitToken = new MockERC20Permit("Permit Token", "PRMT");
+        ERC20Forwarder permitForwarder = new ERC20Forwarder(protocolAdapter, address(permitToken));
+        uint256 ownerKey = 0xA11CE;
+        address owner = vm.addr(ownerKey);
+        permitToken.mint(owner, INITIAL_BALANCE);
+
+        // No prior approve: the permit blob runs first
+        bytes memory permitInput =
+            _signPermit(permitToken, ownerKey, address(permitForwarder), DEPOSIT_AMOUNT, block.timestamp + 1 hours);
+        vm.prank(protocolAdapter);
+        bytes memory output = permitForwarder.forwardCall(bytes32(0), permitInput);
+        assertEq(abi.decode(output, (bool)), true);
+        assertEq(permitToken.allowance(owner, address(permitForwarder)), DEPOSIT_AMOUNT);
+
+        bytes memory depositInput = abi.encodeWithSelector(
+            IERC20.transferFrom.selector, owner, address(permitForwarder), DEPOSIT_AMOUNT
+        );
+        vm.prank(protocolAdapter);
+        permitForwarder.forwardCall(bytes32(0), depositInput);
+        assertEq(permitToken.balanceOf(address(permitForwarder)), DEPOSIT_AMOUNT);
+
+        // Replaying the used permit succeeds only while the allowance covers it
+        vm.prank(protocolAdapter);
+        vm.expectRevert(abi.encodeWithSelector(ERC20Forwarder.PermitFailed.selector, owner));
+        permitForwarder.forwardCall(bytes32(0), permitInput);
+    }
+
+    function test_revert_permit_wrongSpender() public {
+        MockERC20Permit permitToken = new MockERC20Permit("Permit Token", "PRMT");
+        ERC20Forwarder permitForwarder = new ERC20Forwarder(protocolAdapter, address(permitToken));
+
+        bytes memory input = _signPermit(permitToken, 0xA11CE, recipient, DEPOSIT_AMOUNT, block.timestamp + 1 hours);
+        vm.prank(protocolAdapter);
+        vm.expectRevert(
+            abi.encodeWithSelector(ERC20Forwarder.InvalidRecipient.selector, address(permitForwarder), recipient)
+        );
+        permitForwarder.forwardCall(bytes32(0), input);
+    }
 }
@@ -103,10 +103,8 @@ contract UniswapV3ForwarderTest is Test {
         vm.prank(protocolAdapter);
         bytes memory output = forwarder.forwardCall(bytes32(0), input);
 
-        uint256 amountOut = abi.decode(output, (uint256));
-
-        // Verify output amount
-        assertEq(amountOut, router.mockAmountOut());
+        // Fixed success value, whatever the fill
+        assertEq(output, abi.encode(true));
 
         // Verify recipient received tokens
         assertEq(tokenOut.balanceOf(recipient), router.mockAmountOut());
@@ -137,15 +135,61 @@ contract UniswapV3ForwarderTest is Test {
         vm.prank(protocolAdapter);
         bytes memory output = forwarder.forwardCall(bytes32(0), input);
 
-        uint256 amountIn = abi.decode(output, (uint256));
+        assertEq(output, abi.encode(true));
 
         // Verify amount spent
-        assertEq(amountIn, 550 ether);
+        assertEq(tokenIn.balanceOf(address(forwarder)), INITIAL_BALANCE - 550 ether);
 
         // Verify recipient received exact output
         assertEq(tokenOut.balanceOf(recipient), desiredOutput);
     }
 
+    function test_exactInputSingle_outputIndependentOfFill() public {
+        ISwapRouter02.ExactInputSingleParams memory params = ISwapRouter02.ExactInputSingleParams({
+            tokenIn: address(tokenIn),
+            tokenOut: address(tokenOut),
+            fee: 3000,
+            recipient: recipient,
+            amountIn: SWAP_AMOUNT,
+            amountOutMinimum: 900 ether,
+            sqrtPriceLimitX96: 0
+        });
+        bytes memory input = abi.encodeWithSelector(forwarder.exactInputSingle.selector, params);
+
+        // The prover commits to one expected output, so a fill at the minimum
+        // and one above it must return the same bytes
+        router.setMockAmounts(900 ether, 0);
+        vm.prank(protocolAdapter);
+        bytes memory atMinimum = forwarder.forwardCall(bytes32(0), input);
+
+        router.setMockAmounts(990 ether, 0);
+        vm.prank(protocolAdapter);
+        bytes memory aboveMinimum = forwarder.forwardCall(bytes32(0), input);
+
+        assertEq(atMinimum, abi.encode(true));
+        assertEq(aboveMinimum, atMinimum);
+        assertEq(tokenOut.balanceOf(recipient), 1890 ether);
+    }
+
+    function test_revert_exactInputSingle_belowMinimum() public {
+        ISwapRouter02.ExactInputSingleParams memory params = ISwapRouter02.ExactInputSingleParams({
+            tokenIn: address(tokenIn),
+            tokenOut: address(tokenOut),
+            fee: 3000,
+            recipient: recipient,
+            amountIn: SWAP_AMOUNT,
+            amountOutMinimum: 1000 ether,
+            sqrtPriceLimitX96: 0
+        });
+        bytes memory input = abi.encodeWithSelector(forwarder.exactInputSingle.selector, params);
+
+        vm.prank(protocolAdapter);
+        vm.expectRevert(
+            abi.encodeWithSelector(UniswapV3Forwarder.InsufficientOutputAmount.selector, 1000 ether, 950 ether)
+        );
+        forwarder.forwardCall(bytes32(0), input);
+    }
+
     function test_revert_notProtocolAdapter() public {
         ISwapRouter02.ExactInputSingleParams memory params = ISwapRouter02.ExactInputSingleParams({
             tokenIn: address(tokenIn),